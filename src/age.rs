@@ -0,0 +1,92 @@
+use crate::models::todo::Todo;
+use chrono::{DateTime, Duration, Utc};
+
+/// Default ages at which a todo gains another dot: 3 days, 2 weeks and
+/// 2 months.
+pub const DEFAULT_THRESHOLDS: &[&str] = &["3d", "2w", "60d"];
+
+const DOT: char = '·';
+
+/// How many of `thresholds` a todo of this age has reached.
+pub fn dots(age: Duration, thresholds: &[Duration]) -> usize {
+    thresholds
+        .iter()
+        .filter(|threshold| age >= **threshold)
+        .count()
+}
+
+/// The age marker for a todo: one dot per threshold reached, or nothing
+/// for completed todos and unreadable creation times.
+pub fn marker(todo: &Todo, thresholds: &[Duration], now: DateTime<Utc>) -> String {
+    if todo.completed {
+        return String::new();
+    }
+    let Ok(created) = DateTime::parse_from_rfc3339(&todo.created_at) else {
+        return String::new();
+    };
+    let age = now - created.with_timezone(&Utc);
+    std::iter::repeat_n(DOT, dots(age, thresholds)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::parse_duration;
+    use crate::models::todo::Priority;
+
+    fn default_thresholds() -> Vec<Duration> {
+        DEFAULT_THRESHOLDS
+            .iter()
+            .map(|threshold| parse_duration(threshold).unwrap())
+            .collect()
+    }
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_dots_buckets() {
+        let thresholds = default_thresholds();
+        assert_eq!(dots(Duration::hours(71), &thresholds), 0);
+        assert_eq!(dots(Duration::days(3), &thresholds), 1);
+        assert_eq!(dots(Duration::days(13), &thresholds), 1);
+        assert_eq!(dots(Duration::days(14), &thresholds), 2);
+        assert_eq!(dots(Duration::days(59), &thresholds), 2);
+        assert_eq!(dots(Duration::days(60), &thresholds), 3);
+        assert_eq!(dots(Duration::days(400), &thresholds), 3);
+        assert_eq!(dots(Duration::days(-1), &thresholds), 0);
+        assert_eq!(dots(Duration::days(400), &[]), 0);
+    }
+
+    #[test]
+    fn test_marker_with_pinned_clock() {
+        let thresholds = default_thresholds();
+        let mut todo = Todo::new(1, "Old".to_string(), Priority::Low);
+        todo.created_at = "2025-01-01T09:00:00+00:00".to_string();
+
+        assert_eq!(marker(&todo, &thresholds, at("2025-01-02T09:00:00Z")), "");
+        assert_eq!(marker(&todo, &thresholds, at("2025-01-10T09:00:00Z")), "·");
+        assert_eq!(marker(&todo, &thresholds, at("2025-02-01T09:00:00Z")), "··");
+        assert_eq!(
+            marker(&todo, &thresholds, at("2025-06-01T09:00:00Z")),
+            "···"
+        );
+
+        todo.completed = true;
+        assert_eq!(marker(&todo, &thresholds, at("2025-06-01T09:00:00Z")), "");
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let thresholds = [Duration::days(1), Duration::days(7)];
+        let mut todo = Todo::new(1, "Old".to_string(), Priority::Low);
+        todo.created_at = "2025-01-01T09:00:00+00:00".to_string();
+        assert_eq!(marker(&todo, &thresholds, at("2025-03-01T09:00:00Z")), "··");
+
+        todo.created_at = "yesterday".to_string();
+        assert_eq!(marker(&todo, &thresholds, at("2025-03-01T09:00:00Z")), "");
+    }
+}
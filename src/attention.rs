@@ -0,0 +1,194 @@
+use crate::models::todo::{Priority, Todo};
+use chrono::{DateTime, Local, Utc};
+use serde::Deserialize;
+
+/// Weights for each factor in the attention score. Higher scores mean the
+/// todo is more likely being neglected.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AttentionWeights {
+    /// Per priority step above the lowest (low = 0, critical = 3)
+    pub priority: f64,
+    /// Per day since the todo was created
+    pub age: f64,
+    /// Per day past the due date
+    pub overdue: f64,
+    /// Per time the todo has been snoozed
+    pub snooze: f64,
+}
+
+impl Default for AttentionWeights {
+    fn default() -> Self {
+        Self {
+            priority: 10.0,
+            age: 0.5,
+            overdue: 2.0,
+            snooze: 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ranked<'a> {
+    pub todo: &'a Todo,
+    pub score: f64,
+    pub reason: String,
+}
+
+fn age_days(todo: &Todo, now: DateTime<Utc>) -> Option<i64> {
+    DateTime::parse_from_rfc3339(&todo.created_at)
+        .ok()
+        .map(|created| (now - created.with_timezone(&Utc)).num_days().max(0))
+}
+
+fn overdue_days(todo: &Todo, now: DateTime<Utc>) -> i64 {
+    let today = now.with_timezone(&Local).date_naive();
+    todo.due_date
+        .map_or(0, |due| (today - due).num_days().max(0))
+}
+
+pub fn score(todo: &Todo, weights: &AttentionWeights, now: DateTime<Utc>) -> f64 {
+    let priority_steps = (Priority::Low.number() - todo.priority.number()) as f64;
+    weights.priority * priority_steps
+        + weights.age * age_days(todo, now).unwrap_or(0) as f64
+        + weights.overdue * overdue_days(todo, now) as f64
+        + weights.snooze * todo.snooze_count as f64
+}
+
+/// One-line explanation of why a todo ranks where it does,
+/// e.g. "critical priority, 45 days old, overdue by 2 days, snoozed 3 times".
+pub fn reason(todo: &Todo, now: DateTime<Utc>) -> String {
+    let plural = |n: i64, unit: &str| {
+        if n == 1 {
+            format!("1 {unit}")
+        } else {
+            format!("{n} {unit}s")
+        }
+    };
+
+    let mut parts = vec![format!("{} priority", todo.priority)];
+    if let Some(days) = age_days(todo, now) {
+        parts.push(format!("{} old", plural(days, "day")));
+    }
+    let overdue = overdue_days(todo, now);
+    if overdue > 0 {
+        parts.push(format!("overdue by {}", plural(overdue, "day")));
+    }
+    if todo.snooze_count > 0 {
+        parts.push(format!(
+            "snoozed {}",
+            plural(todo.snooze_count.into(), "time")
+        ));
+    }
+    parts.join(", ")
+}
+
+/// Pending todos ranked by descending attention score, highest first.
+/// Ties keep store order.
+pub fn rank<'a>(
+    todos: &'a [Todo],
+    weights: &AttentionWeights,
+    now: DateTime<Utc>,
+) -> Vec<Ranked<'a>> {
+    let mut ranked: Vec<Ranked> = todos
+        .iter()
+        .filter(|todo| !todo.completed)
+        .map(|todo| Ranked {
+            todo,
+            score: score(todo, weights, now),
+            reason: reason(todo, now),
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn todo(id: usize, priority: u8, days_old: i64, now: DateTime<Utc>) -> Todo {
+        Todo {
+            id,
+            priority: Priority::from_number(priority).unwrap(),
+            created_at: (now - Duration::days(days_old)).to_rfc3339(),
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn test_reason_string() {
+        let now = Utc::now();
+        let mut t = todo(1, 1, 45, now);
+        t.due_date = Some(now.with_timezone(&Local).date_naive() - Duration::days(2));
+        t.snooze_count = 3;
+        assert_eq!(
+            reason(&t, now),
+            "critical priority, 45 days old, overdue by 2 days, snoozed 3 times"
+        );
+    }
+
+    #[test]
+    fn test_reason_singular_and_omitted_parts() {
+        let now = Utc::now();
+        let mut t = todo(1, 3, 1, now);
+        t.snooze_count = 1;
+        assert_eq!(
+            reason(&t, now),
+            "medium priority, 1 day old, snoozed 1 time"
+        );
+
+        t.created_at = "garbage".to_string();
+        t.snooze_count = 0;
+        assert_eq!(reason(&t, now), "medium priority");
+    }
+
+    #[test]
+    fn test_rank_orders_by_score_and_skips_completed() {
+        let now = Utc::now();
+        let mut done = todo(4, 1, 100, now);
+        done.completed = true;
+        let todos = vec![
+            todo(1, 4, 1, now),
+            todo(2, 1, 1, now),
+            todo(3, 4, 90, now),
+            done,
+        ];
+
+        let ids: Vec<usize> = rank(&todos, &AttentionWeights::default(), now)
+            .iter()
+            .map(|r| r.todo.id)
+            .collect();
+        assert_eq!(ids, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_weights_change_ranking() {
+        let now = Utc::now();
+        let todos = vec![todo(1, 4, 90, now), todo(2, 1, 1, now)];
+        let priority_only = AttentionWeights {
+            priority: 1.0,
+            age: 0.0,
+            overdue: 0.0,
+            snooze: 0.0,
+        };
+
+        let ranked = rank(&todos, &priority_only, now);
+        assert_eq!(ranked[0].todo.id, 2);
+        assert_eq!(ranked[0].score, 3.0);
+    }
+
+    #[test]
+    fn test_snoozes_raise_score() {
+        let now = Utc::now();
+        let mut snoozed = todo(1, 4, 0, now);
+        let fresh = snoozed.clone();
+        snoozed.snooze_count = 2;
+        let weights = AttentionWeights::default();
+        assert_eq!(
+            score(&snoozed, &weights, now) - score(&fresh, &weights, now),
+            10.0
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::cli::{self, Cli};
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{CommandFactory, Parser};
+use std::process::ExitCode;
+use tt::suggest::edit_distance;
+
+/// How many edits away a typo may be from the command it is taken for.
+/// Very short input gets less leeway, as it is close to everything.
+fn max_typo_distance(input: &str) -> usize {
+    if input.chars().count() <= 3 { 1 } else { 2 }
+}
+
+/// Commands that are never run on a guess without asking first.
+const DESTRUCTIVE: &[&str] = &["delete", "clear", "clear-completed", "clean"];
+
+/// What to do about a mistyped subcommand.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Correction<'a> {
+    /// Run this command instead, after saying so
+    Assume(&'a str),
+    /// Only run this command if the user confirms
+    Confirm(&'a str),
+    /// Just list these candidates (possibly none)
+    Suggest(Vec<&'a str>),
+}
+
+/// Commands (names or aliases) that `input` could have meant: those it is
+/// a prefix of, then those within a couple of edits, closest first.
+pub fn candidates<'a>(input: &str, commands: &[&'a str]) -> Vec<&'a str> {
+    let max_distance = max_typo_distance(input);
+    let mut scored: Vec<(usize, &str)> = commands
+        .iter()
+        .map(|command| {
+            let score = if command.starts_with(input) {
+                0
+            } else {
+                edit_distance(input, command)
+            };
+            (score, *command)
+        })
+        .filter(|(score, _)| *score <= max_distance)
+        .collect();
+    scored.sort_by_key(|(score, _)| *score);
+    scored.into_iter().map(|(_, command)| command).collect()
+}
+
+/// Decide what to do with `input`. Only a single candidate is ever acted
+/// on, and only with `autocorrect` enabled.
+pub fn correct<'a>(input: &str, commands: &[&'a str], autocorrect: bool) -> Correction<'a> {
+    let candidates = candidates(input, commands);
+    match candidates.as_slice() {
+        [only] if autocorrect && DESTRUCTIVE.contains(only) => Correction::Confirm(only),
+        [only] if autocorrect => Correction::Assume(only),
+        _ => Correction::Suggest(candidates),
+    }
+}
+
+/// Parse the command line, correcting or explaining a mistyped subcommand.
+/// Other errors exit the process, like `Cli::parse`. After listing
+/// suggestions it gives back the exit status for `main` to return.
+pub fn parse(autocorrect: bool) -> Result<Cli, ExitCode> {
+    let mut args = cli::protect_due_offset(std::env::args().collect());
+    let error = match Cli::try_parse_from(&args) {
+        Ok(cli) => return Ok(cli),
+        Err(error) if error.kind() == ErrorKind::InvalidSubcommand => error,
+        Err(error) => error.exit(),
+    };
+    let Some(ContextValue::String(typo)) = error.get(ContextKind::InvalidSubcommand) else {
+        error.exit()
+    };
+    let typo = typo.clone();
+
+    let command = Cli::command();
+    let names: Vec<&str> = command
+        .get_subcommands()
+        .flat_map(|sub| std::iter::once(sub.get_name()).chain(sub.get_all_aliases()))
+        .collect();
+
+    let chosen = match correct(&typo, &names, autocorrect) {
+        Correction::Assume(name) => {
+            eprintln!("⚠️  Assuming you meant '{name}'");
+            name
+        }
+        Correction::Confirm(name) if cli::confirm(&format!("Did you mean '{name}'?")) => name,
+        Correction::Confirm(_) => error.exit(),
+        Correction::Suggest(candidates) if candidates.is_empty() => error.exit(),
+        Correction::Suggest(candidates) => {
+            eprintln!("error: unrecognized subcommand '{typo}'\n");
+            if let [only] = candidates.as_slice() {
+                eprintln!("  Did you mean '{only}'?");
+                if !autocorrect {
+                    eprintln!("  (set `autocorrect = true` in config.toml to run it directly)");
+                }
+            } else {
+                eprintln!("  Did you mean one of these?");
+                for candidate in candidates {
+                    eprintln!("    {candidate}");
+                }
+            }
+            eprintln!("\nFor more information, try '--help'.");
+            return Err(ExitCode::from(2));
+        }
+    };
+
+    if let Some(arg) = args.iter_mut().skip(1).find(|arg| **arg == typo) {
+        *arg = chosen.to_string();
+    }
+    Ok(Cli::try_parse_from(&args).unwrap_or_else(|error| error.exit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMANDS: &[&str] = &[
+        "add",
+        "edit",
+        "complete",
+        "incomplete",
+        "toggle",
+        "delete",
+        "digest",
+        "list",
+    ];
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(candidates("comlpete", COMMANDS), vec!["complete"]);
+        assert_eq!(candidates("lsit", COMMANDS), vec!["edit", "list"]);
+        assert_eq!(candidates("tog", COMMANDS), vec!["toggle"]);
+        assert_eq!(candidates("d", COMMANDS), vec!["delete", "digest"]);
+        assert!(candidates("frobnicate", COMMANDS).is_empty());
+    }
+
+    #[test]
+    fn test_prefixes_rank_before_typos() {
+        assert_eq!(candidates("delet", COMMANDS), vec!["delete"]);
+        assert_eq!(candidates("di", COMMANDS), vec!["digest"]);
+        assert_eq!(candidates("dd", COMMANDS), vec!["add"]);
+    }
+
+    #[test]
+    fn test_correct_needs_autocorrect_and_a_single_match() {
+        assert_eq!(
+            correct("comlpete", COMMANDS, true),
+            Correction::Assume("complete")
+        );
+        assert_eq!(
+            correct("comlpete", COMMANDS, false),
+            Correction::Suggest(vec!["complete"])
+        );
+        assert_eq!(
+            correct("d", COMMANDS, true),
+            Correction::Suggest(vec!["delete", "digest"])
+        );
+    }
+
+    #[test]
+    fn test_destructive_commands_need_confirmation() {
+        assert_eq!(
+            correct("delte", COMMANDS, true),
+            Correction::Confirm("delete")
+        );
+    }
+
+    #[test]
+    fn test_every_command_name_is_considered() {
+        let command = Cli::command();
+        let names: Vec<&str> = command
+            .get_subcommands()
+            .map(|sub| sub.get_name())
+            .collect();
+        assert_eq!(
+            correct("comlpete", &names, true),
+            Correction::Assume("complete")
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::models::todo::Priority;
+use clap::ValueEnum;
+use std::fmt;
+
+/// A todo to add, read from one line of `tt add --stdin`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    /// Line number in the input, starting at 1
+    pub number: usize,
+    pub title: String,
+    pub priority: Priority,
+}
+
+/// A line that could not be turned into a todo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub number: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.number, self.message)
+    }
+}
+
+/// The todos read from a batch, plus a problem for every line that could
+/// not be used.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Parsed {
+    pub lines: Vec<Line>,
+    pub problems: Vec<Problem>,
+}
+
+/// Read one title per line, skipping blank lines and `#` comments. A
+/// trailing `!1` to `!4` (or `!high` and the like) sets that line's
+/// priority; other lines get `default_priority`.
+pub fn parse(input: &str, default_priority: Priority) -> Parsed {
+    let mut parsed = Parsed::default();
+    for (index, line) in input.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match split_priority(line) {
+            Some((title, Ok(priority))) => parsed.lines.push(Line {
+                number,
+                title: title.to_string(),
+                priority,
+            }),
+            Some((_, Err(message))) => parsed.problems.push(Problem { number, message }),
+            None => parsed.lines.push(Line {
+                number,
+                title: line.to_string(),
+                priority: default_priority,
+            }),
+        }
+    }
+    parsed
+}
+
+/// The title and priority of a line ending in a `!<number>` or
+/// `!<priority name>` word, if it does.
+fn split_priority(line: &str) -> Option<(&str, Result<Priority, String>)> {
+    let (title, last) = line.rsplit_once(char::is_whitespace)?;
+    let word = last.strip_prefix('!')?;
+    let priority = if !word.is_empty() && word.chars().all(|c| c.is_ascii_digit()) {
+        match word.parse() {
+            Ok(number) => Priority::from_number(number).map_err(|e| e.to_string()),
+            Err(_) => Err(format!("Priority must be between 1 and 4, got {word}")),
+        }
+    } else {
+        Ok(<Priority as ValueEnum>::from_str(word, true).ok()?)
+    };
+    Some((title.trim_end(), priority))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(number: usize, title: &str, priority: Priority) -> Line {
+        Line {
+            number,
+            title: title.to_string(),
+            priority,
+        }
+    }
+
+    #[test]
+    fn test_parse_skips_blanks_and_comments() {
+        let input = "# Brainstorm\nWrite the plan\n\n   \n  Call Sam  \n#later\n";
+        assert_eq!(
+            parse(input, Priority::Medium),
+            Parsed {
+                lines: vec![
+                    line(2, "Write the plan", Priority::Medium),
+                    line(5, "Call Sam", Priority::Medium),
+                ],
+                problems: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_priority_suffix() {
+        let parsed = parse(
+            "Fix the build !1\nShip it!\nWow !\nTune cache !2x\nLog !9\nPlan offsite !High\n",
+            Priority::Low,
+        );
+        assert_eq!(
+            parsed.lines,
+            vec![
+                line(1, "Fix the build", Priority::Critical),
+                line(2, "Ship it!", Priority::Low),
+                line(3, "Wow !", Priority::Low),
+                line(4, "Tune cache !2x", Priority::Low),
+                line(6, "Plan offsite", Priority::High),
+            ]
+        );
+        assert_eq!(
+            parsed
+                .problems
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["line 5: Priority must be between 1 and 4, got 9"]
+        );
+        assert_eq!(parse("Huge !300", Priority::Low).problems[0].number, 1);
+    }
+}
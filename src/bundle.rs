@@ -0,0 +1,447 @@
+use crate::config::Config;
+use crate::models::todo::{Todo, TodoStore};
+use crate::validate;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Version of the bundle layout itself (manifest plus component files).
+pub const FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// One part of "my tt life" carried by a bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Component {
+    Store,
+    Archive,
+    Config,
+}
+
+impl Component {
+    fn file_name(self) -> &'static str {
+        match self {
+            Component::Store => "store.json",
+            Component::Archive => "archive.json",
+            Component::Config => "config.toml",
+        }
+    }
+
+    /// Schema version written for this component, and the newest one
+    /// this build can read.
+    fn schema_version(self) -> u32 {
+        1
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Component::Store => "store",
+            Component::Archive => "archive",
+            Component::Config => "config",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    created_at: String, // ISO 8601 format
+    components: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    component: Component,
+    file: String,
+    schema_version: u32,
+}
+
+/// The validated contents of a bundle. The config is kept as raw TOML so
+/// comments and layout survive the move.
+#[derive(Debug, Default)]
+pub struct Bundle {
+    pub store: Option<TodoStore>,
+    pub archive: Option<Vec<Todo>>,
+    pub config: Option<String>,
+}
+
+/// Write `bundle` to `path` as a gzipped tar of a manifest plus one file
+/// per component.
+pub fn write(path: &Path, bundle: &Bundle, now: DateTime<Utc>) -> Result<()> {
+    let mut files: Vec<(Component, String)> = Vec::new();
+    if let Some(store) = &bundle.store {
+        files.push((Component::Store, to_json(store)?));
+    }
+    if let Some(archive) = &bundle.archive {
+        let archive = TodoStore {
+            todos: archive.clone(),
+            next_id: 0,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+        };
+        files.push((Component::Archive, to_json(&archive)?));
+    }
+    if let Some(config) = &bundle.config {
+        files.push((Component::Config, config.clone()));
+    }
+
+    let manifest = Manifest {
+        format_version: FORMAT_VERSION,
+        created_at: now.to_rfc3339(),
+        components: files
+            .iter()
+            .map(|(component, _)| ManifestEntry {
+                component: *component,
+                file: component.file_name().to_string(),
+                schema_version: component.schema_version(),
+            })
+            .collect(),
+    };
+
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create bundle {}", path.display()))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mtime = u64::try_from(now.timestamp()).unwrap_or(0);
+    append_file(&mut archive, MANIFEST_FILE, &to_json(&manifest)?, mtime)?;
+    for (component, contents) in &files {
+        append_file(&mut archive, component.file_name(), contents, mtime)?;
+    }
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .and_then(|file| file.sync_all())
+        .with_context(|| format!("Failed to write bundle {}", path.display()))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(value).context("Failed to serialize bundle component")
+}
+
+fn append_file(
+    archive: &mut tar::Builder<GzEncoder<File>>,
+    name: &str,
+    contents: &str,
+    mtime: u64,
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, name, contents.as_bytes())
+        .with_context(|| format!("Failed to add {name} to bundle"))
+}
+
+/// Read and fully validate a bundle. Nothing in it is trusted until every
+/// component has passed, so callers can write components without risk of
+/// stopping halfway through on bad data.
+pub fn read(path: &Path) -> Result<Bundle> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open bundle {}", path.display()))?;
+    let mut files = HashMap::new();
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    for entry in archive.entries().context("Failed to read bundle")? {
+        let mut entry = entry.context("Failed to read bundle")?;
+        let name = entry
+            .path()
+            .context("Failed to read bundle")?
+            .to_string_lossy()
+            .into_owned();
+        let mut contents = String::new();
+        entry
+            .read_to_string(&mut contents)
+            .with_context(|| format!("Failed to read {name} from bundle"))?;
+        files.insert(name, contents);
+    }
+    parse(&files)
+}
+
+fn parse(files: &HashMap<String, String>) -> Result<Bundle> {
+    let manifest: Manifest = files
+        .get(MANIFEST_FILE)
+        .context("Bundle has no manifest")
+        .and_then(|manifest| {
+            serde_json::from_str(manifest).context("Bundle manifest is not valid")
+        })?;
+    if manifest.format_version > FORMAT_VERSION {
+        anyhow::bail!(
+            "Bundle format version {} is newer than this tt supports ({})",
+            manifest.format_version,
+            FORMAT_VERSION
+        );
+    }
+
+    let mut bundle = Bundle::default();
+    for entry in &manifest.components {
+        let name = entry.component.name();
+        if entry.schema_version > entry.component.schema_version() {
+            anyhow::bail!(
+                "Bundle {name} schema version {} is newer than this tt supports ({})",
+                entry.schema_version,
+                entry.component.schema_version()
+            );
+        }
+        let contents = files
+            .get(&entry.file)
+            .with_context(|| format!("Bundle is missing {} for its {name}", entry.file))?;
+
+        match entry.component {
+            Component::Store => bundle.store = Some(parse_store(name, contents)?),
+            Component::Archive => bundle.archive = Some(parse_store(name, contents)?.todos),
+            Component::Config => {
+                toml::from_str::<Config>(contents)
+                    .with_context(|| format!("Bundle {name} is not valid"))?;
+                bundle.config = Some(contents.clone());
+            }
+        }
+    }
+    Ok(bundle)
+}
+
+fn parse_store(name: &str, contents: &str) -> Result<TodoStore> {
+    if let Some(problem) = validate::validate_str(contents).first() {
+        anyhow::bail!(
+            "Bundle {name} is not valid: {}: {}",
+            problem.path,
+            problem.message
+        );
+    }
+    serde_json::from_str(contents).with_context(|| format!("Bundle {name} is not valid"))
+}
+
+/// What merging one list of todos into another did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeCounts {
+    pub added: usize,
+    pub present: usize,
+    pub renumbered: usize,
+}
+
+impl fmt::Display for MergeCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added ({} under a new ID), {} already present",
+            self.added, self.renumbered, self.present
+        )
+    }
+}
+
+/// Merge `incoming` into `local` by stable ID. A todo with the same ID and
+/// creation time is the same todo and is left alone, as is one already
+/// merged under a new ID (same creation time and title). One whose ID is
+/// taken by a different todo (in `local` or `reserved`) is added under a
+/// fresh ID.
+pub fn merge_todos(
+    local: &mut Vec<Todo>,
+    incoming: Vec<Todo>,
+    reserved: &[Todo],
+    next_id: &mut usize,
+) -> MergeCounts {
+    let mut counts = MergeCounts::default();
+    let mut ids: HashMap<usize, String> = HashMap::new();
+    let mut contents: HashSet<(String, String)> = HashSet::new();
+    for todo in local.iter().chain(reserved) {
+        ids.insert(todo.id, todo.created_at.clone());
+        contents.insert((todo.created_at.clone(), todo.title.clone()));
+    }
+
+    for mut todo in incoming {
+        let same_id = ids.get(&todo.id);
+        if same_id == Some(&todo.created_at)
+            || contents.contains(&(todo.created_at.clone(), todo.title.clone()))
+        {
+            counts.present += 1;
+            continue;
+        }
+        if same_id.is_some() {
+            todo.id = *next_id;
+            counts.renumbered += 1;
+        }
+        *next_id = (*next_id).max(todo.id + 1);
+        ids.insert(todo.id, todo.created_at.clone());
+        contents.insert((todo.created_at.clone(), todo.title.clone()));
+        local.push(todo);
+        counts.added += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn todo(id: usize, title: &str, created_at: &str) -> Todo {
+        let mut todo = Todo::new(id, title.to_string(), Priority::Medium);
+        todo.created_at = created_at.to_string();
+        todo
+    }
+
+    fn sample_bundle() -> Bundle {
+        Bundle {
+            store: Some(TodoStore {
+                todos: vec![todo(1, "Buy milk", "2025-01-01T00:00:00Z")],
+                next_id: 2,
+                meta: None,
+                deleted: Vec::new(),
+                templates: Vec::new(),
+                trash: Vec::new(),
+            }),
+            archive: Some(vec![todo(7, "Old", "2024-01-01T00:00:00Z")]),
+            config: Some("# mine\nid_style = \"hash\"\n".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("bundle.ttpack");
+        write(&path, &sample_bundle(), Utc::now()).unwrap();
+
+        let bundle = read(&path).unwrap();
+        let store = bundle.store.unwrap();
+        assert_eq!(store.next_id, 2);
+        assert_eq!(store.todos[0].title, "Buy milk");
+        assert_eq!(bundle.archive.unwrap()[0].id, 7);
+        assert_eq!(bundle.config.unwrap(), "# mine\nid_style = \"hash\"\n");
+    }
+
+    #[test]
+    fn test_components_are_optional() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("bundle.ttpack");
+        let bundle = Bundle {
+            config: None,
+            ..sample_bundle()
+        };
+        write(&path, &bundle, Utc::now()).unwrap();
+        assert!(read(&path).unwrap().config.is_none());
+    }
+
+    fn files(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, contents)| (name.to_string(), contents.to_string()))
+            .collect()
+    }
+
+    const MANIFEST: &str = r#"{"format_version": 1, "created_at": "2025-01-01T00:00:00Z",
+        "components": [{"component": "store", "file": "store.json", "schema_version": 1}]}"#;
+
+    #[test]
+    fn test_invalid_store_is_rejected() {
+        let error = parse(&files(&[
+            (MANIFEST_FILE, MANIFEST),
+            (
+                "store.json",
+                r#"{"todos": [{"title": "x", "completed": "no"}]}"#,
+            ),
+        ]))
+        .unwrap_err();
+        assert!(error.to_string().contains("Bundle store is not valid"));
+    }
+
+    #[test]
+    fn test_missing_component_file_is_rejected() {
+        let error = parse(&files(&[(MANIFEST_FILE, MANIFEST)])).unwrap_err();
+        assert!(error.to_string().contains("missing store.json"));
+    }
+
+    #[test]
+    fn test_missing_or_corrupt_manifest_is_rejected() {
+        assert!(parse(&files(&[("store.json", "{}")])).is_err());
+        assert!(parse(&files(&[(MANIFEST_FILE, "{")])).is_err());
+    }
+
+    #[test]
+    fn test_newer_versions_are_rejected() {
+        let newer_format = MANIFEST.replace("\"format_version\": 1", "\"format_version\": 2");
+        let error = parse(&files(&[(MANIFEST_FILE, &newer_format)])).unwrap_err();
+        assert!(error.to_string().contains("newer than this tt supports"));
+
+        let newer_schema = MANIFEST.replace("\"schema_version\": 1", "\"schema_version\": 9");
+        let error = parse(&files(&[
+            (MANIFEST_FILE, &newer_schema),
+            ("store.json", r#"{"todos": []}"#),
+        ]))
+        .unwrap_err();
+        assert!(error.to_string().contains("store schema version 9"));
+    }
+
+    #[test]
+    fn test_truncated_bundle_is_rejected() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("bundle.ttpack");
+        write(&path, &sample_bundle(), Utc::now()).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let mut truncated = File::create(&path).unwrap();
+        truncated.write_all(&bytes[..bytes.len() / 2]).unwrap();
+        drop(truncated);
+
+        assert!(read(&path).is_err());
+    }
+
+    #[test]
+    fn test_merge_by_stable_id() {
+        let mut local = vec![
+            todo(1, "Same", "2025-01-01T00:00:00Z"),
+            todo(2, "Mine", "2025-01-02T00:00:00Z"),
+        ];
+        let archived = vec![todo(3, "Archived here", "2025-01-03T00:00:00Z")];
+        let incoming = vec![
+            todo(1, "Same", "2025-01-01T00:00:00Z"),
+            todo(2, "Theirs", "2025-02-02T00:00:00Z"),
+            todo(3, "Also theirs", "2025-02-03T00:00:00Z"),
+            todo(9, "New", "2025-02-09T00:00:00Z"),
+        ];
+        let mut next_id = 4;
+
+        let counts = merge_todos(&mut local, incoming, &archived, &mut next_id);
+        assert_eq!(
+            counts,
+            MergeCounts {
+                added: 3,
+                present: 1,
+                renumbered: 2
+            }
+        );
+
+        let titles: Vec<(usize, &str)> = local.iter().map(|t| (t.id, t.title.as_str())).collect();
+        assert_eq!(
+            titles,
+            vec![
+                (1, "Same"),
+                (2, "Mine"),
+                (4, "Theirs"),
+                (5, "Also theirs"),
+                (9, "New")
+            ]
+        );
+        assert_eq!(next_id, 10);
+
+        // Merging the same todos again adds nothing, renumbered ones included
+        let again = vec![
+            todo(2, "Theirs", "2025-02-02T00:00:00Z"),
+            todo(9, "New", "2025-02-09T00:00:00Z"),
+        ];
+        let counts = merge_todos(&mut local, again, &archived, &mut next_id);
+        assert_eq!(counts.added, 0);
+        assert_eq!(counts.present, 2);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::duration::format_duration;
+use crate::stats::Stats;
+use chrono::Duration;
+use serde::Serialize;
+
+/// Limits evaluated by `tt check`. A `None` threshold is not checked.
+#[derive(Debug, Default, Clone)]
+pub struct Thresholds {
+    pub max_pending: Option<usize>,
+    pub max_age: Option<Duration>,
+    pub max_overdue: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub limit: String,
+    pub actual: String,
+    pub passed: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Evaluation {
+    pub passed: bool,
+    pub checks: Vec<CheckResult>,
+    pub stats: Stats,
+}
+
+/// Evaluate each configured threshold against the store statistics.
+/// A check passes when the actual value is at or below its limit.
+pub fn evaluate(stats: &Stats, thresholds: &Thresholds) -> Evaluation {
+    let mut checks = Vec::new();
+
+    if let Some(max_pending) = thresholds.max_pending {
+        checks.push(CheckResult {
+            name: "max_pending",
+            limit: max_pending.to_string(),
+            actual: stats.pending.to_string(),
+            passed: stats.pending <= max_pending,
+        });
+    }
+
+    if let Some(max_age) = thresholds.max_age {
+        let oldest = stats.oldest_pending_age();
+        checks.push(CheckResult {
+            name: "max_age",
+            limit: format_duration(max_age),
+            actual: oldest.map_or_else(|| "none".to_string(), format_duration),
+            passed: oldest.is_none_or(|age| age <= max_age),
+        });
+    }
+
+    if let Some(max_overdue) = thresholds.max_overdue {
+        checks.push(CheckResult {
+            name: "max_overdue",
+            limit: max_overdue.to_string(),
+            actual: stats.overdue.to_string(),
+            passed: stats.overdue <= max_overdue,
+        });
+    }
+
+    Evaluation {
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+        stats: stats.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(pending: usize, oldest_days: Option<i64>) -> Stats {
+        Stats {
+            total: pending,
+            pending,
+            completed: 0,
+            overdue: 0,
+            pending_by_priority: Default::default(),
+            oldest_pending_age_secs: oldest_days.map(|d| Duration::days(d).num_seconds()),
+        }
+    }
+
+    #[test]
+    fn test_no_thresholds_passes() {
+        let evaluation = evaluate(&stats(1000, Some(999)), &Thresholds::default());
+        assert!(evaluation.passed);
+        assert!(evaluation.checks.is_empty());
+    }
+
+    #[test]
+    fn test_max_pending_boundaries() {
+        let thresholds = Thresholds {
+            max_pending: Some(50),
+            ..Thresholds::default()
+        };
+
+        assert!(evaluate(&stats(49, None), &thresholds).passed);
+        assert!(evaluate(&stats(50, None), &thresholds).passed);
+
+        let evaluation = evaluate(&stats(51, None), &thresholds);
+        assert!(!evaluation.passed);
+        assert_eq!(evaluation.checks[0].actual, "51");
+        assert_eq!(evaluation.checks[0].limit, "50");
+    }
+
+    #[test]
+    fn test_max_pending_zero() {
+        let thresholds = Thresholds {
+            max_pending: Some(0),
+            ..Thresholds::default()
+        };
+
+        assert!(evaluate(&stats(0, None), &thresholds).passed);
+        assert!(!evaluate(&stats(1, Some(0)), &thresholds).passed);
+    }
+
+    #[test]
+    fn test_max_age_boundaries() {
+        let thresholds = Thresholds {
+            max_age: Some(Duration::days(30)),
+            ..Thresholds::default()
+        };
+
+        assert!(evaluate(&stats(1, Some(29)), &thresholds).passed);
+        assert!(evaluate(&stats(1, Some(30)), &thresholds).passed);
+
+        let evaluation = evaluate(&stats(1, Some(31)), &thresholds);
+        assert!(!evaluation.passed);
+        assert_eq!(evaluation.checks[0].actual, "31d");
+        assert_eq!(evaluation.checks[0].limit, "30d");
+    }
+
+    #[test]
+    fn test_max_age_without_pending_todos_passes() {
+        let thresholds = Thresholds {
+            max_age: Some(Duration::days(1)),
+            ..Thresholds::default()
+        };
+
+        let evaluation = evaluate(&stats(0, None), &thresholds);
+        assert!(evaluation.passed);
+        assert_eq!(evaluation.checks[0].actual, "none");
+    }
+
+    #[test]
+    fn test_max_overdue_boundaries() {
+        let thresholds = Thresholds {
+            max_overdue: Some(2),
+            ..Thresholds::default()
+        };
+        let overdue = |count| Stats {
+            overdue: count,
+            ..stats(5, None)
+        };
+
+        assert!(evaluate(&overdue(1), &thresholds).passed);
+        assert!(evaluate(&overdue(2), &thresholds).passed);
+
+        let evaluation = evaluate(&overdue(3), &thresholds);
+        assert!(!evaluation.passed);
+        assert_eq!(evaluation.checks[0].name, "max_overdue");
+        assert_eq!(evaluation.checks[0].actual, "3");
+        assert_eq!(evaluation.checks[0].limit, "2");
+    }
+
+    #[test]
+    fn test_max_overdue_zero() {
+        let thresholds = Thresholds {
+            max_overdue: Some(0),
+            ..Thresholds::default()
+        };
+
+        assert!(evaluate(&stats(5, Some(60)), &thresholds).passed);
+        let evaluation = evaluate(
+            &Stats {
+                overdue: 1,
+                ..stats(5, None)
+            },
+            &thresholds,
+        );
+        assert!(!evaluation.passed);
+    }
+
+    #[test]
+    fn test_any_failure_fails_evaluation() {
+        let thresholds = Thresholds {
+            max_pending: Some(10),
+            max_age: Some(Duration::days(30)),
+            max_overdue: None,
+        };
+
+        let evaluation = evaluate(&stats(5, Some(60)), &thresholds);
+        assert!(!evaluation.passed);
+        assert!(evaluation.checks[0].passed);
+        assert!(!evaluation.checks[1].passed);
+    }
+}
@@ -1,7 +1,51 @@
-use crate::todo_manager::TodoManager;
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use crate::completions;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use colored::*;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use tt::attention;
+use tt::batch;
+use tt::bundle::{self, Bundle};
+use tt::check::{self, Thresholds};
+use tt::config::Config;
+use tt::conflicts;
+use tt::conventions::Conventions;
+use tt::dates;
+use tt::digest;
+use tt::due::{self, Condition, DueChange};
+use tt::duration::parse_duration;
+use tt::editor::{self, Outcome};
+use tt::error::TtError;
+use tt::export::{self, ExportFormat};
+use tt::focus::{Focus, Status as FocusStatus};
+use tt::groom;
+use tt::groups::{self, GroupBy};
+use tt::ids::{self, Selection};
+use tt::import::{self, ImportFormat};
+use tt::interactive;
+use tt::journal;
+use tt::lists;
+use tt::maintenance;
+use tt::merge::MergeReport;
+use tt::metrics;
+use tt::models::todo::{Priority, StoreMeta, Template, Todo, TodoStore};
+use tt::normalize;
+use tt::notes;
+use tt::output::{self, ColorChoice, DateFormat, Output, esay, say};
+use tt::render::{self, Style};
+use tt::settings::{Flags, Settings};
+use tt::stats::Stats;
+use tt::tags;
+use tt::text;
+use tt::todo_manager::{
+    self, DedupeStrategy, Location, NewTodo, QueryOptions, SortKey, TodoEdit, TodoManager,
+};
+use tt::validate::{self, Problem};
 
 #[derive(Parser)]
 #[command(name = "tt")]
@@ -10,133 +54,2975 @@ use colored::*;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+    /// Todo file to use instead of the default (overrides TT_FILE and TT_LIST)
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "list")]
+    pub file: Option<PathBuf>,
+    /// Named list to use instead of the default one (overrides TT_FILE and TT_LIST)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub list: Option<String>,
+    /// Start from an empty list if the todo file cannot be loaded, and skip
+    /// confirmation prompts
+    #[arg(long, global = true)]
+    pub force: bool,
+    /// Print results as JSON (list, search, show, add, edit, complete, incomplete,
+    /// toggle, check, validate, history)
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// When to color output
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+    /// Never color output (same as --color never)
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    /// Print plain text markers instead of emoji (overrides TT_NO_EMOJI)
+    #[arg(long, global = true)]
+    pub no_emoji: bool,
+    /// How to lay out todos in lists and detail views (overrides the config)
+    #[arg(long, global = true, value_enum)]
+    pub style: Option<Style>,
+}
+
+impl Cli {
+    /// The settings given as flags, for `Settings::resolve`.
+    pub fn setting_flags(&self) -> Flags {
+        let mut flags = Flags {
+            no_emoji: self.no_emoji.then_some(true),
+            file: self.file.clone(),
+            list: self.list.clone(),
+            ..Flags::default()
+        };
+        match &self.command {
+            Some(Commands::Add { priority, .. }) => flags.default_priority = *priority,
+            Some(Commands::List {
+                sort,
+                completed,
+                incomplete,
+                all,
+                ..
+            }) => {
+                flags.sort = *sort;
+                flags.hide_completed = match (completed, incomplete, all) {
+                    (true, _, _) | (_, _, true) => Some(false),
+                    (_, true, _) => Some(true),
+                    _ => None,
+                };
+            }
+            _ => {}
+        }
+        flags
+    }
+
+    /// The color choice, with --no-color taking precedence.
+    pub fn color_choice(&self) -> ColorChoice {
+        if self.no_color {
+            ColorChoice::Never
+        } else {
+            self.color
+        }
+    }
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
-    /// Add a new todo item
+    /// Add a new todo item, or one per line read from stdin
     Add {
-        /// The title of the todo item
-        title: String,
-        /// The priority of the todo item (1-4, 1 = highest, 4 = lowest)
-        #[arg(short, long, value_name = "PRIORITY", default_value_t = 4)]
-        priority: u8,
+        /// The title of the todo item ("-" reads titles from stdin, like --stdin)
+        #[arg(required_unless_present = "stdin")]
+        title: Option<String>,
+        /// The priority of the todo item, by name or 1-4 (1 = critical; default
+        /// from TT_DEFAULT_PRIORITY or the config, else low)
+        #[arg(short, long, value_name = "PRIORITY", ignore_case = true)]
+        priority: Option<Priority>,
+        /// The due date: YYYY-MM-DD, tomorrow, friday, next week, in 3 days, 2d, jun 15, ...
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        due: Option<NaiveDate>,
+        /// A tag for the todo (repeatable)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// The project the todo belongs to
+        #[arg(long, value_name = "NAME")]
+        project: Option<String>,
+        /// A longer description; line breaks are kept
+        #[arg(long, value_name = "TEXT")]
+        notes: Option<String>,
+        /// Read the notes from stdin, exactly as given (e.g. a piped stack trace)
+        #[arg(long, conflicts_with = "notes")]
+        notes_from_stdin: bool,
+        /// Add a todo per line of stdin, skipping blank lines and # comments; a
+        /// trailing "!1" to "!4" or "!high" sets a line's priority
+        #[arg(
+            long,
+            conflicts_with_all = ["title", "due", "tags", "project", "notes", "notes_from_stdin"]
+        )]
+        stdin: bool,
+        /// With titles from stdin, add nothing if any line is invalid
+        #[arg(long)]
+        strict: bool,
     },
     /// Edit an existing todo item
     Edit {
-        /// The ID of the todo item to edit
-        id: usize,
+        /// The ID of the todo item to edit, or text from its title
+        id: String,
         /// The new title (optional)
         #[arg(long)]
         title: Option<String>,
-        /// The new priority (optional, 1-4)
-        #[arg(short, long, value_name = "PRIORITY")]
-        priority: Option<u8>,
+        /// The new priority (optional, by name or 1-4)
+        #[arg(short, long, value_name = "PRIORITY", ignore_case = true)]
+        priority: Option<Priority>,
+        /// The new due date (YYYY-MM-DD, friday, in 3 days, ..., or "none" to remove it)
+        #[arg(long, value_name = "DATE", value_parser = parse_due_update)]
+        due: Option<DueUpdate>,
+        /// Remove the due date
+        #[arg(long, conflicts_with = "due")]
+        clear_due: bool,
+        /// Add a tag (repeatable)
+        #[arg(long = "add-tag", visible_alias = "tag", value_name = "TAG")]
+        add_tags: Vec<String>,
+        /// Remove a tag (repeatable)
+        #[arg(long = "remove-tag", value_name = "TAG")]
+        remove_tags: Vec<String>,
+        /// Move it to another project ("" takes it out of its project)
+        #[arg(long, value_name = "NAME")]
+        project: Option<String>,
+        /// Take it out of its project
+        #[arg(long, conflicts_with = "project")]
+        clear_project: bool,
+        /// Replace the notes ("" removes them)
+        #[arg(long, value_name = "TEXT")]
+        notes: Option<String>,
+        /// Remove the notes
+        #[arg(long, conflicts_with = "notes")]
+        clear_notes: bool,
+        /// Change the title and notes in $VISUAL or $EDITOR (the default when no
+        /// other option is given and stdin is a terminal)
+        #[arg(long, conflicts_with_all = ["title", "notes", "clear_notes"])]
+        editor: bool,
+    },
+    /// Set, move or clear due dates of several todos at once
+    Due {
+        /// IDs of the todos (ranges like 2-5, or text from their titles), then
+        /// the date: YYYY-MM-DD, tomorrow, a weekday, "in 3 days", jun 15, ...,
+        /// an offset from the current due date like +2d or -1w, or none
+        #[arg(required = true, value_name = "ID... DATE")]
+        args: Vec<String>,
+        /// Every open todo with this tag as well (repeatable; all must match)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Every open todo matching a condition as well: "tag = conf",
+        /// "project = launch" or "priority = high" (repeatable; all must match)
+        #[arg(long = "where", value_name = "CONDITION")]
+        conditions: Vec<Condition>,
+        /// Only change todos without a due date
+        #[arg(long)]
+        if_none: bool,
+        /// Change nothing unless every ID is found
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Hide a todo from listings until a date or for a while
+    #[command(group(ArgGroup::new("when").required(true).args(["until", "lasts"])))]
+    Snooze {
+        /// The ID of the todo item to snooze, or text from its title
+        id: String,
+        /// The day it comes back (YYYY-MM-DD, monday, next week, jun 15, ...)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        until: Option<NaiveDate>,
+        /// How long to hide it (e.g. 12h, 3d, 2w)
+        #[arg(long = "for", value_name = "DURATION", value_parser = parse_duration)]
+        lasts: Option<Duration>,
+    },
+    /// Bring a snoozed todo back into listings
+    Unsnooze {
+        /// The ID of the todo item to bring back, or text from its title
+        id: String,
+    },
+    /// Keep a todo at the top of listings until it is completed
+    Pin {
+        /// The ID of the todo item to pin, or text from its title
+        id: String,
+    },
+    /// Let a pinned todo sort with the rest again
+    Unpin {
+        /// The ID of the todo item to unpin, or text from its title
+        id: String,
+    },
+    /// Reserve the next ID for a todo to fill in later (hidden until published)
+    Reserve {
+        /// A placeholder title
+        #[arg(long)]
+        title: Option<String>,
+        /// The priority (default from TT_DEFAULT_PRIORITY or the config, else low)
+        #[arg(short, long, value_name = "PRIORITY", ignore_case = true)]
+        priority: Option<Priority>,
+        /// Drop the draft if it isn't published within this long (e.g. 1d, 12h)
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+        expire: Option<Duration>,
+    },
+    /// Turn a reserved draft into a normal pending todo
+    Publish {
+        /// The ID printed by `tt reserve`
+        id: String,
+        /// The title (required unless the draft was reserved with one)
+        #[arg(long)]
+        title: Option<String>,
+        /// The priority
+        #[arg(short, long, value_name = "PRIORITY", ignore_case = true)]
+        priority: Option<Priority>,
+    },
+    /// Move a todo up or down the list (shown as stored unless sorted)
+    #[command(group(ArgGroup::new("target").required(true).args(["position", "top", "bottom"])))]
+    Move {
+        /// The ID of the todo item to move, or text from its title
+        id: String,
+        /// Where to put it, 1 being the top; past the end means the bottom
+        position: Option<usize>,
+        /// Move it to the top
+        #[arg(long)]
+        top: bool,
+        /// Move it to the bottom
+        #[arg(long)]
+        bottom: bool,
+    },
+    /// Show every detail of one todo, including its notes
+    Show {
+        /// The ID of the todo item to show, or text from its title
+        id: String,
     },
     /// List all todo items
-    List,
-    /// Mark a todo item as completed
+    List {
+        /// Only show todos with this tag (repeatable; all must match)
+        #[arg(short, long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Sort the list (IDs stay the same; default from TT_SORT or the config)
+        #[arg(short, long, value_enum)]
+        sort: Option<SortKey>,
+        /// Reverse the order
+        #[arg(short, long)]
+        reverse: bool,
+        /// Only show completed todos
+        #[arg(long, conflicts_with = "incomplete")]
+        completed: bool,
+        /// Only show incomplete todos
+        #[arg(long, conflicts_with = "all")]
+        incomplete: bool,
+        /// Show snoozed todos, and completed ones despite TT_HIDE_COMPLETED or hide_completed
+        #[arg(long, conflicts_with = "completed")]
+        all: bool,
+        /// Only show todos with this priority
+        #[arg(short, long, value_name = "PRIORITY", ignore_case = true)]
+        priority: Option<Priority>,
+        /// Only show todos in this project
+        #[arg(long, value_name = "NAME")]
+        project: Option<String>,
+        /// Show the todos under a heading per project or priority
+        #[arg(long, value_enum, value_name = "FIELD", conflicts_with_all = ["archived", "drafts"])]
+        group_by: Option<GroupBy>,
+        /// Show archived todos instead of active ones
+        #[arg(long, conflicts_with = "drafts")]
+        archived: bool,
+        /// Show drafts reserved with `tt reserve` instead of active todos
+        #[arg(long)]
+        drafts: bool,
+        /// Show when each todo was done, last changed or added (e.g. "done 2 days ago")
+        #[arg(long)]
+        show_dates: bool,
+        /// Show when each todo was created: how long ago, or with --dates=full the
+        /// exact timestamp
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "relative"
+        )]
+        dates: Option<DateFormat>,
+    },
+    /// List tags with how many todos carry each (nested tags included)
+    #[command(args_conflicts_with_subcommands = true)]
+    Tags {
+        /// Show nested tags (e.g. work/clienta/billing) as a tree
+        #[arg(long)]
+        tree: bool,
+        #[command(subcommand)]
+        action: Option<TagsAction>,
+    },
+    /// List projects with how many of their todos are open
+    #[command(args_conflicts_with_subcommands = true)]
+    Projects {
+        #[command(subcommand)]
+        action: Option<ProjectsAction>,
+    },
+    /// Save sets of todos you add again and again, and add them in one go
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Show what changed since you last looked
+    Digest,
+    /// Move all completed todos into the archive
+    Archive,
+    /// Delete all completed todos (asks first unless --yes)
+    #[command(visible_alias = "clean")]
+    ClearCompleted {
+        /// Only those completed at least this long ago (days, e.g. 2w, or a date)
+        #[arg(long, value_name = "DAYS", value_parser = parse_age)]
+        older_than: Option<Duration>,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show, restore or empty the trash that `tt delete` moves todos to
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+    /// Move an archived todo back into the active list
+    Unarchive {
+        /// The ID of the archived todo
+        id: String,
+    },
+    /// Revert the most recent command that changed the todo list
+    Undo,
+    /// Export todos as CSV, Markdown or JSON
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Write to this file instead of standard output
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Import todos from a JSON, CSV or todo.txt file
+    Import {
+        /// The file to read
+        file: PathBuf,
+        /// Input format (guessed from the extension by default)
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+        /// Import todos even if an incomplete todo has the same title
+        #[arg(long)]
+        allow_duplicates: bool,
+    },
+    /// Edit many pending todos at once as a CSV sheet in any spreadsheet
+    #[command(group(ArgGroup::new("sheet").required(true).args(["export", "apply"])))]
+    Groom {
+        /// Write the pending todos to this CSV file to edit
+        #[arg(long, value_name = "FILE")]
+        export: Option<PathBuf>,
+        /// Apply an edited sheet by ID (rows without an ID add todos)
+        #[arg(long, value_name = "FILE")]
+        apply: Option<PathBuf>,
+        /// Show what --apply would change without changing anything
+        #[arg(long, requires = "apply")]
+        dry_run: bool,
+    },
+    /// Name or describe the current todo list
+    Store {
+        #[command(subcommand)]
+        action: StoreAction,
+    },
+    /// Show settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Show only a few todos until they are done
+    Focus {
+        #[command(subcommand)]
+        action: FocusAction,
+    },
+    /// Browse and change todos interactively (plain list when not in a terminal)
+    Ui,
+    /// Show which todo file is in use, with its name and description
+    Which,
+    /// Show the named lists with their open todo counts
+    #[command(args_conflicts_with_subcommands = true)]
+    Lists {
+        #[command(subcommand)]
+        action: Option<ListsAction>,
+    },
+    /// Merge another copy of the todo file, e.g. from another machine, into this one
+    Sync {
+        /// The other copy's todo file
+        path: PathBuf,
+        /// The copy both started from (e.g. from `git merge-base`), so
+        /// edits to different fields of a todo are both kept
+        #[arg(long, value_name = "PATH")]
+        base: Option<PathBuf>,
+    },
+    /// Deal with conflicting copies of the todo file left by Syncthing
+    Conflicts {
+        #[command(subcommand)]
+        action: ConflictsAction,
+    },
+    /// Move your todos, archive and config to another machine
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+    /// Search todo titles (case-insensitive substring by default)
+    Search {
+        /// The text (or regular expression with --regex) to look for
+        query: String,
+        /// Treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+        /// Only show completed todos
+        #[arg(long, conflicts_with = "incomplete")]
+        completed: bool,
+        /// Only show incomplete todos
+        #[arg(long)]
+        incomplete: bool,
+    },
+    /// Mark todo items as completed
     Complete {
-        /// The ID of the todo item to mark as completed
-        id: usize,
+        /// IDs of the todo items to mark as completed, ranges like 2-5, or text from their titles
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Change nothing unless every ID is found
+        #[arg(long)]
+        strict: bool,
     },
-    /// Mark a todo item as incomplete
+    /// Mark todo items as incomplete
     Incomplete {
-        /// The ID of the todo item to mark as incomplete
-        id: usize,
+        /// IDs of the todo items to mark as incomplete, ranges like 2-5, or text from their titles
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Change nothing unless every ID is found
+        #[arg(long)]
+        strict: bool,
     },
-    /// Toggle a todo item's completed status
+    /// Toggle todo items' completed status
     Toggle {
-        /// The ID of the todo item to toggle
-        id: usize,
+        /// IDs of the todo items to toggle, ranges like 2-5, or text from their titles
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Change nothing unless every ID is found
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Move todo items to the trash (asks first unless --yes)
+    Delete {
+        /// IDs of the todo items to delete, ranges like 2-5, or text from their titles
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// Delete nothing unless every ID is found
+        #[arg(long)]
+        strict: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Delete for good instead of moving to the trash
+        #[arg(long)]
+        permanent: bool,
+    },
+    /// Check the store against thresholds, exiting 1 if any fail; or, with a
+    /// subcommand, change a todo's checklist
+    #[command(args_conflicts_with_subcommands = true)]
+    Check {
+        #[command(subcommand)]
+        action: Option<ChecklistAction>,
+        /// Fail when more than this many todos are pending
+        #[arg(long, value_name = "COUNT")]
+        max_pending: Option<usize>,
+        /// Fail when the oldest pending todo is older than this (e.g. 30d, 2w, 12h)
+        #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+        max_age: Option<Duration>,
+        /// Fail when more than this many pending todos are past their due date
+        #[arg(long, value_name = "COUNT")]
+        max_overdue: Option<usize>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Print a shell completion script, e.g. `tt completions zsh > _tt`
+    Completions {
+        /// The shell to complete in
+        shell: Shell,
+    },
+    /// Print `id<TAB>title` for each incomplete todo, for completion scripts
+    #[command(name = "__complete-ids", hide = true)]
+    CompleteIds,
+    /// Print how many todos are pending as a bare number, e.g. for a status bar
+    Count {
+        /// Count completed todos instead
+        #[arg(long, conflicts_with_all = ["incomplete", "all"])]
+        completed: bool,
+        /// Count pending todos (the default)
+        #[arg(long)]
+        incomplete: bool,
+        /// Count every todo
+        #[arg(long, conflicts_with = "incomplete")]
+        all: bool,
+        /// Only count todos with this priority
+        #[arg(short, long, value_name = "PRIORITY", ignore_case = true)]
+        priority: Option<Priority>,
+    },
+    /// Print counts in Prometheus text format (e.g. for a textfile collector)
+    Metrics,
+    /// Show the pending todos most likely being neglected
+    Attention {
+        /// How many todos to show
+        #[arg(short = 'n', long, default_value_t = 5)]
+        limit: usize,
+    },
+    /// Strictly validate a todo file, exiting 1 if it has problems
+    Validate {
+        /// The file to validate (defaults to the active todo file)
+        file: Option<PathBuf>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Report todos whose titles break the configured title conventions,
+    /// exiting 1 if any do
+    Lint {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Prune old history from the files kept next to the todo file and check
+    /// backups still read (also runs on its own once a week)
+    Maintenance {
+        /// Report what would be pruned without removing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Repair out-of-range priorities and missing timestamps, backing up first
+    Normalize {
+        /// Report what would change without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Replace one priority with another, by name or saved number (repeatable,
+        /// e.g. low=medium or 0=1)
+        #[arg(long, value_name = "FROM=TO")]
+        map_priority: Vec<String>,
+        /// Creation date for todos without one that the journal doesn't know
+        #[arg(long, value_name = "DATE")]
+        created_at: Option<String>,
+    },
+    /// Show the journal of commands tt has run
+    History {
+        /// Only show entries whose command, titles, directory or host contain this text
+        #[arg(long, value_name = "TEXT")]
+        grep: Option<String>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BundleAction {
+    /// Write the store, archive and config into a single portable file
+    Export {
+        /// Where to write the bundle (e.g. bundle.ttpack)
+        path: PathBuf,
+    },
+    /// Restore a bundle written by `tt bundle export`
+    Import {
+        /// The bundle to restore
+        path: PathBuf,
+        /// Add todos missing here, matching by stable ID (the default)
+        #[arg(long, conflicts_with = "replace")]
+        merge: bool,
+        /// Overwrite the store, archive and config with the bundle's
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConflictsAction {
+    /// Merge every .sync-conflict copy into the todo file, then rename it to *.merged
+    MergeFiles,
+}
+
+#[derive(Subcommand)]
+pub enum StoreAction {
+    /// Set the name shown in list headings ("" removes it)
+    SetName {
+        /// e.g. "Work backlog"
+        name: String,
+    },
+    /// Set a one-line description shown under the heading ("" removes it)
+    SetDescription {
+        /// The description
+        description: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagsAction {
+    /// Rename a tag along with every tag nested beneath it
+    Rename {
+        /// The tag to rename (e.g. work/clienta)
+        from: String,
+        /// Its new name (e.g. work/acme)
+        to: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProjectsAction {
+    /// Move every todo in a project, archived ones included, to another
+    Rename {
+        /// The project to rename
+        from: String,
+        /// Its new name
+        to: String,
     },
-    /// Delete a todo item
+}
+
+#[derive(Subcommand)]
+pub enum TrashAction {
+    /// Show deleted todos with when each was deleted
+    List,
+    /// Put a deleted todo back, exactly as it was
+    Restore {
+        /// The ID the todo had
+        id: String,
+    },
+    /// Delete the todos in the trash for good (asks first unless --yes)
+    Empty {
+        /// Only those deleted at least this long ago (days, e.g. 2w, or a date)
+        #[arg(long, value_name = "DAYS", value_parser = parse_age)]
+        older_than: Option<Duration>,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateAction {
+    /// Save the titles, priorities, tags and projects of some todos as a template
+    #[command(group(ArgGroup::new("source").required(true).args(["from_ids", "from_completed"])))]
+    Save {
+        /// The template's name
+        name: String,
+        /// The todos to save, comma-separated (ranges like 2-5, or text from their titles)
+        #[arg(long, value_name = "IDS", value_delimiter = ',', num_args = 1..)]
+        from_ids: Vec<String>,
+        /// Save every completed todo
+        #[arg(long)]
+        from_completed: bool,
+    },
+    /// Show the saved templates
+    List,
+    /// Delete a template (the todos added from it are kept)
     Delete {
-        /// The ID of the todo item to delete
-        id: usize,
+        /// The template's name
+        name: String,
+    },
+    /// Add a new incomplete todo for each todo in a template
+    Apply {
+        /// The template's name
+        name: String,
+        /// Put this in front of every title (e.g. "Sprint 42: ")
+        #[arg(long, value_name = "TEXT")]
+        prefix: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ChecklistAction {
+    /// Add an item to a todo's checklist
+    Add {
+        /// The ID of the todo, or text from its title
+        id: String,
+        /// What the item says
+        text: String,
+    },
+    /// Tick or untick a checklist item
+    Toggle {
+        /// The ID of the todo, or text from its title
+        id: String,
+        /// The item's number, as shown by `tt show`
+        number: usize,
+    },
+    /// Remove a checklist item
+    Rm {
+        /// The ID of the todo, or text from its title
+        id: String,
+        /// The item's number, as shown by `tt show`
+        number: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Show the supported environment variables with their effective values
+    Env,
+}
+
+#[derive(Subcommand)]
+pub enum FocusAction {
+    /// Hide everything but these todos until they are completed
+    Start {
+        /// IDs of the todos to focus on, ranges like 2-5, or text from their titles
+        #[arg(required = true)]
+        ids: Vec<String>,
+        /// How long the session lasts (e.g. 90m, 2h; default from config, else 4h)
+        #[arg(long = "for", value_name = "DURATION", value_parser = parse_duration)]
+        lasts: Option<Duration>,
     },
+    /// End the focus session and show everything again
+    Stop,
+}
+
+#[derive(Subcommand)]
+pub enum ListsAction {
+    /// Delete a list with its archive and history (asks first unless --force)
+    Remove {
+        /// The list to delete
+        name: String,
+    },
+    /// Rename a list
+    Rename {
+        /// The list's current name
+        from: String,
+        /// Its new name
+        to: String,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DueUpdate {
+    Set(NaiveDate),
+    Clear,
+}
+
+fn parse_date(input: &str) -> Result<NaiveDate, String> {
+    dates::parse_day(input, Local::now().date_naive())
+}
+
+fn parse_age(input: &str) -> Result<Duration, String> {
+    dates::parse_age(input, Local::now())
+}
+
+fn parse_due_update(input: &str) -> Result<DueUpdate, String> {
+    if input.trim().eq_ignore_ascii_case("none") {
+        Ok(DueUpdate::Clear)
+    } else {
+        parse_date(input).map(DueUpdate::Set)
+    }
+}
+
+/// Clap reads the date of `tt due 3 -1w` as a flag. The date is the last
+/// value `tt due` takes, so move a backward offset behind `--`, where clap
+/// reads it as a value, leaving any flags typed after it to be parsed.
+/// Letting the values start with a hyphen instead would swallow those flags.
+pub fn protect_due_offset(mut args: Vec<String>) -> Vec<String> {
+    let Some(due) = args.iter().position(|arg| arg == "due") else {
+        return args;
+    };
+    if args[due..].iter().any(|arg| arg == "--") {
+        return args;
+    }
+    if let Some(at) = args[due + 1..]
+        .iter()
+        .rposition(|arg| due::is_backward_offset(arg))
+    {
+        let offset = args.remove(due + 1 + at);
+        args.extend(["--".to_string(), offset]);
+    }
+    args
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    /// This format, unless the global `--json` flag asks for JSON.
+    fn or_json(self, output: &Output) -> Self {
+        if output.json {
+            OutputFormat::Json
+        } else {
+            self
+        }
+    }
+}
+
+/// Returned by commands like `tt check` that have already reported why
+/// they failed, so all that is left is to exit with status 1.
+#[derive(Debug)]
+pub struct Failed;
+
+impl fmt::Display for Failed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "command failed")
+    }
 }
 
-pub fn run_cli(cli: Cli, todo_manager: &mut TodoManager) -> Result<()> {
+impl std::error::Error for Failed {}
+
+impl Commands {
+    /// Name of a command that modifies the store, as reported by `tt undo`.
+    /// Undo itself is not listed: restoring a snapshot must stay possible
+    /// even when the current file is broken.
+    fn operation(&self) -> Option<&'static str> {
+        match self {
+            Commands::Add { .. } => Some("add"),
+            Commands::Edit { .. } => Some("edit"),
+            Commands::Move { .. } => Some("move"),
+            Commands::Reserve { .. } => Some("reserve"),
+            Commands::Publish { .. } => Some("publish"),
+            Commands::Snooze { .. } => Some("snooze"),
+            Commands::Due { .. } => Some("due"),
+            Commands::Unsnooze { .. } => Some("unsnooze"),
+            Commands::Pin { .. } => Some("pin"),
+            Commands::Unpin { .. } => Some("unpin"),
+            Commands::Complete { .. } => Some("complete"),
+            Commands::Incomplete { .. } => Some("incomplete"),
+            Commands::Toggle { .. } => Some("toggle"),
+            Commands::Delete { .. } => Some("delete"),
+            Commands::Archive => Some("archive"),
+            Commands::ClearCompleted { .. } => Some("clear-completed"),
+            Commands::Unarchive { .. } => Some("unarchive"),
+            Commands::Trash {
+                action: TrashAction::Restore { .. },
+            } => Some("trash restore"),
+            Commands::Trash {
+                action: TrashAction::Empty { .. },
+            } => Some("trash empty"),
+            Commands::Import { .. } => Some("import"),
+            Commands::Groom {
+                apply: Some(_),
+                dry_run: false,
+                ..
+            } => Some("groom"),
+            Commands::Normalize { dry_run: false, .. } => Some("normalize"),
+            Commands::Check {
+                action: Some(ChecklistAction::Add { .. }),
+                ..
+            } => Some("check add"),
+            Commands::Check {
+                action: Some(ChecklistAction::Toggle { .. }),
+                ..
+            } => Some("check toggle"),
+            Commands::Check {
+                action: Some(ChecklistAction::Rm { .. }),
+                ..
+            } => Some("check rm"),
+            Commands::Store {
+                action: StoreAction::SetName { .. },
+            } => Some("store set-name"),
+            Commands::Store {
+                action: StoreAction::SetDescription { .. },
+            } => Some("store set-description"),
+            Commands::Tags {
+                action: Some(TagsAction::Rename { .. }),
+                ..
+            } => Some("tags rename"),
+            Commands::Projects {
+                action: Some(ProjectsAction::Rename { .. }),
+            } => Some("projects rename"),
+            Commands::Template { action } => match action {
+                TemplateAction::Save { .. } => Some("template save"),
+                TemplateAction::Delete { .. } => Some("template delete"),
+                TemplateAction::Apply { .. } => Some("template apply"),
+                TemplateAction::List => None,
+            },
+            Commands::Bundle {
+                action: BundleAction::Import { .. },
+            } => Some("bundle import"),
+            Commands::Sync { .. } => Some("sync"),
+            Commands::Conflicts {
+                action: ConflictsAction::MergeFiles,
+            } => Some("conflicts merge-files"),
+            _ => None,
+        }
+    }
+
+    fn is_mutating(&self) -> bool {
+        self.operation().is_some()
+    }
+
+    /// Whether the store must be locked against other tt processes.
+    pub fn needs_lock(&self) -> bool {
+        self.is_mutating()
+            || matches!(
+                self,
+                Commands::Undo | Commands::Ui | Commands::Maintenance { .. }
+            )
+    }
+}
+
+pub fn run_cli(
+    cli: Cli,
+    todo_manager: &mut TodoManager,
+    config: &Config,
+    settings: &Settings,
+    output: &Output,
+) -> Result<()> {
+    if config.validate_external_edits
+        && cli.command.as_ref().is_some_and(Commands::is_mutating)
+        && todo_manager.modified_externally()
+    {
+        let problems = validate::validate_file(todo_manager.file_path())?;
+        if !problems.is_empty() {
+            esay!(
+                "❌ {} was edited outside tt and has problems:",
+                todo_manager.file_path().display()
+            );
+            eprint!("{}", output::plain(&format_problems(&problems)));
+            return Err(anyhow::anyhow!(
+                "Refusing to modify an invalid todo file; fix it and run `tt validate`"
+            ));
+        }
+    }
+
+    if !matches!(cli.command, Some(Commands::Conflicts { .. })) {
+        let found = conflicts::find(todo_manager.file_path());
+        if !found.is_empty() {
+            esay!(
+                "⚠️  Found {} Syncthing conflict file(s) next to {}; run `tt conflicts merge-files` to merge them",
+                found.len(),
+                todo_manager.file_path().display()
+            );
+        }
+    }
+
+    let conventions = Conventions::compile(&config.conventions, todo_manager.list_name())?;
+
+    if let Some(operation) = cli.command.as_ref().and_then(Commands::operation) {
+        todo_manager.begin_operation(operation);
+    }
+
     match cli.command {
         Some(command) => match command {
-            Commands::Add { title, priority } => {
-                TodoManager::validate_priority(priority)?;
-                let todo = todo_manager.add_todo(title, priority)?;
-                println!("✅ Added todo: {} (priority {})", todo.title, todo.priority);
+            Commands::Add {
+                title,
+                priority: _,
+                due,
+                tags,
+                project,
+                notes,
+                notes_from_stdin,
+                stdin,
+                strict,
+            } => {
+                // The resolved default already holds --priority when given
+                let priority = settings.default_priority.value;
+                let title = match title {
+                    Some(title) if title != "-" && !stdin => title,
+                    _ => {
+                        if due.is_some()
+                            || !tags.is_empty()
+                            || project.is_some()
+                            || notes.is_some()
+                            || notes_from_stdin
+                        {
+                            return Err(TtError::Invalid(
+                                "Titles from stdin cannot be combined with --due, --tag, --project or notes"
+                                    .to_string(),
+                            )
+                            .into());
+                        }
+                        let input = std::io::read_to_string(std::io::stdin().lock())
+                            .context("Failed to read titles from stdin")?;
+                        return add_batch(
+                            todo_manager,
+                            &batch::parse(&input, priority),
+                            &conventions,
+                            config.strict_conventions,
+                            strict,
+                            output,
+                        );
+                    }
+                };
+                if strict {
+                    return Err(TtError::Invalid(
+                        "--strict only applies to titles read from stdin".to_string(),
+                    )
+                    .into());
+                }
+                check_conventions(&conventions, &title, config.strict_conventions)?;
+                let notes = if notes_from_stdin {
+                    Some(notes::read(
+                        std::io::stdin().lock(),
+                        config.max_notes_bytes(),
+                    )?)
+                } else {
+                    notes
+                };
+                if let Some(notes) = &notes {
+                    notes::check_size(notes, config.max_notes_bytes())?;
+                }
+                let journal = todo_manager.journal_path();
+                let todo = todo_manager.add(NewTodo {
+                    title,
+                    priority,
+                    due_date: due,
+                    tags,
+                    notes,
+                    project,
+                })?;
+                journal::record(&journal, vec![todo.title.clone()]);
+                let mut details = vec![format!("priority {}", todo.priority)];
+                if let Some(due) = todo.due_date {
+                    details.push(format!("due {due}"));
+                }
+                if let Some(project) = &todo.project {
+                    details.push(format!("project {project}"));
+                }
+                if !todo.tags.is_empty() {
+                    details.push(format!("tags: {}", todo.tags.join(", ")));
+                }
+                let message = format!("✅ Added todo: {} ({})", todo.title, details.join(", "));
+                output.changed(todo, &message);
                 Ok(())
             }
             Commands::Edit {
                 id,
+                mut title,
+                priority,
+                due,
+                clear_due,
+                add_tags,
+                remove_tags,
+                project,
+                clear_project,
+                mut notes,
+                mut clear_notes,
+                editor,
+            } => {
+                let id = resolve_id(todo_manager, &id, config)?;
+                let bare = title.is_none()
+                    && priority.is_none()
+                    && due.is_none()
+                    && !clear_due
+                    && add_tags.is_empty()
+                    && remove_tags.is_empty()
+                    && project.is_none()
+                    && !clear_project
+                    && notes.is_none()
+                    && !clear_notes;
+                if editor || (bare && std::io::stdin().is_terminal()) {
+                    let todo = todo_manager
+                        .get_todo(id)
+                        .ok_or_else(|| TtError::not_found(id))?;
+                    let fields = editor::Fields {
+                        title: todo.title.clone(),
+                        notes: todo.notes.clone(),
+                    };
+                    match editor::edit(&fields, editor::launch)? {
+                        Outcome::Changed(edited) => {
+                            if edited.title != fields.title {
+                                title = Some(edited.title);
+                            }
+                            if edited.notes != fields.notes {
+                                clear_notes = edited.notes.is_none();
+                                notes = edited.notes;
+                            }
+                        }
+                        Outcome::Unchanged if bare => {
+                            say!("Nothing changed; edit cancelled");
+                            return Ok(());
+                        }
+                        Outcome::Emptied => {
+                            say!("The file was empty; edit cancelled");
+                            return Ok(());
+                        }
+                        Outcome::Unchanged => {}
+                    }
+                }
+                if let Some(title) = &title {
+                    check_conventions(&conventions, title, config.strict_conventions)?;
+                }
+                let due_date = match (due, clear_due) {
+                    (_, true) | (Some(DueUpdate::Clear), _) => Some(None),
+                    (Some(DueUpdate::Set(date)), _) => Some(Some(date)),
+                    (None, false) => None,
+                };
+                if let Some(notes) = &notes {
+                    notes::check_size(notes, config.max_notes_bytes())?;
+                }
+                let notes = if clear_notes {
+                    Some(None)
+                } else {
+                    notes.map(Some)
+                };
+                let project = if clear_project {
+                    Some(None)
+                } else {
+                    project.map(Some)
+                };
+                let edit = TodoEdit {
+                    title,
+                    priority,
+                    due_date,
+                    notes,
+                    add_tags,
+                    remove_tags,
+                    project,
+                };
+                todo_manager.edit_todo(id, edit)?;
+                record_history(todo_manager, id);
+                if let Some(todo) = todo_manager.get_todo(id) {
+                    let message = format!(
+                        "✏️  Todo {} updated successfully",
+                        shown_id(todo_manager, id, config)
+                    );
+                    output.changed(todo, &message);
+                }
+                Ok(())
+            }
+            Commands::Reserve {
                 title,
                 priority,
+                expire,
             } => {
-                if let Some(p) = priority {
-                    TodoManager::validate_priority(p)?;
+                let priority = priority.unwrap_or(settings.default_priority.value);
+                let now = Utc::now();
+                let todo = todo_manager.reserve(
+                    title,
+                    priority,
+                    expire.map(|expire| now + expire),
+                    now,
+                )?;
+                journal::record(&todo_manager.journal_path(), vec![todo.title.clone()]);
+                let id = ids::display_id(&todo, config.id_style);
+                let message = format!(
+                    "🔖 Reserved todo {id} (publish it with `tt publish {id} --title ...`)"
+                );
+                output.changed(&todo, &message);
+                Ok(())
+            }
+            Commands::Publish {
+                id,
+                title,
+                priority,
+            } => {
+                if let Some(title) = &title {
+                    check_conventions(&conventions, title, config.strict_conventions)?;
                 }
-                todo_manager.edit_todo(id, title, priority)?;
-                println!("✏️  Todo {id} updated successfully");
+                let now = Utc::now();
+                let id = ids::resolve(&todo_manager.drafts(now), &id, config.id_style)?;
+                let todo = todo_manager.publish(id, title, priority, now)?;
+                journal::record(&todo_manager.journal_path(), vec![todo.title.clone()]);
+                let message = format!(
+                    "✅ Published todo {}: {}",
+                    ids::display_id(&todo, config.id_style),
+                    todo.title
+                );
+                output.changed(&todo, &message);
                 Ok(())
             }
-            Commands::List => {
-                display_todos(todo_manager);
+            Commands::Due {
+                mut args,
+                tags,
+                conditions,
+                if_none,
+                strict,
+            } => {
+                let date = args.pop().unwrap_or_default();
+                let ids = args;
+                if ids.is_empty() && tags.is_empty() && conditions.is_empty() {
+                    bail!("Give the IDs of the todos to change, or --tag or --where");
+                }
+                let today = Local::now().date_naive();
+                let change = DueChange::parse(&date, today).map_err(anyhow::Error::msg)?;
+                let mut selected = if ids.is_empty() {
+                    Vec::new()
+                } else {
+                    select(todo_manager, &ids, strict, config)?.ids
+                };
+                if !tags.is_empty() || !conditions.is_empty() {
+                    let matching = todo_manager
+                        .query(&QueryOptions {
+                            tags,
+                            completed: Some(false),
+                            ..QueryOptions::default()
+                        })
+                        .into_iter()
+                        .filter(|todo| conditions.iter().all(|c| c.matches(todo)));
+                    for todo in matching {
+                        if !selected.contains(&todo.id) {
+                            selected.push(todo.id);
+                        }
+                    }
+                }
+                let todos: Vec<Todo> = selected
+                    .iter()
+                    .filter_map(|&id| todo_manager.get_todo(id).cloned())
+                    .collect();
+                let updates = due::plan(&todos, change, if_none, today);
+                if !updates.is_empty() {
+                    todo_manager.set_due_dates(&updates)?;
+                }
+                let ids: Vec<usize> = updates.iter().map(|update| update.id).collect();
+                let changed = record_changes(todo_manager, &ids);
+                if output.json {
+                    output.changed_many(&changed, "");
+                    return Ok(());
+                }
+                let show =
+                    |date: Option<NaiveDate>| date.map_or("none".to_string(), |d| d.to_string());
+                for (update, todo) in updates.iter().zip(&changed) {
+                    say!(
+                        "📅 {} {}: {} → {}",
+                        ids::display_id(todo, config.id_style),
+                        todo.title,
+                        show(update.from),
+                        show(update.to)
+                    );
+                }
+                let unchanged = todos.len() - updates.len();
+                match (updates.len(), unchanged) {
+                    (0, _) => say!("📅 No due dates changed"),
+                    (_, 0) => {}
+                    (_, unchanged) if if_none => {
+                        say!("   {unchanged} already had a due date or kept it")
+                    }
+                    (_, unchanged) => say!("   {unchanged} already had that due date"),
+                }
                 Ok(())
             }
-            Commands::Complete { id } => {
-                todo_manager.mark_completed(id)?;
+            Commands::Snooze { id, until, lasts } => {
+                let now = Utc::now();
+                let until = match (until, lasts) {
+                    (Some(date), _) => start_of_day(date)?,
+                    (None, Some(lasts)) => now + lasts,
+                    (None, None) => now,
+                };
+                if until <= now {
+                    return Err(TtError::Invalid(
+                        "Snoozing needs a time in the future".to_string(),
+                    )
+                    .into());
+                }
+                let id = resolve_id(todo_manager, &id, config)?;
+                todo_manager.snooze_todo(id, until)?;
+                record_history(todo_manager, id);
                 if let Some(todo) = todo_manager.get_todo(id) {
-                    println!("✅ Marked as completed: {}", todo.title);
+                    let message = format!(
+                        "💤 Snoozed todo {} until {} (`tt list --all` shows it)",
+                        shown_id(todo_manager, id, config),
+                        render::snooze_label(until)
+                    );
+                    output.changed(todo, &message);
                 }
                 Ok(())
             }
-            Commands::Incomplete { id } => {
-                todo_manager.mark_incomplete(id)?;
+            Commands::Unsnooze { id } => {
+                let id = resolve_id(todo_manager, &id, config)?;
+                let shown = shown_id(todo_manager, id, config);
+                let message = if todo_manager.unsnooze_todo(id)? {
+                    record_history(todo_manager, id);
+                    format!("⏰ Todo {shown} is back in the list")
+                } else {
+                    format!("⏰ Todo {shown} isn't snoozed")
+                };
                 if let Some(todo) = todo_manager.get_todo(id) {
-                    println!("⏳ Marked as incomplete: {}", todo.title);
+                    output.changed(todo, &message);
                 }
                 Ok(())
             }
-            Commands::Toggle { id } => {
-                todo_manager.toggle_completed(id)?;
+            Commands::Pin { id } => set_pinned(todo_manager, &id, true, config, output),
+            Commands::Unpin { id } => set_pinned(todo_manager, &id, false, config, output),
+            Commands::Move {
+                id,
+                position,
+                top,
+                bottom: _,
+            } => {
+                let position = match position {
+                    Some(position) => position,
+                    None if top => 1,
+                    None => usize::MAX,
+                };
+                let id = resolve_id(todo_manager, &id, config)?;
+                let (from, to) = todo_manager.move_todo(id, position)?;
+                let shown = shown_id(todo_manager, id, config);
+                let message = if from == to {
+                    format!("↕️  Todo {shown} is already at position {to}")
+                } else {
+                    record_history(todo_manager, id);
+                    format!("↕️  Moved todo {shown} from position {from} to {to}")
+                };
                 if let Some(todo) = todo_manager.get_todo(id) {
-                    let status = if todo.completed {
-                        "✅ completed"
+                    output.changed(todo, &message);
+                }
+                Ok(())
+            }
+            Commands::Show { id } => {
+                let id = resolve_id(todo_manager, &id, config)?;
+                let todo = todo_manager
+                    .get_todo(id)
+                    .ok_or_else(|| TtError::not_found(id))?;
+                output.detail(todo);
+                Ok(())
+            }
+            Commands::List {
+                tags,
+                sort: _,
+                reverse,
+                completed,
+                incomplete: _,
+                all,
+                priority,
+                project,
+                group_by,
+                archived,
+                drafts,
+                show_dates,
+                dates,
+            } => {
+                let output = &Output {
+                    show_dates,
+                    dates,
+                    ..output.clone()
+                };
+                // --sort, --incomplete and --all are folded into the settings
+                let options = QueryOptions {
+                    tags,
+                    completed: match completed {
+                        true => Some(true),
+                        // Everything archived is completed, so hiding would hide it all
+                        false if settings.hide_completed.value && !archived => Some(false),
+                        false => None,
+                    },
+                    priority,
+                    project,
+                    sort: settings.sort.value,
+                    reverse,
+                    hide_snoozed_at: (!all).then(Utc::now),
+                };
+                if archived {
+                    output.archived(&options.apply(&todo_manager.load_archive()?));
+                } else if drafts {
+                    output.drafts(&options.apply(&todo_manager.drafts(Utc::now())));
+                } else {
+                    let todos = todo_manager.query(&options);
+                    match settle_focus(todo_manager, Utc::now()) {
+                        Some(focus) => output.focused(&focus.filter(todos)),
+                        None => {
+                            let counts = todo_manager.counts_matching(&options);
+                            match group_by {
+                                Some(by) => output.grouped(
+                                    &groups::group(&todos, by),
+                                    todo_manager.meta(),
+                                    counts,
+                                ),
+                                None => output.list(&todos, todo_manager.meta(), counts),
+                            }
+                            note_snoozed(todo_manager, &options, todos.len(), output);
+                        }
+                    }
+                    todo_manager.mark_viewed(Utc::now());
+                }
+                Ok(())
+            }
+            Commands::Tags { tree, action } => match action {
+                Some(TagsAction::Rename { from, to }) => {
+                    let before = every_todo(todo_manager);
+                    match todo_manager.rename_tag(&from, &to)? {
+                        0 => say!("🏷️  No todos are tagged '{from}'"),
+                        count => {
+                            journal::record(
+                                &todo_manager.journal_path(),
+                                journal::changed_titles(&before, &every_todo(todo_manager)),
+                            );
+                            say!("🏷️  Renamed '{from}' to '{to}' on {count} todo(s)");
+                        }
+                    }
+                    Ok(())
+                }
+                None => {
+                    let todos = todo_manager.list_todos();
+                    if todos.iter().all(|todo| todo.tags.is_empty()) {
+                        say!("🏷️  No tags yet");
+                    } else if tree {
+                        say!("🏷️  Tags:");
+                        for node in tags::tree(todos) {
+                            let indent = "  ".repeat(node.depth + 1);
+                            say!("{indent}{} ({})", node.name, node.count);
+                        }
+                    } else {
+                        say!("🏷️  Tags:");
+                        for (tag, count) in tags::counts(todos) {
+                            say!("  {tag} ({count})");
+                        }
+                    }
+                    Ok(())
+                }
+            },
+            Commands::Projects { action } => match action {
+                Some(ProjectsAction::Rename { from, to }) => {
+                    let before = every_todo(todo_manager);
+                    match todo_manager.rename_project(&from, &to)? {
+                        0 => say!("📁 No todos are in project '{}'", from.trim()),
+                        count => {
+                            journal::record(
+                                &todo_manager.journal_path(),
+                                journal::changed_titles(&before, &every_todo(todo_manager)),
+                            );
+                            say!(
+                                "📁 Moved {count} todo(s) from '{}' to '{}'",
+                                from.trim(),
+                                to.trim()
+                            );
+                        }
+                    }
+                    Ok(())
+                }
+                None => {
+                    let projects = todo_manager.projects();
+                    if projects.is_empty() {
+                        say!("📁 No projects yet");
+                    } else {
+                        say!("📁 Projects:");
+                        for (name, counts) in projects {
+                            say!(
+                                "  {name} ({} open / {} total)",
+                                counts.pending(),
+                                counts.total
+                            );
+                        }
+                    }
+                    Ok(())
+                }
+            },
+            Commands::Template { action } => match action {
+                TemplateAction::Save {
+                    name,
+                    from_ids,
+                    from_completed,
+                } => {
+                    let ids = if from_completed {
+                        todo_manager
+                            .list_todos()
+                            .iter()
+                            .filter(|todo| todo.completed)
+                            .map(|todo| todo.id)
+                            .collect()
+                    } else {
+                        select(todo_manager, &from_ids, true, config)?.ids
+                    };
+                    let journal = todo_manager.journal_path();
+                    let template = todo_manager.save_template(&name, &ids)?;
+                    journal::record(&journal, template_titles(template));
+                    say!(
+                        "📋 Saved template '{}' with {} todo(s)",
+                        template.name,
+                        template.todos.len()
+                    );
+                    Ok(())
+                }
+                TemplateAction::List => {
+                    output.templates(todo_manager.templates());
+                    Ok(())
+                }
+                TemplateAction::Delete { name } => {
+                    let template = todo_manager.delete_template(&name)?;
+                    journal::record(&todo_manager.journal_path(), template_titles(&template));
+                    say!("🗑️  Deleted template '{}'", template.name);
+                    Ok(())
+                }
+                TemplateAction::Apply { name, prefix } => {
+                    let added =
+                        todo_manager.apply_template(&name, prefix.as_deref().unwrap_or(""))?;
+                    journal::record(
+                        &todo_manager.journal_path(),
+                        added.iter().map(|todo| todo.title.clone()).collect(),
+                    );
+                    let summary = format!(
+                        "✅ Created {} todo(s) from template '{}'",
+                        added.len(),
+                        name.trim()
+                    );
+                    output.changed_many(&added, &summary);
+                    Ok(())
+                }
+            },
+            Commands::Digest => {
+                let now = Utc::now();
+                let digest =
+                    digest::build(todo_manager.list_todos(), todo_manager.last_viewed(), now);
+                let since = digest.since.with_timezone(&Local).format("%a %d %b %H:%M");
+                if digest.first_run {
+                    say!(
+                        "📰 Your first digest, covering the last {} days",
+                        digest::FIRST_DIGEST_WINDOW_DAYS
+                    );
+                } else {
+                    say!("📰 Since you last looked ({since}):");
+                }
+                if digest.is_empty() {
+                    say!("  ✨ Nothing new");
+                }
+                for (header, todos) in [
+                    ("🆕 Added", &digest.added),
+                    ("🔥 Newly overdue", &digest.newly_overdue),
+                    ("⏰ Due in the next 3 days", &digest.due_soon),
+                ] {
+                    if !todos.is_empty() {
+                        say!("\n{}", header.bold());
+                        output.print_todo_lines(todos);
+                    }
+                }
+                todo_manager.mark_viewed(now);
+                Ok(())
+            }
+            Commands::Archive => {
+                let titles: Vec<String> = todo_manager
+                    .list_todos()
+                    .iter()
+                    .filter(|todo| todo.completed)
+                    .map(|todo| todo.title.clone())
+                    .collect();
+                match todo_manager.archive_completed()? {
+                    0 => say!("🗄️  No completed todos to archive"),
+                    count => {
+                        journal::record(&todo_manager.journal_path(), titles);
+                        say!(
+                            "🗄️  Archived {count} completed todo(s) to {}",
+                            todo_manager.archive_path().display()
+                        );
+                    }
+                }
+                Ok(())
+            }
+            Commands::ClearCompleted { older_than, yes } => {
+                let now = Utc::now();
+                let titles: Vec<String> = todo_manager
+                    .list_todos()
+                    .iter()
+                    .filter(|todo| todo.is_clearable(older_than, now))
+                    .map(|todo| todo.title.clone())
+                    .collect();
+                if titles.is_empty() {
+                    say!("🧹 No completed todos to clear");
+                    return Ok(());
+                }
+                let question = format!(
+                    "Delete {} completed todo(s)? `tt undo` can bring them back.",
+                    titles.len()
+                );
+                if !confirmed(&question, yes || cli.force) {
+                    say!("Cancelled");
+                    return Ok(());
+                }
+                let count = todo_manager.remove_completed(older_than, now)?;
+                journal::record(&todo_manager.journal_path(), titles);
+                say!("🧹 Cleared {count} completed todo(s)");
+                Ok(())
+            }
+            Commands::Trash { action } => match action {
+                TrashAction::List => {
+                    output.trash(todo_manager.trash());
+                    Ok(())
+                }
+                TrashAction::Restore { id } => {
+                    let trashed: Vec<Todo> = todo_manager
+                        .trash()
+                        .iter()
+                        .map(|trashed| trashed.todo.clone())
+                        .collect();
+                    let id = ids::resolve(&trashed, &id, config.id_style)?;
+                    let todo = todo_manager.restore_from_trash(id)?;
+                    journal::record(&todo_manager.journal_path(), vec![todo.title.clone()]);
+                    let message = format!(
+                        "♻️  Restored from the trash: {} (ID {})",
+                        todo.title,
+                        ids::display_id(&todo, config.id_style)
+                    );
+                    output.changed(&todo, &message);
+                    Ok(())
+                }
+                TrashAction::Empty { older_than, yes } => {
+                    let now = Utc::now();
+                    let titles: Vec<String> = todo_manager
+                        .trash()
+                        .iter()
+                        .filter(|trashed| trashed.is_purgeable(older_than, now))
+                        .map(|trashed| trashed.todo.title.clone())
+                        .collect();
+                    let count = titles.len();
+                    if count == 0 {
+                        say!("🗑️  Nothing in the trash to empty");
+                        return Ok(());
+                    }
+                    let question = format!(
+                        "Delete {count} todo(s) in the trash for good? `tt undo` can bring them back."
+                    );
+                    if !confirmed(&question, yes || cli.force) {
+                        say!("Cancelled");
+                        return Ok(());
+                    }
+                    let count = todo_manager.purge_trash(older_than, now)?;
+                    journal::record(&todo_manager.journal_path(), titles);
+                    say!("🗑️  Emptied {count} todo(s) from the trash");
+                    Ok(())
+                }
+            },
+            Commands::Unarchive { id } => {
+                let archive = todo_manager.load_archive()?;
+                let id = ids::resolve(&archive, &id, config.id_style)?;
+                let todo = todo_manager.unarchive(id)?;
+                journal::record(&todo_manager.journal_path(), vec![todo.title.clone()]);
+                say!(
+                    "📤 Restored from archive: {} (ID {})",
+                    todo.title,
+                    ids::display_id(&todo, config.id_style)
+                );
+                Ok(())
+            }
+            Commands::Undo => {
+                let before = every_todo(todo_manager);
+                match todo_manager.undo()? {
+                    Some(operation) => {
+                        journal::record(
+                            &todo_manager.journal_path(),
+                            journal::changed_titles(&before, &every_todo(todo_manager)),
+                        );
+                        say!("↩️  Restored state from before: {operation}");
+                    }
+                    None => say!("↩️  Nothing to undo"),
+                }
+                Ok(())
+            }
+            Commands::Export { format, output } => {
+                let rendered = export::render(&todo_manager.to_store(), format)?;
+                match output {
+                    Some(path) => {
+                        fs::write(&path, rendered)
+                            .with_context(|| format!("Failed to write {}", path.display()))?;
+                        esay!("📤 Exported todos to {}", path.display());
+                    }
+                    None => print!("{rendered}"),
+                }
+                Ok(())
+            }
+            Commands::Import {
+                file,
+                format,
+                allow_duplicates,
+            } => {
+                let content = fs::read_to_string(&file)
+                    .with_context(|| format!("Failed to read {}", file.display()))?;
+                let format = format.unwrap_or_else(|| ImportFormat::from_path(&file));
+                let parsed = import::parse(&content, format);
+                for warning in &parsed.warnings {
+                    esay!("⚠️  {}: {warning}", file.display());
+                }
+
+                let titles: Vec<String> = parsed.todos.iter().map(|t| t.title.clone()).collect();
+                let dedupe = if allow_duplicates {
+                    DedupeStrategy::AllowDuplicates
+                } else {
+                    DedupeStrategy::SkipIncompleteTitleMatches
+                };
+                let summary = todo_manager.import_todos(parsed.todos, dedupe)?;
+                if summary.imported > 0 {
+                    journal::record(&todo_manager.journal_path(), titles);
+                }
+                say!(
+                    "📥 Imported {}, skipped {} duplicate(s), {} invalid",
+                    summary.imported,
+                    summary.duplicates,
+                    parsed.warnings.len()
+                );
+                Ok(())
+            }
+            Commands::Groom {
+                export,
+                apply,
+                dry_run,
+            } => {
+                if let Some(path) = export {
+                    let todos = todo_manager.list_todos();
+                    fs::write(&path, groom::export(todos))
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    say!(
+                        "🧹 Wrote {} pending todo(s) to {}; edit it, then run `tt groom --apply {}`",
+                        todos.iter().filter(|todo| !todo.completed).count(),
+                        path.display(),
+                        path.display()
+                    );
+                    return Ok(());
+                }
+                let Some(path) = apply else {
+                    return Ok(());
+                };
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let (rows, problems) = groom::parse(&content);
+                let plan = groom::plan(
+                    todo_manager.list_todos(),
+                    rows,
+                    settings.default_priority.value,
+                );
+                for problem in problems.iter().chain(&plan.rejected) {
+                    esay!("⚠️  {}: {problem}", path.display());
+                }
+                if plan.is_empty() {
+                    say!("✅ Nothing to change");
+                    return Ok(());
+                }
+
+                if dry_run {
+                    say!("🧹 Would change (dry run, nothing changed):");
+                } else {
+                    say!("🧹 Groomed:");
+                }
+                for edit in &plan.edits {
+                    say!(
+                        "  {} {}: {}",
+                        ids::display_id(&edit.todo, config.id_style),
+                        edit.todo.title,
+                        edit.changes.join(", ")
+                    );
+                }
+                for todo in &plan.added {
+                    say!("  new {}", todo.title);
+                }
+                say!(
+                    "{} changed, {} added, {} rejected",
+                    plan.edits.len(),
+                    plan.added.len(),
+                    problems.len() + plan.rejected.len()
+                );
+                if !dry_run {
+                    let titles = plan
+                        .edits
+                        .iter()
+                        .map(|edit| &edit.todo)
+                        .chain(&plan.added)
+                        .map(|todo| todo.title.clone())
+                        .collect();
+                    todo_manager.apply_groom(plan)?;
+                    journal::record(&todo_manager.journal_path(), titles);
+                }
+                Ok(())
+            }
+            Commands::Store { action } => {
+                match action {
+                    StoreAction::SetName { name } => {
+                        todo_manager.set_store_name(&name)?;
+                        match todo_manager.meta().and_then(|m| m.name.as_deref()) {
+                            Some(name) => say!("📝 List name: {name}"),
+                            None => say!("📝 List name removed"),
+                        }
+                    }
+                    StoreAction::SetDescription { description } => {
+                        todo_manager.set_store_description(&description)?;
+                        match todo_manager.meta().and_then(|m| m.description.as_deref()) {
+                            Some(description) => say!("📝 List description: {description}"),
+                            None => say!("📝 List description removed"),
+                        }
+                    }
+                }
+                // Naming the list touches no todo, so there are no titles
+                journal::record(&todo_manager.journal_path(), Vec::new());
+                Ok(())
+            }
+            Commands::Config { action } => match action {
+                ConfigAction::Env => {
+                    let rows = settings.env_rows();
+                    if output.json {
+                        say!("{}", serde_json::to_string_pretty(&rows)?);
                     } else {
-                        "⏳ incomplete"
+                        say!("⚙️  Environment variables:");
+                        for row in rows {
+                            say!(
+                                "  {:<20} {:<12} {}",
+                                row.variable,
+                                row.value,
+                                format!("({})", row.source).dimmed()
+                            );
+                        }
+                    }
+                    Ok(())
+                }
+            },
+            Commands::Focus { action } => match action {
+                FocusAction::Start { ids, lasts } => {
+                    let selection = select(todo_manager, &ids, true, config)?;
+                    if let Some(done) = selection
+                        .ids
+                        .iter()
+                        .filter_map(|&id| todo_manager.get_todo(id))
+                        .find(|todo| todo.completed)
+                    {
+                        anyhow::bail!("{} is already completed", done.title);
+                    }
+                    let lasts = match lasts {
+                        Some(lasts) => lasts,
+                        None => config.focus_expiry()?,
+                    };
+                    let focus = Focus::new(selection.ids, Utc::now(), lasts);
+                    todo_manager.set_focus(Some(focus.clone()))?;
+                    say!(
+                        "🎯 Focusing on {} todo(s) until {}; everything else is hidden",
+                        focus.ids.len(),
+                        output::local_time(&focus.expires_at)
+                    );
+                    output.print_todo_lines(&focus.filter(todo_manager.list_todos().to_vec()));
+                    Ok(())
+                }
+                FocusAction::Stop => {
+                    if todo_manager.focus().is_none() {
+                        say!("🎯 No focus session to stop");
+                    } else {
+                        todo_manager.set_focus(None)?;
+                        say!("🎯 Focus session ended; showing everything again");
+                    }
+                    Ok(())
+                }
+            },
+            Commands::Ui => {
+                if output.json || !std::io::stdout().is_terminal() {
+                    output.list(
+                        todo_manager.list_todos(),
+                        todo_manager.meta(),
+                        todo_manager.counts(None),
+                    );
+                } else {
+                    interactive::run(todo_manager, config.id_style)?;
+                }
+                Ok(())
+            }
+            Commands::Which => {
+                say!("📂 {}", todo_manager.file_path().display());
+                if let Some(list) = todo_manager.list_name() {
+                    say!("  List:        {list}");
+                }
+                if let Some(meta) = todo_manager.meta() {
+                    if let Some(name) = &meta.name {
+                        say!("  Name:        {name}");
+                    }
+                    if let Some(description) = &meta.description {
+                        say!("  Description: {description}");
+                    }
+                    say!("  Created:     {}", output::local_time(&meta.created_at));
+                    say!("  Modified:    {}", output::local_time(&meta.modified_at));
+                }
+                Ok(())
+            }
+            Commands::Lists { action } => match action {
+                None => {
+                    let mut names = vec![lists::DEFAULT.to_string()];
+                    names.extend(
+                        lists::names(&lists::dir()?)
+                            .into_iter()
+                            .filter(|name| name != lists::DEFAULT),
+                    );
+                    let summaries = names
+                        .into_iter()
+                        .map(|name| {
+                            let location = Location::of_list(&name)?;
+                            let open =
+                                TodoManager::open(location.clone(), false)
+                                    .ok()
+                                    .map(|manager| {
+                                        manager
+                                            .list_todos()
+                                            .iter()
+                                            .filter(|todo| !todo.completed)
+                                            .count()
+                                    });
+                            let current = todo_manager.list_name() == Some(name.as_str());
+                            Ok(lists::ListSummary {
+                                name,
+                                path: location.path,
+                                open,
+                                current,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    output.lists(&summaries);
+                    Ok(())
+                }
+                Some(ListsAction::Remove { name }) => {
+                    let location = existing_list(&name)?;
+                    let question = match TodoManager::open(location.clone(), false) {
+                        Ok(manager) => format!(
+                            "Remove list '{name}' with its {} todo(s), archive and history?",
+                            manager.list_todos().len()
+                        ),
+                        Err(_) => format!("Remove list '{name}' and its archive and history?"),
+                    };
+                    if !cli.force && !confirm(&question) {
+                        say!("Cancelled");
+                        return Ok(());
+                    }
+                    lists::remove(&location.path)?;
+                    say!("🗑️  Removed list '{name}'");
+                    Ok(())
+                }
+                Some(ListsAction::Rename { from, to }) => {
+                    let source = existing_list(&from)?;
+                    let target = Location::of_list(&to)?;
+                    if to == lists::DEFAULT || target.path.exists() {
+                        anyhow::bail!("A list named '{to}' already exists");
+                    }
+                    lists::rename(&source.path, &target.path)?;
+                    say!("📚 Renamed list '{from}' to '{to}'");
+                    Ok(())
+                }
+            },
+            Commands::Sync { path, base } => {
+                if fs::canonicalize(&path).ok() == fs::canonicalize(todo_manager.file_path()).ok() {
+                    return Err(TtError::Invalid(format!(
+                        "{} is this todo file; sync it with another copy",
+                        path.display()
+                    ))
+                    .into());
+                }
+                let theirs = read_store(&path)?;
+                let base = base.as_deref().map(read_store).transpose()?;
+                let before = every_todo(todo_manager);
+                let report = todo_manager.merge(theirs, base.as_ref())?;
+                say!("🔄 Synced {}: {report}", path.display());
+                print_merge_details(&report);
+                journal::record(
+                    &todo_manager.journal_path(),
+                    journal::changed_titles(&before, &every_todo(todo_manager)),
+                );
+                Ok(())
+            }
+            Commands::Conflicts {
+                action: ConflictsAction::MergeFiles,
+            } => {
+                let found = conflicts::find(todo_manager.file_path());
+                if found.is_empty() {
+                    say!(
+                        "🔀 No conflict files next to {}",
+                        todo_manager.file_path().display()
+                    );
+                    return Ok(());
+                }
+
+                let before = every_todo(todo_manager);
+                let mut failed = 0;
+                for path in found {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    let theirs = match read_store(&path) {
+                        Ok(theirs) => theirs,
+                        Err(e) => {
+                            esay!("❌ {name}: {e:#}");
+                            failed += 1;
+                            continue;
+                        }
+                    };
+                    let report = todo_manager.merge(theirs, None)?;
+                    let merged = conflicts::mark_merged(&path)?;
+                    say!("🔀 {name}: {report}");
+                    print_merge_details(&report);
+                    say!(
+                        "   renamed to {}",
+                        merged.file_name().unwrap_or_default().to_string_lossy()
+                    );
+                }
+                journal::record(
+                    &todo_manager.journal_path(),
+                    journal::changed_titles(&before, &every_todo(todo_manager)),
+                );
+                if failed > 0 {
+                    anyhow::bail!(
+                        "{failed} conflict file(s) could not be merged and were left in place"
+                    );
+                }
+                Ok(())
+            }
+            Commands::Bundle { action } => match action {
+                BundleAction::Export { path } => {
+                    let bundle = Bundle {
+                        store: Some(todo_manager.to_store()),
+                        archive: Some(todo_manager.load_archive()?),
+                        config: Config::get_file_path().and_then(|p| fs::read_to_string(p).ok()),
                     };
-                    println!("🔄 Toggled: {} is now {}", todo.title, status);
+                    bundle::write(&path, &bundle, Utc::now())?;
+                    say!("📦 Wrote bundle to {}", path.display());
+                    Ok(())
+                }
+                BundleAction::Import {
+                    path,
+                    merge: _,
+                    replace,
+                } => {
+                    // Every component is validated before anything is written
+                    let bundle = bundle::read(&path)?;
+                    let before = every_todo(todo_manager);
+                    let mut recap = Vec::new();
+
+                    if let Some(store) = bundle.store {
+                        let count = store.todos.len();
+                        if replace {
+                            todo_manager.replace_store(store)?;
+                            recap.push(format!("store: replaced ({count} todos)"));
+                        } else {
+                            let counts = todo_manager.merge_store(store.todos)?;
+                            recap.push(format!("store: {counts}"));
+                        }
+                    }
+                    if let Some(archive) = bundle.archive {
+                        let count = archive.len();
+                        if replace {
+                            todo_manager.replace_archive(archive)?;
+                            recap.push(format!("archive: replaced ({count} todos)"));
+                        } else {
+                            let counts = todo_manager.merge_archive(archive)?;
+                            recap.push(format!("archive: {counts}"));
+                        }
+                    }
+                    if let (Some(config), Some(config_path)) =
+                        (bundle.config, Config::get_file_path())
+                    {
+                        if replace || !config_path.exists() {
+                            todo_manager::write_file(&config_path, &config)?;
+                            recap.push(format!("config: restored to {}", config_path.display()));
+                        } else {
+                            recap.push("config: kept existing (use --replace to overwrite)".into());
+                        }
+                    }
+
+                    journal::record(
+                        &todo_manager.journal_path(),
+                        journal::changed_titles(&before, &every_todo(todo_manager)),
+                    );
+                    say!("📦 Imported {}:", path.display());
+                    for line in recap {
+                        say!("  {line}");
+                    }
+                    Ok(())
+                }
+            },
+            Commands::Search {
+                query,
+                regex,
+                completed,
+                incomplete,
+            } => {
+                let matches: Vec<Todo> = todo_manager
+                    .search_todos(&query, regex)?
+                    .into_iter()
+                    .filter(|todo| !completed || todo.completed)
+                    .filter(|todo| !incomplete || !todo.completed)
+                    .collect();
+                output.search(&query, &matches);
+                Ok(())
+            }
+            Commands::Complete { ids, strict } => {
+                let selection = select(todo_manager, &ids, strict, config)?;
+                todo_manager.mark_completed(&selection.ids)?;
+                let changed = record_changes(todo_manager, &selection.ids);
+                warn_open_checklists(&changed);
+                match changed.as_slice() {
+                    [todo] if ids.len() == 1 && selection.is_complete() => {
+                        output.changed(todo, &format!("✅ Marked as completed: {}", todo.title))
+                    }
+                    _ => output
+                        .changed_many(&changed, &summary("✅ completed", &changed, &selection)),
+                }
+                Ok(())
+            }
+            Commands::Incomplete { ids, strict } => {
+                let selection = select(todo_manager, &ids, strict, config)?;
+                todo_manager.mark_incomplete(&selection.ids)?;
+                let changed = record_changes(todo_manager, &selection.ids);
+                match changed.as_slice() {
+                    [todo] if ids.len() == 1 && selection.is_complete() => {
+                        output.changed(todo, &format!("⏳ Marked as incomplete: {}", todo.title))
+                    }
+                    _ => output.changed_many(
+                        &changed,
+                        &summary("⏳ marked incomplete", &changed, &selection),
+                    ),
+                }
+                Ok(())
+            }
+            Commands::Toggle { ids, strict } => {
+                let selection = select(todo_manager, &ids, strict, config)?;
+                todo_manager.toggle_completed(&selection.ids)?;
+                let changed = record_changes(todo_manager, &selection.ids);
+                warn_open_checklists(&changed);
+                match changed.as_slice() {
+                    [todo] if ids.len() == 1 && selection.is_complete() => {
+                        let status = if todo.completed {
+                            "✅ completed"
+                        } else {
+                            "⏳ incomplete"
+                        };
+                        output.changed(
+                            todo,
+                            &format!("🔄 Toggled: {} is now {}", todo.title, status),
+                        );
+                    }
+                    _ => {
+                        output.changed_many(&changed, &summary("🔄 toggled", &changed, &selection))
+                    }
+                }
+                Ok(())
+            }
+            Commands::Delete {
+                ids,
+                strict,
+                yes,
+                permanent,
+            } => {
+                let selection = select(todo_manager, &ids, strict, config)?;
+                let titles: Vec<String> = selection
+                    .ids
+                    .iter()
+                    .filter_map(|&id| todo_manager.get_todo(id))
+                    .map(|todo| format!("'{}'", todo.title))
+                    .collect();
+                let question = match titles.as_slice() {
+                    [title] => format!("Delete {title}?"),
+                    titles => format!("Delete {} todos ({})?", titles.len(), titles.join(", ")),
+                };
+                if !confirmed(&question, yes || cli.force) {
+                    say!("Cancelled");
+                    return Ok(());
+                }
+                let deleted = if permanent {
+                    todo_manager.delete_todos(&selection.ids)?
+                } else {
+                    todo_manager.trash_todos(&selection.ids)?
+                };
+                journal::record(
+                    &todo_manager.journal_path(),
+                    deleted.iter().map(|todo| todo.title.clone()).collect(),
+                );
+                check_focus(todo_manager, &selection.ids);
+                match (permanent, deleted.as_slice()) {
+                    (true, [_]) if ids.len() == 1 && selection.is_complete() => {
+                        say!("🗑️  Todo deleted successfully")
+                    }
+                    (false, [todo]) if ids.len() == 1 && selection.is_complete() => say!(
+                        "🗑️  Moved to the trash: {} (`tt trash restore {}` brings it back)",
+                        todo.title,
+                        ids::display_id(todo, config.id_style)
+                    ),
+                    (true, _) => {
+                        output.changed_many(&deleted, &summary("🗑️  deleted", &deleted, &selection))
+                    }
+                    (false, _) => {
+                        output.changed_many(&deleted, &summary("🗑️  trashed", &deleted, &selection))
+                    }
+                }
+                Ok(())
+            }
+            Commands::Check {
+                action: Some(action),
+                ..
+            } => {
+                let (id, message) = match action {
+                    ChecklistAction::Add { id, text } => {
+                        let id = resolve_id(todo_manager, &id, config)?;
+                        let number = todo_manager.add_checklist_item(id, &text)?;
+                        (
+                            id,
+                            format!("☑️  Added checklist item {number}: {}", text.trim()),
+                        )
+                    }
+                    ChecklistAction::Toggle { id, number } => {
+                        let id = resolve_id(todo_manager, &id, config)?;
+                        let item = todo_manager.toggle_checklist_item(id, number)?;
+                        let state = if item.done { "done" } else { "not done" };
+                        (id, format!("☑️  {} is now {state}", item.text))
+                    }
+                    ChecklistAction::Rm { id, number } => {
+                        let id = resolve_id(todo_manager, &id, config)?;
+                        let item = todo_manager.remove_checklist_item(id, number)?;
+                        (id, format!("☑️  Removed checklist item: {}", item.text))
+                    }
+                };
+                record_history(todo_manager, id);
+                if let Some(todo) = todo_manager.get_todo(id) {
+                    output.changed(todo, &message);
+                }
+                Ok(())
+            }
+            Commands::Check {
+                action: None,
+                max_pending,
+                max_age,
+                max_overdue,
+                format,
+            } => {
+                let thresholds = Thresholds {
+                    max_pending: max_pending.or(config.check.max_pending),
+                    max_age: match max_age {
+                        Some(age) => Some(age),
+                        None => config
+                            .check
+                            .max_age
+                            .as_deref()
+                            .map(parse_duration)
+                            .transpose()
+                            .map_err(|e| {
+                                anyhow::anyhow!("Invalid check.max_age in config: {}", e)
+                            })?,
+                    },
+                    max_overdue: max_overdue.or(config.check.max_overdue),
+                };
+                let stats = Stats::from_todos(todo_manager.list_todos(), Utc::now());
+                let evaluation = check::evaluate(&stats, &thresholds);
+
+                match format.or_json(output) {
+                    OutputFormat::Json => {
+                        say!("{}", serde_json::to_string_pretty(&evaluation)?);
+                    }
+                    OutputFormat::Text => {
+                        if evaluation.checks.is_empty() {
+                            say!(
+                                "⚠️  No thresholds configured. Pass --max-pending/--max-age/--max-overdue or set them under [check] in the config file."
+                            );
+                        } else if evaluation.passed {
+                            say!("✅ All {} checks passed", evaluation.checks.len());
+                        }
+                        for failed in evaluation.checks.iter().filter(|c| !c.passed) {
+                            say!(
+                                "❌ {}: {} (limit {})",
+                                failed.name,
+                                failed.actual,
+                                failed.limit
+                            );
+                        }
+                    }
+                }
+
+                if !evaluation.passed {
+                    return Err(Failed.into());
+                }
+                Ok(())
+            }
+            Commands::Completions { shell } => {
+                print!("{}", completions::script(shell, Cli::command()));
+                Ok(())
+            }
+            Commands::CompleteIds => {
+                print!(
+                    "{}",
+                    completions::id_lines(todo_manager.list_todos(), config.id_style)
+                );
+                Ok(())
+            }
+            Commands::Count {
+                completed,
+                incomplete: _,
+                all,
+                priority,
+            } => {
+                let counts = todo_manager.counts(priority);
+                let count = match (completed, all) {
+                    (true, _) => counts.completed,
+                    (_, true) => counts.total,
+                    _ => counts.pending(),
+                };
+                // Just the number, for $(tt count)
+                println!("{count}");
+                Ok(())
+            }
+            Commands::Metrics => {
+                let stats = Stats::from_todos(todo_manager.list_todos(), Utc::now());
+                let path = todo_manager.file_path();
+                let store = metrics::StoreInfo {
+                    name: StoreMeta::display_name(todo_manager.meta()),
+                    path: &path.to_string_lossy(),
+                    size_bytes: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+                };
+                print!("{}", metrics::render(&stats, &store));
+                Ok(())
+            }
+            Commands::Attention { limit } => {
+                let todos = todo_manager.list_todos();
+                let ranked = attention::rank(todos, &config.attention, Utc::now());
+                if ranked.is_empty() {
+                    say!("🎉 Nothing pending - nothing is being neglected");
+                } else {
+                    say!("👀 Needs your attention:");
+                    for entry in ranked.iter().take(limit) {
+                        say!(
+                            "  {} {} {}",
+                            ids::display_id(entry.todo, config.id_style),
+                            entry.todo.title.bold(),
+                            format!("({})", entry.reason).dimmed()
+                        );
+                    }
+                }
+                Ok(())
+            }
+            Commands::Validate { file, format } => {
+                let path = file.unwrap_or_else(|| todo_manager.file_path().to_path_buf());
+                let problems = validate::validate_file(&path)?;
+
+                match format.or_json(output) {
+                    OutputFormat::Json => {
+                        say!("{}", serde_json::to_string_pretty(&problems)?);
+                    }
+                    OutputFormat::Text if problems.is_empty() => {
+                        say!("✅ {} is valid", path.display());
+                    }
+                    OutputFormat::Text => {
+                        say!("❌ {} has {} problem(s):", path.display(), problems.len());
+                        print!("{}", output::plain(&format_problems(&problems)));
+                    }
+                }
+
+                if !problems.is_empty() {
+                    return Err(Failed.into());
+                }
+                Ok(())
+            }
+            Commands::Lint { format } => {
+                let todos = todo_manager.list_todos();
+                let violations = conventions.lint(todos);
+                match format.or_json(output) {
+                    OutputFormat::Json => {
+                        say!("{}", serde_json::to_string_pretty(&violations)?);
+                    }
+                    OutputFormat::Text if conventions.is_empty() => {
+                        say!("📏 No title conventions apply to this store");
+                    }
+                    OutputFormat::Text if violations.is_empty() => {
+                        say!("✅ Every title follows the conventions");
+                    }
+                    OutputFormat::Text => {
+                        say!("📏 {} todo(s) break title conventions:", violations.len());
+                        for violation in &violations {
+                            let display_id = todo_manager
+                                .get_todo(violation.id)
+                                .map(|todo| ids::display_id(todo, config.id_style))
+                                .unwrap_or_else(|| violation.id.to_string());
+                            say!("  {display_id} {}", text::isolate(violation.title).bold());
+                            for message in &violation.messages {
+                                say!("    - {message}");
+                            }
+                        }
+                    }
+                }
+
+                if !violations.is_empty() {
+                    return Err(Failed.into());
+                }
+                Ok(())
+            }
+            Commands::Maintenance { dry_run } => {
+                let now = Utc::now();
+                let job = maintenance::Job {
+                    store: todo_manager.file_path(),
+                    retention: config.retention()?,
+                    now,
+                    dry_run,
+                };
+                let reports = maintenance::run(maintenance::TASKS, &job);
+                if !dry_run {
+                    todo_manager.mark_maintained(now);
+                }
+                say!("🧹 {}", maintenance::summary(&reports, dry_run));
+                let failed = reports
+                    .iter()
+                    .filter(|report| report.result.is_err())
+                    .count();
+                if failed > 0 {
+                    bail!("{failed} maintenance task(s) failed");
                 }
                 Ok(())
             }
-            Commands::Delete { id } => {
-                todo_manager.delete_todo(id)?;
-                println!("🗑️  Todo deleted successfully");
+            Commands::Normalize {
+                dry_run,
+                map_priority,
+                created_at,
+            } => {
+                // Flags come after the config, so they win for the same priority
+                let mappings: Vec<String> = config
+                    .normalize
+                    .map_priority
+                    .iter()
+                    .chain(&map_priority)
+                    .cloned()
+                    .collect();
+                let settings = normalize::Settings::new(
+                    &mappings,
+                    created_at
+                        .as_deref()
+                        .or(config.normalize.created_at.as_deref()),
+                    &journal::read_entries(&todo_manager.journal_path()),
+                )?;
+                let before = every_todo(todo_manager);
+                let report = todo_manager.normalize(&settings, dry_run)?;
+                if report.changed_anything() && !dry_run {
+                    journal::record(
+                        &todo_manager.journal_path(),
+                        journal::changed_titles(&before, &every_todo(todo_manager)),
+                    );
+                }
+                if output.json {
+                    say!("{}", serde_json::to_string_pretty(&report)?);
+                    return Ok(());
+                }
+
+                if !report.changed_anything() {
+                    say!("✅ Nothing to normalize");
+                } else if dry_run {
+                    say!("🧹 Would normalize (dry run, nothing changed):");
+                } else {
+                    say!("🧹 Normalized:");
+                }
+                if report.changed_anything() {
+                    for count in &report.rules {
+                        say!("  {:<14}{} todo(s)", count.rule, count.changed);
+                    }
+                }
+                if let Some(backup) = &report.backup {
+                    say!("💾 Backup of the previous file: {}", backup.display());
+                }
+                if report.missing_created_at > 0 {
+                    say!(
+                        "⚠️  {} todo(s) still have no creation time; pass --created-at to set one",
+                        report.missing_created_at
+                    );
+                }
+                Ok(())
+            }
+            Commands::History { grep, format } => {
+                let entries: Vec<_> = journal::read_entries(&todo_manager.journal_path())
+                    .into_iter()
+                    .filter(|entry| grep.as_deref().is_none_or(|pattern| entry.matches(pattern)))
+                    .collect();
+
+                match format.or_json(output) {
+                    OutputFormat::Json => {
+                        say!("{}", serde_json::to_string_pretty(&entries)?);
+                    }
+                    OutputFormat::Text => {
+                        if entries.is_empty() {
+                            say!("📜 No matching history entries");
+                        }
+                        for entry in entries {
+                            let when = DateTime::parse_from_rfc3339(&entry.timestamp)
+                                .map(|t| {
+                                    t.with_timezone(&Local)
+                                        .format("%Y-%m-%d %H:%M:%S")
+                                        .to_string()
+                                })
+                                .unwrap_or(entry.timestamp);
+                            say!(
+                                "{} {}@{} {}",
+                                when.dimmed(),
+                                entry.hostname,
+                                entry.cwd,
+                                entry.command.bold()
+                            );
+                            for title in entry.titles {
+                                say!("    {title}");
+                            }
+                        }
+                    }
+                }
                 Ok(())
             }
         },
         None => {
             // Default behavior: list todos
-            display_todos(todo_manager);
+            let options = QueryOptions {
+                completed: settings.hide_completed.value.then_some(false),
+                sort: settings.sort.value,
+                hide_snoozed_at: Some(Utc::now()),
+                ..QueryOptions::default()
+            };
+            let todos = todo_manager.query(&options);
+            match settle_focus(todo_manager, Utc::now()) {
+                Some(focus) => output.focused(&focus.filter(todos)),
+                None => {
+                    output.list(&todos, todo_manager.meta(), todo_manager.counts(None));
+                    note_snoozed(todo_manager, &options, todos.len(), output);
+                }
+            }
+            todo_manager.mark_viewed(Utc::now());
             Ok(())
         }
     }
 }
 
-fn display_todos(todo_manager: &TodoManager) {
-    let todos = todo_manager.list_todos();
-    if todos.is_empty() {
-        println!("📝 No todos found. Add one with `tt add <title>`");
+/// A named list that can be removed or renamed: it exists and is not the
+/// default list.
+fn existing_list(name: &str) -> Result<Location> {
+    let location = Location::of_list(name)?;
+    if name == lists::DEFAULT {
+        anyhow::bail!(
+            "The default list cannot be removed or renamed; it lives at {}",
+            location.path.display()
+        );
+    }
+    if !location.path.exists() {
+        return Err(TtError::Missing(format!("No list named '{name}' (see `tt lists`)")).into());
+    }
+    Ok(location)
+}
+
+/// Warn about a title that breaks the store's conventions, or with
+/// `strict_conventions` refuse it.
+/// Add the todos read for `tt add --stdin`, saving once. Invalid lines
+/// (and, with `strict_conventions`, lines breaking a title convention)
+/// are reported and skipped, or with `strict` stop anything being added.
+fn add_batch(
+    todo_manager: &mut TodoManager,
+    parsed: &batch::Parsed,
+    conventions: &Conventions,
+    strict_conventions: bool,
+    strict: bool,
+    output: &Output,
+) -> Result<()> {
+    let mut problems = parsed.problems.clone();
+    let mut todos = Vec::new();
+    for line in &parsed.lines {
+        let broken = conventions.check(&line.title);
+        if broken.is_empty() || !strict_conventions {
+            for message in broken {
+                esay!("⚠️  Line {}: title convention: {message}", line.number);
+            }
+            todos.push((line.title.clone(), line.priority));
+        } else {
+            problems.push(batch::Problem {
+                number: line.number,
+                message: format!(
+                    "breaks this store's title conventions: {}",
+                    broken.join("; ")
+                ),
+            });
+        }
+    }
+    problems.sort_by_key(|problem| problem.number);
+    for problem in &problems {
+        esay!("⚠️  {problem}");
+    }
+    if strict && !problems.is_empty() {
+        return Err(TtError::Invalid(format!(
+            "{} invalid line(s) on stdin; nothing was added",
+            problems.len()
+        ))
+        .into());
+    }
+
+    let added = if todos.is_empty() {
+        Vec::new()
     } else {
-        println!("📝 Your todos:");
-        for (id, todo) in todos.iter().enumerate() {
-            let status = if todo.completed { "✅" } else { "⏳" };
-            let colored_title = match todo.priority {
-                1 => todo.title.red().bold(),
-                2 => todo.title.yellow().bold(),
-                3 => todo.title.blue().bold(),
-                _ => todo.title.normal(),
-            };
-            println!("  {id} [{status}] {colored_title}");
+        todo_manager.add_todos(todos)?
+    };
+    if !added.is_empty() {
+        journal::record(
+            &todo_manager.journal_path(),
+            added.iter().map(|todo| todo.title.clone()).collect(),
+        );
+    }
+    let mut summary = format!("✅ Added {} todo(s)", added.len());
+    if !problems.is_empty() {
+        summary.push_str(&format!(", skipped {} invalid line(s)", problems.len()));
+    }
+    output.changed_many(&added, &summary);
+    Ok(())
+}
+
+fn check_conventions(conventions: &Conventions, title: &str, strict: bool) -> Result<()> {
+    let broken = conventions.check(title);
+    if broken.is_empty() {
+        return Ok(());
+    }
+    if strict {
+        return Err(TtError::Invalid(format!(
+            "'{title}' breaks this store's title conventions: {}",
+            broken.join("; ")
+        ))
+        .into());
+    }
+    for message in broken {
+        esay!("⚠️  Title convention: {message}");
+    }
+    Ok(())
+}
+
+fn format_problems(problems: &[Problem]) -> String {
+    let mut out = String::new();
+    for problem in problems {
+        out.push_str(&format!("  {}: {}\n", problem.path.bold(), problem.message));
+        if let Some(suggestion) = &problem.suggestion {
+            out.push_str(&format!("    💡 {suggestion}\n"));
+        }
+    }
+    out
+}
+
+/// Pin or unpin a todo for `tt pin` and `tt unpin`.
+fn set_pinned(
+    todo_manager: &mut TodoManager,
+    input: &str,
+    pinned: bool,
+    config: &Config,
+    output: &Output,
+) -> Result<()> {
+    let id = resolve_id(todo_manager, input, config)?;
+    let shown = shown_id(todo_manager, id, config);
+    let changed = todo_manager.set_pinned(id, pinned)?;
+    if changed {
+        record_history(todo_manager, id);
+    }
+    let message = match (changed, pinned) {
+        (true, true) => format!("📌 Pinned todo {shown} to the top of the list"),
+        (true, false) => format!("📌 Unpinned todo {shown}"),
+        (false, true) => format!("📌 Todo {shown} is already pinned"),
+        (false, false) => format!("📌 Todo {shown} isn't pinned"),
+    };
+    if let Some(todo) = todo_manager.get_todo(id) {
+        output.changed(todo, &message);
+    }
+    Ok(())
+}
+
+/// Another copy of a todo file, for merging.
+fn read_store(path: &Path) -> Result<TodoStore> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {} as a todo file", path.display()))
+}
+
+/// A line for each todo the two sides of a merge disagreed about.
+fn print_merge_details(report: &MergeReport) {
+    for title in &report.kept {
+        say!("   kept \"{title}\": edited after it was deleted on the other side");
+    }
+    for title in &report.conflicts {
+        say!("   conflict \"{title}\": changed on both sides; kept the newer change");
+    }
+}
+
+/// Every todo, drafts and archive included, for naming what a command
+/// that rewrites them wholesale changed.
+fn every_todo(todo_manager: &TodoManager) -> Vec<Todo> {
+    let mut todos = todo_manager.to_store().todos;
+    todos.extend(todo_manager.load_archive().unwrap_or_default());
+    todos
+}
+
+fn template_titles(template: &Template) -> Vec<String> {
+    template
+        .todos
+        .iter()
+        .map(|todo| todo.title.clone())
+        .collect()
+}
+
+fn record_history(todo_manager: &TodoManager, id: usize) {
+    let titles = todo_manager
+        .get_todo(id)
+        .map(|todo| vec![todo.title.clone()])
+        .unwrap_or_default();
+    journal::record(&todo_manager.journal_path(), titles);
+    check_focus(todo_manager, &[id]);
+}
+
+/// Resolve the IDs given to a bulk command, matching inputs that aren't IDs
+/// against titles. IDs that cannot be used are left out, unless `strict`
+/// makes them an error; so does finding nothing.
+fn select(
+    todo_manager: &TodoManager,
+    inputs: &[String],
+    strict: bool,
+    config: &Config,
+) -> Result<Selection> {
+    let mut selection = ids::resolve_many(todo_manager.list_todos(), inputs, config.id_style);
+    for query in std::mem::take(&mut selection.titles) {
+        match find_title(todo_manager, &query, config) {
+            Ok(id) => selection.pick(id),
+            Err(error) => selection.errors.push(error.to_string()),
+        }
+    }
+    if selection.ids.is_empty() || (strict && !selection.is_complete()) {
+        // A single ID fails the way it does for every other command
+        if inputs.len() == 1 && selection.errors.len() + selection.not_found.len() == 1 {
+            if let Some(error) = selection.errors.first() {
+                anyhow::bail!("{error}");
+            }
+            return Err(TtError::not_found(&selection.not_found[0]).into());
+        }
+        if selection.errors.is_empty() {
+            return Err(TtError::Missing(selection.failure_message()).into());
+        }
+        anyhow::bail!("{}", selection.failure_message());
+    }
+    for error in &selection.errors {
+        esay!("⚠️  {error}");
+    }
+    Ok(selection)
+}
+
+/// Ask a yes/no question on stderr; anything but yes is no.
+pub fn confirm(question: &str) -> bool {
+    ask(
+        question,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stderr(),
+    )
+}
+
+/// Whether to go ahead with something that deletes todos: yes when
+/// `skip` (--yes or --force) is given or there is no terminal to ask on,
+/// so scripts don't hang, and otherwise whatever the user answers.
+fn confirmed(question: &str, skip: bool) -> bool {
+    skip || !std::io::stdin().is_terminal() || confirm(question)
+}
+
+/// Write `question` to `output` and read the answer from `input`. Only y
+/// or yes, in any case, is yes; Enter, anything else or no input is no.
+fn ask(question: &str, input: &mut impl BufRead, output: &mut impl Write) -> bool {
+    let _ = write!(output, "{question} [y/N] ");
+    let _ = output.flush();
+    let mut answer = String::new();
+    input.read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// The one todo whose title contains `query`. When several do, the user
+/// picks one if there is a terminal to ask on; otherwise they are listed
+/// in the error.
+fn find_title(todo_manager: &TodoManager, query: &str, config: &Config) -> Result<usize> {
+    let matches = todo_manager.find_by_title(query);
+    let candidates: Vec<Todo> = match matches.as_slice() {
+        [] => {
+            return Err(TtError::Missing(format!("No todo ID or title matches '{query}'")).into());
+        }
+        [(id, _)] => return Ok(*id),
+        many => many.iter().map(|(_, todo)| (*todo).clone()).collect(),
+    };
+    let listing: Vec<String> = candidates
+        .iter()
+        .map(|todo| {
+            format!(
+                "  {} {}",
+                ids::display_id(todo, config.id_style),
+                todo.title
+            )
+        })
+        .collect();
+    let listing = listing.join("\n");
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        anyhow::bail!(
+            "'{query}' matches {} todos; use one of their IDs:\n{listing}",
+            candidates.len()
+        );
+    }
+
+    esay!("'{query}' matches {} todos:\n{listing}", candidates.len());
+    eprint!("Which ID? (Enter to cancel) ");
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim().is_empty() {
+        anyhow::bail!("Cancelled");
+    }
+    Ok(ids::resolve(&candidates, &answer, config.id_style)?)
+}
+
+/// The focus session if one is active. A session whose todos are all done,
+/// or whose time is up, is ended here, saying so.
+fn settle_focus(todo_manager: &TodoManager, now: DateTime<Utc>) -> Option<Focus> {
+    let focus = todo_manager.focus()?;
+    match focus.status(todo_manager.list_todos(), now) {
+        FocusStatus::Active => return Some(focus),
+        FocusStatus::Finished => esay!(
+            "🎉 All {} focused todo(s) done - focus session complete!",
+            focus.ids.len()
+        ),
+        FocusStatus::Expired => esay!("⏱️  Focus session timed out; showing everything again"),
+    }
+    let _ = todo_manager.set_focus(None);
+    None
+}
+
+/// After changing `ids`: remind the user of an active focus session if
+/// any of them are outside it, and end the session if that finished it.
+fn check_focus(todo_manager: &TodoManager, ids: &[usize]) {
+    let Some(focus) = settle_focus(todo_manager, Utc::now()) else {
+        return;
+    };
+    let outside = ids.iter().filter(|id| !focus.contains(**id)).count();
+    if outside > 0 {
+        esay!(
+            "🎯 Reminder: you're focusing on {} other todo(s) (`tt focus stop` to end)",
+            focus.ids.len()
+        );
+    }
+}
+
+/// Journal the todos a bulk command changed and return them.
+fn record_changes(todo_manager: &TodoManager, ids: &[usize]) -> Vec<Todo> {
+    let changed: Vec<Todo> = ids
+        .iter()
+        .filter_map(|&id| todo_manager.get_todo(id).cloned())
+        .collect();
+    journal::record(
+        &todo_manager.journal_path(),
+        changed.iter().map(|todo| todo.title.clone()).collect(),
+    );
+    check_focus(todo_manager, ids);
+    changed
+}
+
+/// Point out todos that were just completed with checklist items open.
+fn warn_open_checklists(todos: &[Todo]) {
+    for warning in todos.iter().filter_map(output::checklist_warning) {
+        esay!("{warning}");
+    }
+}
+
+/// "✅ completed 3 todos, 1 ID not found (9)"
+fn summary(done: &str, todos: &[Todo], selection: &Selection) -> String {
+    let mut summary = format!(
+        "{done} {} todo{}",
+        todos.len(),
+        if todos.len() == 1 { "" } else { "s" }
+    );
+    if !selection.not_found.is_empty() {
+        summary.push_str(&format!(
+            ", {} ({})",
+            ids::ids_not_found(selection.not_found.len()),
+            selection.not_found.join(", ")
+        ));
+    }
+    if !selection.errors.is_empty() {
+        summary.push_str(&format!(", {} skipped", selection.errors.len()));
+    }
+    summary
+}
+
+/// The todo `input` names: an ID, or else text from its title.
+fn resolve_id(todo_manager: &TodoManager, input: &str, config: &Config) -> Result<usize> {
+    let todos = todo_manager.list_todos();
+    match ids::resolve(todos, input, config.id_style) {
+        Ok(id) => Ok(id),
+        Err(_) if ids::may_be_title(todos, input, config.id_style) => {
+            find_title(todo_manager, input, config)
         }
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Say how many todos a listing of `shown` todos left out for being snoozed.
+fn note_snoozed(todo_manager: &TodoManager, options: &QueryOptions, shown: usize, output: &Output) {
+    if output.json || options.hide_snoozed_at.is_none() {
+        return;
+    }
+    let unhidden = QueryOptions {
+        hide_snoozed_at: None,
+        ..options.clone()
+    };
+    let hidden = todo_manager.query(&unhidden).len() - shown;
+    if hidden > 0 {
+        say!(
+            "💤 {}",
+            format!("{hidden} snoozed (`tt list --all` shows them)").dimmed()
+        );
+    }
+}
+
+/// The start of `date` in local time.
+fn start_of_day(date: NaiveDate) -> Result<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&date.and_time(chrono::NaiveTime::MIN))
+        .earliest()
+        .map(|start| start.with_timezone(&Utc))
+        .ok_or_else(|| anyhow::anyhow!("{date} has no midnight in the local time zone"))
+}
+
+fn shown_id(todo_manager: &TodoManager, id: usize, config: &Config) -> String {
+    todo_manager.get_todo(id).map_or_else(
+        || id.to_string(),
+        |todo| ids::display_id(todo, config.id_style),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notes_flags_conflict() {
+        let error = Cli::try_parse_from([
+            "tt",
+            "add",
+            "Investigate crash",
+            "--notes",
+            "inline",
+            "--notes-from-stdin",
+        ])
+        .err()
+        .unwrap();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        assert!(
+            Cli::try_parse_from(["tt", "add", "Investigate crash", "--notes-from-stdin"]).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_ask_defaults_to_no() {
+        for (answer, expected) in [
+            ("y\n", true),
+            (" YES \n", true),
+            ("n\n", false),
+            ("\n", false),
+            ("sure\n", false),
+            ("", false),
+        ] {
+            let mut output = Vec::new();
+            let confirmed = ask(
+                "Delete 'file taxes'?",
+                &mut std::io::Cursor::new(answer),
+                &mut output,
+            );
+            assert_eq!(confirmed, expected, "{answer:?}");
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                "Delete 'file taxes'? [y/N] "
+            );
+        }
+    }
+
+    #[test]
+    fn test_due_takes_bare_backward_offsets() {
+        let parse = |line: &str| {
+            let args = std::iter::once("tt")
+                .chain(line.split(' '))
+                .map(String::from)
+                .collect();
+            match Cli::try_parse_from(protect_due_offset(args))
+                .unwrap()
+                .command
+            {
+                Some(Commands::Due { args, if_none, .. }) => (args, if_none),
+                _ => panic!("not tt due"),
+            }
+        };
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(parse("due 3 -1w"), (args(&["3", "-1w"]), false));
+        assert_eq!(
+            parse("due 3 4 -2d --if-none"),
+            (args(&["3", "4", "-2d"]), true)
+        );
+        assert_eq!(parse("due --if-none 3 -1w"), (args(&["3", "-1w"]), true));
+        assert_eq!(
+            parse("due 3 friday --if-none"),
+            (args(&["3", "friday"]), true)
+        );
+        assert_eq!(parse("due 3 -- -1w"), (args(&["3", "-1w"]), false));
+        assert!(
+            Cli::try_parse_from(protect_due_offset(
+                ["tt", "due", "--where", "tag = conf", "-1w", "--json"]
+                    .map(String::from)
+                    .to_vec()
+            ))
+            .is_ok_and(|cli| cli.json)
+        );
     }
 }
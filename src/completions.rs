@@ -0,0 +1,184 @@
+use clap::Command;
+use clap_complete::Shell;
+use tt::ids::{self, IdStyle};
+use tt::models::todo::Todo;
+
+/// Names of the positional arguments that take todo IDs.
+const ID_ARGS: [&str; 2] = ["id", "ids"];
+
+/// Subcommands whose IDs name archived todos or drafts, which
+/// `__complete-ids` does not list.
+const OTHER_IDS: [&str; 2] = ["unarchive", "publish"];
+
+/// The completion script for `shell`, without hidden subcommands. The zsh
+/// and fish scripts also complete todo IDs, with titles, by asking
+/// `tt __complete-ids`.
+pub fn script(shell: Shell, cmd: Command) -> String {
+    let mut cmd = without_hidden(cmd);
+    let name = cmd.get_name().to_string();
+    let commands = id_commands(&cmd);
+    let mut buf = Vec::new();
+    clap_complete::generate(shell, &mut cmd, &name, &mut buf);
+    let script = String::from_utf8_lossy(&buf).into_owned();
+    match shell {
+        Shell::Zsh => with_zsh_ids(&script, &name, &commands),
+        Shell::Fish => with_fish_ids(script, &name, &commands),
+        _ => script,
+    }
+}
+
+/// One `id<TAB>title` line per incomplete todo, for shells to offer.
+pub fn id_lines(todos: &[Todo], style: IdStyle) -> String {
+    todos
+        .iter()
+        .filter(|todo| !todo.completed)
+        .map(|todo| {
+            let title: String = todo
+                .title
+                .chars()
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .collect();
+            format!("{}\t{}\n", ids::display_id(todo, style), title)
+        })
+        .collect()
+}
+
+/// `cmd` without its hidden subcommands, which the generators would
+/// otherwise offer.
+fn without_hidden(cmd: Command) -> Command {
+    let mut visible = Command::new("tt")
+        .version(env!("CARGO_PKG_VERSION"))
+        .args(cmd.get_arguments().cloned())
+        .subcommands(
+            cmd.get_subcommands()
+                .filter(|sub| !sub.is_hide_set())
+                .cloned(),
+        );
+    if let Some(about) = cmd.get_about() {
+        visible = visible.about(about.clone());
+    }
+    visible
+}
+
+/// The top-level subcommands with a positional argument taking the IDs of
+/// listed todos.
+fn id_commands(cmd: &Command) -> Vec<String> {
+    cmd.get_subcommands()
+        .filter(|sub| !OTHER_IDS.contains(&sub.get_name()))
+        .filter(|sub| {
+            sub.get_positionals()
+                .any(|arg| ID_ARGS.contains(&arg.get_id().as_str()))
+        })
+        .map(|sub| sub.get_name().to_string())
+        .collect()
+}
+
+/// Lists todos for zsh to offer, as `id:title` pairs for `_describe`.
+const ZSH_TODO_IDS: &str = r#"(( $+functions[_NAME__todo_ids] )) ||
+_NAME__todo_ids() {
+    local -a todos
+    todos=(${(f)"$(NAME __complete-ids 2>/dev/null)"})
+    todos=("${(@)todos/$'\t'/:}")
+    _describe -t todos 'todo' todos
+}
+
+"#;
+
+/// Complete the ID arguments of `commands` with a function listing todos,
+/// defined before the script first runs `_tt`.
+fn with_zsh_ids(script: &str, name: &str, commands: &[String]) -> String {
+    let function = format!("_{name}__todo_ids");
+    let mut out = String::new();
+    let mut command = "";
+    for line in script.lines() {
+        if let Some(sub) = line.strip_prefix('(').and_then(|l| l.strip_suffix(')')) {
+            command = sub;
+        }
+        let spec = line.trim_start_matches(['\'', '*', ':']);
+        let takes_ids = ID_ARGS
+            .iter()
+            .any(|arg| spec.starts_with(&format!("{arg} -- ")));
+        match line.strip_suffix(":_default' \\") {
+            Some(start) if takes_ids && commands.iter().any(|c| c == command) => {
+                out.push_str(&format!("{start}:{function}' \\\n"));
+            }
+            _ => {
+                if line.starts_with("if [ \"$funcstack[1]\"") {
+                    out.push_str(&ZSH_TODO_IDS.replace("NAME", name));
+                }
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+/// Offer todo IDs, with titles, after each of `commands`.
+fn with_fish_ids(mut script: String, name: &str, commands: &[String]) -> String {
+    script.push_str(&format!(
+        "complete -c {name} -n \"__fish_{name}_using_subcommand {}\" -f -a \"({name} __complete-ids 2>/dev/null)\"\n",
+        commands.join(" ")
+    ));
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::CommandFactory;
+    use tt::models::todo::Priority;
+
+    #[test]
+    fn test_id_lines_list_incomplete_todos() {
+        let mut todos: Vec<Todo> = ["Write\treport", "Ship it", "Done already"]
+            .iter()
+            .enumerate()
+            .map(|(i, title)| Todo::new(i + 1, title.to_string(), Priority::Low))
+            .collect();
+        todos[2].set_completed(true);
+        assert_eq!(
+            id_lines(&todos, IdStyle::Sequential),
+            "1\tWrite report\n2\tShip it\n"
+        );
+    }
+
+    #[test]
+    fn test_zsh_and_fish_complete_todo_ids() {
+        let zsh = script(Shell::Zsh, Cli::command());
+        assert!(zsh.contains("_tt__todo_ids() {"));
+        assert!(zsh.contains(":_tt__todo_ids' \\\n"));
+        // Defined before the script first runs _tt
+        assert!(zsh.find("_tt__todo_ids() {") < zsh.find("if [ \"$funcstack[1]\""));
+        let unarchive = zsh.find("\n(unarchive)\n").unwrap();
+        let block = &zsh[unarchive..][..zsh[unarchive + 1..].find("\n;;").unwrap()];
+        assert!(block.contains("':id -- "), "{block}");
+        assert!(!block.contains("_tt__todo_ids"), "{block}");
+
+        let fish = script(Shell::Fish, Cli::command());
+        let line = fish
+            .lines()
+            .find(|line| line.contains("(tt __complete-ids 2>/dev/null)"))
+            .unwrap();
+        assert!(line.contains(" complete "), "{line}");
+        assert!(line.contains(" show "), "{line}");
+        assert!(!line.contains("unarchive"), "{line}");
+    }
+
+    #[test]
+    fn test_hidden_subcommands_are_not_offered() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let script = script(shell, Cli::command());
+            assert!(script.contains("completions"), "{shell}");
+            // Only the calls made to complete IDs mention it
+            assert!(
+                script
+                    .lines()
+                    .filter(|line| line.contains("__complete-ids"))
+                    .all(|line| line.contains("2>/dev/null")),
+                "{shell}"
+            );
+        }
+    }
+}
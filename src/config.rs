@@ -0,0 +1,340 @@
+use crate::age;
+use crate::attention::AttentionWeights;
+use crate::conventions::ConventionRule;
+use crate::duration::parse_duration;
+use crate::focus;
+use crate::ids::IdStyle;
+use crate::maintenance::Retention;
+use crate::notes;
+use crate::render::Style;
+use crate::todo_manager::SortKey;
+use anyhow::{Context, Result};
+use chrono::Duration;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// User configuration read from `config.toml` in the tt config directory.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Strictly validate the todo file before modifying it if it was
+    /// edited outside tt since the last write
+    pub validate_external_edits: bool,
+    /// How todo IDs are displayed and typed
+    pub id_style: IdStyle,
+    /// Run the only close match for a mistyped subcommand instead of just
+    /// suggesting it (destructive commands still ask first)
+    pub autocorrect: bool,
+    pub check: CheckConfig,
+    /// Weights used to rank `tt attention`
+    pub attention: AttentionWeights,
+    /// Mark open todos in listings with dots that grow with their age
+    pub show_age_dots: bool,
+    pub age_dots: AgeDotsConfig,
+    /// Largest notes, in bytes, that `add` and `edit` accept
+    pub max_notes_bytes: Option<usize>,
+    pub normalize: NormalizeConfig,
+    pub focus: FocusConfig,
+    pub maintenance: MaintenanceConfig,
+    /// Priority of new todos when `add` is given none (default 4)
+    pub default_priority: Option<u8>,
+    /// Leave completed todos out of listings
+    pub hide_completed: bool,
+    /// Order listings by this key unless `--sort` is given
+    pub sort: Option<SortKey>,
+    /// Print plain text markers instead of emoji
+    pub no_emoji: bool,
+    /// Rules titles are checked against on add and edit and by `tt lint`
+    pub conventions: Vec<ConventionRule>,
+    /// Refuse titles that break a convention instead of warning
+    pub strict_conventions: bool,
+    /// How todos are laid out unless `--style` is given
+    pub style: Style,
+    /// Save the todo file as compact single-line JSON, for big stores
+    pub compact_json: bool,
+}
+
+/// When todos gain age dots, with `show_age_dots` on.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct AgeDotsConfig {
+    /// Ages (e.g. "3d", "2w") at which another dot is added
+    pub thresholds: Vec<String>,
+}
+
+impl Default for AgeDotsConfig {
+    fn default() -> Self {
+        Self {
+            thresholds: age::DEFAULT_THRESHOLDS
+                .iter()
+                .map(|threshold| threshold.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Default thresholds for `tt check`, overridden by command-line flags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CheckConfig {
+    pub max_pending: Option<usize>,
+    pub max_age: Option<String>,
+    pub max_overdue: Option<usize>,
+}
+
+/// How `tt focus` sessions behave.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FocusConfig {
+    /// How long a session lasts (e.g. "90m", "4h"), unless `--for` is given
+    pub expire_after: Option<String>,
+}
+
+/// How `tt maintenance` runs and how long it keeps sidecar history.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    /// Also run maintenance, at most once a week, after commands that
+    /// change the store
+    pub auto: bool,
+    /// How long to keep the rotated journal (e.g. "365d")
+    pub journal: Option<String>,
+    /// How long to keep undo snapshots
+    pub undo: Option<String>,
+    /// How long to keep backups taken by `tt normalize`
+    pub backups: Option<String>,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            auto: true,
+            journal: None,
+            undo: None,
+            backups: None,
+        }
+    }
+}
+
+/// Defaults for `tt normalize`, added to by command-line flags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct NormalizeConfig {
+    /// Priorities to replace, as `FROM=TO`
+    pub map_priority: Vec<String>,
+    /// Creation date for todos the journal knows nothing about
+    pub created_at: Option<String>,
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        match Self::get_file_path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))
+    }
+
+    /// The notes size cap, `notes::DEFAULT_MAX_BYTES` unless configured.
+    pub fn max_notes_bytes(&self) -> usize {
+        self.max_notes_bytes.unwrap_or(notes::DEFAULT_MAX_BYTES)
+    }
+
+    /// How long focus sessions last, `focus::DEFAULT_EXPIRY` unless configured.
+    pub fn focus_expiry(&self) -> Result<Duration> {
+        match &self.focus.expire_after {
+            Some(expire_after) => parse_duration(expire_after)
+                .map_err(|e| anyhow::anyhow!("Invalid focus.expire_after in config: {e}")),
+            None => Ok(focus::DEFAULT_EXPIRY),
+        }
+    }
+
+    /// Retention for `tt maintenance`, `Retention::default()` for what is
+    /// not configured.
+    pub fn retention(&self) -> Result<Retention> {
+        let parse = |value: &Option<String>, key: &str, default: Duration| match value {
+            Some(value) => parse_duration(value)
+                .map_err(|e| anyhow::anyhow!("Invalid maintenance.{key} in config: {e}")),
+            None => Ok(default),
+        };
+        let defaults = Retention::default();
+        Ok(Retention {
+            journal: parse(&self.maintenance.journal, "journal", defaults.journal)?,
+            undo: parse(&self.maintenance.undo, "undo", defaults.undo)?,
+            backups: parse(&self.maintenance.backups, "backups", defaults.backups)?,
+        })
+    }
+
+    pub fn get_file_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("tt").join("config.toml"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_missing_config_is_default() {
+        let temp_dir = tempdir().unwrap();
+        let config = Config::load_from(&temp_dir.path().join("config.toml")).unwrap();
+        assert_eq!(config.check.max_pending, None);
+        assert_eq!(config.check.max_age, None);
+        assert_eq!(config.check.max_overdue, None);
+    }
+
+    #[test]
+    fn test_load_check_thresholds() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[check]\nmax_pending = 50\nmax_age = \"30d\"\nmax_overdue = 0\n",
+        )
+        .unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.check.max_pending, Some(50));
+        assert_eq!(config.check.max_age.as_deref(), Some("30d"));
+        assert_eq!(config.check.max_overdue, Some(0));
+    }
+
+    #[test]
+    fn test_partial_attention_weights_keep_defaults() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "[attention]\nage = 2.0\n").unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.attention.age, 2.0);
+        assert_eq!(
+            config.attention.priority,
+            AttentionWeights::default().priority
+        );
+    }
+
+    #[test]
+    fn test_id_style() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        assert_eq!(
+            Config::load_from(&path).unwrap().id_style,
+            IdStyle::Sequential
+        );
+
+        fs::write(&path, "id_style = \"hash\"\n").unwrap();
+        assert_eq!(Config::load_from(&path).unwrap().id_style, IdStyle::Hash);
+
+        fs::write(&path, "id_style = \"emoji\"\n").unwrap();
+        assert!(Config::load_from(&path).is_err());
+    }
+
+    #[test]
+    fn test_age_dots() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let config = Config::load_from(&path).unwrap();
+        assert!(!config.show_age_dots);
+        assert_eq!(config.age_dots.thresholds, vec!["3d", "2w", "60d"]);
+
+        fs::write(
+            &path,
+            "show_age_dots = true\n[age_dots]\nthresholds = [\"1d\", \"1w\"]\n",
+        )
+        .unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert!(config.show_age_dots);
+        assert_eq!(config.age_dots.thresholds, vec!["1d", "1w"]);
+    }
+
+    #[test]
+    fn test_list_defaults() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "default_priority = 2\nhide_completed = true\nsort = \"due\"\nno_emoji = true\nstyle = \"table\"\n",
+        )
+        .unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert_eq!(config.default_priority, Some(2));
+        assert!(config.hide_completed);
+        assert_eq!(config.sort, Some(SortKey::Due));
+        assert!(config.no_emoji);
+        assert_eq!(config.style, Style::Table);
+    }
+
+    #[test]
+    fn test_conventions() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(
+            &path,
+            r#"strict_conventions = true
+
+[[conventions]]
+name = "prefix"
+must_match = "^[a-z]+: "
+message = "Start with a component prefix like 'api:'"
+lists = ["work"]
+
+[[conventions]]
+name = "period"
+must_not_match = '\.$'
+message = "No trailing period"
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load_from(&path).unwrap();
+        assert!(config.strict_conventions);
+        assert_eq!(config.conventions.len(), 2);
+        assert_eq!(config.conventions[0].lists, vec!["work"]);
+        assert_eq!(
+            config.conventions[1].must_not_match.as_deref(),
+            Some(r"\.$")
+        );
+    }
+
+    #[test]
+    fn test_maintenance_retention() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let config = Config::load_from(&path).unwrap();
+        assert!(config.maintenance.auto);
+        assert_eq!(config.retention().unwrap().undo, Retention::default().undo);
+
+        fs::write(&path, "[maintenance]\nauto = false\nundo = \"1w\"\n").unwrap();
+        let config = Config::load_from(&path).unwrap();
+        assert!(!config.maintenance.auto);
+        let retention = config.retention().unwrap();
+        assert_eq!(retention.undo, Duration::weeks(1));
+        assert_eq!(retention.journal, Retention::default().journal);
+
+        fs::write(&path, "[maintenance]\nbackups = \"soon\"\n").unwrap();
+        let error = Config::load_from(&path).unwrap().retention().unwrap_err();
+        assert!(error.to_string().contains("maintenance.backups"), "{error}");
+    }
+
+    #[test]
+    fn test_invalid_config_is_error() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        fs::write(&path, "[check]\nmax_pending = \"lots\"\n").unwrap();
+
+        assert!(Config::load_from(&path).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Marker Syncthing puts in the name of a conflicting copy, as in
+/// `.tt.sync-conflict-20250101-120000-ABCDEFG.json` for `.tt.json`.
+const MARKER: &str = ".sync-conflict-";
+
+/// Suffix given to conflict files once they have been merged.
+const MERGED_SUFFIX: &str = "merged";
+
+/// Conflicting copies of `path` left next to it by Syncthing, oldest name
+/// first. A missing or unreadable directory has none.
+pub fn find(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|n| n.to_str())) else {
+        return Vec::new();
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|candidate| is_conflict_of(candidate, name))
+        })
+        .map(|entry| entry.path())
+        .collect();
+    found.sort();
+    found
+}
+
+/// Whether `candidate` is the name of a conflicting copy of `name`.
+fn is_conflict_of(candidate: &str, name: &str) -> bool {
+    // Syncthing inserts the marker before the extension; names without one
+    // get it at the end
+    let (stem, extension) = match name.rfind('.') {
+        Some(index) if index > 0 => name.split_at(index),
+        _ => (name, ""),
+    };
+    candidate
+        .strip_prefix(stem)
+        .and_then(|rest| rest.strip_prefix(MARKER))
+        .and_then(|rest| rest.strip_suffix(extension))
+        .is_some_and(|stamp| !stamp.is_empty())
+}
+
+/// Rename a merged conflict file out of the way, returning its new path.
+pub fn mark_merged(path: &Path) -> Result<PathBuf> {
+    let mut merged = path.as_os_str().to_owned();
+    merged.push(".");
+    merged.push(MERGED_SUFFIX);
+    let merged = PathBuf::from(merged);
+    fs::rename(path, &merged).with_context(|| format!("Failed to rename {}", path.display()))?;
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_conflict_of() {
+        let name = ".tt.json";
+        assert!(is_conflict_of(
+            ".tt.sync-conflict-20250101-120000-ABCDEFG.json",
+            name
+        ));
+        assert!(!is_conflict_of(".tt.json", name));
+        assert!(!is_conflict_of(".tt.sync-conflict-.json", name));
+        assert!(!is_conflict_of(
+            ".tt.archive.sync-conflict-20250101-120000-ABCDEFG.json",
+            name
+        ));
+        assert!(!is_conflict_of(
+            ".tt.sync-conflict-20250101-120000-ABCDEFG.json.merged",
+            name
+        ));
+        assert!(is_conflict_of("todos.sync-conflict-1", "todos"));
+    }
+
+    #[test]
+    fn test_find_and_mark_merged() {
+        let temp_dir = tempdir().unwrap();
+        let store = temp_dir.path().join(".tt.json");
+        for name in [
+            ".tt.json",
+            ".tt.sync-conflict-20250102-090000-BBBBBBB.json",
+            ".tt.sync-conflict-20250101-090000-AAAAAAA.json",
+            ".tt.archive.json",
+        ] {
+            fs::write(temp_dir.path().join(name), "{}").unwrap();
+        }
+
+        let found = find(&store);
+        let names: Vec<_> = found.iter().map(|p| p.file_name().unwrap()).collect();
+        assert_eq!(
+            names,
+            vec![
+                ".tt.sync-conflict-20250101-090000-AAAAAAA.json",
+                ".tt.sync-conflict-20250102-090000-BBBBBBB.json",
+            ]
+        );
+
+        let merged = mark_merged(&found[0]).unwrap();
+        assert!(merged.to_string_lossy().ends_with(".json.merged"));
+        assert_eq!(find(&store).len(), 1);
+        assert!(find(&temp_dir.path().join("missing/.tt.json")).is_empty());
+    }
+}
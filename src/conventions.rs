@@ -0,0 +1,189 @@
+use crate::models::todo::Todo;
+use anyhow::{Result, bail};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// A title convention as written in the config file: a regex titles must
+/// or must not match, and the message shown when one doesn't.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConventionRule {
+    pub name: String,
+    #[serde(default)]
+    pub must_match: Option<String>,
+    #[serde(default)]
+    pub must_not_match: Option<String>,
+    pub message: String,
+    /// Named lists the rule applies to; every store when empty
+    #[serde(default)]
+    pub lists: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    pattern: Regex,
+    must_match: bool,
+    message: String,
+}
+
+/// The title conventions of one store, compiled once and applied to each
+/// title.
+#[derive(Debug, Default)]
+pub struct Conventions {
+    rules: Vec<Rule>,
+}
+
+/// A todo whose title breaks conventions, as reported by `tt lint`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Violation<'a> {
+    pub id: usize,
+    pub title: &'a str,
+    pub messages: Vec<&'a str>,
+}
+
+impl Conventions {
+    /// Compile the rules that apply to the store `list` (`None` for a store
+    /// opened by path, which only gets rules without `lists`). Every rule is
+    /// compiled, so a broken one is reported whichever store is in use.
+    pub fn compile(rules: &[ConventionRule], list: Option<&str>) -> Result<Self> {
+        let mut compiled = Vec::new();
+        for rule in rules {
+            let (pattern, must_match) = match (&rule.must_match, &rule.must_not_match) {
+                (Some(pattern), None) => (pattern, true),
+                (None, Some(pattern)) => (pattern, false),
+                _ => bail!(
+                    "Title convention '{}' needs exactly one of must_match and must_not_match",
+                    rule.name
+                ),
+            };
+            let pattern = Regex::new(pattern).map_err(|e| {
+                anyhow::anyhow!("Invalid regex in title convention '{}': {e}", rule.name)
+            })?;
+            let applies = rule.lists.is_empty()
+                || list.is_some_and(|list| rule.lists.iter().any(|name| name == list));
+            if applies {
+                compiled.push(Rule {
+                    pattern,
+                    must_match,
+                    message: rule.message.clone(),
+                });
+            }
+        }
+        Ok(Self { rules: compiled })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The messages of the rules `title` breaks, in config order.
+    pub fn check(&self, title: &str) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(title) != rule.must_match)
+            .map(|rule| rule.message.as_str())
+            .collect()
+    }
+
+    /// The todos among `todos` whose titles break a rule.
+    pub fn lint<'a>(&'a self, todos: &'a [Todo]) -> Vec<Violation<'a>> {
+        todos
+            .iter()
+            .filter_map(|todo| {
+                let messages = self.check(&todo.title);
+                (!messages.is_empty()).then_some(Violation {
+                    id: todo.id,
+                    title: &todo.title,
+                    messages,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn rule(name: &str, must_match: Option<&str>, must_not_match: Option<&str>) -> ConventionRule {
+        ConventionRule {
+            name: name.to_string(),
+            must_match: must_match.map(str::to_string),
+            must_not_match: must_not_match.map(str::to_string),
+            message: format!("{name} broken"),
+            lists: Vec::new(),
+        }
+    }
+
+    fn rules() -> Vec<ConventionRule> {
+        vec![
+            rule("prefix", Some(r"^[a-z]+: "), None),
+            rule("period", None, Some(r"\.$")),
+        ]
+    }
+
+    #[test]
+    fn test_check_applies_each_rule() {
+        let conventions = Conventions::compile(&rules(), None).unwrap();
+        assert!(conventions.check("api: add rate limits").is_empty());
+        assert_eq!(
+            conventions.check("api: add rate limits."),
+            vec!["period broken"]
+        );
+        assert_eq!(
+            conventions.check("Add rate limits."),
+            vec!["prefix broken", "period broken"]
+        );
+    }
+
+    #[test]
+    fn test_rules_can_be_limited_to_lists() {
+        let mut rules = rules();
+        rules[0].lists = vec!["work".to_string()];
+        let work = Conventions::compile(&rules, Some("work")).unwrap();
+        assert_eq!(work.check("Add rate limits"), vec!["prefix broken"]);
+        let home = Conventions::compile(&rules, Some("home")).unwrap();
+        assert!(home.check("Add rate limits").is_empty());
+        let by_path = Conventions::compile(&rules, None).unwrap();
+        assert!(by_path.check("Add rate limits").is_empty());
+        assert!(Conventions::compile(&[], None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bad_rules_are_errors_naming_the_rule() {
+        let mut rules = rules();
+        rules.push(rule("broken", Some("api: ("), None));
+        let error = Conventions::compile(&rules, Some("home")).unwrap_err();
+        assert!(error.to_string().contains("'broken'"), "{error}");
+
+        let both = rule("both", Some("a"), Some("b"));
+        let error = Conventions::compile(&[both], None).unwrap_err();
+        assert!(error.to_string().contains("'both'"), "{error}");
+        assert!(Conventions::compile(&[rule("neither", None, None)], None).is_err());
+    }
+
+    #[test]
+    fn test_lint_reports_breaking_todos() {
+        let conventions = Conventions::compile(&rules(), None).unwrap();
+        let todos: Vec<Todo> = ["api: fine", "Fix login.", "db: tidy up."]
+            .iter()
+            .enumerate()
+            .map(|(i, title)| Todo::new(i + 1, title.to_string(), Priority::Low))
+            .collect();
+        assert_eq!(
+            conventions.lint(&todos),
+            vec![
+                Violation {
+                    id: 2,
+                    title: "Fix login.",
+                    messages: vec!["prefix broken", "period broken"],
+                },
+                Violation {
+                    id: 3,
+                    title: "db: tidy up.",
+                    messages: vec!["period broken"],
+                },
+            ]
+        );
+    }
+}
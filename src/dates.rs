@@ -0,0 +1,275 @@
+use crate::duration::parse_days;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveTime, TimeZone, Weekday};
+
+/// The forms a date can be typed in, for error messages.
+pub const ACCEPTED: &str = "YYYY-MM-DD, today, tomorrow, yesterday, a weekday (friday), \
+     next week, next month, in 3 days, 2d, 1w or jun 15";
+
+/// The start of the day `input` names, in the time zone of `now` and
+/// counting from `now`. See [`parse_day`] for what is accepted.
+pub fn parse_human_date<Tz: TimeZone>(
+    input: &str,
+    now: DateTime<Tz>,
+) -> Result<DateTime<Tz>, String> {
+    let day = parse_day(input, now.date_naive())?;
+    now.timezone()
+        .from_local_datetime(&day.and_time(NaiveTime::MIN))
+        .earliest()
+        .ok_or_else(|| format!("'{input}' has no midnight in this time zone"))
+}
+
+/// The day `input` names, counting from `today`:
+///
+/// - `2025-06-15`, `today`, `tomorrow` or `yesterday`
+/// - a weekday (`friday`, `fri`, `next friday`): the next one after
+///   today, so `friday` on a Friday is a week away
+/// - `next week` (the coming Monday) or `next month` (the 1st of it)
+/// - `in 3 days`, `in 2 weeks`, `in 1 month`, or `3d` and `2w` for short
+/// - a month and day (`jun 15`, `15 june`), the next one from today on,
+///   or with a year (`jun 15 2026`)
+///
+/// Times of day and ambiguous forms like `06/07` are refused with a
+/// message listing what is accepted.
+pub fn parse_day(input: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let normalized = input.trim().to_lowercase();
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+    let day = match words.as_slice() {
+        ["today"] => Some(today),
+        ["tomorrow"] => today.succ_opt(),
+        ["yesterday"] => today.pred_opt(),
+        ["next", "week"] => Some(after(today, Weekday::Mon)),
+        ["next", "month"] => today
+            .with_day(1)
+            .and_then(|first| first.checked_add_months(Months::new(1))),
+        ["next", weekday] | [weekday] if weekday.parse::<Weekday>().is_ok() => {
+            weekday.parse().ok().map(|weekday| after(today, weekday))
+        }
+        ["in", amount, unit] => amount.parse().ok().and_then(|n| ahead(today, n, unit)),
+        [shorthand] if shorthand.ends_with(['d', 'w']) => {
+            let (amount, unit) = shorthand.split_at(shorthand.len() - 1);
+            amount.parse().ok().and_then(|n| ahead(today, n, unit))
+        }
+        [date] if date.contains('-') => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+        [first, second] => month_day(first, second, today),
+        [first, second, year] => year
+            .parse()
+            .ok()
+            .and_then(|year| month_day_in(first, second, year)),
+        _ => None,
+    };
+    day.ok_or_else(|| {
+        if looks_like_time(&normalized) {
+            format!("'{input}' is a time of day, but dates here are whole days; use {ACCEPTED}")
+        } else if normalized.contains('/') {
+            format!("'{input}' is ambiguous (day or month first?); use {ACCEPTED}")
+        } else {
+            format!("Invalid date '{input}', expected {ACCEPTED}")
+        }
+    })
+}
+
+/// How long ago `input` was: a number of days or a duration like `2w`, as
+/// `parse_days` takes, or a date that isn't in the future.
+pub fn parse_age<Tz: TimeZone>(input: &str, now: DateTime<Tz>) -> Result<Duration, String> {
+    if let Ok(age) = parse_days(input) {
+        return Ok(age);
+    }
+    let since = parse_human_date(input, now.clone()).map_err(|_| {
+        format!("Invalid age '{input}', expected days (30), 2w, or a date: {ACCEPTED}")
+    })?;
+    if since > now {
+        return Err(format!(
+            "'{input}' is in the future; give a past date like 2025-06-15 or an age like 30"
+        ));
+    }
+    Ok(now - since)
+}
+
+/// The first `weekday` after `today`.
+fn after(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead =
+        (weekday.num_days_from_monday() + 6 - today.weekday().num_days_from_monday()) % 7 + 1;
+    today + Duration::days(ahead.into())
+}
+
+/// `amount` days, weeks or months after `today`. Months keep the day of
+/// the month where they can, and otherwise end on the month's last day.
+fn ahead(today: NaiveDate, amount: u32, unit: &str) -> Option<NaiveDate> {
+    match unit {
+        "d" | "day" | "days" => today.checked_add_signed(Duration::days(amount.into())),
+        "w" | "week" | "weeks" => today.checked_add_signed(Duration::weeks(amount.into())),
+        "month" | "months" => today.checked_add_months(Months::new(amount)),
+        _ => None,
+    }
+}
+
+/// `jun 15` or `15 jun`, on or after `today`. February 29th waits for
+/// the next leap year.
+fn month_day(first: &str, second: &str, today: NaiveDate) -> Option<NaiveDate> {
+    (today.year()..today.year() + 8)
+        .filter_map(|year| month_day_in(first, second, year))
+        .find(|day| *day >= today)
+}
+
+fn month_day_in(first: &str, second: &str, year: i32) -> Option<NaiveDate> {
+    let (month, day) = match (month(first), month(second)) {
+        (Some(month), None) => (month, second),
+        (None, Some(month)) => (month, first),
+        _ => return None,
+    };
+    NaiveDate::from_ymd_opt(year, month, day.parse().ok()?)
+}
+
+/// The month number of a month name or its first three letters.
+fn month(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    if name.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(name))
+        .map(|index| index as u32 + 1)
+}
+
+fn looks_like_time(input: &str) -> bool {
+    input.contains(':')
+        || ["am", "pm", "noon", "midnight"]
+            .iter()
+            .any(|suffix| input.ends_with(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_day() {
+        // Friday 2025-01-31, the end of a month
+        let today = date(2025, 1, 31);
+        let cases = [
+            ("2025-06-15", date(2025, 6, 15)),
+            ("today", today),
+            (" Tomorrow ", date(2025, 2, 1)),
+            ("yesterday", date(2025, 1, 30)),
+            ("friday", date(2025, 2, 7)),
+            ("FRI", date(2025, 2, 7)),
+            ("next friday", date(2025, 2, 7)),
+            ("saturday", date(2025, 2, 1)),
+            ("thu", date(2025, 2, 6)),
+            ("next week", date(2025, 2, 3)),
+            ("next month", date(2025, 2, 1)),
+            ("in 3 days", date(2025, 2, 3)),
+            ("in 1 day", date(2025, 2, 1)),
+            ("in 2 weeks", date(2025, 2, 14)),
+            ("in 1 month", date(2025, 2, 28)),
+            ("in 13 months", date(2026, 2, 28)),
+            ("2d", date(2025, 2, 2)),
+            ("0d", today),
+            ("1w", date(2025, 2, 7)),
+            ("jun 15", date(2025, 6, 15)),
+            ("15 June", date(2025, 6, 15)),
+            ("jan 31", today),
+            ("jan 30", date(2026, 1, 30)),
+            ("dec 31", date(2025, 12, 31)),
+            ("jun 15 2027", date(2027, 6, 15)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_day(input, today), Ok(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_leap_days() {
+        let today = date(2025, 3, 1);
+        assert_eq!(parse_day("feb 29", today), Ok(date(2028, 2, 29)));
+        assert_eq!(parse_day("feb 29 2024", today), Ok(date(2024, 2, 29)));
+        assert!(parse_day("feb 29 2025", today).is_err());
+        assert!(parse_day("2025-02-29", today).is_err());
+        assert_eq!(
+            parse_day("in 1 month", date(2024, 1, 31)),
+            Ok(date(2024, 2, 29))
+        );
+        assert_eq!(
+            parse_day("tomorrow", date(2024, 2, 28)),
+            Ok(date(2024, 2, 29))
+        );
+        assert_eq!(parse_day("1w", date(2024, 2, 25)), Ok(date(2024, 3, 3)));
+    }
+
+    #[test]
+    fn test_parse_day_refuses_unclear_input() {
+        let today = date(2025, 4, 16);
+        for bad in [
+            "",
+            "someday",
+            "in days",
+            "in 3 years",
+            "3x",
+            "-2d",
+            "ju 15",
+            "jun 31",
+            "jun",
+            "2025-13-01",
+        ] {
+            let error = parse_day(bad, today).unwrap_err();
+            assert!(error.contains("YYYY-MM-DD"), "{bad}: {error}");
+        }
+        for time in ["3pm", "15:00", "noon", "tomorrow 9am"] {
+            assert!(
+                parse_day(time, today).unwrap_err().contains("time of day"),
+                "{time}"
+            );
+        }
+        assert!(parse_day("06/07", today).unwrap_err().contains("ambiguous"));
+    }
+
+    #[test]
+    fn test_parse_human_date_is_start_of_day() {
+        let now = DateTime::parse_from_rfc3339("2025-04-18T15:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let expected = DateTime::parse_from_rfc3339("2025-04-25T00:00:00Z").unwrap();
+        // A Friday, so "friday" is next week's
+        assert_eq!(parse_human_date("friday", now).unwrap(), expected);
+        let offset = DateTime::parse_from_rfc3339("2025-04-18T23:30:00-05:00").unwrap();
+        assert_eq!(
+            parse_human_date("tomorrow", offset).unwrap().to_rfc3339(),
+            "2025-04-19T00:00:00-05:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_age() {
+        let now = DateTime::parse_from_rfc3339("2025-04-18T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_age("30", now), Ok(Duration::days(30)));
+        assert_eq!(parse_age("2w", now), Ok(Duration::weeks(2)));
+        assert_eq!(parse_age("yesterday", now), Ok(Duration::hours(36)));
+        assert_eq!(
+            parse_age("2025-04-01", now),
+            Ok(Duration::hours(17 * 24 + 12))
+        );
+        assert!(parse_age("tomorrow", now).unwrap_err().contains("future"));
+        assert!(parse_age("soon", now).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use crate::models::todo::Todo;
+use chrono::{DateTime, Duration, Local, Utc};
+
+/// How far ahead "due soon" looks, in days.
+const DUE_SOON_DAYS: i64 = 3;
+
+/// How far back the very first digest looks.
+pub const FIRST_DIGEST_WINDOW_DAYS: i64 = 7;
+
+/// What changed in a store between `since` and now.
+#[derive(Debug)]
+pub struct Digest {
+    pub since: DateTime<Utc>,
+    pub first_run: bool,
+    pub added: Vec<Todo>,
+    pub newly_overdue: Vec<Todo>,
+    pub due_soon: Vec<Todo>,
+}
+
+impl Digest {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.newly_overdue.is_empty() && self.due_soon.is_empty()
+    }
+}
+
+/// Build a digest of `todos` for the window from `last_viewed` to `now`.
+/// A `last_viewed` in the future (clock skew between machines) is clamped
+/// to `now`, so the window is never negative. Without a `last_viewed` the
+/// window covers the last week.
+pub fn build(todos: &[Todo], last_viewed: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Digest {
+    let since = last_viewed
+        .unwrap_or(now - Duration::days(FIRST_DIGEST_WINDOW_DAYS))
+        .min(now);
+    let since_date = since.with_timezone(&Local).date_naive();
+    let today = now.with_timezone(&Local).date_naive();
+
+    let added = todos
+        .iter()
+        .filter(|todo| {
+            DateTime::parse_from_rfc3339(&todo.created_at)
+                .is_ok_and(|created| created > since && created <= now)
+        })
+        .cloned()
+        .collect();
+
+    let pending = || todos.iter().filter(|todo| !todo.completed);
+
+    // Overdue now, but was not yet overdue when last viewed
+    let newly_overdue = pending()
+        .filter(|todo| {
+            todo.due_date
+                .is_some_and(|due| due >= since_date && due < today)
+        })
+        .cloned()
+        .collect();
+
+    let due_soon = pending()
+        .filter(|todo| {
+            todo.due_date
+                .is_some_and(|due| due >= today && (due - today).num_days() <= DUE_SOON_DAYS)
+        })
+        .cloned()
+        .collect();
+
+    Digest {
+        since,
+        first_run: last_viewed.is_none(),
+        added,
+        newly_overdue,
+        due_soon,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        // Midday keeps local dates stable across test machine time zones
+        Utc.with_ymd_and_hms(2025, 6, 15, 12, 0, 0).unwrap()
+    }
+
+    fn today() -> chrono::NaiveDate {
+        now().with_timezone(&Local).date_naive()
+    }
+
+    fn todo(id: usize, created: DateTime<Utc>) -> Todo {
+        Todo {
+            id,
+            created_at: created.to_rfc3339(),
+            ..Todo::default()
+        }
+    }
+
+    fn ids(todos: &[Todo]) -> Vec<usize> {
+        todos.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn test_added_since_last_viewed() {
+        let last_viewed = now() - Duration::days(2);
+        let todos = vec![
+            todo(1, now() - Duration::days(5)),
+            todo(2, now() - Duration::days(1)),
+            todo(3, now() - Duration::hours(1)),
+        ];
+
+        let digest = build(&todos, Some(last_viewed), now());
+        assert_eq!(ids(&digest.added), vec![2, 3]);
+        assert!(!digest.first_run);
+    }
+
+    #[test]
+    fn test_newly_overdue() {
+        let last_viewed = now() - Duration::days(3);
+        let old = now() - Duration::days(30);
+        let mut already_overdue = todo(1, old);
+        already_overdue.due_date = Some(today() - Duration::days(10));
+        let mut newly = todo(2, old);
+        newly.due_date = Some(today() - Duration::days(1));
+        let mut due_today = todo(3, old);
+        due_today.due_date = Some(today());
+        let mut done = todo(4, old);
+        done.due_date = Some(today() - Duration::days(1));
+        done.completed = true;
+
+        let digest = build(
+            &[already_overdue, newly, due_today, done],
+            Some(last_viewed),
+            now(),
+        );
+        assert_eq!(ids(&digest.newly_overdue), vec![2]);
+    }
+
+    #[test]
+    fn test_due_soon() {
+        let old = now() - Duration::days(30);
+        let due_in = |id, days| {
+            let mut t = todo(id, old);
+            t.due_date = Some(today() + Duration::days(days));
+            t
+        };
+        let todos = vec![due_in(1, 0), due_in(2, 3), due_in(3, 4), due_in(4, -1)];
+
+        let digest = build(&todos, Some(now() - Duration::hours(1)), now());
+        assert_eq!(ids(&digest.due_soon), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_quiet_window_is_empty() {
+        let todos = vec![todo(1, now() - Duration::days(30))];
+        let digest = build(&todos, Some(now() - Duration::days(1)), now());
+        assert!(digest.is_empty());
+    }
+
+    #[test]
+    fn test_first_run_looks_back_a_week() {
+        let todos = vec![
+            todo(1, now() - Duration::days(8)),
+            todo(2, now() - Duration::days(6)),
+        ];
+
+        let digest = build(&todos, None, now());
+        assert!(digest.first_run);
+        assert_eq!(
+            digest.since,
+            now() - Duration::days(FIRST_DIGEST_WINDOW_DAYS)
+        );
+        assert_eq!(ids(&digest.added), vec![2]);
+    }
+
+    #[test]
+    fn test_future_last_viewed_is_clamped() {
+        let todos = vec![todo(1, now() - Duration::hours(1))];
+        let digest = build(&todos, Some(now() + Duration::days(2)), now());
+        assert_eq!(digest.since, now());
+        assert!(digest.added.is_empty());
+    }
+}
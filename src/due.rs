@@ -0,0 +1,280 @@
+use crate::dates;
+use crate::models::todo::{Priority, Todo};
+use chrono::{Duration, NaiveDate};
+use std::str::FromStr;
+
+/// A due date change as typed for `tt due`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DueChange {
+    /// Remove the due date (`none`)
+    Clear,
+    /// A fixed day, in any form `dates::parse_day` takes
+    Set(NaiveDate),
+    /// Move the due date by this many days (`+2d`, `-1w`), counting from
+    /// today for todos without one
+    Shift(i64),
+}
+
+impl DueChange {
+    /// Parse a due date expression, with relative dates counted from
+    /// `today`. Weekday names mean the next such day after today.
+    pub fn parse(input: &str, today: NaiveDate) -> Result<Self, String> {
+        let input = input.trim().to_lowercase();
+        if input == "none" {
+            return Ok(DueChange::Clear);
+        }
+        let sign = match input.chars().next() {
+            Some('+') => Some(1),
+            Some('-') => Some(-1),
+            _ => None,
+        };
+        if let Some(sign) = sign {
+            return parse_offset(&input[1..])
+                .map(|days| DueChange::Shift(sign * days))
+                .ok_or_else(|| {
+                    format!("Invalid offset '{input}', expected e.g. +2d or -1w (days or weeks)")
+                });
+        }
+        dates::parse_day(&input, today)
+            .map(DueChange::Set)
+            .map_err(|error| format!("{error}; or an offset like +2d or -1w, or none"))
+    }
+
+    /// The due date a todo due on `current` ends up with.
+    pub fn apply(self, current: Option<NaiveDate>, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            DueChange::Clear => None,
+            DueChange::Set(date) => Some(date),
+            DueChange::Shift(days) => Some(current.unwrap_or(today) + Duration::days(days)),
+        }
+    }
+}
+
+/// A `--where` condition, like `tag = conf`, picking todos by what they
+/// are rather than by ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Tag(String),
+    Project(String),
+    Priority(Priority),
+}
+
+impl Condition {
+    pub fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            Condition::Tag(tag) => todo.has_tag(tag),
+            Condition::Project(project) => todo.project.as_deref() == Some(project.as_str()),
+            Condition::Priority(priority) => todo.priority == *priority,
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid condition '{input}', expected FIELD = VALUE with tag, project or priority"
+            )
+        };
+        let (field, value) = input.split_once('=').ok_or_else(invalid)?;
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+        if value.is_empty() {
+            return Err(invalid());
+        }
+        match field.trim().to_lowercase().as_str() {
+            "tag" => Ok(Condition::Tag(value.to_string())),
+            "project" => Ok(Condition::Project(value.to_string())),
+            "priority" => value
+                .parse()
+                .map(Condition::Priority)
+                .map_err(|error| error.to_string()),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// A todo's due date before and after a change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    pub id: usize,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// What `change` does to each of `todos`, leaving out todos it leaves as
+/// they are and, with `if_none`, todos that already have a due date.
+pub fn plan(todos: &[Todo], change: DueChange, if_none: bool, today: NaiveDate) -> Vec<Update> {
+    todos
+        .iter()
+        .filter(|todo| !if_none || todo.due_date.is_none())
+        .map(|todo| Update {
+            id: todo.id,
+            from: todo.due_date,
+            to: change.apply(todo.due_date, today),
+        })
+        .filter(|update| update.from != update.to)
+        .collect()
+}
+
+/// Whether `input` is an offset back in time like `-1w`, which looks like
+/// a flag on the command line.
+pub fn is_backward_offset(input: &str) -> bool {
+    input
+        .to_lowercase()
+        .strip_prefix('-')
+        .and_then(parse_offset)
+        .is_some()
+}
+
+/// `2d` or `1w` as a number of days.
+fn parse_offset(input: &str) -> Option<i64> {
+    let (amount, days_per_unit) = match input.char_indices().last()? {
+        (at, 'd') => (&input[..at], 1),
+        (at, 'w') => (&input[..at], 7),
+        _ => return None,
+    };
+    amount
+        .parse::<i64>()
+        .ok()
+        .map(|amount| amount * days_per_unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    // A Wednesday
+    fn today() -> NaiveDate {
+        date(2025, 4, 16)
+    }
+
+    #[test]
+    fn test_parse() {
+        let parse = |input| DueChange::parse(input, today());
+        assert_eq!(parse("none"), Ok(DueChange::Clear));
+        assert_eq!(parse("2025-05-01"), Ok(DueChange::Set(date(2025, 5, 1))));
+        assert_eq!(parse("Today"), Ok(DueChange::Set(today())));
+        assert_eq!(parse("tomorrow"), Ok(DueChange::Set(date(2025, 4, 17))));
+        assert_eq!(parse("friday"), Ok(DueChange::Set(date(2025, 4, 18))));
+        assert_eq!(parse("mon"), Ok(DueChange::Set(date(2025, 4, 21))));
+        // The same weekday is a week away, not today
+        assert_eq!(parse("wednesday"), Ok(DueChange::Set(date(2025, 4, 23))));
+        assert_eq!(parse("+2d"), Ok(DueChange::Shift(2)));
+        assert_eq!(parse("-1w"), Ok(DueChange::Shift(-7)));
+        // Without a sign, counted from today rather than the current date
+        assert_eq!(parse("2d"), Ok(DueChange::Set(date(2025, 4, 18))));
+        assert_eq!(parse("jun 15"), Ok(DueChange::Set(date(2025, 6, 15))));
+        for bad in ["+2", "+d", "+2h", "someday", "2025-13-01", ""] {
+            assert!(parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_plan_with_and_without_if_none() {
+        let todos: Vec<Todo> = [Some(date(2025, 4, 30)), None, Some(today())]
+            .into_iter()
+            .enumerate()
+            .map(|(i, due)| {
+                let mut todo = Todo::new(i + 1, format!("Todo {}", i + 1), Priority::Low);
+                todo.due_date = due;
+                todo
+            })
+            .collect();
+        let update = |id, from, to| Update { id, from, to };
+
+        assert_eq!(
+            plan(&todos, DueChange::Shift(1), false, today()),
+            vec![
+                update(1, Some(date(2025, 4, 30)), Some(date(2025, 5, 1))),
+                update(2, None, Some(date(2025, 4, 17))),
+                update(3, Some(today()), Some(date(2025, 4, 17))),
+            ]
+        );
+        assert_eq!(
+            plan(&todos, DueChange::Shift(1), true, today()),
+            vec![update(2, None, Some(date(2025, 4, 17)))]
+        );
+        // Todos the change leaves alone are left out
+        assert_eq!(
+            plan(&todos, DueChange::Set(today()), false, today()),
+            vec![
+                update(1, Some(date(2025, 4, 30)), Some(today())),
+                update(2, None, Some(today())),
+            ]
+        );
+        assert_eq!(
+            plan(&todos, DueChange::Clear, false, today()),
+            vec![
+                update(1, Some(date(2025, 4, 30)), None),
+                update(3, Some(today()), None),
+            ]
+        );
+        assert!(plan(&todos, DueChange::Clear, true, today()).is_empty());
+    }
+
+    #[test]
+    fn test_is_backward_offset() {
+        assert!(is_backward_offset("-1w"));
+        assert!(is_backward_offset("-10D"));
+        for other in ["+1w", "1w", "-w", "--if-none", "-t", "-1h"] {
+            assert!(!is_backward_offset(other), "{other}");
+        }
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        assert_eq!("tag = conf".parse(), Ok(Condition::Tag("conf".to_string())));
+        assert_eq!(
+            "Project='Q3 launch'".parse(),
+            Ok(Condition::Project("Q3 launch".to_string()))
+        );
+        assert_eq!(
+            "priority = high".parse(),
+            Ok(Condition::Priority(Priority::High))
+        );
+        for bad in ["tag", "tag =", "colour = red", "priority = urgent", ""] {
+            assert!(bad.parse::<Condition>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_condition_matches() {
+        let mut todo = Todo::new(1, "Book venue".to_string(), Priority::High);
+        todo.tags = vec!["conf/travel".to_string()];
+        todo.project = Some("launch".to_string());
+
+        assert!(Condition::Tag("conf".to_string()).matches(&todo));
+        assert!(!Condition::Tag("travel".to_string()).matches(&todo));
+        assert!(Condition::Project("launch".to_string()).matches(&todo));
+        assert!(!Condition::Project("other".to_string()).matches(&todo));
+        assert!(Condition::Priority(Priority::High).matches(&todo));
+        assert!(!Condition::Priority(Priority::Low).matches(&todo));
+    }
+
+    #[test]
+    fn test_offsets_move_the_existing_date() {
+        let due = Some(date(2025, 4, 30));
+        assert_eq!(
+            DueChange::Shift(2).apply(due, today()),
+            Some(date(2025, 5, 2))
+        );
+        assert_eq!(
+            DueChange::Shift(-7).apply(due, today()),
+            Some(date(2025, 4, 23))
+        );
+        // Without a due date, offsets count from today
+        assert_eq!(
+            DueChange::Shift(3).apply(None, today()),
+            Some(date(2025, 4, 19))
+        );
+        assert_eq!(DueChange::Clear.apply(due, today()), None);
+        assert_eq!(DueChange::Set(today()).apply(due, today()), Some(today()));
+    }
+}
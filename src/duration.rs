@@ -0,0 +1,86 @@
+use chrono::Duration;
+
+/// Parse a compact duration such as `30d`, `2w`, `12h` or `45m`.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("Duration '{input}' is missing a unit (m, h, d or w)"))?;
+    let (amount, unit) = input.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("Duration '{input}' must start with a number, e.g. 30d"))?;
+
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(format!(
+            "Unknown duration unit '{unit}' in '{input}', expected m, h, d or w"
+        )),
+    }
+}
+
+/// Parse an age given as a whole number of days (`7`) or as a compact
+/// duration (`2w`).
+pub fn parse_days(input: &str) -> Result<Duration, String> {
+    match input.trim().parse::<i64>() {
+        Ok(days) if days >= 0 => Ok(Duration::days(days)),
+        Ok(_) => Err(format!("'{input}' is negative; give a number of days")),
+        Err(_) => parse_duration(input),
+    }
+}
+
+/// Render a duration using the largest whole unit, e.g. `45d` or `3h`.
+pub fn format_duration(duration: Duration) -> String {
+    if duration.num_weeks() > 0 && duration.num_days() % 7 == 0 {
+        format!("{}w", duration.num_weeks())
+    } else if duration.num_days() > 0 {
+        format!("{}d", duration.num_days())
+    } else if duration.num_hours() > 0 {
+        format!("{}h", duration.num_hours())
+    } else {
+        format!("{}m", duration.num_minutes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration("45m").unwrap(), Duration::minutes(45));
+        assert_eq!(parse_duration("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_duration("30d").unwrap(), Duration::days(30));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::weeks(2));
+        assert_eq!(parse_duration(" 3d ").unwrap(), Duration::days(3));
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+        assert!(parse_duration("-3d").is_err());
+    }
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(parse_days("7").unwrap(), Duration::days(7));
+        assert_eq!(parse_days("0").unwrap(), Duration::days(0));
+        assert_eq!(parse_days("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_days("-1").is_err());
+        assert!(parse_days("soon").is_err());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::weeks(2)), "2w");
+        assert_eq!(format_duration(Duration::days(45)), "45d");
+        assert_eq!(format_duration(Duration::hours(5)), "5h");
+        assert_eq!(format_duration(Duration::minutes(7)), "7m");
+    }
+}
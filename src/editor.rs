@@ -0,0 +1,218 @@
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+/// Used when neither `$VISUAL` nor `$EDITOR` is set.
+pub const DEFAULT_EDITOR: &str = "vi";
+
+/// The parts of a todo `tt edit --editor` opens: the title on the first
+/// line and, after a blank line, the notes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fields {
+    pub title: String,
+    pub notes: Option<String>,
+}
+
+/// What came back from the editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Changed(Fields),
+    /// Saved as it was, or not saved at all
+    Unchanged,
+    /// Saved with nothing in it
+    Emptied,
+}
+
+impl Fields {
+    /// The file handed to the editor.
+    pub fn to_text(&self) -> String {
+        match self.notes.as_deref().map(str::trim_end) {
+            Some(notes) if !notes.is_empty() => format!("{}\n\n{notes}\n", self.title),
+            _ => format!("{}\n", self.title),
+        }
+    }
+
+    /// Read an edited file back, or `None` if nothing but whitespace is
+    /// left. Blank lines around the notes are dropped; indentation inside
+    /// them is kept.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let (title, notes) = text.split_once('\n').unwrap_or((text, ""));
+        let notes = notes.trim_start_matches(['\n', '\r']).trim_end();
+        Some(Self {
+            title: title.trim().to_string(),
+            notes: (!notes.is_empty()).then(|| notes.to_string()),
+        })
+    }
+
+    /// Whether the notes of `other` are the same, ignoring whitespace the
+    /// file format doesn't keep.
+    fn same_notes(&self, other: &Fields) -> bool {
+        let notes = |fields: &Fields| {
+            fields
+                .notes
+                .as_deref()
+                .map(str::trim_end)
+                .filter(|notes| !notes.is_empty())
+                .map(str::to_string)
+        };
+        notes(self) == notes(other)
+    }
+}
+
+/// Write `fields` to a temporary file, let `launch` open it in an editor
+/// and read back what was saved. `launch` returns once the editor has
+/// exited, failing if it didn't exit cleanly.
+pub fn edit(fields: &Fields, launch: impl FnOnce(&Path) -> Result<()>) -> Result<Outcome> {
+    let mut file = tempfile::Builder::new()
+        .prefix("tt-edit-")
+        .suffix(".txt")
+        .tempfile()
+        .context("Failed to create a file to edit")?;
+    file.write_all(fields.to_text().as_bytes())
+        .and_then(|()| file.flush())
+        .context("Failed to write the file to edit")?;
+    launch(file.path())?;
+    // Read by path: some editors save by replacing the file
+    let text = fs::read_to_string(file.path()).context("Failed to read the edited file")?;
+    let Some(mut edited) = Fields::parse(&text) else {
+        return Ok(Outcome::Emptied);
+    };
+    if edited.same_notes(fields) {
+        // Keep them exactly, trailing newline and all
+        edited.notes = fields.notes.clone();
+    }
+    Ok(if edited == *fields {
+        Outcome::Unchanged
+    } else {
+        Outcome::Changed(edited)
+    })
+}
+
+/// The editor to run: `$VISUAL`, then `$EDITOR`, then `vi`. Empty
+/// variables count as unset.
+pub fn command(env: impl Fn(&str) -> Option<String>) -> String {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(env)
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// Open `path` in the user's editor and wait for it to exit. The editor
+/// runs through the shell, so `EDITOR="code --wait"` works.
+pub fn launch(path: &Path) -> Result<()> {
+    let editor = command(|name| std::env::var(name).ok());
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg(&editor)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start editor '{editor}'"))?;
+    if !status.success() {
+        bail!("Editor '{editor}' exited with {status}; nothing was changed");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(title: &str, notes: Option<&str>) -> Fields {
+        Fields {
+            title: title.to_string(),
+            notes: notes.map(str::to_string),
+        }
+    }
+
+    /// An "editor" that replaces the file with `text`.
+    fn writes(text: &'static str) -> impl FnOnce(&Path) -> Result<()> {
+        move |path| Ok(fs::write(path, text)?)
+    }
+
+    #[test]
+    fn test_text_round_trips() {
+        for original in [
+            fields("Call \"Sam\" about the 'big' launch", None),
+            fields("Fix crash", Some("Stack trace:\n    at main\n\nSee #12")),
+        ] {
+            assert_eq!(Fields::parse(&original.to_text()), Some(original));
+        }
+        assert_eq!(
+            Fields::parse("\n  Title  \r\n\r\n\n  indented notes\n\n"),
+            Some(fields("Title", Some("  indented notes")))
+        );
+        assert_eq!(Fields::parse(" \n\n"), None);
+    }
+
+    #[test]
+    fn test_edit_reads_back_saved_text() {
+        let original = fields("Write report", Some("Draft first"));
+        assert_eq!(
+            edit(
+                &original,
+                writes("Write the Q3 report\n\nDraft first\nThen review\n")
+            )
+            .unwrap(),
+            Outcome::Changed(fields(
+                "Write the Q3 report",
+                Some("Draft first\nThen review")
+            ))
+        );
+        assert_eq!(
+            edit(&original, writes("Write report\n")).unwrap(),
+            Outcome::Changed(fields("Write report", None))
+        );
+        let traced = fields("Crash", Some("trace\n"));
+        assert_eq!(
+            edit(&traced, writes("Crash on start\n\ntrace")).unwrap(),
+            Outcome::Changed(fields("Crash on start", Some("trace\n")))
+        );
+    }
+
+    #[test]
+    fn test_unchanged_or_empty_file_cancels() {
+        let original = fields("Write report", Some("Draft first\n"));
+        assert_eq!(edit(&original, |_| Ok(())).unwrap(), Outcome::Unchanged);
+        assert_eq!(
+            edit(&original, writes("Write report  \n\nDraft first")).unwrap(),
+            Outcome::Unchanged
+        );
+        assert_eq!(edit(&original, writes("\n \n")).unwrap(), Outcome::Emptied);
+    }
+
+    #[test]
+    fn test_failed_editor_aborts() {
+        let original = fields("Write report", None);
+        let error = edit(&original, |_| {
+            bail!("Editor 'vi' exited with exit status: 1")
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("exit status: 1"));
+    }
+
+    #[test]
+    fn test_command_prefers_visual() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(command(env(&[])), "vi");
+        assert_eq!(command(env(&[("EDITOR", "nano")])), "nano");
+        assert_eq!(
+            command(env(&[("VISUAL", "code --wait"), ("EDITOR", "nano")])),
+            "code --wait"
+        );
+        assert_eq!(command(env(&[("VISUAL", ""), ("EDITOR", "nano")])), "nano");
+    }
+}
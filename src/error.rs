@@ -0,0 +1,137 @@
+use std::fmt;
+
+/// What went wrong, by kind, so callers can tell a missing todo from a
+/// bad value or a failing disk. Most commands still return
+/// `anyhow::Result`, which carries a `TtError` (with context added on
+/// top) wherever one was the cause; `main` picks the exit code from it.
+#[derive(Debug)]
+pub enum TtError {
+    /// No todo has this ID, as typed
+    NotFound {
+        id: String,
+    },
+    /// Something else that doesn't exist, e.g. an archived todo, a draft or
+    /// a checklist item
+    Missing(String),
+    InvalidPriority(u8),
+    /// Any other value tt refuses, with the message to show
+    Invalid(String),
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl TtError {
+    pub fn not_found(id: impl ToString) -> Self {
+        TtError::NotFound { id: id.to_string() }
+    }
+
+    /// The exit status for a command failing with this error: 2 when
+    /// something doesn't exist, 3 for a refused value, 1 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TtError::NotFound { .. } | TtError::Missing(_) => 2,
+            TtError::InvalidPriority(_) | TtError::Invalid(_) => 3,
+            TtError::Io(_) | TtError::Parse(_) => 1,
+        }
+    }
+
+    /// The exit status for `error`: that of the first `TtError` among its
+    /// causes, or 1.
+    pub fn exit_code_of(error: &anyhow::Error) -> i32 {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<TtError>())
+            .map_or(1, TtError::exit_code)
+    }
+}
+
+impl fmt::Display for TtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TtError::NotFound { id } => write!(f, "Todo with id {id} not found"),
+            TtError::InvalidPriority(priority) => {
+                write!(f, "Priority must be between 1 and 4, got {priority}")
+            }
+            TtError::Missing(message) | TtError::Invalid(message) => f.write_str(message),
+            TtError::Io(e) => write!(f, "{e}"),
+            TtError::Parse(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+// I/O and parse errors show as themselves, so what caused them is what
+// caused the wrapped error
+impl std::error::Error for TtError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TtError::Io(e) => e.source(),
+            TtError::Parse(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for TtError {
+    fn from(e: std::io::Error) -> Self {
+        TtError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for TtError {
+    fn from(e: serde_json::Error) -> Self {
+        TtError::Parse(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_messages_and_exit_codes() {
+        let cases = [
+            (TtError::not_found(7), "Todo with id 7 not found", 2),
+            (
+                TtError::Missing("No draft with id 3".to_string()),
+                "No draft with id 3",
+                2,
+            ),
+            (
+                TtError::InvalidPriority(9),
+                "Priority must be between 1 and 4, got 9",
+                3,
+            ),
+            (
+                TtError::Invalid("Title cannot be empty".to_string()),
+                "Title cannot be empty",
+                3,
+            ),
+            (
+                TtError::from(std::io::Error::other("disk full")),
+                "disk full",
+                1,
+            ),
+        ];
+        for (error, message, code) in cases {
+            assert_eq!(error.to_string(), message);
+            assert_eq!(error.exit_code(), code, "{message}");
+        }
+    }
+
+    #[test]
+    fn test_exit_code_looks_through_context() {
+        let error = Err::<(), _>(TtError::not_found(4))
+            .context("Failed to complete")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Failed to complete");
+        assert_eq!(TtError::exit_code_of(&error), 2);
+
+        let parse = serde_json::from_str::<u8>("{").unwrap_err();
+        let error = Err::<(), _>(TtError::from(parse))
+            .context("Failed to parse todo file as JSON")
+            .unwrap_err();
+        assert_eq!(TtError::exit_code_of(&error), 1);
+        assert_eq!(TtError::exit_code_of(&anyhow::anyhow!("Cancelled")), 1);
+    }
+}
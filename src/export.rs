@@ -0,0 +1,166 @@
+use crate::models::todo::{Todo, TodoStore};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Markdown,
+    Json,
+}
+
+/// Columns written by `to_csv`, in order.
+pub const CSV_COLUMNS: &[&str] = &["id", "title", "priority", "completed", "created_at"];
+
+/// Render the store in the given format, ending with a newline.
+pub fn render(store: &TodoStore, format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Csv => Ok(to_csv(&store.todos)),
+        ExportFormat::Markdown => Ok(to_markdown(&store.todos)),
+        ExportFormat::Json => serde_json::to_string_pretty(store)
+            .map(|json| json + "\n")
+            .context("Failed to serialize todos to JSON"),
+    }
+}
+
+/// One row per todo under a header row, quoted as described in RFC 4180.
+pub fn to_csv(todos: &[Todo]) -> String {
+    let mut csv = CSV_COLUMNS.join(",");
+    csv.push('\n');
+    for todo in todos {
+        let row = [
+            todo.id.to_string(),
+            csv_field(&todo.title),
+            todo.priority.number().to_string(),
+            todo.completed.to_string(),
+            csv_field(&todo.created_at),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a field if it contains a delimiter, quote or line break, doubling
+/// any quotes inside it.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A checkbox list, highest priority first (keeping the stored order
+/// within a priority).
+pub fn to_markdown(todos: &[Todo]) -> String {
+    let mut sorted: Vec<&Todo> = todos.iter().collect();
+    sorted.sort_by_key(|todo| todo.priority);
+
+    sorted
+        .iter()
+        .map(|todo| {
+            let check = if todo.completed { "x" } else { " " };
+            format!(
+                "- [{check}] {} (P{})\n",
+                markdown_text(&todo.title),
+                todo.priority.number()
+            )
+        })
+        .collect()
+}
+
+/// Escape characters Markdown would treat as formatting, and keep the item
+/// on one line.
+fn markdown_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn todo(id: usize, title: &str, priority: u8, completed: bool) -> Todo {
+        let priority = Priority::from_number(priority).unwrap();
+        let mut todo = Todo::new(id, title.to_string(), priority);
+        todo.completed = completed;
+        todo.created_at = "2025-01-01T09:00:00+00:00".to_string();
+        todo
+    }
+
+    #[test]
+    fn test_csv_plain() {
+        let csv = to_csv(&[todo(1, "Buy milk", 2, false)]);
+        assert_eq!(
+            csv,
+            "id,title,priority,completed,created_at\n1,Buy milk,2,false,2025-01-01T09:00:00+00:00\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_escaping() {
+        assert_eq!(csv_field("milk, eggs"), "\"milk, eggs\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("\""), "\"\"\"\"");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("semi;colon"), "semi;colon");
+    }
+
+    #[test]
+    fn test_markdown_sorted_by_priority() {
+        let markdown = to_markdown(&[
+            todo(1, "Low", 4, false),
+            todo(2, "Urgent", 1, true),
+            todo(3, "Also low", 4, false),
+        ]);
+        assert_eq!(
+            markdown,
+            "- [x] Urgent (P1)\n- [ ] Low (P4)\n- [ ] Also low (P4)\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_escaping() {
+        assert_eq!(
+            markdown_text("fix *all* the [bugs]"),
+            "fix \\*all\\* the \\[bugs\\]"
+        );
+        assert_eq!(markdown_text("a_b `c` <d>"), "a\\_b \\`c\\` \\<d\\>");
+        assert_eq!(markdown_text("one\ntwo"), "one two");
+    }
+
+    #[test]
+    fn test_json_is_the_store() {
+        let store = TodoStore {
+            todos: vec![todo(1, "Buy milk", 2, false)],
+            next_id: 2,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+        };
+        let json = render(&store, ExportFormat::Json).unwrap();
+        let parsed: TodoStore = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.next_id, 2);
+        assert_eq!(parsed.todos[0].title, "Buy milk");
+    }
+
+    #[test]
+    fn test_empty_list() {
+        assert_eq!(to_csv(&[]), "id,title,priority,completed,created_at\n");
+        assert_eq!(to_markdown(&[]), "");
+    }
+}
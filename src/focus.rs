@@ -0,0 +1,135 @@
+use crate::models::todo::Todo;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How long a focus session lasts unless configured otherwise.
+pub const DEFAULT_EXPIRY: Duration = Duration::hours(4);
+
+/// A set of todos to work on while everything else is hidden, kept in the
+/// store's state file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Focus {
+    pub ids: Vec<usize>,
+    pub started_at: String, // ISO 8601 format
+    pub expires_at: String, // ISO 8601 format
+}
+
+/// Where a focus session stands.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Status {
+    Active,
+    /// Every focused todo is completed (or gone)
+    Finished,
+    /// Its time ran out first
+    Expired,
+}
+
+impl Focus {
+    pub fn new(ids: Vec<usize>, now: DateTime<Utc>, lasts: Duration) -> Self {
+        Self {
+            ids,
+            started_at: now.to_rfc3339(),
+            expires_at: (now + lasts).to_rfc3339(),
+        }
+    }
+
+    pub fn status(&self, todos: &[Todo], now: DateTime<Utc>) -> Status {
+        let finished = self.ids.iter().all(|id| {
+            todos
+                .iter()
+                .find(|todo| todo.id == *id)
+                .is_none_or(|todo| todo.completed)
+        });
+        // An unreadable expiry counts as expired rather than focusing forever
+        let expired = DateTime::parse_from_rfc3339(&self.expires_at)
+            .map_or(true, |expires_at| now >= expires_at);
+        match (finished, expired) {
+            (true, _) => Status::Finished,
+            (false, true) => Status::Expired,
+            (false, false) => Status::Active,
+        }
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.ids.contains(&id)
+    }
+
+    /// The focused todos among `todos`, in the order given.
+    pub fn filter(&self, todos: Vec<Todo>) -> Vec<Todo> {
+        todos
+            .into_iter()
+            .filter(|todo| self.contains(todo.id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(timestamp)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn todos() -> Vec<Todo> {
+        (1..=4)
+            .map(|id| Todo::new(id, format!("Todo {id}"), Priority::Low))
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_keeps_only_focused_in_order() {
+        let focus = Focus::new(vec![3, 1], at("2025-04-15T09:00:00Z"), DEFAULT_EXPIRY);
+        let ids: Vec<usize> = focus.filter(todos()).iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert!(focus.contains(3));
+        assert!(!focus.contains(2));
+    }
+
+    #[test]
+    fn test_status_expires_with_the_clock() {
+        let focus = Focus::new(vec![1, 2], at("2025-04-15T09:00:00Z"), Duration::hours(2));
+        let todos = todos();
+        assert_eq!(
+            focus.status(&todos, at("2025-04-15T10:59:59Z")),
+            Status::Active
+        );
+        assert_eq!(
+            focus.status(&todos, at("2025-04-15T11:00:00Z")),
+            Status::Expired
+        );
+
+        let broken = Focus {
+            expires_at: "later".to_string(),
+            ..focus
+        };
+        assert_eq!(
+            broken.status(&todos, at("2025-04-15T09:00:00Z")),
+            Status::Expired
+        );
+    }
+
+    #[test]
+    fn test_status_finished_once_every_focused_todo_is_done() {
+        let focus = Focus::new(vec![1, 2], at("2025-04-15T09:00:00Z"), Duration::hours(2));
+        let now = at("2025-04-15T09:30:00Z");
+        let mut todos = todos();
+        todos[0].set_completed(true);
+        assert_eq!(focus.status(&todos, now), Status::Active);
+
+        // Completing the rest finishes it; so does deleting it
+        todos[1].set_completed(true);
+        assert_eq!(focus.status(&todos, now), Status::Finished);
+        todos.remove(1);
+        assert_eq!(focus.status(&todos, now), Status::Finished);
+
+        // Done counts even after the time is up
+        assert_eq!(
+            focus.status(&todos, at("2025-04-16T09:00:00Z")),
+            Status::Finished
+        );
+    }
+}
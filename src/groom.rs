@@ -0,0 +1,427 @@
+use crate::export::csv_field;
+use crate::import::csv_records;
+use crate::models::todo::{Priority, Todo};
+use chrono::NaiveDate;
+use std::collections::HashSet;
+
+/// Columns written by `export`, in order. `changed_at` is how `plan` tells
+/// whether a todo changed after the export; it is not meant to be edited.
+pub const COLUMNS: &[&str] = &[
+    "id",
+    "title",
+    "priority",
+    "due",
+    "tags",
+    "status",
+    "changed_at",
+];
+
+/// The pending todos as an editable CSV grooming sheet.
+pub fn export(todos: &[Todo]) -> String {
+    let mut csv = COLUMNS.join(",");
+    csv.push('\n');
+    for todo in todos.iter().filter(|todo| !todo.completed) {
+        let row = [
+            todo.id.to_string(),
+            csv_field(&todo.title),
+            todo.priority.number().to_string(),
+            todo.due_date.map(|due| due.to_string()).unwrap_or_default(),
+            csv_field(&todo.tags.join(" ")),
+            status(todo.completed).to_string(),
+            csv_field(version(todo)),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn status(completed: bool) -> &'static str {
+    if completed { "done" } else { "pending" }
+}
+
+/// What a todo's `changed_at` cell holds: when it last changed.
+fn version(todo: &Todo) -> &str {
+    todo.updated_at.as_deref().unwrap_or(&todo.created_at)
+}
+
+/// One row of an edited sheet. Cells of columns the sheet leaves out are
+/// `None`, and leave the todo as it is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub line: usize,
+    /// `None` for a row that adds a todo
+    pub id: Option<usize>,
+    pub title: String,
+    pub priority: Option<Priority>,
+    pub due: Option<Option<NaiveDate>>,
+    pub tags: Option<Vec<String>>,
+    pub completed: Option<bool>,
+    pub changed_at: Option<String>,
+}
+
+/// The rows of an edited sheet, plus a problem for every row that cannot
+/// be used. Only the `title` column is required.
+pub fn parse(input: &str) -> (Vec<Row>, Vec<String>) {
+    let mut problems = Vec::new();
+    let mut records = csv_records(input, &mut problems).into_iter();
+    let Some((_, header)) = records.next() else {
+        return (Vec::new(), problems);
+    };
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let Some(title_column) = column("title") else {
+        problems.push(format!(
+            "line 1: no 'title' column (expected {})",
+            COLUMNS.join(",")
+        ));
+        return (Vec::new(), problems);
+    };
+    let columns = [
+        column("id"),
+        column("priority"),
+        column("due"),
+        column("tags"),
+        column("status"),
+        column("changed_at"),
+    ];
+
+    let mut rows = Vec::new();
+    for (line, fields) in records {
+        if fields.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let cell = |index: Option<usize>| {
+            index.map(|i| fields.get(i).map(|f| f.trim()).unwrap_or_default())
+        };
+        let [id, priority, due, tags, status, changed_at] = columns.map(cell);
+        match parse_row(
+            line,
+            cell(Some(title_column)).unwrap_or_default(),
+            [id, priority, due, tags, status, changed_at],
+        ) {
+            Ok(row) => rows.push(row),
+            Err(problem) => problems.push(format!("line {line}: {problem}")),
+        }
+    }
+    (rows, problems)
+}
+
+fn parse_row(line: usize, title: &str, cells: [Option<&str>; 6]) -> Result<Row, String> {
+    let [id, priority, due, tags, status, changed_at] = cells;
+    if title.is_empty() {
+        return Err("title is empty".to_string());
+    }
+    let id = id
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse().map_err(|_| format!("id '{id}' is not a number")))
+        .transpose()?;
+    let priority = priority
+        .filter(|priority| !priority.is_empty())
+        .map(|priority| priority.parse::<Priority>().map_err(|e| e.to_string()))
+        .transpose()?;
+    let due = due
+        .map(|due| match due {
+            "" => Ok(None),
+            due => NaiveDate::parse_from_str(due, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| format!("due '{due}' is not a YYYY-MM-DD date")),
+        })
+        .transpose()?;
+    let tags = tags.map(|tags| {
+        let mut normalized = Vec::new();
+        for tag in tags.split_whitespace().filter_map(Todo::normalize_tag) {
+            if !normalized.contains(&tag) {
+                normalized.push(tag);
+            }
+        }
+        normalized
+    });
+    let completed = status
+        .filter(|status| !status.is_empty())
+        .map(|status| match status.to_lowercase().as_str() {
+            "pending" => Ok(false),
+            "done" => Ok(true),
+            _ => Err(format!("status '{status}' is not pending or done")),
+        })
+        .transpose()?;
+    Ok(Row {
+        line,
+        id,
+        title: title.to_string(),
+        priority,
+        due,
+        tags,
+        completed,
+        changed_at: changed_at.filter(|at| !at.is_empty()).map(str::to_string),
+    })
+}
+
+/// An existing todo as the sheet leaves it, with what changed.
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub todo: Todo,
+    pub changes: Vec<String>,
+}
+
+/// What applying a sheet changes. New todos have no ID yet.
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub edits: Vec<Edit>,
+    pub added: Vec<Todo>,
+    /// Rows left out, and why
+    pub rejected: Vec<String>,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty() && self.added.is_empty()
+    }
+}
+
+/// Compare `rows` with the store by ID. Rows whose todo is gone, that name
+/// a todo twice, or whose todo changed since the export are rejected; the
+/// rest become edits. Rows without an ID become new todos with
+/// `default_priority`, unless a pending todo has the same title, so applying
+/// a sheet twice adds nothing twice.
+pub fn plan(todos: &[Todo], rows: Vec<Row>, default_priority: Priority) -> Plan {
+    let mut plan = Plan::default();
+    let mut seen = HashSet::new();
+    for row in rows {
+        let line = row.line;
+        let Some(id) = row.id else {
+            let title = row.title.to_lowercase();
+            let pending = todos
+                .iter()
+                .chain(&plan.added)
+                .any(|todo| !todo.completed && todo.title.to_lowercase() == title);
+            if pending {
+                plan.rejected.push(format!(
+                    "line {line}: '{}' is already a pending todo",
+                    row.title
+                ));
+            } else {
+                plan.added.push(new_todo(row, default_priority));
+            }
+            continue;
+        };
+        if !seen.insert(id) {
+            plan.rejected
+                .push(format!("line {line}: todo {id} appears more than once"));
+            continue;
+        }
+        let Some(todo) = todos.iter().find(|todo| todo.id == id) else {
+            plan.rejected
+                .push(format!("line {line}: todo {id} no longer exists"));
+            continue;
+        };
+        if row
+            .changed_at
+            .as_deref()
+            .is_some_and(|at| at != version(todo))
+        {
+            plan.rejected.push(format!(
+                "line {line}: todo {id} changed since the export; export again to groom it"
+            ));
+            continue;
+        }
+        let edit = edit(todo, row);
+        if !edit.changes.is_empty() {
+            plan.edits.push(edit);
+        }
+    }
+    plan
+}
+
+/// `todo` with the row's values, through the setters so it is stamped as
+/// changed.
+fn edit(todo: &Todo, row: Row) -> Edit {
+    let mut todo = todo.clone();
+    let mut changes = Vec::new();
+    if row.title != todo.title {
+        changes.push(format!("title '{}' → '{}'", todo.title, row.title));
+        todo.set_title(row.title);
+    }
+    if let Some(priority) = row.priority.filter(|&p| p != todo.priority) {
+        changes.push(format!("priority {} → {priority}", todo.priority));
+        // Validated while parsing
+        todo.set_priority(priority);
+    }
+    if let Some(due) = row.due.filter(|&due| due != todo.due_date) {
+        let show = |due: Option<NaiveDate>| due.map_or("none".to_string(), |d| d.to_string());
+        changes.push(format!("due {} → {}", show(todo.due_date), show(due)));
+        todo.set_due_date(due);
+    }
+    if let Some(tags) = row.tags {
+        for tag in todo.tags.clone() {
+            if !tags.contains(&tag) {
+                changes.push(format!("-#{tag}"));
+                todo.remove_tag(&tag);
+            }
+        }
+        for tag in tags {
+            if !todo.tags.contains(&tag) {
+                changes.push(format!("+#{tag}"));
+                todo.add_tag(&tag);
+            }
+        }
+    }
+    if let Some(completed) = row.completed.filter(|&c| c != todo.completed) {
+        changes.push(status(completed).to_string());
+        todo.set_completed(completed);
+    }
+    Edit { todo, changes }
+}
+
+fn new_todo(row: Row, default_priority: Priority) -> Todo {
+    let mut todo = Todo {
+        title: row.title,
+        priority: row.priority.unwrap_or(default_priority),
+        due_date: row.due.flatten(),
+        ..Todo::default()
+    };
+    for tag in row.tags.unwrap_or_default() {
+        todo.add_tag(&tag);
+    }
+    todo.set_completed(row.completed.unwrap_or(false));
+    // Filling in a new todo does not count as changing it
+    todo.updated_at = None;
+    todo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todos() -> Vec<Todo> {
+        let mut one = Todo::new(1, "Write report".to_string(), Priority::High);
+        one.add_tag("work");
+        one.updated_at = None;
+        let two = Todo::new(2, "Buy milk, eggs".to_string(), Priority::Low);
+        let mut done = Todo::new(3, "Old".to_string(), Priority::Medium);
+        done.set_completed(true);
+        vec![one, two, done]
+    }
+
+    fn apply(todos: &[Todo], sheet: &str) -> Plan {
+        let (rows, problems) = parse(sheet);
+        assert!(problems.is_empty(), "{problems:?}");
+        plan(todos, rows, Priority::Low)
+    }
+
+    #[test]
+    fn test_unchanged_export_plans_nothing() {
+        let todos = todos();
+        let sheet = export(&todos);
+        assert_eq!(sheet.lines().count(), 3, "completed todos are left out");
+        let plan = apply(&todos, &sheet);
+        assert!(plan.is_empty());
+        assert!(plan.rejected.is_empty());
+    }
+
+    #[test]
+    fn test_edits_are_diffed_by_id() {
+        let todos = todos();
+        let sheet = export(&todos)
+            .replace("Write report,2,", "Write the report,1,2025-05-01")
+            .replace(",work,pending", ",#Home work/q2,done");
+        let plan = apply(&todos, &sheet);
+        assert_eq!(plan.edits.len(), 1);
+        let edit = &plan.edits[0];
+        assert_eq!(
+            edit.changes,
+            vec![
+                "title 'Write report' → 'Write the report'",
+                "priority high → critical",
+                "due none → 2025-05-01",
+                "-#work",
+                "+#home",
+                "+#work/q2",
+                "done",
+            ]
+        );
+        assert_eq!(edit.todo.tags, vec!["home", "work/q2"]);
+        assert!(edit.todo.completed);
+        assert!(edit.todo.updated_at.is_some());
+    }
+
+    #[test]
+    fn test_rows_without_ids_are_new_todos() {
+        let todos = todos();
+        let sheet = format!(
+            "{}\n,Call plumber,,2025-06-01,home,\n,Done already,2,,,done\n,\"buy MILK, EGGS\",,,,\n,Call plumber,,,,\n",
+            export(&todos).trim_end()
+        );
+        let plan = apply(&todos, &sheet);
+        let added: Vec<(&str, Priority, bool)> = plan
+            .added
+            .iter()
+            .map(|todo| (todo.title.as_str(), todo.priority, todo.completed))
+            .collect();
+        assert_eq!(
+            added,
+            vec![
+                ("Call plumber", Priority::Low, false),
+                ("Done already", Priority::High, true)
+            ]
+        );
+        assert_eq!(plan.added[0].tags, vec!["home"]);
+        assert!(plan.added[1].completed_at.is_some());
+        assert!(plan.added.iter().all(|todo| todo.updated_at.is_none()));
+        assert_eq!(
+            plan.rejected,
+            vec![
+                "line 6: 'buy MILK, EGGS' is already a pending todo",
+                "line 7: 'Call plumber' is already a pending todo",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_columns_leave_fields_alone() {
+        let todos = todos();
+        let plan = apply(&todos, "id,title\n1,Write report\n2,Buy oat milk\n");
+        assert_eq!(plan.edits.len(), 1);
+        assert_eq!(
+            plan.edits[0].changes,
+            vec!["title 'Buy milk, eggs' → 'Buy oat milk'"]
+        );
+        assert_eq!(plan.edits[0].todo.priority, Priority::Low);
+    }
+
+    #[test]
+    fn test_invalid_cells_are_problems() {
+        let sheet = "id,title,priority,due,status\nx,A,1,,\n1,,1,,\n1,A,7,,\n1,A,1,soon,\n1,A,1,,later\n2,Fine,1,,\n";
+        let (rows, problems) = parse(sheet);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            problems,
+            vec![
+                "line 2: id 'x' is not a number",
+                "line 3: title is empty",
+                "line 4: Priority must be between 1 and 4, got 7",
+                "line 5: due 'soon' is not a YYYY-MM-DD date",
+                "line 6: status 'later' is not pending or done",
+            ]
+        );
+        let (_, problems) = parse("name\nA\n");
+        assert_eq!(problems.len(), 1);
+    }
+
+    #[test]
+    fn test_vanished_duplicate_and_changed_todos_are_rejected() {
+        let mut todos = todos();
+        let sheet = format!("{}9,Gone,1,,,,\n", export(&todos)) + "2,Buy milk,1,,,,\n";
+        todos[0].set_priority(Priority::Critical);
+        let plan = apply(&todos, &sheet);
+        assert!(plan.edits.is_empty());
+        assert_eq!(
+            plan.rejected,
+            vec![
+                "line 2: todo 1 changed since the export; export again to groom it",
+                "line 4: todo 9 no longer exists",
+                "line 5: todo 2 appears more than once",
+            ]
+        );
+    }
+}
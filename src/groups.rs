@@ -0,0 +1,116 @@
+use crate::models::todo::{Priority, Todo};
+use clap::ValueEnum;
+
+/// The heading for todos that are not in any project.
+pub const NO_PROJECT: &str = "(no project)";
+
+/// What `tt list --group-by` puts todos under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    Project,
+    Priority,
+}
+
+/// A heading and the todos beneath it, in the order they were given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub label: String,
+    pub todos: Vec<Todo>,
+}
+
+/// Split `todos` into groups, leaving out empty ones. Projects are
+/// alphabetical ignoring case, with todos outside any project last;
+/// priorities go from critical to low.
+pub fn group(todos: &[Todo], by: GroupBy) -> Vec<Group> {
+    match by {
+        GroupBy::Project => {
+            let mut projects: Vec<&str> = todos
+                .iter()
+                .filter_map(|todo| todo.project.as_deref())
+                .collect();
+            projects.sort_by_key(|name| (name.to_lowercase(), *name));
+            projects.dedup();
+            let mut groups: Vec<Group> = projects
+                .into_iter()
+                .map(|name| collect(name, todos, |todo| todo.project.as_deref() == Some(name)))
+                .collect();
+            groups.push(collect(NO_PROJECT, todos, |todo| todo.project.is_none()));
+            groups.retain(|group| !group.todos.is_empty());
+            groups
+        }
+        GroupBy::Priority => Priority::ALL
+            .into_iter()
+            .map(|priority| collect(priority.name(), todos, |todo| todo.priority == priority))
+            .filter(|group| !group.todos.is_empty())
+            .collect(),
+    }
+}
+
+fn collect(label: &str, todos: &[Todo], belongs: impl Fn(&Todo) -> bool) -> Group {
+    Group {
+        label: label.to_string(),
+        todos: todos.iter().filter(|todo| belongs(todo)).cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: usize, project: Option<&str>, priority: Priority) -> Todo {
+        let mut todo = Todo::new(id, format!("Todo {id}"), priority);
+        todo.project = project.map(str::to_string);
+        todo
+    }
+
+    fn summary(groups: &[Group]) -> Vec<(&str, Vec<usize>)> {
+        groups
+            .iter()
+            .map(|group| {
+                let ids = group.todos.iter().map(|todo| todo.id).collect();
+                (group.label.as_str(), ids)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_projects_sorted_with_no_project_last() {
+        let todos = [
+            todo(1, None, Priority::Low),
+            todo(2, Some("work"), Priority::Low),
+            todo(3, Some("Garden"), Priority::Low),
+            todo(4, Some("work"), Priority::High),
+            todo(5, Some("apps"), Priority::Low),
+        ];
+        assert_eq!(
+            summary(&group(&todos, GroupBy::Project)),
+            [
+                ("apps", vec![5]),
+                ("Garden", vec![3]),
+                ("work", vec![2, 4]),
+                (NO_PROJECT, vec![1]),
+            ]
+        );
+        assert_eq!(
+            summary(&group(&todos[1..], GroupBy::Project))
+                .last()
+                .unwrap()
+                .0,
+            "work"
+        );
+    }
+
+    #[test]
+    fn test_priorities_critical_first() {
+        let todos = [
+            todo(1, None, Priority::Low),
+            todo(2, None, Priority::Critical),
+            todo(3, None, Priority::Low),
+        ];
+        assert_eq!(
+            summary(&group(&todos, GroupBy::Priority)),
+            [("critical", vec![2]), ("low", vec![1, 3])]
+        );
+        assert!(group(&[], GroupBy::Priority).is_empty());
+    }
+}
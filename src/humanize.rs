@@ -0,0 +1,75 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Shown for a timestamp that cannot be read, e.g. from a hand-edited file.
+pub const UNKNOWN: &str = "unknown";
+
+/// How long ago something happened, in the largest unit that fits:
+/// "just now", "59m ago", "3h ago", "yesterday", "5 days ago",
+/// "2 weeks ago", "3 months ago" or "1 year ago". Times in the future, as
+/// a skewed clock can give, count as just now.
+pub fn ago(elapsed: Duration) -> String {
+    let days = elapsed.num_days();
+    match days {
+        _ if elapsed.num_minutes() < 1 => "just now".to_string(),
+        0 if elapsed.num_hours() < 1 => format!("{}m ago", elapsed.num_minutes()),
+        0 => format!("{}h ago", elapsed.num_hours()),
+        1 => "yesterday".to_string(),
+        2..14 => format!("{days} days ago"),
+        14..60 => format!("{} weeks ago", days / 7),
+        60..365 => format!("{} months ago", days / 30),
+        _ => match days / 365 {
+            1 => "1 year ago".to_string(),
+            years => format!("{years} years ago"),
+        },
+    }
+}
+
+/// How long before `now` the RFC 3339 `timestamp` was, or "unknown".
+pub fn since(timestamp: &str, now: DateTime<Utc>) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| ago(now - time.with_timezone(&Utc)))
+        .unwrap_or_else(|_| UNKNOWN.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ago_boundaries() {
+        let cases = [
+            (Duration::seconds(-30), "just now"),
+            (Duration::seconds(59), "just now"),
+            (Duration::minutes(1), "1m ago"),
+            (Duration::minutes(59), "59m ago"),
+            (Duration::minutes(60), "1h ago"),
+            (Duration::hours(23) + Duration::minutes(59), "23h ago"),
+            (Duration::hours(24), "yesterday"),
+            (Duration::hours(47), "yesterday"),
+            (Duration::hours(48), "2 days ago"),
+            (Duration::days(13), "13 days ago"),
+            (Duration::days(14), "2 weeks ago"),
+            (Duration::days(59), "8 weeks ago"),
+            (Duration::days(60), "2 months ago"),
+            (Duration::days(364), "12 months ago"),
+            (Duration::days(365), "1 year ago"),
+            (Duration::days(800), "2 years ago"),
+        ];
+        for (elapsed, expected) in cases {
+            assert_eq!(ago(elapsed), expected, "{elapsed}");
+        }
+    }
+
+    #[test]
+    fn test_since_reads_timestamps() {
+        let now = DateTime::parse_from_rfc3339("2025-04-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(since("2025-04-15T09:00:00Z", now), "3h ago");
+        assert_eq!(since("2025-04-15T13:30:00+02:00", now), "30m ago");
+        assert_eq!(since("2025-04-01T12:00:00Z", now), "2 weeks ago");
+        for malformed in ["", "yesterday", "2025-04-01"] {
+            assert_eq!(since(malformed, now), "unknown", "{malformed}");
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use crate::error::TtError;
+use crate::models::todo::Todo;
+use serde::Deserialize;
+
+/// Number of hex characters shown for hash-style IDs.
+const HASH_DISPLAY_LEN: usize = 6;
+
+/// How todo IDs are shown and typed. The stored identity is the same
+/// either way; this only changes presentation and lookup.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdStyle {
+    /// Small increasing integers
+    #[default]
+    Sequential,
+    /// Hex digest of the todo's stable ID and creation time, which does not
+    /// collide across branches of a shared store
+    Hash,
+}
+
+/// Full 16-character hex identifier for a todo, derived only from fields
+/// that never change after creation (FNV-1a, stable across builds).
+pub fn full_hash(todo: &Todo) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in format!("{}:{}", todo.id, todo.created_at).bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+pub fn display_id(todo: &Todo, style: IdStyle) -> String {
+    match style {
+        IdStyle::Sequential => todo.id.to_string(),
+        IdStyle::Hash => full_hash(todo)[..HASH_DISPLAY_LEN].to_string(),
+    }
+}
+
+/// Why an ID typed by the user matched no todo.
+#[derive(Debug, PartialEq, Eq)]
+enum LookupError {
+    NotFound,
+    /// Not written like an ID at all, with the message to show
+    NotAnId(String),
+    /// A hash prefix shared by several todos, with the message to show
+    Ambiguous(String),
+}
+
+impl LookupError {
+    /// Whether the input may have been meant as part of a title instead:
+    /// it isn't an ID, or in hash style happens to be hex but no prefix.
+    fn may_be_title(&self, style: IdStyle) -> bool {
+        match self {
+            LookupError::NotAnId(_) => true,
+            LookupError::NotFound => style == IdStyle::Hash,
+            LookupError::Ambiguous(_) => false,
+        }
+    }
+}
+
+/// Resolve an ID typed by the user to a stored todo ID. In hash style any
+/// unambiguous prefix is accepted, like git commit hashes.
+pub fn resolve(todos: &[Todo], input: &str, style: IdStyle) -> Result<usize, TtError> {
+    lookup(todos, input, style).map_err(|error| match error {
+        LookupError::NotFound => TtError::not_found(input.trim()),
+        LookupError::NotAnId(message) | LookupError::Ambiguous(message) => {
+            TtError::Invalid(message)
+        }
+    })
+}
+
+/// Whether `input`, which `resolve` could not use, should be looked up
+/// among titles instead.
+pub fn may_be_title(todos: &[Todo], input: &str, style: IdStyle) -> bool {
+    lookup(todos, input, style).is_err_and(|error| error.may_be_title(style))
+}
+
+fn lookup(todos: &[Todo], input: &str, style: IdStyle) -> Result<usize, LookupError> {
+    let input = input.trim();
+    match style {
+        IdStyle::Sequential => {
+            let id: usize = input
+                .parse()
+                .map_err(|_| LookupError::NotAnId(format!("'{input}' is not a valid todo ID")))?;
+            todos
+                .iter()
+                .any(|todo| todo.id == id)
+                .then_some(id)
+                .ok_or(LookupError::NotFound)
+        }
+        IdStyle::Hash => {
+            let prefix = input.to_lowercase();
+            if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(LookupError::NotAnId(format!(
+                    "'{input}' is not a valid todo ID"
+                )));
+            }
+
+            let candidates: Vec<&Todo> = todos
+                .iter()
+                .filter(|todo| full_hash(todo).starts_with(&prefix))
+                .collect();
+            match candidates.as_slice() {
+                [] => Err(LookupError::NotFound),
+                [todo] => Ok(todo.id),
+                many => {
+                    let listing: Vec<String> = many
+                        .iter()
+                        .map(|todo| {
+                            // Show enough characters to tell the candidates apart
+                            let shown = HASH_DISPLAY_LEN.max(prefix.len() + 1);
+                            format!("  {} {}", &full_hash(todo)[..shown], todo.title)
+                        })
+                        .collect();
+                    Err(LookupError::Ambiguous(format!(
+                        "ID prefix '{input}' is ambiguous; it matches:\n{}",
+                        listing.join("\n")
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// Ranges wider than this are refused rather than walked.
+const MAX_RANGE_LEN: usize = 10_000;
+
+/// The todos picked out by several IDs typed by the user.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Selection {
+    /// Stored IDs, in the order given, each once
+    pub ids: Vec<usize>,
+    /// IDs that matched no todo, as typed (or as numbers from a range)
+    pub not_found: Vec<String>,
+    /// Messages for IDs that could not be used for any other reason
+    pub errors: Vec<String>,
+    /// Inputs that aren't IDs, left to be matched against titles
+    pub titles: Vec<String>,
+}
+
+impl Selection {
+    pub fn is_complete(&self) -> bool {
+        self.not_found.is_empty() && self.errors.is_empty() && self.titles.is_empty()
+    }
+
+    pub fn pick(&mut self, id: usize) {
+        if !self.ids.contains(&id) {
+            self.ids.push(id);
+        }
+    }
+
+    /// Every failure as one message, for refusing the whole selection.
+    pub fn failure_message(&self) -> String {
+        let mut messages = self.errors.clone();
+        if !self.not_found.is_empty() {
+            messages.push(format!(
+                "{} ({})",
+                ids_not_found(self.not_found.len()),
+                self.not_found.join(", ")
+            ));
+        }
+        messages.join("\n")
+    }
+}
+
+/// "1 ID not found" or "3 IDs not found".
+pub fn ids_not_found(count: usize) -> String {
+    if count == 1 {
+        "1 ID not found".to_string()
+    } else {
+        format!("{count} IDs not found")
+    }
+}
+
+/// Resolve every ID in `inputs`, which may also be ranges of sequential IDs
+/// like `2-5`. All of them are resolved against `todos` as they are now, so
+/// acting on the result in any order touches the todos the user meant.
+/// Inputs that aren't IDs are set aside in `titles`.
+pub fn resolve_many(todos: &[Todo], inputs: &[String], style: IdStyle) -> Selection {
+    let mut selection = Selection::default();
+    for input in inputs {
+        let input = input.trim();
+        if let Some((start, end)) = as_range(input) {
+            match parse_range(start, end, style) {
+                Ok(range) => {
+                    for id in range {
+                        if todos.iter().any(|todo| todo.id == id) {
+                            selection.pick(id);
+                        } else {
+                            selection.not_found.push(id.to_string());
+                        }
+                    }
+                }
+                Err(message) => selection.errors.push(message),
+            }
+            continue;
+        }
+        match lookup(todos, input, style) {
+            Ok(id) => selection.pick(id),
+            Err(error) if error.may_be_title(style) => selection.titles.push(input.to_string()),
+            Err(LookupError::NotFound) => selection.not_found.push(input.to_string()),
+            Err(LookupError::NotAnId(message) | LookupError::Ambiguous(message)) => {
+                selection.errors.push(message)
+            }
+        }
+    }
+    selection
+}
+
+/// The two ends of `input` if it is written as a range: digits, a dash,
+/// digits.
+fn as_range(input: &str) -> Option<(&str, &str)> {
+    let (start, end) = input.split_once('-')?;
+    let is_number = |end: &str| !end.is_empty() && end.chars().all(|c| c.is_ascii_digit());
+    (is_number(start) && is_number(end)).then_some((start, end))
+}
+
+fn parse_range(
+    start: &str,
+    end: &str,
+    style: IdStyle,
+) -> Result<std::ops::RangeInclusive<usize>, String> {
+    let range = format!("{start}-{end}");
+    if style == IdStyle::Hash {
+        return Err(format!(
+            "'{range}' is not a valid todo ID: ranges only work with sequential IDs"
+        ));
+    }
+    let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) else {
+        return Err(format!("'{range}' is not a valid range of todo IDs"));
+    };
+    if start > end {
+        return Err(format!("Range '{range}' ends before it starts"));
+    }
+    if end - start >= MAX_RANGE_LEN {
+        return Err(format!(
+            "Range '{range}' is too long; at most {MAX_RANGE_LEN} IDs at once"
+        ));
+    }
+    Ok(start..=end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn todo(id: usize, created_at: &str, title: &str) -> Todo {
+        Todo {
+            id,
+            title: title.to_string(),
+            created_at: created_at.to_string(),
+            ..Todo::default()
+        }
+    }
+
+    fn fixture() -> Vec<Todo> {
+        vec![
+            todo(1, "2025-01-01T00:00:00+00:00", "Buy milk"),
+            todo(2, "2025-01-02T00:00:00+00:00", "Walk dog"),
+            todo(3, "2025-01-03T00:00:00+00:00", "Read book"),
+        ]
+    }
+
+    #[test]
+    fn test_hash_is_stable_and_ignores_title() {
+        let a = todo(1, "2025-01-01T00:00:00+00:00", "Buy milk");
+        let mut b = a.clone();
+        b.title = "Buy oat milk".to_string();
+        b.priority = Priority::Critical;
+        assert_eq!(full_hash(&a), full_hash(&b));
+        assert_eq!(full_hash(&a).len(), 16);
+        // Pinned so the algorithm can't silently change between releases
+        assert_eq!(full_hash(&a), "52d3c783d5c40e02");
+    }
+
+    #[test]
+    fn test_same_sequential_id_on_different_branches_differs() {
+        let ours = todo(7, "2025-01-01T09:00:00+00:00", "Ours");
+        let theirs = todo(7, "2025-01-01T09:05:00+00:00", "Theirs");
+        assert_ne!(full_hash(&ours), full_hash(&theirs));
+    }
+
+    #[test]
+    fn test_display_id() {
+        let t = todo(42, "2025-01-01T00:00:00+00:00", "x");
+        assert_eq!(display_id(&t, IdStyle::Sequential), "42");
+        let hashed = display_id(&t, IdStyle::Hash);
+        assert_eq!(hashed.len(), 6);
+        assert!(full_hash(&t).starts_with(&hashed));
+    }
+
+    #[test]
+    fn test_resolve_sequential() {
+        let todos = fixture();
+        assert_eq!(resolve(&todos, "2", IdStyle::Sequential).ok(), Some(2));
+        assert!(resolve(&todos, "9", IdStyle::Sequential).is_err());
+        assert!(resolve(&todos, "abc", IdStyle::Sequential).is_err());
+    }
+
+    #[test]
+    fn test_resolve_hash_prefixes() {
+        let todos = fixture();
+        for todo in &todos {
+            let hash = full_hash(todo);
+            assert_eq!(resolve(&todos, &hash, IdStyle::Hash).ok(), Some(todo.id));
+            assert_eq!(
+                resolve(&todos, &hash[..6], IdStyle::Hash).ok(),
+                Some(todo.id)
+            );
+            assert_eq!(
+                resolve(&todos, &hash[..6].to_uppercase(), IdStyle::Hash).ok(),
+                Some(todo.id)
+            );
+        }
+        assert!(resolve(&todos, "", IdStyle::Hash).is_err());
+        assert!(resolve(&todos, "xyz", IdStyle::Hash).is_err());
+    }
+
+    #[test]
+    fn test_resolve_hash_ambiguous_lists_candidates() {
+        // Find two todos whose hashes share a first character
+        let todos: Vec<Todo> = (1..=40)
+            .map(|id| todo(id, "2025-01-01T00:00:00+00:00", &format!("Todo {id}")))
+            .collect();
+        let first = full_hash(&todos[0]);
+        let prefix = &first[..1];
+        let sharing: Vec<&Todo> = todos
+            .iter()
+            .filter(|t| full_hash(t).starts_with(prefix))
+            .collect();
+        assert!(sharing.len() > 1, "fixture should contain a shared prefix");
+
+        let err = resolve(&todos, prefix, IdStyle::Hash).unwrap_err();
+        assert_eq!(err.exit_code(), 3);
+        let err = err.to_string();
+        assert!(err.contains("ambiguous"));
+        for todo in sharing {
+            assert!(err.contains(&todo.title));
+        }
+    }
+
+    #[test]
+    fn test_resolve_hash_not_found() {
+        let todos = fixture();
+        let used: Vec<String> = todos
+            .iter()
+            .map(|t| full_hash(t)[..1].to_string())
+            .collect();
+        let unused = "0123456789abcdef"
+            .chars()
+            .map(String::from)
+            .find(|c| !used.contains(c))
+            .unwrap();
+        let err = resolve(&todos, &unused, IdStyle::Hash).unwrap_err();
+        assert_eq!(err.exit_code(), 2);
+        assert!(err.to_string().contains("not found"));
+    }
+
+    fn inputs(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolve_many_ids_and_ranges() {
+        let todos: Vec<Todo> = [1, 2, 3, 5, 6]
+            .into_iter()
+            .map(|id| todo(id, "2025-01-01T00:00:00+00:00", "x"))
+            .collect();
+        let selection = resolve_many(
+            &todos,
+            &inputs(&["6", "1", "2-5", "9", "3"]),
+            IdStyle::Sequential,
+        );
+        assert_eq!(selection.ids, vec![6, 1, 2, 3, 5]);
+        assert_eq!(selection.not_found, vec!["4", "9"]);
+        assert!(selection.errors.is_empty());
+        assert!(!selection.is_complete());
+        assert_eq!(selection.failure_message(), "2 IDs not found (4, 9)");
+
+        let selection = resolve_many(&todos, &inputs(&["1", "2"]), IdStyle::Sequential);
+        assert!(selection.is_complete());
+    }
+
+    #[test]
+    fn test_resolve_many_bad_input() {
+        let todos = fixture();
+        for bad in ["3-1", "1-99999999999999999999999", "1-99999"] {
+            let selection = resolve_many(&todos, &inputs(&[bad, "1"]), IdStyle::Sequential);
+            assert_eq!(selection.ids, vec![1], "{bad}");
+            assert_eq!(selection.errors.len(), 1, "{bad}");
+            assert!(selection.not_found.is_empty(), "{bad}");
+        }
+
+        let hash = full_hash(&todos[1]);
+        let selection = resolve_many(&todos, &inputs(&[&hash[..6], "1-2"]), IdStyle::Hash);
+        assert_eq!(selection.ids, vec![2]);
+        assert!(selection.errors[0].contains("sequential"));
+    }
+
+    #[test]
+    fn test_resolve_many_sets_titles_aside() {
+        let todos = fixture();
+        let selection = resolve_many(
+            &todos,
+            &inputs(&["milk", "follow-up", "-2", "2"]),
+            IdStyle::Sequential,
+        );
+        assert_eq!(selection.ids, vec![2]);
+        assert_eq!(selection.titles, vec!["milk", "follow-up", "-2"]);
+        assert!(!selection.is_complete());
+
+        // Hex words that aren't a hash prefix may be titles too
+        let unused = ["bed", "cafe", "dead", "face"]
+            .into_iter()
+            .find(|word| !todos.iter().any(|t| full_hash(t).starts_with(word)))
+            .unwrap();
+        let selection = resolve_many(&todos, &inputs(&[unused]), IdStyle::Hash);
+        assert_eq!(selection.titles, vec![unused]);
+        assert!(may_be_title(&todos, unused, IdStyle::Hash));
+        assert!(!may_be_title(&todos, "9", IdStyle::Sequential));
+        assert!(may_be_title(&todos, "taxes", IdStyle::Sequential));
+    }
+}
@@ -0,0 +1,429 @@
+use crate::error::TtError;
+use crate::export::CSV_COLUMNS;
+use crate::models::todo::{Priority, Todo};
+use chrono::{DateTime, NaiveDate};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::path::Path;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum ImportFormat {
+    Json,
+    Csv,
+    Todotxt,
+}
+
+impl ImportFormat {
+    /// Guess the format from a file extension, defaulting to JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ImportFormat::Csv,
+            Some(ext) if ext.eq_ignore_ascii_case("txt") => ImportFormat::Todotxt,
+            _ => ImportFormat::Json,
+        }
+    }
+}
+
+/// Todos read from an import file, plus a warning for every entry that
+/// could not be used. Imported todos have no ID yet.
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub todos: Vec<Todo>,
+    pub warnings: Vec<String>,
+}
+
+pub fn parse(input: &str, format: ImportFormat) -> Parsed {
+    match format {
+        ImportFormat::Json => parse_json(input),
+        ImportFormat::Csv => parse_csv(input),
+        ImportFormat::Todotxt => parse_todotxt(input),
+    }
+}
+
+/// A `TodoStore` as written by `tt export --format json`, or a bare array
+/// of todos. Each todo is read on its own so one bad entry does not sink
+/// the rest.
+fn parse_json(input: &str) -> Parsed {
+    let mut parsed = Parsed::default();
+    let root: Value = match serde_json::from_str(input) {
+        Ok(root) => root,
+        Err(e) => {
+            parsed.warnings.push(format!("invalid JSON: {e}"));
+            return parsed;
+        }
+    };
+    let items = match root {
+        Value::Array(items) => items,
+        Value::Object(mut store) => match store.remove("todos") {
+            Some(Value::Array(items)) => items,
+            _ => {
+                parsed
+                    .warnings
+                    .push("expected a \"todos\" array".to_string());
+                return parsed;
+            }
+        },
+        _ => {
+            parsed.warnings.push("expected a list of todos".to_string());
+            return parsed;
+        }
+    };
+
+    for (index, item) in items.into_iter().enumerate() {
+        match serde_json::from_value::<Todo>(item) {
+            Ok(todo) => match check(todo) {
+                Ok(todo) => parsed.todos.push(todo),
+                Err(problem) => parsed
+                    .warnings
+                    .push(format!("todo {}: {problem}", index + 1)),
+            },
+            Err(e) => parsed.warnings.push(format!("todo {}: {e}", index + 1)),
+        }
+    }
+    parsed
+}
+
+/// CSV with a header row naming its columns, as written by `tt export
+/// --format csv`. Only `title` is required; columns tt does not know are
+/// ignored.
+fn parse_csv(input: &str) -> Parsed {
+    let mut parsed = Parsed::default();
+    let mut records = csv_records(input, &mut parsed.warnings).into_iter();
+    let Some((_, header)) = records.next() else {
+        return parsed;
+    };
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let Some(title_column) = column("title") else {
+        parsed.warnings.push(format!(
+            "line 1: no 'title' column (expected {})",
+            CSV_COLUMNS.join(",")
+        ));
+        return parsed;
+    };
+    let priority_column = column("priority");
+    let completed_column = column("completed");
+    let created_column = column("created_at");
+
+    for (line, fields) in records {
+        if fields.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|i| fields.get(i))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+        };
+
+        let result = csv_todo(
+            field(Some(title_column)).unwrap_or_default(),
+            field(priority_column),
+            field(completed_column),
+            field(created_column),
+        );
+        match result {
+            Ok(todo) => parsed.todos.push(todo),
+            Err(problem) => parsed.warnings.push(format!("line {line}: {problem}")),
+        }
+    }
+    parsed
+}
+
+fn csv_todo(
+    title: &str,
+    priority: Option<&str>,
+    completed: Option<&str>,
+    created_at: Option<&str>,
+) -> Result<Todo, String> {
+    let mut todo = Todo {
+        title: title.to_string(),
+        ..Todo::default()
+    };
+    if let Some(priority) = priority {
+        todo.priority = priority.parse().map_err(|e: TtError| e.to_string())?;
+    }
+    if let Some(completed) = completed {
+        todo.completed = parse_bool(completed)
+            .ok_or_else(|| format!("completed '{completed}' is not true or false"))?;
+    }
+    if let Some(created_at) = created_at {
+        todo.created_at = created_at.to_string();
+    }
+    check(todo)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" | "x" => Some(true),
+        "false" | "no" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Split CSV into records of fields, each with the line it starts on.
+/// Quoted fields may contain commas, doubled quotes and line breaks.
+pub fn csv_records(input: &str, warnings: &mut Vec<String>) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push((record_line, std::mem::take(&mut record)));
+                line += 1;
+                record_line = line;
+            }
+            '\n' => {
+                line += 1;
+                field.push(c);
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        warnings.push(format!("line {record_line}: unterminated quoted field"));
+    } else if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((record_line, record));
+    }
+    records
+}
+
+/// The todo.txt format: `x (A) 2025-01-01 Title +project @context due:2025-04-15`.
+/// Projects and contexts become tags; priorities A-C map to 1-3 and
+/// anything lower to 4.
+fn parse_todotxt(input: &str) -> Parsed {
+    let mut parsed = Parsed::default();
+    for (index, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_todotxt_line(line).and_then(check) {
+            Ok(todo) => parsed.todos.push(todo),
+            Err(problem) => parsed
+                .warnings
+                .push(format!("line {}: {problem}", index + 1)),
+        }
+    }
+    parsed
+}
+
+fn parse_todotxt_line(line: &str) -> Result<Todo, String> {
+    let mut todo = Todo::default();
+    let mut words = line.split_whitespace().peekable();
+    let is_date = |word: &str| NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok();
+
+    if words.peek() == Some(&"x") {
+        words.next();
+        todo.completed = true;
+        // Completion date
+        if words.peek().is_some_and(|word| is_date(word)) {
+            words.next();
+        }
+    }
+    if let Some(letter) = words.peek().and_then(|word| todotxt_priority(word)) {
+        words.next();
+        todo.priority = match letter {
+            'A' => Priority::Critical,
+            'B' => Priority::High,
+            'C' => Priority::Medium,
+            _ => Priority::Low,
+        };
+    }
+    if let Some(created) = words.next_if(|word| is_date(word)) {
+        todo.created_at = format!("{created}T00:00:00+00:00");
+    }
+
+    let mut title = Vec::new();
+    for word in words {
+        if let Some(due) = word.strip_prefix("due:") {
+            todo.due_date = Some(
+                NaiveDate::parse_from_str(due, "%Y-%m-%d")
+                    .map_err(|_| format!("due date '{due}' is not YYYY-MM-DD"))?,
+            );
+        } else if let Some(tag) = word.strip_prefix('+').or_else(|| word.strip_prefix('@')) {
+            todo.add_tag(tag);
+        } else {
+            title.push(word);
+        }
+    }
+    todo.title = title.join(" ");
+    // Tagging it above is part of reading it, not a change
+    todo.updated_at = None;
+    Ok(todo)
+}
+
+fn todotxt_priority(word: &str) -> Option<char> {
+    let mut chars = word.chars();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+        (Some('('), Some(letter), Some(')'), None) if letter.is_ascii_uppercase() => Some(letter),
+        _ => None,
+    }
+}
+
+/// Reject todos tt could not store, and normalize what can be fixed.
+fn check(mut todo: Todo) -> Result<Todo, String> {
+    todo.title = todo.title.trim().to_string();
+    if todo.title.is_empty() {
+        return Err("title is empty".to_string());
+    }
+    if DateTime::parse_from_rfc3339(&todo.created_at).is_err() {
+        return Err(format!(
+            "created_at '{}' is not a timestamp",
+            todo.created_at
+        ));
+    }
+    todo.id = 0;
+    Ok(todo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export;
+    use crate::models::todo::TodoStore;
+
+    fn titles(parsed: &Parsed) -> Vec<&str> {
+        parsed.todos.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let mut tricky = Todo::new(7, "milk, \"eggs\"\nand bread".to_string(), Priority::High);
+        tricky.completed = true;
+        let plain = Todo::new(8, "Plain".to_string(), Priority::Low);
+        let csv = export::to_csv(&[tricky.clone(), plain]);
+
+        let parsed = parse(&csv, ImportFormat::Csv);
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_eq!(titles(&parsed), vec![tricky.title.as_str(), "Plain"]);
+        assert_eq!(parsed.todos[0].priority, Priority::High);
+        assert!(parsed.todos[0].completed);
+        assert_eq!(parsed.todos[0].created_at, tricky.created_at);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let store = TodoStore {
+            todos: vec![Todo::new(1, "Buy milk".to_string(), Priority::Critical)],
+            next_id: 2,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+        };
+        let json = export::render(&store, export::ExportFormat::Json).unwrap();
+        let parsed = parse(&json, ImportFormat::Json);
+        assert_eq!(titles(&parsed), vec!["Buy milk"]);
+        assert_eq!(parsed.todos[0].id, 0);
+    }
+
+    #[test]
+    fn test_bad_rows_warn_without_aborting() {
+        let csv = "title,priority,completed\nGood,1,false\n,2,false\nBad priority,9,false\nBad bool,2,maybe\nAlso good,,\n";
+        let parsed = parse(csv, ImportFormat::Csv);
+        assert_eq!(titles(&parsed), vec!["Good", "Also good"]);
+        assert_eq!(parsed.warnings.len(), 3);
+        assert!(parsed.warnings[0].starts_with("line 3:"));
+        assert!(parsed.warnings[2].contains("maybe"));
+    }
+
+    #[test]
+    fn test_csv_line_numbers_account_for_multiline_fields() {
+        let csv = "title,priority\n\"two\nlines\",1\nBroken,x\n";
+        let parsed = parse(csv, ImportFormat::Csv);
+        assert_eq!(
+            parsed.warnings,
+            vec!["line 4: Priority must be 1-4 or critical, high, medium or low, got 'x'"]
+        );
+    }
+
+    #[test]
+    fn test_csv_without_title_column() {
+        let parsed = parse("name,priority\nx,1\n", ImportFormat::Csv);
+        assert!(parsed.todos.is_empty());
+        assert!(parsed.warnings[0].contains("no 'title' column"));
+    }
+
+    #[test]
+    fn test_csv_unterminated_quote() {
+        let parsed = parse("title\nok\n\"never closed\n", ImportFormat::Csv);
+        assert_eq!(titles(&parsed), vec!["ok"]);
+        assert_eq!(parsed.warnings, vec!["line 3: unterminated quoted field"]);
+    }
+
+    #[test]
+    fn test_json_bad_entries() {
+        let parsed = parse(
+            r#"[{"title": "ok", "completed": false, "created_at": "2025-01-01T00:00:00Z"},
+                {"title": "no status"},
+                {"title": "", "completed": false, "created_at": "2025-01-01T00:00:00Z"}]"#,
+            ImportFormat::Json,
+        );
+        assert_eq!(titles(&parsed), vec!["ok"]);
+        assert_eq!(parsed.warnings.len(), 2);
+        assert!(parsed.warnings[1].starts_with("todo 3:"));
+
+        assert_eq!(parse("{", ImportFormat::Json).warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_todotxt() {
+        let parsed = parse(
+            "(A) 2025-03-01 Call mom +family @phone due:2025-03-05\n\nx 2025-03-02 2025-03-01 Pay rent\n(E) Someday\n",
+            ImportFormat::Todotxt,
+        );
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_eq!(titles(&parsed), vec!["Call mom", "Pay rent", "Someday"]);
+
+        let call = &parsed.todos[0];
+        assert_eq!(call.priority, Priority::Critical);
+        assert_eq!(call.tags, vec!["family", "phone"]);
+        assert_eq!(call.due_date, NaiveDate::from_ymd_opt(2025, 3, 5));
+        assert_eq!(call.created_at, "2025-03-01T00:00:00+00:00");
+        assert!(parsed.todos[1].completed);
+        assert_eq!(parsed.todos[2].priority, Priority::Low);
+    }
+
+    #[test]
+    fn test_todotxt_bad_due_date() {
+        let parsed = parse("Thing due:tomorrow\n", ImportFormat::Todotxt);
+        assert_eq!(
+            parsed.warnings,
+            vec!["line 1: due date 'tomorrow' is not YYYY-MM-DD"]
+        );
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ImportFormat::from_path(Path::new("a.CSV")),
+            ImportFormat::Csv
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("todo.txt")),
+            ImportFormat::Todotxt
+        );
+        assert_eq!(
+            ImportFormat::from_path(Path::new("x.json")),
+            ImportFormat::Json
+        );
+        assert_eq!(ImportFormat::from_path(Path::new("x")), ImportFormat::Json);
+    }
+}
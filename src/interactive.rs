@@ -0,0 +1,373 @@
+use crate::ids::IdStyle;
+use crate::journal;
+use crate::models::todo::Priority;
+use crate::output::format_todo_line;
+use crate::text;
+use crate::todo_manager::{TodoEdit, TodoManager};
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Write};
+
+const HELP: &str = "↑/↓ move · space toggle · e edit · a add · d delete · q quit";
+
+/// What the keyboard is currently doing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    /// Typing a new title for the todo under the cursor
+    Edit(String),
+    /// Typing the title of a new todo
+    Add(String),
+    /// Waiting for y/n before deleting the todo under the cursor
+    ConfirmDelete,
+}
+
+/// The state of `tt ui`. Every change goes straight through the
+/// `TodoManager` methods the other commands use, so each one is saved,
+/// journaled and undoable on its own.
+#[derive(Debug)]
+struct App {
+    cursor: usize,
+    mode: Mode,
+    /// Result of the last action, shown under the list
+    message: Option<String>,
+    quit: bool,
+}
+
+impl App {
+    fn new() -> Self {
+        Self {
+            cursor: 0,
+            mode: Mode::Browse,
+            message: None,
+            quit: false,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, manager: &mut TodoManager) -> Result<()> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return Ok(());
+        }
+        let todos = manager.list_todos().to_vec();
+        let selected = todos.get(self.cursor).map(|todo| todo.id);
+
+        match (&mut self.mode, key.code) {
+            (Mode::Browse, KeyCode::Char('q') | KeyCode::Esc) => self.quit = true,
+            (Mode::Browse, KeyCode::Up | KeyCode::Char('k')) => {
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            (Mode::Browse, KeyCode::Down | KeyCode::Char('j')) => {
+                self.cursor = (self.cursor + 1).min(todos.len().saturating_sub(1));
+            }
+            (Mode::Browse, KeyCode::Char(' ')) => {
+                if let Some(id) = selected {
+                    manager.begin_operation("toggle");
+                    manager.toggle_completed(&[id])?;
+                    self.record(manager, id);
+                }
+            }
+            (Mode::Browse, KeyCode::Char('e')) => {
+                if let Some(todo) = todos.get(self.cursor) {
+                    self.mode = Mode::Edit(todo.title.clone());
+                }
+            }
+            (Mode::Browse, KeyCode::Char('a')) => self.mode = Mode::Add(String::new()),
+            (Mode::Browse, KeyCode::Char('d')) if selected.is_some() => {
+                self.mode = Mode::ConfirmDelete;
+            }
+            (Mode::ConfirmDelete, KeyCode::Char('y')) => {
+                if let Some(todo) = todos.get(self.cursor) {
+                    manager.begin_operation("delete");
+                    manager.trash_todos(&[todo.id])?;
+                    journal::record(&manager.journal_path(), vec![todo.title.clone()]);
+                    self.message = Some(format!("🗑️  Moved to the trash: {}", todo.title));
+                    self.cursor = self.cursor.min(todos.len().saturating_sub(2));
+                }
+                self.mode = Mode::Browse;
+            }
+            (Mode::ConfirmDelete, _) => self.mode = Mode::Browse,
+            (Mode::Edit(buffer) | Mode::Add(buffer), KeyCode::Char(c)) => buffer.push(c),
+            (Mode::Edit(buffer) | Mode::Add(buffer), KeyCode::Backspace) => {
+                buffer.pop();
+            }
+            (Mode::Edit(_) | Mode::Add(_), KeyCode::Esc) => self.mode = Mode::Browse,
+            (Mode::Edit(buffer), KeyCode::Enter) => {
+                let title = buffer.trim().to_string();
+                if let (Some(id), false) = (selected, title.is_empty()) {
+                    manager.begin_operation("edit");
+                    let edit = TodoEdit {
+                        title: Some(title),
+                        ..TodoEdit::default()
+                    };
+                    manager.edit_todo(id, edit)?;
+                    self.record(manager, id);
+                }
+                self.mode = Mode::Browse;
+            }
+            (Mode::Add(buffer), KeyCode::Enter) => {
+                let title = buffer.trim().to_string();
+                if !title.is_empty() {
+                    manager.begin_operation("add");
+                    let journal = manager.journal_path();
+                    let todo = manager.add_todo(title, Priority::Low, None, &[], None)?;
+                    journal::record(&journal, vec![todo.title.clone()]);
+                    self.message = Some(format!("✅ Added todo: {}", todo.title));
+                    self.cursor = manager.list_todos().len() - 1;
+                }
+                self.mode = Mode::Browse;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, manager: &TodoManager, id: usize) {
+        if let Some(todo) = manager.get_todo(id) {
+            journal::record(&manager.journal_path(), vec![todo.title.clone()]);
+            let status = if todo.completed {
+                "completed"
+            } else {
+                "incomplete"
+            };
+            self.message = Some(format!("{} ({status})", todo.title));
+        }
+    }
+
+    /// The screen as lines of text, cursor row marked with `›`. Titles are
+    /// shortened so rows fit in `columns`.
+    fn render(
+        &self,
+        manager: &TodoManager,
+        id_style: IdStyle,
+        today: NaiveDate,
+        columns: usize,
+    ) -> Vec<String> {
+        let todos = manager.list_todos();
+        let mut lines = vec!["📝 tt".to_string(), String::new()];
+        if todos.is_empty() {
+            lines.push("  No todos yet; press a to add one".to_string());
+        }
+        for (index, todo) in todos.iter().enumerate() {
+            let marker = if index == self.cursor { "›" } else { " " };
+            let mut line = format!("{marker}{}", format_todo_line(todo, id_style, today));
+            let overflow = text::display_width(&line).saturating_sub(columns);
+            if overflow > 0 {
+                let mut shortened = todo.clone();
+                let room = text::display_width(&todo.title).saturating_sub(overflow);
+                shortened.title = text::truncate(&todo.title, room);
+                line = format!("{marker}{}", format_todo_line(&shortened, id_style, today));
+            }
+            lines.push(line);
+        }
+        lines.push(String::new());
+        lines.push(match &self.mode {
+            Mode::Browse => self.message.clone().unwrap_or_else(|| HELP.to_string()),
+            Mode::Edit(buffer) => format!("Edit title (enter saves, esc cancels): {buffer}"),
+            Mode::Add(buffer) => format!("New todo (enter adds, esc cancels): {buffer}"),
+            Mode::ConfirmDelete => match todos.get(self.cursor) {
+                Some(todo) => format!("Delete '{}'? (y/n)", todo.title),
+                None => String::new(),
+            },
+        });
+        lines
+    }
+}
+
+/// Run `tt ui` until the user quits. Changes are saved as they are made.
+pub fn run(manager: &mut TodoManager, id_style: IdStyle) -> Result<()> {
+    let mut stdout = io::stdout();
+    let _terminal = RawTerminal::enter(&mut stdout)?;
+    let mut app = App::new();
+
+    while !app.quit {
+        let today = Local::now().date_naive();
+        queue!(
+            stdout,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        let (columns, _) = terminal::size()?;
+        for line in app.render(manager, id_style, today, columns.into()) {
+            write!(stdout, "{line}\r\n")?;
+        }
+        stdout.flush()?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                app.message = None;
+                if let Err(error) = app.handle_key(key, manager) {
+                    app.mode = Mode::Browse;
+                    app.message = Some(format!("❌ {error:#}"));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Raw mode on the alternate screen, undone when dropped so the terminal
+/// is restored on errors too.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter(stdout: &mut io::Stdout) -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn press(app: &mut App, manager: &mut TodoManager, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE), manager)
+            .unwrap();
+    }
+
+    fn type_text(app: &mut App, manager: &mut TodoManager, text: &str) {
+        for c in text.chars() {
+            press(app, manager, KeyCode::Char(c));
+        }
+    }
+
+    fn titles(manager: &TodoManager) -> Vec<(String, bool)> {
+        manager
+            .list_todos()
+            .iter()
+            .map(|todo| (todo.title.clone(), todo.completed))
+            .collect()
+    }
+
+    #[test]
+    fn test_keys_drive_the_manager_and_save() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("todos.json");
+        let mut manager = TodoManager::open_empty(path.clone(), false).unwrap();
+        let mut app = App::new();
+
+        press(&mut app, &mut manager, KeyCode::Char('a'));
+        type_text(&mut app, &mut manager, "Buy milk");
+        press(&mut app, &mut manager, KeyCode::Enter);
+        press(&mut app, &mut manager, KeyCode::Char('a'));
+        type_text(&mut app, &mut manager, "Walk dog");
+        press(&mut app, &mut manager, KeyCode::Enter);
+        assert_eq!(app.cursor, 1);
+
+        press(&mut app, &mut manager, KeyCode::Up);
+        press(&mut app, &mut manager, KeyCode::Char(' '));
+        press(&mut app, &mut manager, KeyCode::Down);
+        press(&mut app, &mut manager, KeyCode::Char('e'));
+        for _ in 0.."dog".len() {
+            press(&mut app, &mut manager, KeyCode::Backspace);
+        }
+        type_text(&mut app, &mut manager, "cat");
+        press(&mut app, &mut manager, KeyCode::Enter);
+
+        let expected = vec![
+            ("Buy milk".to_string(), true),
+            ("Walk cat".to_string(), false),
+        ];
+        assert_eq!(titles(&manager), expected);
+        let reloaded = TodoManager::open(path, false).unwrap();
+        assert_eq!(titles(&reloaded), expected);
+
+        press(&mut app, &mut manager, KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_delete_asks_first_and_escape_cancels() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager =
+            TodoManager::open_empty(temp_dir.path().join("todos.json"), false).unwrap();
+        manager
+            .add_todo("Keep".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Drop".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        let mut app = App::new();
+        press(&mut app, &mut manager, KeyCode::Down);
+
+        press(&mut app, &mut manager, KeyCode::Char('d'));
+        press(&mut app, &mut manager, KeyCode::Char('n'));
+        assert_eq!(manager.list_todos().len(), 2);
+
+        press(&mut app, &mut manager, KeyCode::Char('e'));
+        type_text(&mut app, &mut manager, " me");
+        press(&mut app, &mut manager, KeyCode::Esc);
+        assert_eq!(manager.list_todos()[1].title, "Drop");
+
+        press(&mut app, &mut manager, KeyCode::Char('d'));
+        press(&mut app, &mut manager, KeyCode::Char('y'));
+        assert_eq!(titles(&manager), vec![("Keep".to_string(), false)]);
+        assert_eq!(manager.trash()[0].todo.title, "Drop");
+        assert_eq!(app.cursor, 0);
+        assert!(!app.quit);
+    }
+
+    #[test]
+    fn test_render_marks_cursor_and_mode() {
+        colored::control::set_override(false);
+        let temp_dir = tempdir().unwrap();
+        let mut manager =
+            TodoManager::open_empty(temp_dir.path().join("todos.json"), false).unwrap();
+        manager
+            .add_todo("First".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Second".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let mut app = App::new();
+        app.cursor = 1;
+        app.mode = Mode::Add("Thi".to_string());
+
+        let lines = app.render(&manager, IdStyle::Sequential, today, 80);
+        assert_eq!(lines[2], "   1 [⏳] First");
+        assert_eq!(lines[3], "›  2 [⏳] Second");
+        assert_eq!(
+            lines.last().unwrap(),
+            "New todo (enter adds, esc cancels): Thi"
+        );
+    }
+
+    #[test]
+    fn test_render_fits_wide_titles_in_the_terminal() {
+        colored::control::set_override(false);
+        let temp_dir = tempdir().unwrap();
+        let mut manager =
+            TodoManager::open_empty(temp_dir.path().join("todos.json"), false).unwrap();
+        for title in [
+            "שלום עולם ".repeat(6),
+            "👨\u{200D}👩\u{200D}👧".repeat(20),
+            "e\u{0301}".repeat(40),
+        ] {
+            manager
+                .add_todo(title, Priority::Low, None, &[], None)
+                .unwrap();
+        }
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        let lines = App::new().render(&manager, IdStyle::Sequential, today, 30);
+        for (index, line) in lines[2..5].iter().enumerate() {
+            let marker = if index == 0 { "›" } else { " " };
+            assert!(line.starts_with(&format!("{marker}  {} [⏳] ", index + 1)));
+            assert!(text::display_width(line) <= 30, "{line}");
+            assert!(line.ends_with('…') || line.ends_with("…\u{2069}"), "{line}");
+        }
+    }
+}
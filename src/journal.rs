@@ -0,0 +1,319 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::models::todo::Todo;
+
+/// Rotate the journal once it grows past this many bytes.
+const MAX_JOURNAL_BYTES: u64 = 256 * 1024;
+
+/// Flags whose values are never written to the journal.
+const SECRET_FLAGS: &[&str] = &[
+    "--passphrase",
+    "--password",
+    "--token",
+    "--secret",
+    "--api-key",
+];
+
+const REDACTED: &str = "***";
+
+/// A single human-readable record of a command tt executed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: String, // ISO 8601 format
+    pub command: String,
+    pub cwd: String,
+    pub hostname: String,
+    #[serde(default)]
+    pub titles: Vec<String>,
+}
+
+impl JournalEntry {
+    pub fn new(args: &[String], titles: Vec<String>, now: DateTime<Utc>) -> Self {
+        Self {
+            timestamp: now.to_rfc3339(),
+            command: format_command(&sanitize_args(args)),
+            cwd: std::env::current_dir()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+            hostname: hostname(),
+            titles,
+        }
+    }
+
+    /// Case-insensitive substring match against every recorded field.
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.to_lowercase();
+        [&self.command, &self.cwd, &self.hostname]
+            .into_iter()
+            .chain(self.titles.iter())
+            .any(|field| field.to_lowercase().contains(&pattern))
+    }
+}
+
+/// Replace the values of secret-looking flags, in both `--flag value`
+/// and `--flag=value` forms.
+pub fn sanitize_args(args: &[String]) -> Vec<String> {
+    let mut sanitized = Vec::with_capacity(args.len());
+    let mut redact_next = false;
+
+    for arg in args {
+        if redact_next {
+            sanitized.push(REDACTED.to_string());
+            redact_next = false;
+            continue;
+        }
+
+        match arg.split_once('=') {
+            Some((flag, _)) if SECRET_FLAGS.contains(&flag) => {
+                sanitized.push(format!("{flag}={REDACTED}"));
+            }
+            _ => {
+                redact_next = SECRET_FLAGS.contains(&arg.as_str());
+                sanitized.push(arg.clone());
+            }
+        }
+    }
+
+    sanitized
+}
+
+/// Join arguments into a single shell-like line, quoting where needed.
+pub fn format_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) || arg.contains('"') {
+                format!("\"{}\"", arg.replace('"', "\\\""))
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn hostname() -> String {
+    system_hostname()
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(unix)]
+fn system_hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer outlives the call and its real length is passed,
+    // so the kernel never writes past it
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[cfg(not(unix))]
+fn system_hostname() -> Option<String> {
+    None
+}
+
+/// Where the journal goes when it is rotated.
+pub fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// Append an entry, rotating the journal when it is too large.
+pub fn append(path: &Path, entry: &JournalEntry) -> std::io::Result<()> {
+    append_with_limit(path, entry, MAX_JOURNAL_BYTES)
+}
+
+fn append_with_limit(path: &Path, entry: &JournalEntry, max_bytes: u64) -> std::io::Result<()> {
+    if fs::metadata(path).is_ok_and(|meta| meta.len() >= max_bytes) {
+        fs::rename(path, rotated_path(path))?;
+    }
+
+    let line = serde_json::to_string(entry)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// Record an entry, ignoring any failure so journaling can never break
+/// the command being journaled. Commands that change no todo, such as
+/// naming the list, pass no titles.
+pub fn record(path: &Path, titles: Vec<String>) {
+    let args: Vec<String> = std::env::args().collect();
+    let entry = JournalEntry::new(&args, titles, Utc::now());
+    let _ = append(path, &entry);
+}
+
+/// Titles of the todos that differ between two copies of a list: added,
+/// removed or edited. Names what a command that rewrites the list
+/// wholesale, like `tt undo` or `tt sync`, actually touched.
+pub fn changed_titles(before: &[Todo], after: &[Todo]) -> Vec<String> {
+    let old: HashMap<usize, &Todo> = before.iter().map(|todo| (todo.id, todo)).collect();
+    let kept: HashSet<usize> = after.iter().map(|todo| todo.id).collect();
+    after
+        .iter()
+        .filter(|todo| old.get(&todo.id) != Some(todo))
+        .chain(before.iter().filter(|todo| !kept.contains(&todo.id)))
+        .map(|todo| todo.title.clone())
+        .collect()
+}
+
+/// Read all entries, oldest first, including the rotated journal.
+/// Lines that fail to parse are skipped.
+pub fn read_entries(path: &Path) -> Vec<JournalEntry> {
+    [rotated_path(path), path.to_path_buf()]
+        .iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .flat_map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect::<Vec<JournalEntry>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+    use tempfile::tempdir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn entry(command: &str, titles: &[&str]) -> JournalEntry {
+        JournalEntry {
+            timestamp: Utc::now().to_rfc3339(),
+            command: command.to_string(),
+            cwd: "/home/me/projects".to_string(),
+            hostname: "laptop".to_string(),
+            titles: titles.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_sanitize_separate_value() {
+        let sanitized = sanitize_args(&args(&["tt", "sync", "--passphrase", "hunter2", "-v"]));
+        assert_eq!(
+            sanitized,
+            args(&["tt", "sync", "--passphrase", "***", "-v"])
+        );
+    }
+
+    #[test]
+    fn test_sanitize_inline_value() {
+        let sanitized = sanitize_args(&args(&["tt", "--token=abc123", "list"]));
+        assert_eq!(sanitized, args(&["tt", "--token=***", "list"]));
+    }
+
+    #[test]
+    fn test_sanitize_leaves_other_args() {
+        let original = args(&["tt", "add", "change password", "--priority", "1"]);
+        assert_eq!(sanitize_args(&original), original);
+    }
+
+    #[test]
+    fn test_sanitize_trailing_secret_flag() {
+        let sanitized = sanitize_args(&args(&["tt", "--password"]));
+        assert_eq!(sanitized, args(&["tt", "--password"]));
+    }
+
+    #[test]
+    fn test_format_command_quotes() {
+        let line = format_command(&args(&["tt", "add", "deploy \"prod\"", "-p", "1"]));
+        assert_eq!(line, "tt add \"deploy \\\"prod\\\"\" -p 1");
+    }
+
+    #[test]
+    fn test_matches_is_case_insensitive_across_fields() {
+        let e = entry("tt delete 3", &["Deploy to prod"]);
+        assert!(e.matches("deploy"));
+        assert!(e.matches("DELETE"));
+        assert!(e.matches("laptop"));
+        assert!(e.matches("projects"));
+        assert!(!e.matches("staging"));
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("journal.jsonl");
+
+        append(&path, &entry("tt add one", &["one"])).unwrap();
+        append(&path, &entry("tt add two", &["two"])).unwrap();
+
+        let entries = read_entries(&path);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].command, "tt add one");
+        assert_eq!(entries[1].titles, vec!["two"]);
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_entries_readable() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("journal.jsonl");
+
+        append_with_limit(&path, &entry("tt add one", &[]), 1).unwrap();
+        append_with_limit(&path, &entry("tt add two", &[]), 1).unwrap();
+
+        assert!(rotated_path(&path).exists());
+        let entries = read_entries(&path);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].command, "tt add one");
+        assert_eq!(entries[1].command, "tt add two");
+    }
+
+    #[test]
+    fn test_read_skips_corrupt_lines() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("journal.jsonl");
+        append(&path, &entry("tt add one", &[])).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "not json").unwrap();
+
+        assert_eq!(read_entries(&path).len(), 1);
+    }
+
+    #[test]
+    fn test_record_to_unwritable_path_does_not_panic() {
+        let temp_dir = tempdir().unwrap();
+        record(
+            &temp_dir.path().join("missing").join("journal.jsonl"),
+            vec![],
+        );
+    }
+
+    #[test]
+    fn test_hostname_is_known() {
+        assert_ne!(hostname(), "unknown");
+    }
+
+    #[test]
+    fn test_changed_titles() {
+        let kept = Todo::new(1, "Kept".to_string(), Priority::Medium);
+        let edited = Todo::new(2, "Edited".to_string(), Priority::Medium);
+        let removed = Todo::new(3, "Removed".to_string(), Priority::Medium);
+        let before = vec![kept.clone(), edited.clone(), removed];
+
+        let mut after_edit = edited;
+        after_edit.completed = true;
+        let added = Todo::new(4, "Added".to_string(), Priority::Medium);
+        let after = vec![kept, after_edit, added];
+
+        assert_eq!(
+            changed_titles(&before, &after),
+            vec!["Edited", "Added", "Removed"]
+        );
+        assert!(changed_titles(&before, &before).is_empty());
+    }
+}
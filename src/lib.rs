@@ -0,0 +1,116 @@
+//! The todo list behind the `tt` command, for programs that want to read
+//! and change the same todo files.
+//!
+//! [`TodoManager`] opens a todo file and makes every change to it; each
+//! todo is a [`Todo`] with a [`Priority`], and [`TodoStore`] is the file's
+//! contents as saved.
+//! Failures are `anyhow` errors, and those tt raises itself carry a
+//! [`TtError`] saying what kind of failure it was.
+//!
+//! ```
+//! # let dir = tempfile::tempdir().unwrap();
+//! # let path = dir.path().join("todos.json");
+//! use tt::{Priority, TodoManager, TtError};
+//!
+//! let mut manager = TodoManager::with_path(&path)?;
+//! manager.add_todo("Renew passport".to_string(), Priority::Critical, None, &["admin".to_string()], None)?;
+//! manager.add_todo("Call the bank".to_string(), Priority::High, None, &[], None)?;
+//!
+//! let titles: Vec<&str> = manager.list_todos().iter().map(|t| t.title.as_str()).collect();
+//! assert_eq!(titles, ["Renew passport", "Call the bank"]);
+//!
+//! let error = manager.mark_completed(&[42]).unwrap_err();
+//! assert!(matches!(error.downcast_ref(), Some(TtError::NotFound { .. })));
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod error;
+pub mod models;
+pub mod todo_manager;
+
+pub use error::TtError;
+pub use models::todo::{Priority, Todo, TodoStore};
+pub use todo_manager::TodoManager;
+
+// The rest is shared with the tt binary rather than a stable API
+
+#[doc(hidden)]
+pub mod age;
+#[doc(hidden)]
+pub mod attention;
+#[doc(hidden)]
+pub mod batch;
+#[doc(hidden)]
+pub mod bundle;
+#[doc(hidden)]
+pub mod check;
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod conflicts;
+#[doc(hidden)]
+pub mod conventions;
+#[doc(hidden)]
+pub mod dates;
+#[doc(hidden)]
+pub mod digest;
+#[doc(hidden)]
+pub mod due;
+#[doc(hidden)]
+pub mod duration;
+#[doc(hidden)]
+pub mod editor;
+#[doc(hidden)]
+pub mod export;
+#[doc(hidden)]
+pub mod focus;
+#[doc(hidden)]
+pub mod groom;
+#[doc(hidden)]
+pub mod groups;
+#[doc(hidden)]
+pub mod humanize;
+#[doc(hidden)]
+pub mod ids;
+#[doc(hidden)]
+pub mod import;
+#[doc(hidden)]
+pub mod interactive;
+#[doc(hidden)]
+pub mod journal;
+#[doc(hidden)]
+pub mod lists;
+#[doc(hidden)]
+pub mod lock;
+#[doc(hidden)]
+pub mod maintenance;
+#[doc(hidden)]
+pub mod merge;
+#[doc(hidden)]
+pub mod metrics;
+#[doc(hidden)]
+pub mod migrate;
+#[doc(hidden)]
+pub mod normalize;
+#[doc(hidden)]
+pub mod notes;
+#[doc(hidden)]
+pub mod output;
+#[doc(hidden)]
+pub mod render;
+#[doc(hidden)]
+pub mod settings;
+#[doc(hidden)]
+pub mod state;
+#[doc(hidden)]
+pub mod stats;
+#[doc(hidden)]
+pub mod suggest;
+#[doc(hidden)]
+pub mod tags;
+#[doc(hidden)]
+pub mod text;
+#[doc(hidden)]
+pub mod undo;
+#[doc(hidden)]
+pub mod validate;
@@ -0,0 +1,166 @@
+use crate::todo_manager::TodoManager;
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The list used when none is named, kept in the default todo file.
+pub const DEFAULT: &str = "default";
+
+/// Environment variable naming the list, overridden by `--list`.
+pub const ENV: &str = "TT_LIST";
+
+/// Directory holding the named lists, one `<name>.json` each, next to the
+/// default todo file in the data directory.
+pub fn dir() -> Result<PathBuf> {
+    dirs::data_dir()
+        .context("Could not determine data directory")
+        .map(|dir| dir.join("tt").join("lists"))
+}
+
+/// One line of `tt lists`.
+#[derive(Debug, Serialize)]
+pub struct ListSummary {
+    pub name: String,
+    pub path: PathBuf,
+    /// Incomplete todos, or `None` when the file cannot be loaded
+    pub open: Option<usize>,
+    /// Whether this is the list the command ran against
+    pub current: bool,
+}
+
+/// List names become file names, so they are limited to letters, digits,
+/// `-` and `_`.
+pub fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("List name cannot be empty");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!("Invalid list name '{name}': use letters, digits, '-' and '_' only");
+    }
+    Ok(())
+}
+
+/// The todo file of the named list `name` in `dir`.
+pub fn path_in(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.json"))
+}
+
+/// Names of the lists in `dir`, sorted. Archives, undo history and other
+/// files kept next to a list have a dot in their stem and are skipped.
+pub fn names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let stem = file_name.to_str()?.strip_suffix(".json")?;
+            (!stem.is_empty() && !stem.contains('.')).then(|| stem.to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Delete a list's todo file along with its archive, undo history and
+/// journal, waiting for any other tt process using it to finish.
+pub fn remove(path: &Path) -> Result<()> {
+    let manager = TodoManager::open_empty(path.to_path_buf(), true)?;
+    for file in manager.store_files() {
+        if file.exists() {
+            fs::remove_file(&file)
+                .with_context(|| format!("Failed to remove {}", file.display()))?;
+        }
+    }
+    let lock_path = manager.lock_path();
+    drop(manager);
+    let _ = fs::remove_file(lock_path);
+    Ok(())
+}
+
+/// Move a list's todo file and the files kept next to it to `to`, which
+/// must not be in use by another list.
+pub fn rename(from: &Path, to: &Path) -> Result<()> {
+    if to.exists() {
+        bail!("{} already exists", to.display());
+    }
+    let source = TodoManager::open_empty(from.to_path_buf(), true)?;
+    let target = TodoManager::open_empty(to.to_path_buf(), true)?;
+    for (old, new) in source.store_files().into_iter().zip(target.store_files()) {
+        if old.exists() {
+            fs::rename(&old, &new).with_context(|| {
+                format!("Failed to rename {} to {}", old.display(), new.display())
+            })?;
+        }
+    }
+    let lock_path = source.lock_path();
+    drop(source);
+    let _ = fs::remove_file(lock_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("work").is_ok());
+        assert!(validate_name("side-project_2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("work.archive").is_err());
+        assert!(validate_name("with space").is_err());
+    }
+
+    #[test]
+    fn test_names_skip_files_kept_next_to_lists() {
+        let temp_dir = tempdir().unwrap();
+        for name in [
+            "work.json",
+            "personal.json",
+            "work.archive.json",
+            "work.undo.json",
+            "work.lock",
+            "work.sync-conflict-20250101-120000-ABCDEFG.json",
+        ] {
+            fs::write(temp_dir.path().join(name), "{}").unwrap();
+        }
+        assert_eq!(names(temp_dir.path()), vec!["personal", "work"]);
+        assert!(names(&temp_dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_rename_and_remove_take_sidecars_along() {
+        let temp_dir = tempdir().unwrap();
+        let work = path_in(temp_dir.path(), "work");
+        let mut manager = TodoManager::open_empty(work.clone(), false).unwrap();
+        manager
+            .add_todo("Demo".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        drop(manager);
+        fs::write(work.with_extension("archive.json"), "[]").unwrap();
+
+        let job = path_in(temp_dir.path(), "job");
+        rename(&work, &job).unwrap();
+        assert!(!work.exists());
+        assert!(!work.with_extension("archive.json").exists());
+        assert!(job.with_extension("archive.json").exists());
+        let reopened = TodoManager::open(job.clone(), false).unwrap();
+        assert_eq!(reopened.list_todos()[0].title, "Demo");
+
+        fs::write(&work, r#"{"todos":[],"next_id":1}"#).unwrap();
+        assert!(rename(&job, &work).is_err());
+
+        remove(&job).unwrap();
+        assert_eq!(names(temp_dir.path()), vec!["work"]);
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+}
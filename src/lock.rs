@@ -0,0 +1,90 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How long to wait for another tt process before giving up.
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(3);
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(25);
+
+/// An exclusive advisory lock on a store, held for as long as the value
+/// lives. It is taken on a separate lock file because saves replace the
+/// todo file itself.
+///
+/// Locking uses `flock` and is a no-op on platforms without it.
+#[derive(Debug)]
+pub struct StoreLock {
+    _file: File,
+}
+
+impl StoreLock {
+    /// Lock `path`, creating it if needed and waiting up to `timeout` for
+    /// another holder to let go.
+    pub fn acquire(path: &Path, timeout: Duration) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create directory for lock file")?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .context("Failed to open lock file")?;
+
+        let started = Instant::now();
+        while !try_lock(&file)? {
+            if started.elapsed() >= timeout {
+                anyhow::bail!(
+                    "Another tt process is modifying your todos (waited {}s for {})",
+                    timeout.as_secs(),
+                    path.display()
+                );
+            }
+            std::thread::sleep(RETRY_INTERVAL);
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+/// Try to take the lock without blocking; `false` means someone else has it.
+#[cfg(unix)]
+fn try_lock(file: &File) -> Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: the descriptor stays open for the duration of the call, and
+    // the lock is released by the kernel when the file is closed
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let error = std::io::Error::last_os_error();
+    if error.kind() == std::io::ErrorKind::WouldBlock {
+        Ok(false)
+    } else {
+        Err(error).context("Failed to lock todo file")
+    }
+}
+
+#[cfg(not(unix))]
+fn try_lock(_file: &File) -> Result<bool> {
+    Ok(true)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_second_lock_times_out_while_held() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(".tt.lock");
+
+        let held = StoreLock::acquire(&path, LOCK_TIMEOUT).unwrap();
+        let error = StoreLock::acquire(&path, Duration::from_millis(50)).unwrap_err();
+        assert!(error.to_string().contains("Another tt process"));
+
+        drop(held);
+        assert!(StoreLock::acquire(&path, Duration::from_millis(50)).is_ok());
+    }
+}
@@ -1,18 +1,119 @@
+mod autocorrect;
 mod cli;
-mod models;
-mod todo_manager;
+mod completions;
 
 use anyhow::Result;
-use clap::Parser;
-use cli::{Cli, run_cli};
-use todo_manager::TodoManager;
+use chrono::Utc;
+use cli::{Cli, Commands, Failed, run_cli};
+use std::process::ExitCode;
+use tt::config::Config;
+use tt::duration::parse_duration;
+use tt::error::TtError;
+use tt::maintenance;
+use tt::output::{self, Output, esay};
+use tt::settings::Settings;
+use tt::todo_manager::{Location, TodoManager};
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() -> Result<ExitCode> {
+    let config = Config::load()?;
+    let cli = match autocorrect::parse(config.autocorrect) {
+        Ok(cli) => cli,
+        Err(code) => return Ok(code),
+    };
+    cli.color_choice().apply();
+    let settings = Settings::resolve(&config, cli.setting_flags(), |name| {
+        std::env::var(name).ok()
+    });
+    output::set_emoji(!settings.no_emoji.value);
+    for warning in &settings.warnings {
+        esay!("⚠️  {warning}");
+    }
+    let mut output = Output::new(cli.json, config.id_style);
+    output.style = cli.style.unwrap_or(config.style);
+    if config.show_age_dots {
+        let mut thresholds = config
+            .age_dots
+            .thresholds
+            .iter()
+            .map(|threshold| parse_duration(threshold))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!("Invalid age_dots.thresholds in config: {}", e))?;
+        thresholds.sort();
+        output.age_dots = Some(thresholds);
+    }
 
+    // Scripts can branch on the exit status: 2 when a todo (or anything
+    // else) doesn't exist, 3 for a refused value, 1 for everything else
+    match run(cli, &config, &settings, &output) {
+        Ok(()) => Ok(ExitCode::SUCCESS),
+        // The command already said why it failed
+        Err(e) if e.is::<Failed>() => Ok(ExitCode::FAILURE),
+        Err(e) => {
+            if output.json {
+                output.json_error(&e);
+            } else {
+                eprintln!("Error: {e:?}");
+            }
+            Ok(ExitCode::from(TtError::exit_code_of(&e) as u8))
+        }
+    }
+}
+
+fn run(cli: Cli, config: &Config, settings: &Settings, output: &Output) -> Result<()> {
     // Initialize TodoManager with persistence - fail fast on errors
-    let mut todo_manager = TodoManager::new()?;
+    let lock = cli.command.as_ref().is_some_and(Commands::needs_lock);
+    let location = Location::resolve(&settings.file, &settings.list)?;
+    let mut todo_manager = match TodoManager::open(location.clone(), lock) {
+        Ok(manager) => manager,
+        // These work on the raw file, so they must still run when it is broken
+        Err(_)
+            if matches!(
+                cli.command,
+                Some(Commands::Validate { .. } | Commands::Undo | Commands::Completions { .. })
+            ) =>
+        {
+            TodoManager::open_empty(location, lock)?
+        }
+        Err(e) if cli.force => {
+            esay!(
+                "⚠️  Warning: Could not load existing todos: {}",
+                e.root_cause()
+            );
+            esay!("   Starting with an empty todo list; `tt undo` brings the old file back.");
+            TodoManager::open_empty(location, lock)?
+        }
+        Err(e) => return Err(e),
+    }
+    .compact(config.compact_json);
 
-    run_cli(cli, &mut todo_manager)?;
+    let maintain = config.maintenance.auto && lock;
+    run_cli(cli, &mut todo_manager, config, settings, output)?;
+    if maintain {
+        maintain_if_due(&todo_manager, config);
+    }
     Ok(())
 }
+
+/// Run maintenance if it has not run for a week, after a command that
+/// held the lock. It stays quiet unless a task fails, and never fails the
+/// command that triggered it.
+fn maintain_if_due(todo_manager: &TodoManager, config: &Config) {
+    let now = Utc::now();
+    let Ok(retention) = config.retention() else {
+        return;
+    };
+    if !maintenance::is_due(todo_manager.last_maintenance(), now) {
+        return;
+    }
+    let job = maintenance::Job {
+        store: todo_manager.file_path(),
+        retention,
+        now,
+        dry_run: false,
+    };
+    let reports = maintenance::run(maintenance::TASKS, &job);
+    todo_manager.mark_maintained(now);
+    if reports.iter().any(|report| report.result.is_err()) {
+        esay!("⚠️  Maintenance: {}", maintenance::summary(&reports, false));
+    }
+}
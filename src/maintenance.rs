@@ -0,0 +1,495 @@
+use crate::journal;
+use crate::state::State;
+use crate::undo;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How often maintenance runs on its own after commands that change the
+/// store.
+pub const AUTO_INTERVAL: Duration = Duration::weeks(1);
+
+/// How long maintenance keeps the history in each sidecar file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// The rotated journal, counted from its last entry
+    pub journal: Duration,
+    /// Undo snapshots
+    pub undo: Duration,
+    /// Backups taken by `tt normalize`; the newest is always kept
+    pub backups: Duration,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            journal: Duration::days(365),
+            undo: Duration::days(30),
+            backups: Duration::days(90),
+        }
+    }
+}
+
+/// One maintenance run over the files kept alongside the todo file at
+/// `store`.
+pub struct Job<'a> {
+    pub store: &'a Path,
+    pub retention: Retention,
+    pub now: DateTime<Utc>,
+    /// Only report what would be pruned
+    pub dry_run: bool,
+}
+
+/// What a task pruned, or would prune in a dry run.
+#[derive(Debug, Default, PartialEq)]
+pub struct Outcome {
+    pub pruned: usize,
+    /// Problems that need a person, e.g. an unreadable backup
+    pub problems: Vec<String>,
+}
+
+/// A housekeeping task for some of the files kept alongside the todo file.
+pub struct Task {
+    pub name: &'static str,
+    /// What it prunes, for the summary
+    pub what: &'static str,
+    /// Extensions of the todo file it looks after; `*` matches anything
+    pub sidecars: &'static [&'static str],
+    /// Look after those of its sidecars that exist
+    pub run: fn(&Job, Vec<PathBuf>) -> Result<Outcome>,
+}
+
+/// Every maintenance task, in the order they run. A new kind of file kept
+/// next to the todo file must be looked after by one of these, or the
+/// registration test fails.
+pub const TASKS: &[Task] = &[
+    Task {
+        name: "journal",
+        what: "rotated journal(s)",
+        sidecars: &["journal.jsonl*"],
+        run: prune_journal,
+    },
+    Task {
+        name: "undo",
+        what: "undo snapshot(s)",
+        sidecars: &["undo.json"],
+        run: prune_undo,
+    },
+    Task {
+        name: "state",
+        what: "unreadable state file(s)",
+        sidecars: &["state.json"],
+        run: check_state,
+    },
+    Task {
+        name: "backups",
+        what: "backup(s)",
+        sidecars: &["backup-*.json"],
+        run: prune_backups,
+    },
+];
+
+/// How one task went.
+pub struct Report {
+    pub name: &'static str,
+    pub what: &'static str,
+    pub result: Result<Outcome>,
+}
+
+/// Run each of `tasks` on its sidecars, carrying on past any that fail.
+pub fn run(tasks: &[Task], job: &Job) -> Vec<Report> {
+    tasks
+        .iter()
+        .map(|task| Report {
+            name: task.name,
+            what: task.what,
+            result: sidecars_matching(job.store, task.sidecars)
+                .and_then(|paths| (task.run)(job, paths)),
+        })
+        .collect()
+}
+
+/// Whether maintenance that last ran at `last` should run again.
+pub fn is_due(last: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    last.is_none_or(|last| now - last >= AUTO_INTERVAL)
+}
+
+/// One line saying how each task went, e.g.
+/// `journal: nothing to prune; undo: pruned 3 undo snapshot(s)`.
+pub fn summary(reports: &[Report], dry_run: bool) -> String {
+    reports
+        .iter()
+        .map(|report| {
+            let result = match &report.result {
+                Err(e) => format!("failed: {e:#}"),
+                Ok(outcome) => {
+                    let mut parts = vec![match (outcome.pruned, dry_run) {
+                        (0, _) => "nothing to prune".to_string(),
+                        (n, true) => format!("would prune {n} {}", report.what),
+                        (n, false) => format!("pruned {n} {}", report.what),
+                    }];
+                    parts.extend(outcome.problems.iter().cloned());
+                    parts.join(", ")
+                }
+            };
+            format!("{}: {result}", report.name)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn modified(path: &Path) -> Result<DateTime<Utc>> {
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .with_context(|| format!("Failed to read the age of {}", path.display()))?;
+    Ok(modified.into())
+}
+
+fn remove(path: &Path, job: &Job) -> Result<()> {
+    if job.dry_run {
+        return Ok(());
+    }
+    fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))
+}
+
+/// Remove the rotated journal once its newest entry is past retention. The
+/// live journal rotates itself as it grows.
+fn prune_journal(job: &Job, paths: Vec<PathBuf>) -> Result<Outcome> {
+    let rotated = journal::rotated_path(&job.store.with_extension("journal.jsonl"));
+    let mut outcome = Outcome::default();
+    for path in paths.into_iter().filter(|path| *path == rotated) {
+        if modified(&path)? < job.now - job.retention.journal {
+            remove(&path, job)?;
+            outcome.pruned += 1;
+        }
+    }
+    Ok(outcome)
+}
+
+fn prune_undo(job: &Job, paths: Vec<PathBuf>) -> Result<Outcome> {
+    let mut outcome = Outcome::default();
+    for path in paths {
+        outcome.pruned += undo::prune(&path, job.now - job.retention.undo, job.dry_run)?;
+    }
+    Ok(outcome)
+}
+
+/// Remove a state file that no longer parses; everything in it has a safe
+/// default, and tt would otherwise keep ignoring it.
+fn check_state(job: &Job, paths: Vec<PathBuf>) -> Result<Outcome> {
+    let mut outcome = Outcome::default();
+    for path in paths {
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        if serde_json::from_str::<State>(&content).is_err() {
+            remove(&path, job)?;
+            outcome.pruned += 1;
+        }
+    }
+    Ok(outcome)
+}
+
+/// Check every backup still reads as JSON, and remove readable ones past
+/// retention except the newest. Unreadable backups are reported, not
+/// removed.
+fn prune_backups(job: &Job, paths: Vec<PathBuf>) -> Result<Outcome> {
+    let mut outcome = Outcome::default();
+    let mut readable = Vec::new();
+    for path in paths {
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        if serde_json::from_str::<serde_json::Value>(&content).is_ok() {
+            readable.push((modified(&path)?, path));
+        } else {
+            outcome.problems.push(format!(
+                "{} is unreadable",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+        }
+    }
+    readable.sort();
+    readable.pop();
+    let cutoff = job.now - job.retention.backups;
+    for (modified, path) in readable {
+        if modified < cutoff {
+            remove(&path, job)?;
+            outcome.pruned += 1;
+        }
+    }
+    Ok(outcome)
+}
+
+/// The files next to `store` whose names match one of the sidecar
+/// `patterns`.
+fn sidecars_matching(store: &Path, patterns: &[&str]) -> Result<Vec<PathBuf>> {
+    let dir = match store.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to list {}", dir.display()))?;
+        let name = entry.file_name();
+        if patterns
+            .iter()
+            .any(|pattern| is_sidecar(store, &name.to_string_lossy(), pattern))
+        {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Whether `name` is the sidecar of `store` described by `pattern`.
+fn is_sidecar(store: &Path, name: &str, pattern: &str) -> bool {
+    let stem = store.file_stem().unwrap_or_default().to_string_lossy();
+    let Some(extension) = name
+        .strip_prefix(stem.as_ref())
+        .and_then(|rest| rest.strip_prefix('.'))
+    else {
+        return false;
+    };
+    match pattern.split_once('*') {
+        Some((start, end)) => {
+            extension.len() >= start.len() + end.len()
+                && extension.starts_with(start)
+                && extension.ends_with(end)
+        }
+        None => extension == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+    use crate::normalize;
+    use crate::todo_manager::TodoManager;
+    use std::fs::File;
+    use std::time::SystemTime;
+    use tempfile::{TempDir, tempdir};
+
+    fn store() -> (TempDir, PathBuf) {
+        let temp_dir = tempdir().unwrap();
+        let store = temp_dir.path().join("todos.json");
+        (temp_dir, store)
+    }
+
+    fn job(store: &Path, dry_run: bool) -> Job<'_> {
+        Job {
+            store,
+            retention: Retention::default(),
+            now: Utc::now(),
+            dry_run,
+        }
+    }
+
+    /// Run the registered task `name` as `tt maintenance` would.
+    fn run_task(name: &str, store: &Path, dry_run: bool) -> Outcome {
+        let task = TASKS.iter().find(|task| task.name == name).unwrap();
+        let paths = sidecars_matching(store, task.sidecars).unwrap();
+        (task.run)(&job(store, dry_run), paths).unwrap()
+    }
+
+    fn age(path: &Path, days: i64) {
+        let time = SystemTime::now() - std::time::Duration::from_secs(days as u64 * 86_400);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_every_sidecar_is_registered() {
+        let (temp_dir, store) = store();
+        fs::write(
+            &store,
+            r#"{"todos":[{"id":1,"title":"Imported","completed":false,"priority":0}],"next_id":2}"#,
+        )
+        .unwrap();
+        let mut manager = TodoManager::open(store.clone(), true).unwrap();
+        manager
+            .normalize(
+                &normalize::Settings::new(&["low=critical".to_string()], None, &[]).unwrap(),
+                false,
+            )
+            .unwrap();
+        manager.begin_operation("add");
+        manager
+            .add_todo("Write report".to_string(), Priority::High, None, &[], None)
+            .unwrap();
+        manager.toggle_completed(&[1]).unwrap();
+        manager.archive_completed().unwrap();
+        manager.mark_viewed(Utc::now());
+        journal::record(&manager.journal_path(), Vec::new());
+        fs::write(journal::rotated_path(&manager.journal_path()), "").unwrap();
+
+        // Never pruned: archived todos are kept for good, and the lock file
+        // is in use while tt runs
+        let kept = ["archive.json", "lock"];
+        let registered: Vec<&str> = TASKS
+            .iter()
+            .flat_map(|task| task.sidecars.iter().copied())
+            .chain(kept)
+            .collect();
+        let names: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name != "todos.json")
+            .collect();
+        assert!(names.len() >= 7, "{names:?}");
+        for name in names {
+            assert!(
+                registered
+                    .iter()
+                    .any(|pattern| is_sidecar(&store, &name, pattern)),
+                "{name} is not looked after by a maintenance task"
+            );
+        }
+    }
+
+    #[test]
+    fn test_prune_journal_removes_only_an_old_rotation() {
+        let (_temp_dir, store) = store();
+        let journal = store.with_extension("journal.jsonl");
+        let rotated = journal::rotated_path(&journal);
+        fs::write(&journal, "{}\n").unwrap();
+        assert_eq!(run_task("journal", &store, false).pruned, 0);
+
+        fs::write(&rotated, "{}\n").unwrap();
+        age(&rotated, 30);
+        assert_eq!(run_task("journal", &store, false).pruned, 0);
+
+        age(&rotated, 400);
+        assert_eq!(run_task("journal", &store, true).pruned, 1);
+        assert!(rotated.exists());
+        assert_eq!(run_task("journal", &store, false).pruned, 1);
+        assert!(!rotated.exists());
+        assert!(journal.exists());
+    }
+
+    #[test]
+    fn test_prune_undo_uses_retention() {
+        let (_temp_dir, store) = store();
+        let path = store.with_extension("undo.json");
+        for days in [45, 1] {
+            let mut snapshot = undo::Snapshot::take("add", &store, &store, Utc::now());
+            snapshot.taken_at = (Utc::now() - Duration::days(days)).to_rfc3339();
+            undo::push(&path, snapshot).unwrap();
+        }
+        assert_eq!(run_task("undo", &store, true).pruned, 1);
+        assert_eq!(run_task("undo", &store, false).pruned, 1);
+        assert_eq!(undo::load(&path).len(), 1);
+    }
+
+    #[test]
+    fn test_check_state_removes_only_a_broken_file() {
+        let (_temp_dir, store) = store();
+        let path = store.with_extension("state.json");
+        assert_eq!(run_task("state", &store, false).pruned, 0);
+
+        State::default().save(&path).unwrap();
+        assert_eq!(run_task("state", &store, false).pruned, 0);
+        assert!(path.exists());
+
+        fs::write(&path, "{ not json").unwrap();
+        assert_eq!(run_task("state", &store, true).pruned, 1);
+        assert!(path.exists());
+        assert_eq!(run_task("state", &store, false).pruned, 1);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_prune_backups_keeps_the_newest_and_reports_unreadable_ones() {
+        let (temp_dir, store) = store();
+        let backup = |name: &str, content: &str, days: i64| {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, content).unwrap();
+            age(&path, days);
+            path
+        };
+        let oldest = backup("todos.backup-20240101-000000.json", "{}", 200);
+        let newest = backup("todos.backup-20240201-000000.json", "{}", 170);
+        let broken = backup("todos.backup-20240301-000000.json", "{ trunc", 150);
+        let other = backup("work.backup-20240101-000000.json", "{}", 200);
+
+        let outcome = run_task("backups", &store, false);
+        assert_eq!(
+            outcome,
+            Outcome {
+                pruned: 1,
+                problems: vec!["todos.backup-20240301-000000.json is unreadable".to_string()],
+            }
+        );
+        assert!(!oldest.exists());
+        assert!(newest.exists());
+        assert!(broken.exists());
+        assert!(other.exists());
+    }
+
+    #[test]
+    fn test_a_failing_task_does_not_stop_the_others() {
+        fn fails(_: &Job, _: Vec<PathBuf>) -> Result<Outcome> {
+            anyhow::bail!("disk on fire")
+        }
+        fn prunes(_: &Job, _: Vec<PathBuf>) -> Result<Outcome> {
+            Ok(Outcome {
+                pruned: 2,
+                problems: vec!["one looked odd".to_string()],
+            })
+        }
+        let tasks = [
+            Task {
+                name: "first",
+                what: "thing(s)",
+                sidecars: &[],
+                run: fails,
+            },
+            Task {
+                name: "second",
+                what: "thing(s)",
+                sidecars: &[],
+                run: prunes,
+            },
+        ];
+        let (_temp_dir, store) = store();
+        let reports = run(&tasks, &job(&store, false));
+        assert_eq!(reports.len(), 2);
+        assert!(reports[0].result.is_err());
+        assert_eq!(
+            summary(&reports, false),
+            "first: failed: disk on fire; second: pruned 2 thing(s), one looked odd"
+        );
+        assert_eq!(
+            summary(&reports[1..], true),
+            "second: would prune 2 thing(s), one looked odd"
+        );
+    }
+
+    #[test]
+    fn test_is_due_weekly() {
+        let now = Utc::now();
+        assert!(is_due(None, now));
+        assert!(!is_due(Some(now - Duration::days(6)), now));
+        assert!(is_due(Some(now - Duration::days(7)), now));
+    }
+
+    #[test]
+    fn test_is_sidecar() {
+        let store = Path::new("/data/todos.json");
+        assert!(is_sidecar(store, "todos.undo.json", "undo.json"));
+        assert!(is_sidecar(store, "todos.backup-1-2.json", "backup-*.json"));
+        assert!(!is_sidecar(
+            store,
+            "todos.backup-1-2.json.bak",
+            "backup-*.json"
+        ));
+        assert!(!is_sidecar(store, "work.undo.json", "undo.json"));
+        assert!(!is_sidecar(store, "todosundo.json", "undo.json"));
+    }
+}
@@ -0,0 +1,525 @@
+use crate::models::todo::{Deletion, Todo, TodoStore};
+use chrono::{DateTime, Duration, Utc};
+use std::fmt;
+
+/// How long deletions are remembered. A copy of the store that has been
+/// out of sync for longer may bring deleted todos back.
+pub const DELETION_RETENTION_DAYS: i64 = 90;
+
+/// What merging another copy of a store changed.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub added: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Titles of todos deleted on one side but edited on the other since;
+    /// the edit wins
+    pub kept: Vec<String>,
+    /// Titles of todos changed in different ways on both sides; the newer
+    /// change wins, but a completion from either side is kept
+    pub conflicts: Vec<String>,
+}
+
+impl MergeReport {
+    /// How many todos the two sides disagreed about.
+    pub fn conflicted(&self) -> usize {
+        self.kept.len() + self.conflicts.len()
+    }
+}
+
+impl fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} deleted, {} conflicted",
+            self.added,
+            self.updated,
+            self.deleted,
+            self.conflicted()
+        )
+    }
+}
+
+/// Merge `theirs`, another copy of the store, into `ours`.
+///
+/// Todos are matched by ID and creation time. A todo on one side only is
+/// added, unless the other side deleted it without it changing since (or,
+/// for theirs, it is in our archive). Deletions from both sides are kept.
+///
+/// A todo on both sides takes the most recently changed version, unless
+/// `base`, the copy both sides started from, is given: then each field
+/// takes the change made to it, and only fields changed on both sides go
+/// to the newer version. `base` also shows todos deleted on one side once
+/// their deletion has been forgotten. Either way a todo completed on one
+/// side stays completed unless the other side reopened it.
+pub fn merge(
+    ours: &mut TodoStore,
+    theirs: TodoStore,
+    base: Option<&TodoStore>,
+    archive: &[Todo],
+) -> MergeReport {
+    let mut report = MergeReport::default();
+    let highest = ours
+        .todos
+        .iter()
+        .chain(&theirs.todos)
+        .chain(archive)
+        .map(|todo| todo.id)
+        .max()
+        .unwrap_or(0);
+    ours.next_id = ours.next_id.max(theirs.next_id).max(highest + 1);
+    let base_of =
+        |todo: &Todo| base.and_then(|base| base.todos.iter().find(|other| other.same_todo(todo)));
+
+    // Apply their deletions to our todos
+    let mut kept = Vec::new();
+    for todo in std::mem::take(&mut ours.todos) {
+        if theirs.todos.iter().any(|other| other.same_todo(&todo)) {
+            kept.push(todo);
+            continue;
+        }
+        let deletion = theirs.deleted.iter().find(|deletion| deletion.is_of(&todo));
+        let unchanged = match (deletion, base_of(&todo)) {
+            (Some(deletion), _) => !changed_since(&todo, deletion),
+            (None, Some(original)) => *original == todo,
+            // Added on our side
+            (None, None) => {
+                kept.push(todo);
+                continue;
+            }
+        };
+        if unchanged {
+            report.deleted += 1;
+        } else {
+            report.kept.push(todo.title.clone());
+            kept.push(todo);
+        }
+    }
+    ours.todos = kept;
+
+    for mut todo in theirs.todos {
+        if let Some(existing) = ours.todos.iter_mut().find(|other| other.same_todo(&todo)) {
+            let (merged, clashed) = merge_todo(existing, &todo, base_of(&todo));
+            if clashed {
+                report.conflicts.push(merged.title.clone());
+            }
+            if merged != *existing {
+                *existing = merged;
+                report.updated += 1;
+            }
+            continue;
+        }
+        if archive.iter().any(|archived| archived.same_todo(&todo)) {
+            continue;
+        }
+        let deletion = ours.deleted.iter().find(|deletion| deletion.is_of(&todo));
+        let deleted_unchanged = match (deletion, base_of(&todo)) {
+            (Some(deletion), _) => Some(!changed_since(&todo, deletion)),
+            (None, Some(original)) => Some(*original == todo),
+            (None, None) => None,
+        };
+        match deleted_unchanged {
+            Some(true) => continue,
+            Some(false) => report.kept.push(todo.title.clone()),
+            None => report.added += 1,
+        }
+
+        // Both copies may have handed out the same ID to different todos
+        let taken = |id: usize| ours.todos.iter().chain(archive).any(|other| other.id == id);
+        if taken(todo.id) {
+            todo.id = ours.next_id;
+            ours.next_id += 1;
+        }
+        ours.todos.push(todo);
+    }
+
+    for deletion in theirs.deleted {
+        if !ours.deleted.contains(&deletion) {
+            ours.deleted.push(deletion);
+        }
+    }
+    // A todo that survived (edited after its deletion) is no longer deleted
+    let todos = &ours.todos;
+    ours.deleted
+        .retain(|deletion| !todos.iter().any(|todo| deletion.is_of(todo)));
+    // In the order they happened, so both sides save the same list
+    ours.deleted.sort_by_key(Deletion::time);
+
+    report
+}
+
+/// Our and their versions of one todo merged, and whether they clashed:
+/// changed the same field in different ways, or disagreed about whether
+/// it is done in a way only keeping the completion could settle.
+fn merge_todo(ours: &Todo, theirs: &Todo, base: Option<&Todo>) -> (Todo, bool) {
+    let newer = if theirs.last_modified() > ours.last_modified() {
+        theirs
+    } else {
+        ours
+    };
+    let mut merged = newer.clone();
+    let mut clashed = false;
+
+    if let Some(base) = base {
+        let versions = Versions {
+            ours,
+            theirs,
+            base,
+            newer,
+        };
+        let clashed = &mut clashed;
+        merged.title = versions.pick(|t| &t.title, clashed).title.clone();
+        merged.priority = versions.pick(|t| &t.priority, clashed).priority;
+        merged.due_date = versions.pick(|t| &t.due_date, clashed).due_date;
+        merged.tags = versions.pick(|t| &t.tags, clashed).tags.clone();
+        merged.project = versions.pick(|t| &t.project, clashed).project.clone();
+        merged.notes = versions.pick(|t| &t.notes, clashed).notes.clone();
+        merged.checklist = versions.pick(|t| &t.checklist, clashed).checklist.clone();
+        merged.pinned = versions.pick(|t| &t.pinned, clashed).pinned;
+        let snoozed = versions.pick(|t| &t.snoozed_until, clashed);
+        merged.snoozed_until = snoozed.snoozed_until.clone();
+        merged.snooze_count = ours.snooze_count.max(theirs.snooze_count);
+    }
+
+    // Never lose a completion to a side that simply hadn't seen it
+    if ours.completed != theirs.completed {
+        let (done, open) = if ours.completed {
+            (ours, theirs)
+        } else {
+            (theirs, ours)
+        };
+        let reopened = match base {
+            // Done in the base, so the open side reopened it
+            Some(base) => base.completed,
+            // A later change on the open side may be a reopen or an edit
+            // made before the completion arrived; keep the completion and
+            // say so
+            None => {
+                clashed |= open.last_modified() > done.last_modified();
+                false
+            }
+        };
+        let winner = if reopened { open } else { done };
+        merged.completed = winner.completed;
+        merged.completed_at = winner.completed_at.clone();
+    }
+    (merged, clashed)
+}
+
+/// The versions of a todo in a three-way merge.
+struct Versions<'a> {
+    ours: &'a Todo,
+    theirs: &'a Todo,
+    base: &'a Todo,
+    newer: &'a Todo,
+}
+
+impl<'a> Versions<'a> {
+    /// The version whose value of `field` to keep: that of the side that
+    /// changed it, or the newer one's if both did. Sets `clashed` when
+    /// both changed it in different ways.
+    fn pick<T: PartialEq>(&self, field: impl Fn(&Todo) -> &T, clashed: &mut bool) -> &'a Todo {
+        let (ours, theirs, base) = (field(self.ours), field(self.theirs), field(self.base));
+        match (ours == base, theirs == base) {
+            (true, false) => self.theirs,
+            (false, true) => self.ours,
+            _ => {
+                *clashed |= ours != theirs;
+                self.newer
+            }
+        }
+    }
+}
+
+/// Whether `todo` changed after it was deleted elsewhere. Unreadable times
+/// err on the side of keeping the todo.
+fn changed_since(todo: &Todo, deletion: &Deletion) -> bool {
+    match (todo.last_modified(), deletion.time()) {
+        (Some(modified), Some(deleted)) => modified > deleted,
+        _ => true,
+    }
+}
+
+/// Forget deletions older than `DELETION_RETENTION_DAYS`.
+pub fn prune_deletions(deleted: &mut Vec<Deletion>, now: DateTime<Utc>) {
+    let cutoff = now - Duration::days(DELETION_RETENTION_DAYS);
+    deleted.retain(|deletion| deletion.time().is_none_or(|time| time > cutoff));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn todo(id: usize, title: &str, created_at: &str) -> Todo {
+        let mut todo = Todo::new(id, title.to_string(), Priority::Low);
+        todo.created_at = created_at.to_string();
+        todo
+    }
+
+    fn edited(mut todo: Todo, title: &str, at: &str) -> Todo {
+        todo.title = title.to_string();
+        todo.updated_at = Some(at.to_string());
+        todo
+    }
+
+    fn store(todos: Vec<Todo>, deleted: Vec<Deletion>) -> TodoStore {
+        let next_id = todos.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        TodoStore {
+            todos,
+            next_id,
+            meta: None,
+            deleted,
+            templates: Vec::new(),
+            trash: Vec::new(),
+        }
+    }
+
+    fn deletion(todo: &Todo, at: &str) -> Deletion {
+        Deletion {
+            id: todo.id,
+            created_at: todo.created_at.clone(),
+            deleted_at: at.to_string(),
+        }
+    }
+
+    fn titles(store: &TodoStore) -> Vec<(usize, &str)> {
+        store
+            .todos
+            .iter()
+            .map(|todo| (todo.id, todo.title.as_str()))
+            .collect()
+    }
+
+    const T0: &str = "2025-01-01T09:00:00+00:00";
+    const T1: &str = "2025-01-02T09:00:00+00:00";
+    const T2: &str = "2025-01-03T09:00:00+00:00";
+    const T3: &str = "2025-01-04T09:00:00+00:00";
+
+    #[test]
+    fn test_newer_edit_wins() {
+        let base = todo(1, "Buy milk", T0);
+        let mut ours = store(vec![edited(base.clone(), "Buy oat milk", T1)], vec![]);
+        let theirs = store(vec![edited(base, "Buy milk and eggs", T2)], vec![]);
+
+        let report = merge(&mut ours, theirs, None, &[]);
+        assert_eq!(titles(&ours), vec![(1, "Buy milk and eggs")]);
+        assert_eq!(report.updated, 1);
+
+        let older = store(vec![edited(todo(1, "Buy milk", T0), "Old", T0)], vec![]);
+        let report = merge(&mut ours, older, None, &[]);
+        assert_eq!(titles(&ours), vec![(1, "Buy milk and eggs")]);
+        assert_eq!(report, MergeReport::default());
+    }
+
+    #[test]
+    fn test_adds_are_united_and_clashing_ids_renumbered() {
+        let shared = todo(1, "Shared", T0);
+        let mut ours = store(vec![shared.clone(), todo(2, "Ours", T1)], vec![]);
+        let theirs = store(vec![shared, todo(2, "Theirs", T2)], vec![]);
+
+        let report = merge(&mut ours, theirs, None, &[]);
+        assert_eq!(
+            titles(&ours),
+            vec![(1, "Shared"), (2, "Ours"), (3, "Theirs")]
+        );
+        assert_eq!(report.added, 1);
+        assert_eq!(ours.next_id, 4);
+    }
+
+    #[test]
+    fn test_their_delete_applies_to_unchanged_todo() {
+        let gone = todo(2, "Gone", T0);
+        let mut ours = store(vec![todo(1, "Stays", T0), gone.clone()], vec![]);
+        let theirs = store(vec![todo(1, "Stays", T0)], vec![deletion(&gone, T1)]);
+
+        let report = merge(&mut ours, theirs, None, &[]);
+        assert_eq!(titles(&ours), vec![(1, "Stays")]);
+        assert_eq!(report.deleted, 1);
+        assert_eq!(ours.deleted, vec![deletion(&gone, T1)]);
+    }
+
+    #[test]
+    fn test_delete_vs_later_edit_keeps_the_edit() {
+        let base = todo(1, "Plan trip", T0);
+
+        // They deleted it, we edited it afterwards
+        let mut ours = store(vec![edited(base.clone(), "Plan trip to Rome", T2)], vec![]);
+        let theirs = store(vec![], vec![deletion(&base, T1)]);
+        let report = merge(&mut ours, theirs, None, &[]);
+        assert_eq!(titles(&ours), vec![(1, "Plan trip to Rome")]);
+        assert_eq!(report.kept, vec!["Plan trip to Rome"]);
+        assert!(ours.deleted.is_empty());
+
+        // We deleted it, they edited it afterwards
+        let mut ours = store(vec![], vec![deletion(&base, T1)]);
+        ours.next_id = 2;
+        let theirs = store(vec![edited(base.clone(), "Plan trip to Oslo", T3)], vec![]);
+        let report = merge(&mut ours, theirs, None, &[]);
+        assert_eq!(titles(&ours), vec![(1, "Plan trip to Oslo")]);
+        assert_eq!(report.kept, vec!["Plan trip to Oslo"]);
+        assert!(ours.deleted.is_empty());
+    }
+
+    #[test]
+    fn test_our_delete_beats_earlier_edit() {
+        let base = todo(1, "Plan trip", T0);
+        let mut ours = store(vec![], vec![deletion(&base, T2)]);
+        let theirs = store(vec![edited(base, "Plan trip to Oslo", T1)], vec![]);
+
+        let report = merge(&mut ours, theirs, None, &[]);
+        assert!(ours.todos.is_empty());
+        assert_eq!(report, MergeReport::default());
+        assert_eq!(ours.deleted.len(), 1);
+    }
+
+    #[test]
+    fn test_archived_todos_are_not_brought_back() {
+        let archived = todo(1, "Done", T0);
+        let mut ours = store(vec![], vec![]);
+        let theirs = store(vec![archived.clone()], vec![]);
+
+        let report = merge(&mut ours, theirs, None, &[archived]);
+        assert!(ours.todos.is_empty());
+        assert_eq!(report.added, 0);
+        assert_eq!(ours.next_id, 2);
+    }
+
+    #[test]
+    fn test_base_merges_edits_field_by_field() {
+        let original = todo(1, "Buy milk", T0);
+        let base = store(vec![original.clone()], vec![]);
+        let mut ours = edited(original.clone(), "Buy oat milk", T1);
+        ours.pinned = true;
+        let mut theirs = original.clone();
+        theirs.priority = Priority::High;
+        theirs.updated_at = Some(T2.to_string());
+        let mut ours = store(vec![ours], vec![]);
+
+        let report = merge(&mut ours, store(vec![theirs], vec![]), Some(&base), &[]);
+        let merged = &ours.todos[0];
+        assert_eq!(merged.title, "Buy oat milk");
+        assert_eq!(merged.priority, Priority::High);
+        assert!(merged.pinned);
+        assert_eq!(merged.updated_at.as_deref(), Some(T2));
+        assert_eq!(report.updated, 1);
+        assert!(report.conflicts.is_empty());
+
+        // Without the base, the newer version wins whole
+        let mut ours = store(vec![edited(original.clone(), "Buy oat milk", T1)], vec![]);
+        let theirs = edited(original, "Buy milk", T2);
+        merge(&mut ours, store(vec![theirs], vec![]), None, &[]);
+        assert_eq!(titles(&ours), vec![(1, "Buy milk")]);
+    }
+
+    #[test]
+    fn test_both_sides_editing_a_field_is_a_conflict() {
+        let original = todo(1, "Plan trip", T0);
+        let base = store(vec![original.clone()], vec![]);
+        let mut ours = store(
+            vec![edited(original.clone(), "Plan trip to Rome", T1)],
+            vec![],
+        );
+        let theirs = store(
+            vec![edited(original.clone(), "Plan trip to Oslo", T2)],
+            vec![],
+        );
+
+        let report = merge(&mut ours, theirs, Some(&base), &[]);
+        assert_eq!(titles(&ours), vec![(1, "Plan trip to Oslo")]);
+        assert_eq!(report.conflicts, vec!["Plan trip to Oslo"]);
+        assert_eq!(report.conflicted(), 1);
+
+        // Making the same change on both sides is no conflict
+        let same = edited(original, "Plan trip to Oslo", T3);
+        let report = merge(&mut ours, store(vec![same], vec![]), Some(&base), &[]);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_completions_are_never_lost() {
+        let original = todo(1, "Pay rent", T0);
+        let mut done = original.clone();
+        done.completed = true;
+        done.completed_at = Some(T1.to_string());
+        done.updated_at = Some(T1.to_string());
+        let renamed = edited(original.clone(), "Pay the rent", T2);
+
+        // They renamed it later without having seen our completion
+        let base = store(vec![original.clone()], vec![]);
+        let mut ours = store(vec![done.clone()], vec![]);
+        let theirs = store(vec![renamed.clone()], vec![]);
+        let report = merge(&mut ours, theirs, Some(&base), &[]);
+        assert!(ours.todos[0].completed);
+        assert_eq!(ours.todos[0].completed_at.as_deref(), Some(T1));
+        assert_eq!(titles(&ours), vec![(1, "Pay the rent")]);
+        assert!(report.conflicts.is_empty());
+
+        // Without a base that could have been a reopen, so it is reported
+        let mut ours = store(vec![done.clone()], vec![]);
+        let report = merge(&mut ours, store(vec![renamed], vec![]), None, &[]);
+        assert!(ours.todos[0].completed);
+        assert_eq!(report.conflicts, vec!["Pay the rent"]);
+
+        // Reopening something done in the base sticks
+        let base = store(vec![done.clone()], vec![]);
+        let mut reopened = done.clone();
+        reopened.completed = false;
+        reopened.completed_at = None;
+        reopened.updated_at = Some(T2.to_string());
+        let mut ours = store(vec![done], vec![]);
+        merge(&mut ours, store(vec![reopened], vec![]), Some(&base), &[]);
+        assert!(!ours.todos[0].completed);
+    }
+
+    #[test]
+    fn test_base_shows_forgotten_deletions() {
+        let kept = todo(1, "Stays", T0);
+        let gone = todo(2, "Gone", T0);
+        let base = store(vec![kept.clone(), gone.clone()], vec![]);
+
+        // Deleted on their side long enough ago that the record is gone
+        let mut ours = store(vec![kept.clone(), gone.clone()], vec![]);
+        let theirs = store(vec![kept.clone()], vec![]);
+        let report = merge(&mut ours, theirs, Some(&base), &[]);
+        assert_eq!(titles(&ours), vec![(1, "Stays")]);
+        assert_eq!(report.deleted, 1);
+
+        // Deleted on our side, but edited on theirs: the edit is a conflict
+        // and comes back
+        let mut ours = store(vec![kept.clone()], vec![]);
+        ours.next_id = 3;
+        let theirs = store(vec![kept, edited(gone, "Not gone", T1)], vec![]);
+        let report = merge(&mut ours, theirs, Some(&base), &[]);
+        assert_eq!(titles(&ours), vec![(1, "Stays"), (2, "Not gone")]);
+        assert_eq!(report.kept, vec!["Not gone"]);
+        assert_eq!(report.added, 0);
+    }
+
+    #[test]
+    fn test_prune_deletions() {
+        let now = DateTime::parse_from_rfc3339(T0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let recent = deletion(&todo(1, "A", T0), "2024-12-01T00:00:00+00:00");
+        let old = deletion(&todo(2, "B", T0), "2024-09-01T00:00:00+00:00");
+        let mut deleted = vec![recent.clone(), old];
+        prune_deletions(&mut deleted, now);
+        assert_eq!(deleted, vec![recent]);
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = MergeReport {
+            added: 2,
+            updated: 1,
+            deleted: 0,
+            kept: vec!["x".to_string()],
+            conflicts: vec!["y".to_string()],
+        };
+        assert_eq!(
+            report.to_string(),
+            "2 added, 1 updated, 0 deleted, 2 conflicted"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::stats::Stats;
+use std::fmt::Write;
+
+/// What the metrics describe besides the todos themselves.
+#[derive(Debug)]
+pub struct StoreInfo<'a> {
+    /// The store's name, or the default heading when it has none
+    pub name: &'a str,
+    pub path: &'a str,
+    /// Size of the todo file on disk, 0 when it does not exist yet
+    pub size_bytes: u64,
+}
+
+/// Render `stats` in the Prometheus text exposition format, e.g. for the
+/// node exporter's textfile collector. Metric names and labels are a
+/// public interface: add new ones, but never rename or remove them.
+pub fn render(stats: &Stats, store: &StoreInfo) -> String {
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        "tt_todos_pending",
+        "Todos not yet completed.",
+        [(String::new(), stats.pending as f64)],
+    );
+    gauge(
+        &mut out,
+        "tt_todos_completed",
+        "Completed todos that have not been archived.",
+        [(String::new(), stats.completed as f64)],
+    );
+    gauge(
+        &mut out,
+        "tt_todos_overdue",
+        "Pending todos whose due date has passed.",
+        [(String::new(), stats.overdue as f64)],
+    );
+    gauge(
+        &mut out,
+        "tt_todos_pending_by_priority",
+        "Pending todos by priority; label priority is 1 (highest) to 4.",
+        stats
+            .pending_by_priority
+            .iter()
+            .map(|(priority, count)| (format!("priority=\"{priority}\""), *count as f64)),
+    );
+    // No sample at all when there is nothing pending: 0 would read as fresh
+    gauge(
+        &mut out,
+        "tt_oldest_pending_age_seconds",
+        "Age of the oldest pending todo; absent when none is pending.",
+        stats
+            .oldest_pending_age_secs
+            .map(|secs| (String::new(), secs as f64)),
+    );
+    gauge(
+        &mut out,
+        "tt_store_size_bytes",
+        "Size of the todo file on disk.",
+        [(String::new(), store.size_bytes as f64)],
+    );
+    let info = format!(
+        "name=\"{}\",path=\"{}\"",
+        escape_label_value(store.name),
+        escape_label_value(store.path)
+    );
+    gauge(
+        &mut out,
+        "tt_store_info",
+        "Always 1; labels name (the store's name) and path (the todo file).",
+        [(info, 1.0)],
+    );
+    out
+}
+
+/// One gauge with its HELP and TYPE lines and a sample per label set.
+fn gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, f64)>,
+) {
+    // Writing to a String cannot fail
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{name} {value}");
+        } else {
+            let _ = writeln!(out, "{name}{{{labels}}} {value}");
+        }
+    }
+}
+
+/// Escape a label value: backslash, double quote and line feed are the
+/// only characters the format requires escaping.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn stats(oldest_pending_age_secs: Option<i64>) -> Stats {
+        Stats {
+            total: 5,
+            pending: 3,
+            completed: 2,
+            overdue: 1,
+            pending_by_priority: BTreeMap::from([(1, 1), (2, 0), (3, 0), (4, 2)]),
+            oldest_pending_age_secs,
+        }
+    }
+
+    const STORE: StoreInfo = StoreInfo {
+        name: "Your todos",
+        path: "/home/me/.tt.json",
+        size_bytes: 512,
+    };
+
+    #[test]
+    fn test_render_snapshot() {
+        assert_eq!(
+            render(&stats(Some(86400)), &STORE),
+            r#"# HELP tt_todos_pending Todos not yet completed.
+# TYPE tt_todos_pending gauge
+tt_todos_pending 3
+# HELP tt_todos_completed Completed todos that have not been archived.
+# TYPE tt_todos_completed gauge
+tt_todos_completed 2
+# HELP tt_todos_overdue Pending todos whose due date has passed.
+# TYPE tt_todos_overdue gauge
+tt_todos_overdue 1
+# HELP tt_todos_pending_by_priority Pending todos by priority; label priority is 1 (highest) to 4.
+# TYPE tt_todos_pending_by_priority gauge
+tt_todos_pending_by_priority{priority="1"} 1
+tt_todos_pending_by_priority{priority="2"} 0
+tt_todos_pending_by_priority{priority="3"} 0
+tt_todos_pending_by_priority{priority="4"} 2
+# HELP tt_oldest_pending_age_seconds Age of the oldest pending todo; absent when none is pending.
+# TYPE tt_oldest_pending_age_seconds gauge
+tt_oldest_pending_age_seconds 86400
+# HELP tt_store_size_bytes Size of the todo file on disk.
+# TYPE tt_store_size_bytes gauge
+tt_store_size_bytes 512
+# HELP tt_store_info Always 1; labels name (the store's name) and path (the todo file).
+# TYPE tt_store_info gauge
+tt_store_info{name="Your todos",path="/home/me/.tt.json"} 1
+"#
+        );
+    }
+
+    #[test]
+    fn test_oldest_age_absent_without_pending() {
+        let rendered = render(&stats(None), &STORE);
+        assert!(rendered.contains("# TYPE tt_oldest_pending_age_seconds gauge\n# HELP"));
+        assert!(
+            !rendered
+                .lines()
+                .any(|line| line.starts_with("tt_oldest_pending_age_seconds"))
+        );
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(
+            escape_label_value("C:\\todos \"work\"\nlist"),
+            r#"C:\\todos \"work\"\nlist"#
+        );
+        let store = StoreInfo {
+            name: "Say \"hi\"",
+            ..STORE
+        };
+        assert!(
+            render(&stats(None), &store)
+                .contains(r#"tt_store_info{name="Say \"hi\"",path="/home/me/.tt.json"} 1"#)
+        );
+    }
+}
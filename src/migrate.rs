@@ -0,0 +1,185 @@
+use crate::models::todo::TodoStore;
+use crate::output::esay;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files kept next to the todo file that move along with it, by the
+/// extension `TodoManager` gives them. The lock file and the write
+/// bookkeeping in the state file (which the copy makes stale) stay behind.
+const SIDECARS: &[&str] = &["archive.json", "undo.json", "journal.jsonl"];
+
+/// What `migrate` did about a todo file at the old location.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Migration {
+    /// Nothing to move: the new file exists already, or the old one doesn't
+    NotNeeded,
+    /// The old file and its sidecars were copied to the new location
+    Migrated,
+    /// The old file has to stay in use: it is a symlink (likely into a
+    /// synced folder) or could not be parsed
+    KeptLegacy,
+}
+
+/// The default todo file: `tt/todos.json` in the platform data directory
+/// (`$XDG_DATA_HOME`, falling back to `~/.local/share`, on Linux). Todos
+/// still in `~/.tt.json` are moved there first.
+pub fn default_file_path() -> Result<PathBuf> {
+    let legacy = dirs::home_dir()
+        .context("Could not determine home directory")?
+        .join(".tt.json");
+    let target = dirs::data_dir()
+        .context("Could not determine data directory")?
+        .join("tt")
+        .join("todos.json");
+
+    match migrate(&legacy, &target)? {
+        Migration::NotNeeded => Ok(target),
+        Migration::Migrated => {
+            esay!(
+                "📦 Moved your todos from {} to {}; the old file is no longer used and can be deleted.",
+                legacy.display(),
+                target.display()
+            );
+            Ok(target)
+        }
+        Migration::KeptLegacy => Ok(legacy),
+    }
+}
+
+/// Copy the todo file at `legacy`, and the files kept next to it, to
+/// `target` unless `target` exists already. The copy is read back and
+/// parsed before it is used; `legacy` itself is never changed.
+pub fn migrate(legacy: &Path, target: &Path) -> Result<Migration> {
+    if target.exists() || !legacy.exists() {
+        return Ok(Migration::NotNeeded);
+    }
+    let is_symlink = fs::symlink_metadata(legacy)
+        .map(|metadata| metadata.file_type().is_symlink())
+        .unwrap_or(false);
+    if is_symlink || !parses(legacy) {
+        return Ok(Migration::KeptLegacy);
+    }
+
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {} for your todos", dir.display()))?;
+    }
+    // Sidecars first, so the todo file appearing marks a complete move
+    for extension in SIDECARS {
+        let from = legacy.with_extension(extension);
+        if from.exists() {
+            let to = target.with_extension(extension);
+            fs::copy(&from, &to).with_context(|| {
+                format!("Failed to copy {} to {}", from.display(), to.display())
+            })?;
+        }
+    }
+    let partial = target.with_extension("json.migrating");
+    fs::copy(legacy, &partial).with_context(|| {
+        format!(
+            "Failed to copy {} to {}",
+            legacy.display(),
+            partial.display()
+        )
+    })?;
+    if !parses(&partial) {
+        let _ = fs::remove_file(&partial);
+        anyhow::bail!(
+            "Copying {} to {} did not produce a readable file; your todos are still in {}",
+            legacy.display(),
+            target.display(),
+            legacy.display()
+        );
+    }
+    fs::rename(&partial, target)
+        .with_context(|| format!("Failed to move your todos into {}", target.display()))?;
+    Ok(Migration::Migrated)
+}
+
+fn parses(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .is_some_and(|content| serde_json::from_str::<TodoStore>(&content).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const STORE: &str = r#"{"todos":[{"id":1,"title":"Keep me","completed":false,"created_at":"2025-01-01T09:00:00+00:00"}],"next_id":2}"#;
+
+    #[test]
+    fn test_only_old_exists_is_migrated() {
+        let temp_dir = tempdir().unwrap();
+        let legacy = temp_dir.path().join(".tt.json");
+        let target = temp_dir.path().join("data/tt/todos.json");
+        fs::write(&legacy, STORE).unwrap();
+        fs::write(legacy.with_extension("archive.json"), "[]").unwrap();
+
+        assert_eq!(migrate(&legacy, &target).unwrap(), Migration::Migrated);
+        assert_eq!(fs::read_to_string(&target).unwrap(), STORE);
+        assert_eq!(
+            fs::read_to_string(target.with_extension("archive.json")).unwrap(),
+            "[]"
+        );
+        assert!(!target.with_extension("undo.json").exists());
+        assert!(!target.with_extension("json.migrating").exists());
+        assert_eq!(fs::read_to_string(&legacy).unwrap(), STORE);
+
+        // Only ever once
+        assert_eq!(migrate(&legacy, &target).unwrap(), Migration::NotNeeded);
+    }
+
+    #[test]
+    fn test_both_exist_leaves_new_file_alone() {
+        let temp_dir = tempdir().unwrap();
+        let legacy = temp_dir.path().join(".tt.json");
+        let target = temp_dir.path().join("todos.json");
+        fs::write(&legacy, STORE).unwrap();
+        fs::write(&target, r#"{"todos":[],"next_id":1}"#).unwrap();
+
+        assert_eq!(migrate(&legacy, &target).unwrap(), Migration::NotNeeded);
+        assert_eq!(
+            fs::read_to_string(&target).unwrap(),
+            r#"{"todos":[],"next_id":1}"#
+        );
+    }
+
+    #[test]
+    fn test_neither_exists_creates_nothing() {
+        let temp_dir = tempdir().unwrap();
+        let legacy = temp_dir.path().join(".tt.json");
+        let target = temp_dir.path().join("data/tt/todos.json");
+
+        assert_eq!(migrate(&legacy, &target).unwrap(), Migration::NotNeeded);
+        assert!(!temp_dir.path().join("data").exists());
+    }
+
+    #[test]
+    fn test_unreadable_old_file_stays_in_use() {
+        let temp_dir = tempdir().unwrap();
+        let legacy = temp_dir.path().join(".tt.json");
+        let target = temp_dir.path().join("todos.json");
+        fs::write(&legacy, "{ not json").unwrap();
+
+        assert_eq!(migrate(&legacy, &target).unwrap(), Migration::KeptLegacy);
+        assert!(!target.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinked_old_file_stays_in_use() {
+        let temp_dir = tempdir().unwrap();
+        let synced = temp_dir.path().join("Sync/todos.json");
+        fs::create_dir_all(synced.parent().unwrap()).unwrap();
+        fs::write(&synced, STORE).unwrap();
+        let legacy = temp_dir.path().join(".tt.json");
+        std::os::unix::fs::symlink(&synced, &legacy).unwrap();
+        let target = temp_dir.path().join("todos.json");
+
+        assert_eq!(migrate(&legacy, &target).unwrap(), Migration::KeptLegacy);
+        assert!(!target.exists());
+    }
+}
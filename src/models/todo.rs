@@ -1,67 +1,662 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use crate::error::TtError;
+use crate::output::esay;
+use crate::tags;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use clap::ValueEnum;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Todo {
+    #[serde(default)]
+    pub id: usize, // Stable identifier, 0 until assigned by the manager
     pub title: String,
     pub completed: bool,
-    pub created_at: String, // ISO 8601 format
-    #[serde(default = "default_priority")]
-    pub priority: u8, // 1-4, where 1 is highest priority
+    #[serde(default)]
+    pub created_at: String, // ISO 8601 format, empty if imported without one
+    #[serde(default)]
+    pub priority: Priority, // Saved as 1-4, where 1 is highest priority
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>, // ISO 8601 date (YYYY-MM-DD)
+    #[serde(default)]
+    pub tags: Vec<String>, // Lowercase, without duplicates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>, // Trimmed, never blank
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub snooze_count: u32, // How many times the todo has been snoozed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<String>, // ISO 8601 format, hidden from listings until then
+    #[serde(default)]
+    pub notes: Option<String>, // Free-form description, line breaks preserved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>, // ISO 8601 format, None until first changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>, // ISO 8601 format, None while incomplete
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklist: Vec<ChecklistItem>, // Small steps inside the todo, in order
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub draft: bool, // Reserved by `tt reserve` and hidden until published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft_expires_at: Option<String>, // ISO 8601 format, when an unpublished draft is dropped
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pinned: bool, // Listed above everything else while incomplete
+    #[serde(skip)]
+    pub saved_priority: Option<u8>, // The number the file had when out of range and read as low
+}
+
+/// One line of a todo's checklist. Items are addressed by their position,
+/// starting at 1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecklistItem {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub done: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct TodoStore {
     pub todos: Vec<Todo>,
+    #[serde(default)]
+    pub next_id: usize, // Next stable ID to hand out, never reused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<StoreMeta>, // Absent until a name or description is set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deleted: Vec<Deletion>, // Recently deleted todos, for merging copies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub templates: Vec<Template>, // Saved with `tt template save`, in the order saved
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trash: Vec<TrashedTodo>, // Deleted todos that can still be restored, oldest first
+}
+
+/// A `TodoStore` borrowed from whoever holds the todos, saved in the same
+/// form without copying them first.
+#[derive(Debug, Serialize)]
+pub struct StoreRef<'a> {
+    pub todos: &'a [Todo],
+    pub next_id: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<&'a StoreMeta>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub deleted: &'a [Deletion],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub templates: &'a [Template],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub trash: &'a [TrashedTodo],
+}
+
+/// A todo `tt delete` moved to the trash, kept exactly as it was until it
+/// is restored or the trash is emptied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashedTodo {
+    pub todo: Todo,
+    pub deleted_at: String, // ISO 8601 format
+}
+
+impl TrashedTodo {
+    /// Whether `tt trash empty` removes it: always, or with `older_than`
+    /// when it was deleted at least that long before `now`. A deletion
+    /// time that cannot be read counts as long ago.
+    pub fn is_purgeable(&self, older_than: Option<Duration>, now: DateTime<Utc>) -> bool {
+        match older_than {
+            None => true,
+            Some(age) => DateTime::parse_from_rfc3339(&self.deleted_at)
+                .map_or(true, |deleted| now - deleted.with_timezone(&Utc) >= age),
+        }
+    }
+}
+
+/// A named set of todos to add again and again. It keeps its own copy of
+/// what each todo was, so it outlives the todos it was saved from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub todos: Vec<TemplateTodo>,
+}
+
+/// One todo of a template: what a todo added from it starts with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateTodo {
+    pub title: String,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+impl TemplateTodo {
+    pub fn of(todo: &Todo) -> Self {
+        Self {
+            title: todo.title.clone(),
+            priority: todo.priority,
+            tags: todo.tags.clone(),
+            project: todo.project.clone(),
+        }
+    }
+}
+
+/// Record of a deleted todo, kept for a while so that merging another
+/// copy of the store removes it there too instead of bringing it back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deletion {
+    pub id: usize,
+    pub created_at: String, // Of the todo, telling apart todos that share an ID
+    pub deleted_at: String, // ISO 8601 format
+}
+
+/// Optional description of a store as a whole. Stores only gain it once
+/// something is set, so older files are left unchanged on save.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub created_at: String,  // ISO 8601 format
+    pub modified_at: String, // ISO 8601 format, bumped on every save
+}
+
+impl Deletion {
+    pub fn of(todo: &Todo, now: DateTime<Utc>) -> Self {
+        Self {
+            id: todo.id,
+            created_at: todo.created_at.clone(),
+            deleted_at: now.to_rfc3339(),
+        }
+    }
+
+    /// Whether this records the deletion of `todo`.
+    pub fn is_of(&self, todo: &Todo) -> bool {
+        self.id == todo.id && self.created_at == todo.created_at
+    }
+
+    /// When the todo was deleted, or `None` if the timestamp cannot be read.
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.deleted_at)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+}
+
+impl StoreMeta {
+    /// Fresh metadata for a store holding `todos`. The store is taken to
+    /// date from its oldest todo, as nothing recorded its creation before.
+    pub fn new(todos: &[Todo], now: DateTime<Utc>) -> Self {
+        let oldest = todos
+            .iter()
+            .filter_map(|todo| DateTime::parse_from_rfc3339(&todo.created_at).ok())
+            .map(|created| created.with_timezone(&Utc))
+            .min();
+        Self {
+            name: None,
+            description: None,
+            created_at: oldest.map_or(now, |oldest| oldest.min(now)).to_rfc3339(),
+            modified_at: now.to_rfc3339(),
+        }
+    }
+
+    /// What to call a store in headings: its name, if it has one.
+    pub fn display_name(meta: Option<&Self>) -> &str {
+        meta.and_then(|meta| meta.name.as_deref())
+            .unwrap_or("Your todos")
+    }
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// How urgent a todo is. Saved as its number, 1 (critical) to 4 (low), as
+/// priorities were before they had names; on the command line either works.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, ValueEnum)]
+pub enum Priority {
+    #[value(alias = "1")]
+    Critical = 1,
+    #[value(alias = "2")]
+    High = 2,
+    #[value(alias = "3")]
+    Medium = 3,
+    // The default, and what todos saved without a priority get
+    #[default]
+    #[value(alias = "4")]
+    Low = 4,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [
+        Priority::Critical,
+        Priority::High,
+        Priority::Medium,
+        Priority::Low,
+    ];
+
+    /// The priority numbered `number`, 1 (critical) to 4 (low).
+    pub fn from_number(number: u8) -> Result<Self, TtError> {
+        Self::ALL
+            .into_iter()
+            .find(|priority| priority.number() == number)
+            .ok_or(TtError::InvalidPriority(number))
+    }
+
+    pub fn number(self) -> u8 {
+        self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Critical => "critical",
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A number from 1 to 4 or a name, ignoring case, as in config files,
+/// environment variables and spreadsheets.
+impl FromStr for Priority {
+    type Err = TtError;
+
+    fn from_str(input: &str) -> Result<Self, TtError> {
+        let input = input.trim();
+        if let Ok(number) = input.parse::<u8>() {
+            return Self::from_number(number);
+        }
+        <Self as ValueEnum>::from_str(input, true).map_err(|_| {
+            TtError::Invalid(format!(
+                "Priority must be 1-4 or critical, high, medium or low, got '{input}'"
+            ))
+        })
+    }
 }
 
-fn default_priority() -> u8 {
-    4 // Default to lowest priority for backward compatibility
+impl Serialize for Priority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.number())
+    }
+}
+
+/// Reads the saved number. One out of range, from an old or hand-edited
+/// file, is read as low with a warning rather than making the file
+/// unreadable; the todo manager remembers the number for `tt normalize`.
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PriorityNumber;
+
+        impl Visitor<'_> for PriorityNumber {
+            type Value = Priority;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a priority number from 1 to 4")
+            }
+
+            fn visit_i64<E: de::Error>(self, number: i64) -> Result<Priority, E> {
+                let priority = u8::try_from(number)
+                    .ok()
+                    .and_then(|number| Priority::from_number(number).ok());
+                Ok(priority.unwrap_or_else(|| {
+                    esay!(
+                        "⚠️  Priority {number} is out of range (1-4); treating it as low (`tt normalize` fixes it)"
+                    );
+                    Priority::Low
+                }))
+            }
+
+            fn visit_u64<E: de::Error>(self, number: u64) -> Result<Priority, E> {
+                self.visit_i64(i64::try_from(number).unwrap_or(i64::MAX))
+            }
+        }
+
+        deserializer.deserialize_i64(PriorityNumber)
+    }
 }
 
 impl Default for Todo {
     fn default() -> Self {
         let now: DateTime<Utc> = Utc::now();
         Self {
+            id: 0,
             title: String::new(),
             completed: false,
             created_at: now.to_rfc3339(),
-            priority: default_priority(),
+            priority: Priority::default(),
+            due_date: None,
+            tags: Vec::new(),
+            project: None,
+            snooze_count: 0,
+            snoozed_until: None,
+            notes: None,
+            updated_at: None,
+            completed_at: None,
+            checklist: Vec::new(),
+            draft: false,
+            draft_expires_at: None,
+            pinned: false,
+            saved_priority: None,
         }
     }
 }
 
 impl Todo {
-    pub fn new(title: String, priority: u8) -> Result<Self, String> {
-        Self::validate_priority(priority)?;
+    pub fn new(id: usize, title: String, priority: Priority) -> Self {
         let now: DateTime<Utc> = Utc::now();
-        Ok(Self {
+        Self {
+            id,
             title,
             completed: false,
             created_at: now.to_rfc3339(),
             priority,
-        })
+            due_date: None,
+            tags: Vec::new(),
+            project: None,
+            snooze_count: 0,
+            snoozed_until: None,
+            notes: None,
+            updated_at: None,
+            completed_at: None,
+            checklist: Vec::new(),
+            draft: false,
+            draft_expires_at: None,
+            pinned: false,
+            saved_priority: None,
+        }
+    }
+
+    /// Record that the todo was changed at `now`. The setters below do
+    /// this themselves; it is for code that fixes fields directly.
+    pub fn touch(&mut self, now: DateTime<Utc>) {
+        self.updated_at = Some(now.to_rfc3339());
+    }
+
+    /// Record that the todo changed just now, returning the timestamp.
+    fn stamp(&mut self) -> String {
+        let now = Utc::now().to_rfc3339();
+        self.updated_at = Some(now.clone());
+        now
+    }
+
+    /// When the todo last changed: its update time, or its creation time if
+    /// it never changed. `None` if the timestamp cannot be read.
+    pub fn last_modified(&self) -> Option<DateTime<Utc>> {
+        let timestamp = self.updated_at.as_deref().unwrap_or(&self.created_at);
+        DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// When the todo was completed, falling back to its creation time for
+    /// todos completed before that was recorded. `None` while incomplete or
+    /// if neither timestamp can be read.
+    pub fn completion_time(&self) -> Option<DateTime<Utc>> {
+        if !self.completed {
+            return None;
+        }
+        let timestamp = self.completed_at.as_deref().unwrap_or(&self.created_at);
+        DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// When a snooze ends, if the todo has one that can be read.
+    pub fn snooze_end(&self) -> Option<DateTime<Utc>> {
+        let until = self.snoozed_until.as_deref()?;
+        DateTime::parse_from_rfc3339(until)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// Whether the todo is snoozed at `now`. Completed todos never are.
+    pub fn is_snoozed(&self, now: DateTime<Utc>) -> bool {
+        !self.completed && self.snooze_end().is_some_and(|until| until > now)
+    }
+
+    /// Hide the todo from listings until `until`, counting the snooze.
+    pub fn snooze(&mut self, until: DateTime<Utc>) {
+        self.snoozed_until = Some(until.to_rfc3339());
+        self.snooze_count += 1;
+        self.stamp();
+    }
+
+    /// End a snooze early. Returns whether there was one to end.
+    pub fn unsnooze(&mut self) -> bool {
+        if self.snoozed_until.take().is_none() {
+            return false;
+        }
+        self.stamp();
+        true
+    }
+
+    /// Pin or unpin the todo. Returns whether that changed anything.
+    pub fn set_pinned(&mut self, pinned: bool) -> bool {
+        if self.pinned == pinned {
+            return false;
+        }
+        self.pinned = pinned;
+        self.stamp();
+        true
+    }
+
+    /// Whether the todo is listed in the pinned section: pinned and not yet
+    /// completed.
+    pub fn is_pinned(&self) -> bool {
+        self.pinned && !self.completed
+    }
+
+    /// Whether the todo is a draft whose expiry has passed at `now`. Drafts
+    /// without a readable expiry never expire.
+    pub fn is_expired_draft(&self, now: DateTime<Utc>) -> bool {
+        self.draft
+            && self
+                .draft_expires_at
+                .as_deref()
+                .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                .is_some_and(|at| at <= now)
+    }
+
+    /// Whether `tt clear-completed` removes the todo: it is completed, and
+    /// with `older_than` was completed at least that long before `now`.
+    pub fn is_clearable(&self, older_than: Option<Duration>, now: DateTime<Utc>) -> bool {
+        match older_than {
+            _ if !self.completed => false,
+            None => true,
+            Some(age) => self
+                .completion_time()
+                .is_some_and(|completed| now - completed >= age),
+        }
+    }
+
+    /// Whether `other` is the same todo: same ID and creation time. Copies
+    /// of a store that each added a todo may have given both the same ID.
+    pub fn same_todo(&self, other: &Todo) -> bool {
+        self.id == other.id && self.created_at == other.created_at
     }
 
     pub fn toggle_completed(&mut self) {
-        self.completed = !self.completed;
+        self.set_completed(!self.completed);
     }
 
+    /// Complete or reopen the todo, stamping when it was completed.
     pub fn set_completed(&mut self, value: bool) {
+        if value == self.completed {
+            return;
+        }
+        let now = self.stamp();
+        self.completed_at = value.then_some(now);
         self.completed = value;
     }
 
-    pub fn set_priority(&mut self, priority: u8) -> Result<(), String> {
-        Self::validate_priority(priority)?;
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+        self.stamp();
+    }
+
+    pub fn set_due_date(&mut self, due_date: Option<NaiveDate>) {
+        self.due_date = due_date;
+        self.stamp();
+    }
+
+    /// Append a checklist item, returning its number.
+    pub fn add_checklist_item(&mut self, text: &str) -> Result<usize, TtError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(TtError::Invalid(
+                "Checklist item cannot be empty".to_string(),
+            ));
+        }
+        self.checklist.push(ChecklistItem {
+            text: text.to_string(),
+            done: false,
+        });
+        self.stamp();
+        Ok(self.checklist.len())
+    }
+
+    /// Tick or untick checklist item `number`, returning it.
+    pub fn toggle_checklist_item(&mut self, number: usize) -> Result<&ChecklistItem, TtError> {
+        let index = self.checklist_index(number)?;
+        self.stamp();
+        let item = &mut self.checklist[index];
+        item.done = !item.done;
+        Ok(item)
+    }
+
+    /// Remove checklist item `number`; the items after it move up.
+    pub fn remove_checklist_item(&mut self, number: usize) -> Result<ChecklistItem, TtError> {
+        let index = self.checklist_index(number)?;
+        self.stamp();
+        Ok(self.checklist.remove(index))
+    }
+
+    /// Done and total checklist items, or `None` without a checklist.
+    pub fn checklist_progress(&self) -> Option<(usize, usize)> {
+        if self.checklist.is_empty() {
+            return None;
+        }
+        let done = self.checklist.iter().filter(|item| item.done).count();
+        Some((done, self.checklist.len()))
+    }
+
+    fn checklist_index(&self, number: usize) -> Result<usize, TtError> {
+        match self.checklist.len() {
+            0 => Err(TtError::Missing(format!(
+                "Todo {} has no checklist",
+                self.id
+            ))),
+            len if (1..=len).contains(&number) => Ok(number - 1),
+            len => Err(TtError::Missing(format!(
+                "Checklist item {number} does not exist; todo {} has items 1 to {len}",
+                self.id
+            ))),
+        }
+    }
+
+    /// Set or clear the notes; blank notes count as none.
+    pub fn set_notes(&mut self, notes: Option<String>) {
+        self.notes = notes.filter(|notes| !notes.trim().is_empty());
+        self.stamp();
+    }
+
+    pub fn set_priority(&mut self, priority: Priority) {
         self.priority = priority;
-        Ok(())
+        self.stamp();
+    }
+
+    /// Put the todo in a project, or with `None` or a blank name take it
+    /// out of its project.
+    pub fn set_project(&mut self, project: Option<String>) {
+        self.project = project.as_deref().and_then(Self::normalize_project);
+        self.stamp();
+    }
+
+    /// A project name as stored: trimmed, or `None` if blank.
+    pub fn normalize_project(project: &str) -> Option<String> {
+        let project = project.trim();
+        (!project.is_empty()).then(|| project.to_string())
+    }
+
+    /// Normalize a tag for storage: trimmed, lowercase and without a leading
+    /// `#`, with blank segments of a nested tag dropped (` work//a/ ` becomes
+    /// `work/a`). Returns `None` for tags that are empty once normalized.
+    pub fn normalize_tag(tag: &str) -> Option<String> {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        let segments: Vec<&str> = tag
+            .split(tags::SEPARATOR)
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        (!segments.is_empty()).then(|| segments.join("/"))
+    }
+
+    pub fn add_tag(&mut self, tag: &str) {
+        if let Some(tag) = Self::normalize_tag(tag) {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+                self.stamp();
+            }
+        }
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        if let Some(tag) = Self::normalize_tag(tag) {
+            if self.tags.contains(&tag) {
+                self.tags.retain(|t| *t != tag);
+                self.stamp();
+            }
+        }
     }
 
-    pub fn validate_priority(priority: u8) -> Result<(), String> {
-        if !(1..=4).contains(&priority) {
-            return Err(format!("Priority must be between 1 and 4, got {priority}",));
+    /// Move the tags within `from` (normalized) to `to`, keeping the tags
+    /// free of duplicates. Returns whether anything changed.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> bool {
+        let old = std::mem::take(&mut self.tags);
+        for tag in &old {
+            let tag = tags::rename(tag, from, to).unwrap_or_else(|| tag.clone());
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
         }
-        Ok(())
+        let changed = self.tags != old;
+        if changed {
+            self.stamp();
+        }
+        changed
+    }
+
+    /// Whether the todo carries `tag` or a tag nested beneath it.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        Self::normalize_tag(tag)
+            .is_some_and(|tag| self.tags.iter().any(|t| tags::is_within(t, &tag)))
+    }
+
+    /// Whether the todo is still open and its due date has passed.
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.completed && self.due_date.is_some_and(|due| due < today)
+    }
+
+    /// Relative description of the due date, e.g. "due in 3 days" or
+    /// "overdue by 2 days". `None` when there is no due date.
+    pub fn due_description(&self, today: NaiveDate) -> Option<String> {
+        let days = (self.due_date? - today).num_days();
+        let plural = |n: i64| if n == 1 { "" } else { "s" };
+        Some(match days {
+            0 => "due today".to_string(),
+            1 => "due tomorrow".to_string(),
+            d if d > 1 => format!("due in {d} days"),
+            d => format!("overdue by {} day{}", -d, plural(-d)),
+        })
     }
 }
 
@@ -72,35 +667,61 @@ mod tests {
     #[test]
     fn test_new_todo() {
         let title = "Test todo".to_string();
-        let todo = Todo::new(title.clone(), 4).unwrap();
+        let todo = Todo::new(1, title.clone(), Priority::Low);
 
+        assert_eq!(todo.id, 1);
         assert_eq!(todo.title, title);
         assert_eq!(todo.completed, false);
-        assert_eq!(todo.priority, 4);
+        assert_eq!(todo.priority, Priority::Low);
         assert!(!todo.created_at.is_empty());
     }
 
     #[test]
     fn test_new_todo_with_priority() {
         let title = "Test todo".to_string();
-        let todo = Todo::new(title.clone(), 1).unwrap();
+        let todo = Todo::new(1, title.clone(), Priority::Critical);
 
         assert_eq!(todo.title, title);
         assert_eq!(todo.completed, false);
-        assert_eq!(todo.priority, 1);
+        assert_eq!(todo.priority, Priority::Critical);
         assert!(!todo.created_at.is_empty());
     }
 
     #[test]
-    fn test_new_todo_with_invalid_priority() {
-        let title = "Test todo".to_string();
-        assert!(Todo::new(title.clone(), 0).is_err());
-        assert!(Todo::new(title, 5).is_err());
+    fn test_store_ref_saves_like_the_store() {
+        let mut todo = Todo::new(1, "Ship it".to_string(), Priority::High);
+        todo.pinned = true;
+        let deleted = Todo::new(2, "Gone".to_string(), Priority::Low);
+        let now = Utc::now();
+        let mut store = TodoStore {
+            todos: vec![todo],
+            next_id: 3,
+            meta: None,
+            deleted: vec![Deletion::of(&deleted, now)],
+            templates: Vec::new(),
+            trash: Vec::new(),
+        };
+        let borrowed = |store: &TodoStore| {
+            serde_json::to_string(&StoreRef {
+                todos: &store.todos,
+                next_id: store.next_id,
+                meta: store.meta.as_ref(),
+                deleted: &store.deleted,
+                templates: &store.templates,
+                trash: &store.trash,
+            })
+            .unwrap()
+        };
+        assert_eq!(borrowed(&store), serde_json::to_string(&store).unwrap());
+
+        store.meta = Some(StoreMeta::new(&store.todos, now));
+        store.deleted.clear();
+        assert_eq!(borrowed(&store), serde_json::to_string(&store).unwrap());
     }
 
     #[test]
     fn test_toggle_completed() {
-        let mut todo = Todo::new("Test".to_string(), 4).unwrap();
+        let mut todo = Todo::new(1, "Test".to_string(), Priority::Low);
 
         // Initially false
         assert_eq!(todo.completed, false);
@@ -116,7 +737,7 @@ mod tests {
 
     #[test]
     fn test_set_completed() {
-        let mut todo = Todo::new("Test".to_string(), 4).unwrap();
+        let mut todo = Todo::new(1, "Test".to_string(), Priority::Low);
 
         // Initially false
         assert_eq!(todo.completed, false);
@@ -124,51 +745,397 @@ mod tests {
         // Set to true
         todo.set_completed(true);
         assert_eq!(todo.completed, true);
+        let completed_at = todo.completed_at.clone();
+        assert!(completed_at.is_some());
+
+        // Completing again keeps the original time
+        todo.set_completed(true);
+        assert_eq!(todo.completed_at, completed_at);
 
         // Set to false
         todo.set_completed(false);
         assert_eq!(todo.completed, false);
+        assert_eq!(todo.completed_at, None);
+    }
+
+    #[test]
+    fn test_snoozing() {
+        let now = DateTime::parse_from_rfc3339("2025-06-01T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut todo = Todo::new(1, "Renew passport".to_string(), Priority::High);
+        assert!(!todo.is_snoozed(now));
+
+        todo.snooze(now + Duration::days(3));
+        assert_eq!(todo.snooze_count, 1);
+        assert!(todo.is_snoozed(now));
+        assert!(todo.is_snoozed(now + Duration::days(3) - Duration::seconds(1)));
+        assert!(!todo.is_snoozed(now + Duration::days(3)));
+
+        // Completed todos are not hidden, and a snooze can end early
+        todo.set_completed(true);
+        assert!(!todo.is_snoozed(now));
+        todo.set_completed(false);
+        assert!(todo.unsnooze());
+        assert!(!todo.is_snoozed(now));
+        assert!(!todo.unsnooze());
+
+        todo.snoozed_until = Some("soon".to_string());
+        assert!(!todo.is_snoozed(now));
+    }
+
+    #[test]
+    fn test_setters_stamp_updated_at() {
+        const EARLIER: &str = "2020-01-01T00:00:00+00:00";
+        let mut todo = Todo::new(1, "Test".to_string(), Priority::Low);
+        assert_eq!(todo.updated_at, None);
+
+        type Change = (&'static str, fn(&mut Todo));
+        let changes: [Change; 12] = [
+            ("title", |t| t.set_title("Renamed".to_string())),
+            ("priority", |t| t.set_priority(Priority::High)),
+            ("due date", |t| t.set_due_date(None)),
+            ("notes", |t| t.set_notes(Some("Details".to_string()))),
+            ("add tag", |t| t.add_tag("home")),
+            ("rename tag", |t| assert!(t.rename_tag("home", "house"))),
+            ("remove tag", |t| t.remove_tag("house")),
+            ("checklist", |t| {
+                t.add_checklist_item("Step").unwrap();
+            }),
+            ("snooze", |t| t.snooze(Utc::now())),
+            ("unsnooze", |t| assert!(t.unsnooze())),
+            ("complete", |t| t.set_completed(true)),
+            ("reopen", |t| t.set_completed(false)),
+        ];
+        for (name, change) in changes {
+            todo.updated_at = Some(EARLIER.to_string());
+            change(&mut todo);
+            assert_ne!(todo.updated_at.as_deref(), Some(EARLIER), "{name}");
+        }
+
+        // Completing stamps both; reopening clears the completion time
+        todo.set_completed(true);
+        assert_eq!(todo.completed_at, todo.updated_at);
+        todo.set_completed(false);
+        assert_eq!(todo.completed_at, None);
+        assert!(todo.updated_at.is_some());
+
+        // Changes that change nothing leave it alone
+        todo.add_tag("work");
+        todo.updated_at = Some(EARLIER.to_string());
+        todo.set_completed(false);
+        todo.add_tag("work");
+        todo.remove_tag("home");
+        assert!(!todo.rename_tag("home", "house"));
+        assert_eq!(todo.updated_at.as_deref(), Some(EARLIER));
+    }
+
+    #[test]
+    fn test_checklist_crud() {
+        let mut todo = Todo::new(3, "Post parcel".to_string(), Priority::Low);
+        assert_eq!(todo.checklist_progress(), None);
+        assert!(
+            todo.toggle_checklist_item(1)
+                .unwrap_err()
+                .to_string()
+                .contains("no checklist")
+        );
+
+        assert_eq!(todo.add_checklist_item("buy stamps").ok(), Some(1));
+        assert_eq!(todo.add_checklist_item(" find tape ").ok(), Some(2));
+        assert_eq!(todo.add_checklist_item("write label").ok(), Some(3));
+        assert!(todo.add_checklist_item("  ").is_err());
+        assert_eq!(todo.checklist[1].text, "find tape");
+        assert_eq!(todo.checklist_progress(), Some((0, 3)));
+
+        assert!(todo.toggle_checklist_item(2).unwrap().done);
+        assert!(todo.toggle_checklist_item(3).unwrap().done);
+        assert_eq!(todo.checklist_progress(), Some((2, 3)));
+        assert!(!todo.toggle_checklist_item(3).unwrap().done);
+        assert_eq!(todo.checklist_progress(), Some((1, 3)));
+
+        for out_of_bounds in [0, 4] {
+            let err = todo.remove_checklist_item(out_of_bounds).unwrap_err();
+            assert!(err.to_string().contains("items 1 to 3"), "{err}");
+        }
+        assert_eq!(todo.remove_checklist_item(1).unwrap().text, "buy stamps");
+        assert_eq!(todo.checklist[0].text, "find tape");
+        assert_eq!(todo.checklist_progress(), Some((1, 2)));
+    }
+
+    #[test]
+    fn test_checklist_serialization_is_compact() {
+        let mut todo = Todo::new(1, "Post parcel".to_string(), Priority::Low);
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(!json.contains("checklist"));
+
+        todo.add_checklist_item("buy stamps").unwrap();
+        todo.add_checklist_item("find tape").unwrap();
+        todo.toggle_checklist_item(2).unwrap();
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(
+            json.contains(
+                r#""checklist":[{"text":"buy stamps"},{"text":"find tape","done":true}]"#
+            )
+        );
+        let back: Todo = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.checklist, todo.checklist);
+    }
+
+    #[test]
+    fn test_is_clearable_by_completion_age() {
+        let now = DateTime::parse_from_rfc3339("2025-04-15T12:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut todo = Todo::new(1, "Done".to_string(), Priority::Low);
+        todo.created_at = "2025-03-01T12:00:00+00:00".to_string();
+        assert!(!todo.is_clearable(None, now));
+
+        todo.completed = true;
+        // Completed before completion times were kept: created_at counts
+        assert!(todo.is_clearable(None, now));
+        assert!(todo.is_clearable(Some(Duration::days(30)), now));
+        assert!(!todo.is_clearable(Some(Duration::days(60)), now));
+
+        todo.completed_at = Some("2025-04-10T12:00:00+00:00".to_string());
+        assert!(todo.is_clearable(Some(Duration::days(5)), now));
+        assert!(!todo.is_clearable(Some(Duration::days(6)), now));
+
+        // Without a readable time it is kept when an age is asked for
+        todo.completed_at = Some("soon".to_string());
+        assert!(!todo.is_clearable(Some(Duration::days(1)), now));
+        assert!(todo.is_clearable(None, now));
     }
 
     #[test]
     fn test_set_priority() {
-        let mut todo = Todo::new("Test".to_string(), 4).unwrap();
-        assert_eq!(todo.priority, 4);
+        let mut todo = Todo::new(1, "Test".to_string(), Priority::Low);
+        todo.set_priority(Priority::Medium);
+        assert_eq!(todo.priority, Priority::Medium);
+        assert!(todo.updated_at.is_some());
+    }
+
+    #[test]
+    fn test_parse_priority() {
+        for (input, priority) in [
+            ("1", Priority::Critical),
+            ("high", Priority::High),
+            ("MEDIUM", Priority::Medium),
+            (" 4 ", Priority::Low),
+        ] {
+            assert_eq!(input.parse::<Priority>().ok(), Some(priority), "{input}");
+        }
+        assert_eq!(
+            "5".parse::<Priority>().unwrap_err().to_string(),
+            "Priority must be between 1 and 4, got 5"
+        );
+        assert!("urgent".parse::<Priority>().is_err());
+        assert!("0".parse::<Priority>().is_err());
+        assert_eq!(Priority::High.to_string(), "high");
+        assert_eq!(Priority::High.number(), 2);
+        assert!(Priority::Critical < Priority::Low);
+    }
+
+    #[test]
+    fn test_priority_is_stored_as_a_number() {
+        let todo = Todo::new(1, "Test".to_string(), Priority::High);
+        let json = serde_json::to_value(&todo).unwrap();
+        assert_eq!(json["priority"], 2);
+
+        // Priorities saved out of range read as low
+        for stored in [0, 5, 255, -1] {
+            let mut json = json.clone();
+            json["priority"] = stored.into();
+            let todo: Todo = serde_json::from_value(json).unwrap();
+            assert_eq!(todo.priority, Priority::Low, "{stored}");
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_is_overdue() {
+        let today = date("2025-04-15");
+        let mut todo = Todo::new(1, "Taxes".to_string(), Priority::Critical);
+        assert!(!todo.is_overdue(today));
+
+        todo.due_date = Some(date("2025-04-15"));
+        assert!(!todo.is_overdue(today));
+
+        todo.due_date = Some(date("2025-04-14"));
+        assert!(todo.is_overdue(today));
+
+        // Completed todos are never overdue
+        todo.set_completed(true);
+        assert!(!todo.is_overdue(today));
+    }
+
+    #[test]
+    fn test_due_description() {
+        let today = date("2025-04-15");
+        let mut todo = Todo::new(1, "Taxes".to_string(), Priority::Critical);
+        assert_eq!(todo.due_description(today), None);
+
+        let cases = [
+            ("2025-04-15", "due today"),
+            ("2025-04-16", "due tomorrow"),
+            ("2025-04-18", "due in 3 days"),
+            ("2025-04-14", "overdue by 1 day"),
+            ("2025-04-13", "overdue by 2 days"),
+        ];
+        for (due, expected) in cases {
+            todo.due_date = Some(date(due));
+            assert_eq!(todo.due_description(today).unwrap(), expected);
+        }
+    }
 
-        // Set valid priorities
-        assert!(todo.set_priority(1).is_ok());
-        assert_eq!(todo.priority, 1);
+    #[test]
+    fn test_due_date_serialization() {
+        let mut todo = Todo::new(1, "Taxes".to_string(), Priority::Critical);
+        todo.due_date = Some(date("2025-04-15"));
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(json.contains(r#""due_date":"2025-04-15""#));
+
+        // Older files without a due date still parse
+        let legacy: Todo = serde_json::from_str(
+            r#"{"title": "Old", "completed": false, "created_at": "2025-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.due_date, None);
+    }
+
+    #[test]
+    fn test_snooze_count_serialization() {
+        let mut todo = Todo::new(1, "Call dentist".to_string(), Priority::High);
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(!json.contains("snooze_count"));
+
+        todo.snooze_count = 3;
+        let json = serde_json::to_string(&todo).unwrap();
+        assert!(json.contains(r#""snooze_count":3"#));
+        let parsed: Todo = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.snooze_count, 3);
+
+        let legacy: Todo = serde_json::from_str(
+            r#"{"title": "Old", "completed": false, "created_at": "2025-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.snooze_count, 0);
+    }
+
+    #[test]
+    fn test_set_notes() {
+        let mut todo = Todo::new(1, "Plan trip".to_string(), Priority::High);
+        todo.set_notes(Some("Line 1\n  Line 2".to_string()));
+        assert_eq!(todo.notes.as_deref(), Some("Line 1\n  Line 2"));
+
+        todo.set_notes(Some(" \n ".to_string()));
+        assert_eq!(todo.notes, None);
+
+        let legacy: Todo = serde_json::from_str(
+            r#"{"title": "Old", "completed": false, "created_at": "2025-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.notes, None);
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(Todo::normalize_tag("Work"), Some("work".to_string()));
+        assert_eq!(Todo::normalize_tag("  #Code "), Some("code".to_string()));
+        assert_eq!(Todo::normalize_tag(""), None);
+        assert_eq!(Todo::normalize_tag(" # "), None);
+        assert_eq!(
+            Todo::normalize_tag("#Work/ClientA//Billing/"),
+            Some("work/clienta/billing".to_string())
+        );
+        assert_eq!(Todo::normalize_tag(" / "), None);
+    }
+
+    #[test]
+    fn test_add_and_remove_tags() {
+        let mut todo = Todo::new(1, "Review PR".to_string(), Priority::High);
+        todo.add_tag("Work");
+        todo.add_tag("code");
+        todo.add_tag("WORK");
+        todo.add_tag("");
+        assert_eq!(todo.tags, vec!["work", "code"]);
+        assert!(todo.has_tag("#Work"));
+
+        todo.remove_tag("Work");
+        assert_eq!(todo.tags, vec!["code"]);
+        assert!(!todo.has_tag("work"));
 
-        assert!(todo.set_priority(3).is_ok());
-        assert_eq!(todo.priority, 3);
+        // Removing a missing tag is a no-op
+        todo.remove_tag("home");
+        assert_eq!(todo.tags, vec!["code"]);
+    }
 
-        // Set invalid priorities
-        assert!(todo.set_priority(0).is_err());
-        assert!(todo.set_priority(5).is_err());
-        assert_eq!(todo.priority, 3); // Should remain unchanged
+    #[test]
+    fn test_has_tag_matches_nested_tags() {
+        let mut todo = Todo::new(1, "Send invoice".to_string(), Priority::High);
+        todo.add_tag("#work/clientA/billing");
+        assert!(todo.has_tag("work"));
+        assert!(todo.has_tag("Work/ClientA"));
+        assert!(todo.has_tag("work/clienta/billing"));
+        assert!(!todo.has_tag("work/cli"));
+        assert!(!todo.has_tag("work/clienta/billing/q3"));
+        assert!(!todo.has_tag("clienta"));
     }
 
     #[test]
-    fn test_validate_priority() {
-        // Valid priorities
-        assert!(Todo::validate_priority(1).is_ok());
-        assert!(Todo::validate_priority(2).is_ok());
-        assert!(Todo::validate_priority(3).is_ok());
-        assert!(Todo::validate_priority(4).is_ok());
+    fn test_rename_tag_merges_into_existing() {
+        let mut todo = Todo::new(1, "Send invoice".to_string(), Priority::High);
+        todo.add_tag("work/clienta/billing");
+        todo.add_tag("work/acme/billing");
+        todo.add_tag("work/clientab");
+        assert!(todo.rename_tag("work/clienta", "work/acme"));
+        assert_eq!(todo.tags, vec!["work/acme/billing", "work/clientab"]);
+        assert!(!todo.rename_tag("home", "house"));
+    }
 
-        // Invalid priorities
-        assert!(Todo::validate_priority(0).is_err());
-        assert!(Todo::validate_priority(5).is_err());
-        assert!(Todo::validate_priority(255).is_err());
+    #[test]
+    fn test_store_without_meta_round_trips_unchanged() {
+        let store: TodoStore = serde_json::from_str(r#"{"todos": [], "next_id": 1}"#).unwrap();
+        assert!(store.meta.is_none());
+        let json = serde_json::to_string(&store).unwrap();
+        assert_eq!(json, r#"{"todos":[],"next_id":1}"#);
+    }
+
+    #[test]
+    fn test_store_meta_dates_from_oldest_todo() {
+        let now = Utc::now();
+        let mut old = Todo::new(1, "Old".to_string(), Priority::Low);
+        old.created_at = "2024-03-01T09:00:00+00:00".to_string();
+        let meta = StoreMeta::new(&[old, Todo::new(2, "New".to_string(), Priority::Low)], now);
+        assert_eq!(meta.created_at, "2024-03-01T09:00:00+00:00");
+        assert_eq!(meta.modified_at, now.to_rfc3339());
+
+        let empty = StoreMeta::new(&[], now);
+        assert_eq!(empty.created_at, now.to_rfc3339());
+    }
+
+    #[test]
+    fn test_store_display_name() {
+        let mut meta = StoreMeta::new(&[], Utc::now());
+        assert_eq!(StoreMeta::display_name(None), "Your todos");
+        assert_eq!(StoreMeta::display_name(Some(&meta)), "Your todos");
+        meta.name = Some("Work backlog".to_string());
+        assert_eq!(StoreMeta::display_name(Some(&meta)), "Work backlog");
     }
 
     #[test]
     fn test_default_todo() {
         let todo = Todo::default();
 
+        assert_eq!(todo.id, 0);
         assert_eq!(todo.title, "");
         assert_eq!(todo.completed, false);
-        assert_eq!(todo.priority, 4);
+        assert_eq!(todo.priority, Priority::Low);
+        assert_eq!(todo.due_date, None);
+        assert!(todo.tags.is_empty());
         assert!(!todo.created_at.is_empty());
     }
 }
@@ -0,0 +1,345 @@
+use crate::journal::JournalEntry;
+use crate::models::todo::{Priority, Todo};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// What the rules may draw on besides the todo itself.
+#[derive(Debug, Default)]
+pub struct Settings {
+    /// Saved priority numbers to replace, in range or not, e.g. 4 → critical
+    pub priority_map: BTreeMap<u8, Priority>,
+    /// When each title was first recorded in the journal
+    pub first_seen: HashMap<String, String>,
+    /// Creation time for todos the journal knows nothing about
+    pub default_created_at: Option<String>,
+}
+
+impl Settings {
+    /// Build settings from `FROM=TO` priority mappings, a default creation
+    /// date (`YYYY-MM-DD` or RFC 3339) and the journal.
+    pub fn new(
+        priority_map: &[String],
+        default_created_at: Option<&str>,
+        journal: &[JournalEntry],
+    ) -> Result<Self> {
+        let priority_map = priority_map
+            .iter()
+            .map(|mapping| parse_mapping(mapping))
+            .collect::<Result<_>>()?;
+        let default_created_at = default_created_at.map(parse_timestamp).transpose()?;
+        Ok(Self {
+            priority_map,
+            first_seen: first_seen(journal),
+            default_created_at,
+        })
+    }
+}
+
+/// One repair rule: changes a todo if it needs it and says whether it did.
+pub struct Rule {
+    pub name: &'static str,
+    pub apply: fn(&mut Todo, &Settings) -> bool,
+}
+
+/// The rules in the order they run. Completion times come from creation
+/// times, so creation times are backfilled first.
+pub const RULES: &[Rule] = &[
+    Rule {
+        name: "priority",
+        apply: fix_priority,
+    },
+    Rule {
+        name: "created_at",
+        apply: backfill_created_at,
+    },
+    Rule {
+        name: "completed_at",
+        apply: backfill_completed_at,
+    },
+];
+
+/// How many todos one rule changed.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct RuleCount {
+    pub rule: &'static str,
+    pub changed: usize,
+}
+
+/// What `tt normalize` did, or would do with `--dry-run`.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub rules: Vec<RuleCount>,
+    /// Todos still without a readable creation time
+    pub missing_created_at: usize,
+    /// Copy of the todo file taken before anything was saved
+    pub backup: Option<PathBuf>,
+}
+
+impl Report {
+    pub fn changed_anything(&self) -> bool {
+        self.rules.iter().any(|count| count.changed > 0)
+    }
+}
+
+/// Run every rule over every todo, returning a count per rule. Todos any
+/// rule changed are marked as updated at `now`.
+pub fn normalize(todos: &mut [Todo], settings: &Settings, now: DateTime<Utc>) -> Vec<RuleCount> {
+    let mut touched = vec![false; todos.len()];
+    let counts = RULES
+        .iter()
+        .map(|rule| {
+            let mut changed = 0;
+            for (todo, touched) in todos.iter_mut().zip(touched.iter_mut()) {
+                if (rule.apply)(todo, settings) {
+                    *touched = true;
+                    changed += 1;
+                }
+            }
+            RuleCount {
+                rule: rule.name,
+                changed,
+            }
+        })
+        .collect();
+    for (todo, touched) in todos.iter_mut().zip(touched) {
+        if touched {
+            todo.touch(now);
+        }
+    }
+    counts
+}
+
+/// Map priorities listed in `priority_map` by the number the file had,
+/// then clamp the rest of those saved out of range into 1-4.
+pub fn fix_priority(todo: &mut Todo, settings: &Settings) -> bool {
+    let saved = todo.saved_priority.take();
+    let number = saved.unwrap_or(todo.priority.number());
+    let fixed = match settings.priority_map.get(&number) {
+        Some(&fixed) => fixed,
+        None => Priority::from_number(number.clamp(1, 4)).unwrap_or(todo.priority),
+    };
+    let changed = saved.is_some() || fixed != todo.priority;
+    todo.priority = fixed;
+    changed
+}
+
+/// Give a todo without a readable creation time the time its title first
+/// appears in the journal, or else the default.
+pub fn backfill_created_at(todo: &mut Todo, settings: &Settings) -> bool {
+    if DateTime::parse_from_rfc3339(&todo.created_at).is_ok() {
+        return false;
+    }
+    let Some(created_at) = settings
+        .first_seen
+        .get(&todo.title)
+        .or(settings.default_created_at.as_ref())
+    else {
+        return false;
+    };
+    todo.created_at = created_at.clone();
+    true
+}
+
+/// Completed todos from before completion times were kept count as
+/// completed when they were created.
+pub fn backfill_completed_at(todo: &mut Todo, _settings: &Settings) -> bool {
+    if !todo.completed
+        || todo.completed_at.is_some()
+        || DateTime::parse_from_rfc3339(&todo.created_at).is_err()
+    {
+        return false;
+    }
+    todo.completed_at = Some(todo.created_at.clone());
+    true
+}
+
+/// Todos still without a readable creation time after normalizing.
+pub fn missing_created_at(todos: &[Todo]) -> usize {
+    todos
+        .iter()
+        .filter(|todo| DateTime::parse_from_rfc3339(&todo.created_at).is_err())
+        .count()
+}
+
+fn first_seen(journal: &[JournalEntry]) -> HashMap<String, String> {
+    let mut first_seen = HashMap::new();
+    // Entries are oldest first, so the first time a title shows up wins
+    for entry in journal {
+        for title in &entry.titles {
+            first_seen
+                .entry(title.clone())
+                .or_insert_with(|| entry.timestamp.clone());
+        }
+    }
+    first_seen
+}
+
+/// `FROM=TO`, where FROM is any saved number, even one out of range, or a
+/// priority name, and TO is a priority.
+fn parse_mapping(mapping: &str) -> Result<(u8, Priority)> {
+    let (from, to) = mapping
+        .split_once('=')
+        .with_context(|| format!("Invalid priority mapping '{mapping}', expected FROM=TO"))?;
+    let from = match from.trim().parse::<u8>() {
+        Ok(number) => number,
+        Err(_) => from
+            .parse::<Priority>()
+            .with_context(|| format!("Invalid priority mapping '{mapping}'"))?
+            .number(),
+    };
+    let to = to
+        .parse::<Priority>()
+        .with_context(|| format!("Invalid priority mapping '{mapping}'"))?;
+    Ok((from, to))
+}
+
+fn parse_timestamp(input: &str) -> Result<String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(time.to_rfc3339());
+    }
+    let date = NaiveDate::parse_from_str(input, "%Y-%m-%d").with_context(|| {
+        format!("Invalid date '{input}', expected YYYY-MM-DD or an RFC 3339 timestamp")
+    })?;
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Ok(DateTime::<Utc>::from_naive_utc_and_offset(midnight, Utc).to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str, priority: Priority, created_at: &str) -> Todo {
+        Todo {
+            title: title.to_string(),
+            priority,
+            created_at: created_at.to_string(),
+            ..Todo::default()
+        }
+    }
+
+    fn journal_entry(timestamp: &str, titles: &[&str]) -> JournalEntry {
+        JournalEntry {
+            timestamp: timestamp.to_string(),
+            command: "tt add".to_string(),
+            cwd: String::new(),
+            hostname: String::new(),
+            titles: titles.iter().map(|title| title.to_string()).collect(),
+        }
+    }
+
+    const CREATED: &str = "2025-01-01T09:00:00+00:00";
+
+    #[test]
+    fn test_fix_priority_maps() {
+        let mappings = ["1=4".to_string(), "low=critical".to_string()];
+        let settings = Settings::new(&mappings, None, &[]).unwrap();
+        for (before, after) in [
+            (Priority::Critical, Priority::Low),
+            (Priority::High, Priority::High),
+            (Priority::Low, Priority::Critical),
+        ] {
+            let mut todo = todo("x", before, CREATED);
+            assert_eq!(fix_priority(&mut todo, &settings), before != after);
+            assert_eq!(todo.priority, after);
+        }
+    }
+
+    #[test]
+    fn test_fix_priority_clamps_saved_out_of_range() {
+        let settings = Settings::new(&["9=medium".to_string()], None, &[]).unwrap();
+        for (saved, after) in [
+            (0, Priority::Critical),
+            (7, Priority::Low),
+            (9, Priority::Medium),
+        ] {
+            let mut todo = todo("x", Priority::Low, CREATED);
+            todo.saved_priority = Some(saved);
+            // Changed even when the clamped priority is the low it was read as
+            assert!(fix_priority(&mut todo, &settings), "{saved}");
+            assert_eq!(todo.priority, after);
+            assert_eq!(todo.saved_priority, None);
+        }
+    }
+
+    #[test]
+    fn test_priority_mappings_are_validated() {
+        assert!(Settings::new(&["2=high".to_string()], None, &[]).is_ok());
+        assert!(Settings::new(&["0=1".to_string()], None, &[]).is_ok());
+        for bad in ["1=5", "1", "a=1", "1=-1", "256=1", "0=0"] {
+            assert!(
+                Settings::new(&[bad.to_string()], None, &[]).is_err(),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_backfill_created_at_prefers_journal() {
+        let journal = [
+            journal_entry("2024-03-01T10:00:00+00:00", &["Old task"]),
+            journal_entry("2024-04-01T10:00:00+00:00", &["Old task"]),
+        ];
+        let settings = Settings::new(&[], Some("2024-01-01"), &journal).unwrap();
+
+        let mut known = todo("Old task", Priority::Low, "");
+        assert!(backfill_created_at(&mut known, &settings));
+        assert_eq!(known.created_at, "2024-03-01T10:00:00+00:00");
+
+        let mut unknown = todo("Imported", Priority::Low, "yesterday");
+        assert!(backfill_created_at(&mut unknown, &settings));
+        assert_eq!(unknown.created_at, "2024-01-01T00:00:00+00:00");
+
+        let mut fine = todo("Old task", Priority::Low, CREATED);
+        assert!(!backfill_created_at(&mut fine, &settings));
+        assert_eq!(fine.created_at, CREATED);
+
+        let mut stuck = todo("Imported", Priority::Low, "");
+        assert!(!backfill_created_at(&mut stuck, &Settings::default()));
+        assert_eq!(missing_created_at(&[stuck, fine]), 1);
+    }
+
+    #[test]
+    fn test_backfill_completed_at() {
+        let settings = Settings::default();
+        let mut legacy = todo("Done", Priority::Low, CREATED);
+        legacy.completed = true;
+        assert!(backfill_completed_at(&mut legacy, &settings));
+        assert_eq!(legacy.completed_at.as_deref(), Some(CREATED));
+        assert!(!backfill_completed_at(&mut legacy, &settings));
+
+        let mut open = todo("Open", Priority::Low, CREATED);
+        assert!(!backfill_completed_at(&mut open, &settings));
+        let mut undated = todo("Done", Priority::Low, "");
+        undated.completed = true;
+        assert!(!backfill_completed_at(&mut undated, &settings));
+    }
+
+    #[test]
+    fn test_normalize_runs_rules_in_order() {
+        let mut done = todo("Done", Priority::Low, "");
+        done.completed = true;
+        let mut todos = vec![
+            done,
+            todo("Fine", Priority::High, CREATED),
+            todo("Urgent", Priority::Low, CREATED),
+        ];
+        let settings = Settings::new(&["low=critical".to_string()], Some(CREATED), &[]).unwrap();
+
+        let now = Utc::now();
+        let counts = normalize(&mut todos, &settings, now);
+        let counts: Vec<(&str, usize)> = counts.iter().map(|c| (c.rule, c.changed)).collect();
+        assert_eq!(
+            counts,
+            vec![("priority", 2), ("created_at", 1), ("completed_at", 1)]
+        );
+        // completed_at saw the backfilled creation time
+        assert_eq!(todos[0].completed_at.as_deref(), Some(CREATED));
+        assert_eq!(todos[1].updated_at, None);
+        assert_eq!(todos[2].updated_at, Some(now.to_rfc3339()));
+        let again = normalize(&mut todos, &settings, Utc::now());
+        assert!(again.iter().all(|c| c.changed == 0));
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::{Context, Result, bail};
+use std::io::Read;
+
+/// Largest notes tt accepts unless `max_notes_bytes` is configured.
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024;
+
+/// Refuse notes longer than `limit` bytes.
+pub fn check_size(notes: &str, limit: usize) -> Result<()> {
+    if notes.len() > limit {
+        bail!(
+            "Notes are {} bytes, more than the {limit} allowed (raise max_notes_bytes in the config)",
+            notes.len()
+        );
+    }
+    Ok(())
+}
+
+/// Read notes from `reader` (stdin, for `--notes-from-stdin`) exactly as
+/// given, trailing newline included. Stops reading once the input is known
+/// to be over `limit`, so piping in a huge log fails fast.
+pub fn read(reader: impl Read, limit: usize) -> Result<String> {
+    let mut bytes = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut bytes)
+        .context("Failed to read notes from stdin")?;
+    if bytes.len() > limit {
+        bail!(
+            "Notes from stdin are more than the {limit} bytes allowed (raise max_notes_bytes in the config)"
+        );
+    }
+    String::from_utf8(bytes).context("Notes from stdin are not valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_preserves_bytes_exactly() {
+        let trace = "panicked at src/main.rs:10:5\n  0: foo\n\n  1: bar\r\n\ttabbed\n";
+        assert_eq!(read(trace.as_bytes(), DEFAULT_MAX_BYTES).unwrap(), trace);
+
+        let without_newline = "no trailing newline";
+        assert_eq!(
+            read(without_newline.as_bytes(), DEFAULT_MAX_BYTES).unwrap(),
+            without_newline
+        );
+        assert_eq!(read("\n\n".as_bytes(), DEFAULT_MAX_BYTES).unwrap(), "\n\n");
+    }
+
+    #[test]
+    fn test_size_cap() {
+        assert_eq!(read("12345".as_bytes(), 5).unwrap(), "12345");
+        assert!(read("123456".as_bytes(), 5).is_err());
+        assert!(check_size("12345", 5).is_ok());
+        assert!(check_size("123456", 5).is_err());
+        assert!(read(&[0xff, 0xfe][..], 5).is_err());
+    }
+}
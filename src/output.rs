@@ -0,0 +1,745 @@
+use crate::groups::Group;
+use crate::humanize;
+use crate::ids::{self, IdStyle};
+use crate::lists::ListSummary;
+use crate::models::todo::{ChecklistItem, StoreMeta, Template, Todo, TrashedTodo};
+use crate::render::{Pretty, Style, TodoView};
+use crate::stats::Counts;
+use crate::text;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use clap::ValueEnum;
+use colored::*;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether output may use emoji, turned off with `no_emoji`.
+static EMOJI: AtomicBool = AtomicBool::new(true);
+
+/// `println!`, without the emoji that start lines when they are turned off.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        println!("{}", $crate::output::plain(&format!($($arg)*)))
+    };
+}
+
+/// `eprintln!`, without the emoji that start lines when they are turned off.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! esay {
+    ($($arg:tt)*) => {
+        eprintln!("{}", $crate::output::plain(&format!($($arg)*)))
+    };
+}
+
+pub use crate::{esay, say};
+
+/// Turn emoji on or off for everything printed from now on.
+pub fn set_emoji(enabled: bool) {
+    EMOJI.store(enabled, Ordering::Relaxed);
+}
+
+pub(crate) fn emoji() -> bool {
+    EMOJI.load(Ordering::Relaxed)
+}
+
+/// `text` with the emoji that start its lines (after any indentation)
+/// removed if emoji are turned off, and unchanged otherwise.
+pub fn plain(text: &str) -> Cow<'_, str> {
+    if emoji() {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(
+        text.split('\n')
+            .map(strip_icon)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+fn strip_icon(line: &str) -> Cow<'_, str> {
+    let rest = line.trim_start();
+    let icon = |c: char| !c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace();
+    let stripped = rest.trim_start_matches(icon);
+    if stripped.len() == rest.len() {
+        return Cow::Borrowed(line);
+    }
+    let indent = &line[..line.len() - rest.len()];
+    Cow::Owned(format!("{indent}{}", stripped.trim_start_matches(' ')))
+}
+
+/// When to color output, as chosen with `--color`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and NO_COLOR is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color, given the NO_COLOR environment variable and
+    /// whether stdout is a terminal. Following https://no-color.org, an
+    /// empty NO_COLOR does not count.
+    pub fn enabled(self, no_color: Option<&OsStr>, stdout_is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                no_color.is_none_or(|value| value.is_empty()) && stdout_is_terminal
+            }
+        }
+    }
+
+    /// Turn color on or off for everything printed from now on.
+    pub fn apply(self) {
+        let no_color = std::env::var_os("NO_COLOR");
+        let enabled = self.enabled(no_color.as_deref(), std::io::stdout().is_terminal());
+        colored::control::set_override(enabled);
+    }
+}
+
+/// Renders command results either for people (emoji, color, headings) or,
+/// with `--json`, as plain JSON on stdout for scripts.
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub json: bool,
+    pub id_style: IdStyle,
+    /// Ages at which open todos gain another dot; `None` shows no dots
+    pub age_dots: Option<Vec<Duration>>,
+    /// Follow each todo line with when it was done, changed or added
+    pub show_dates: bool,
+    /// Add when each todo was created to listings, with `--dates`
+    pub dates: Option<DateFormat>,
+    /// How todos are laid out in lists and detail views
+    pub style: Style,
+}
+
+/// How `tt list --dates` shows when todos were created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DateFormat {
+    /// How long ago, e.g. "3h ago" or "2 weeks ago"
+    Relative,
+    /// The stored RFC 3339 timestamp
+    Full,
+}
+
+/// The JSON shape of a todo. Field names are part of the scripting
+/// interface, so they only ever gain new fields.
+#[derive(Debug, Serialize)]
+pub struct TodoJson<'a> {
+    /// Stable numeric ID
+    pub id: usize,
+    /// The ID as shown and typed, which differs with `id_style = "hash"`
+    pub display_id: String,
+    pub title: &'a str,
+    pub priority: u8,
+    pub completed: bool,
+    pub created_at: &'a str,
+    pub due_date: Option<NaiveDate>,
+    pub tags: &'a [String],
+    pub notes: Option<&'a str>,
+    pub checklist: &'a [ChecklistItem],
+    pub updated_at: Option<&'a str>,
+    pub completed_at: Option<&'a str>,
+    pub snoozed_until: Option<&'a str>,
+    pub pinned: bool,
+    pub project: Option<&'a str>,
+}
+
+/// The JSON shape of a todo in the trash.
+#[derive(Debug, Serialize)]
+pub struct TrashedJson<'a> {
+    pub deleted_at: &'a str,
+    pub todo: TodoJson<'a>,
+}
+
+/// The JSON shape of one heading of `tt list --group-by`.
+#[derive(Debug, Serialize)]
+pub struct GroupJson<'a> {
+    pub group: &'a str,
+    pub count: usize,
+    pub todos: Vec<TodoJson<'a>>,
+}
+
+impl<'a> TodoJson<'a> {
+    pub fn new(todo: &'a Todo, id_style: IdStyle) -> Self {
+        Self {
+            id: todo.id,
+            display_id: ids::display_id(todo, id_style),
+            title: &todo.title,
+            priority: todo.priority.number(),
+            completed: todo.completed,
+            created_at: &todo.created_at,
+            due_date: todo.due_date,
+            tags: &todo.tags,
+            notes: todo.notes.as_deref(),
+            checklist: &todo.checklist,
+            updated_at: todo.updated_at.as_deref(),
+            completed_at: todo.completed_at.as_deref(),
+            snoozed_until: todo.snoozed_until.as_deref(),
+            pinned: todo.pinned,
+            project: todo.project.as_deref(),
+        }
+    }
+}
+
+impl Output {
+    pub fn new(json: bool, id_style: IdStyle) -> Self {
+        Self {
+            json,
+            id_style,
+            age_dots: None,
+            show_dates: false,
+            dates: None,
+            style: Style::default(),
+        }
+    }
+
+    /// The active list, headed by the store's name and followed by how many
+    /// todos are open out of all of them. Pinned todos come first whatever
+    /// the sort order.
+    pub fn list(&self, todos: &[Todo], meta: Option<&StoreMeta>, counts: Counts) {
+        let todos = &pinned_first(todos);
+        if self.json {
+            self.print_json_list(todos);
+            return;
+        }
+        if todos.is_empty() {
+            say!("📝 No todos found. Add one with `tt add <title>`");
+        } else {
+            say!("📝 {}:", StoreMeta::display_name(meta));
+            if let Some(description) = meta.and_then(|meta| meta.description.as_deref()) {
+                say!("   {}", description.dimmed());
+            }
+            self.print_todo_lines(todos);
+            let footer = format!("{} open / {} total", counts.pending(), counts.total);
+            say!("{}", footer.dimmed());
+        }
+    }
+
+    /// The active list split under headings, each saying how many todos
+    /// are beneath it, with the same header and footer as `list`.
+    pub fn grouped(&self, groups: &[Group], meta: Option<&StoreMeta>, counts: Counts) {
+        if self.json {
+            let groups: Vec<GroupJson> = groups
+                .iter()
+                .map(|group| GroupJson {
+                    group: &group.label,
+                    count: group.todos.len(),
+                    todos: group
+                        .todos
+                        .iter()
+                        .map(|todo| TodoJson::new(todo, self.id_style))
+                        .collect(),
+                })
+                .collect();
+            self.print_json(&groups);
+            return;
+        }
+        if groups.is_empty() {
+            say!("📝 No todos found. Add one with `tt add <title>`");
+            return;
+        }
+        say!("📝 {}:", StoreMeta::display_name(meta));
+        if let Some(description) = meta.and_then(|meta| meta.description.as_deref()) {
+            say!("   {}", description.dimmed());
+        }
+        for group in groups {
+            say!(
+                "{}",
+                format!("{} ({})", group.label, group.todos.len()).bold()
+            );
+            self.print_todo_lines(&pinned_first(&group.todos));
+        }
+        let footer = format!("{} open / {} total", counts.pending(), counts.total);
+        say!("{}", footer.dimmed());
+    }
+
+    /// The focused todos, under a banner saying everything else is hidden.
+    pub fn focused(&self, todos: &[Todo]) {
+        if self.json {
+            self.print_json_list(todos);
+            return;
+        }
+        say!(
+            "🎯 FOCUS ({} items) {}",
+            todos.len(),
+            "- `tt focus stop` shows everything".dimmed()
+        );
+        self.print_todo_lines(todos);
+    }
+
+    pub fn archived(&self, todos: &[Todo]) {
+        if self.json {
+            self.print_json_list(todos);
+        } else if todos.is_empty() {
+            say!("🗄️  No archived todos");
+        } else {
+            say!("🗄️  Archived todos:");
+            self.print_todo_lines(todos);
+        }
+    }
+
+    /// Drafts reserved with `tt reserve` and not yet published.
+    pub fn drafts(&self, todos: &[Todo]) {
+        if self.json {
+            self.print_json_list(todos);
+        } else if todos.is_empty() {
+            say!("🔖 No drafts");
+        } else {
+            say!("🔖 Drafts (publish with `tt publish <id> --title ...`):");
+            self.print_todo_lines(todos);
+        }
+    }
+
+    pub fn search(&self, query: &str, todos: &[Todo]) {
+        if self.json {
+            self.print_json_list(todos);
+        } else if todos.is_empty() {
+            say!("🔍 No todos match '{query}'");
+        } else {
+            say!("🔍 {} matching todo(s):", todos.len());
+            self.print_todo_lines(todos);
+        }
+    }
+
+    /// The named lists, marking the one in use.
+    pub fn lists(&self, lists: &[ListSummary]) {
+        if self.json {
+            self.print_json(&lists);
+            return;
+        }
+        let width = lists
+            .iter()
+            .map(|list| text::display_width(&list.name))
+            .max()
+            .unwrap_or(0);
+        say!("📚 Lists:");
+        for list in lists {
+            let marker = if list.current { "*" } else { " " };
+            let open = match list.open {
+                Some(open) => format!("{open} open"),
+                None => "cannot be loaded".red().to_string(),
+            };
+            say!("  {marker} {}  {open}", text::pad(&list.name, width));
+        }
+    }
+
+    /// Todos in the trash, each with how long ago it was deleted.
+    pub fn trash(&self, trash: &[TrashedTodo]) {
+        if self.json {
+            let trash: Vec<TrashedJson> = trash
+                .iter()
+                .map(|trashed| TrashedJson {
+                    deleted_at: &trashed.deleted_at,
+                    todo: TodoJson::new(&trashed.todo, self.id_style),
+                })
+                .collect();
+            self.print_json(&trash);
+            return;
+        }
+        if trash.is_empty() {
+            say!("🗑️  The trash is empty");
+            return;
+        }
+        say!("🗑️  Trash (restore with `tt trash restore <id>`):");
+        let now = Utc::now();
+        let today = now.with_timezone(&Local).date_naive();
+        let views: Vec<TodoView> = trash
+            .iter()
+            .map(|trashed| TodoView {
+                dates: Some(format!(
+                    "deleted {}",
+                    humanize::since(&trashed.deleted_at, now)
+                )),
+                ..TodoView::new(&trashed.todo, self, today, now)
+            })
+            .collect();
+        say!("{}", self.style.renderer().render_list(&views));
+    }
+
+    /// The saved templates with the titles of their todos.
+    pub fn templates(&self, templates: &[Template]) {
+        if self.json {
+            self.print_json(&templates);
+        } else if templates.is_empty() {
+            say!("📋 No templates yet. Save one with `tt template save <name> --from-ids 2,3`");
+        } else {
+            say!("📋 Templates:");
+            for template in templates {
+                say!("  {} ({} todos)", template.name, template.todos.len());
+                for todo in &template.todos {
+                    say!("    {}", todo.title.dimmed());
+                }
+            }
+        }
+    }
+
+    /// Every detail of one todo, including its notes.
+    pub fn detail(&self, todo: &Todo) {
+        if self.json {
+            self.print_json(&TodoJson::new(todo, self.id_style));
+        } else {
+            let now = Utc::now();
+            let view = TodoView::new(todo, self, now.with_timezone(&Local).date_naive(), now);
+            say!("{}", self.style.renderer().render_detail(&view));
+        }
+    }
+
+    /// A todo a command just created or changed, with a human message
+    /// saying what happened.
+    pub fn changed(&self, todo: &Todo, message: &str) {
+        if self.json {
+            self.print_json(&TodoJson::new(todo, self.id_style));
+        } else {
+            say!("{message}");
+        }
+    }
+
+    /// Todos a bulk command changed, with a human summary.
+    pub fn changed_many(&self, todos: &[Todo], summary: &str) {
+        if self.json {
+            self.print_json_list(todos);
+        } else {
+            say!("{summary}");
+        }
+    }
+
+    /// Report an error as JSON on stderr. Without `--json`, errors are left
+    /// to the default `Error: ...` report.
+    pub fn json_error(&self, error: &anyhow::Error) {
+        esay!("{}", error_json(error));
+    }
+
+    /// The todos laid out in the chosen style, with age dots if they are
+    /// turned on.
+    pub fn print_todo_lines(&self, todos: &[Todo]) {
+        if todos.is_empty() {
+            return;
+        }
+        let now = Utc::now();
+        let today = now.with_timezone(&Local).date_naive();
+        let views: Vec<TodoView> = todos
+            .iter()
+            .map(|todo| TodoView::new(todo, self, today, now))
+            .collect();
+        say!("{}", self.style.renderer().render_list(&views));
+    }
+
+    fn print_json_list(&self, todos: &[Todo]) {
+        let todos: Vec<TodoJson> = todos
+            .iter()
+            .map(|todo| TodoJson::new(todo, self.id_style))
+            .collect();
+        self.print_json(&todos);
+    }
+
+    fn print_json(&self, value: &impl Serialize) {
+        // Serializing these plain structs cannot fail
+        say!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_default()
+        );
+    }
+}
+
+/// `{"error": "..."}`, with the context chain joined into the message.
+fn error_json(error: &anyhow::Error) -> String {
+    serde_json::json!({ "error": format!("{error:#}") }).to_string()
+}
+
+/// `todos` with the open pinned ones moved to the front, each group keeping
+/// its order.
+pub fn pinned_first(todos: &[Todo]) -> Vec<Todo> {
+    let (mut pinned, rest): (Vec<Todo>, Vec<Todo>) =
+        todos.iter().cloned().partition(Todo::is_pinned);
+    pinned.extend(rest);
+    pinned
+}
+
+/// One line of a todo listing, colored unless color is turned off.
+pub fn format_todo_line(todo: &Todo, id_style: IdStyle, today: NaiveDate) -> String {
+    let output = Output::new(false, id_style);
+    Pretty::line(&TodoView::new(todo, &output, today, Utc::now()))
+}
+
+/// A warning for a todo that was completed with checklist items open.
+pub fn checklist_warning(todo: &Todo) -> Option<String> {
+    let (done, total) = todo.checklist_progress()?;
+    (todo.completed && done < total).then(|| {
+        format!(
+            "⚠️  {} still has {} of {total} checklist items open",
+            todo.title,
+            total - done
+        )
+    })
+}
+
+/// When a todo was done, or else last changed or added, relative to `now`,
+/// e.g. "done 2d ago". `None` if the timestamp cannot be read.
+pub fn date_summary(todo: &Todo, now: DateTime<Utc>) -> Option<String> {
+    let (what, time) = if todo.completed {
+        ("done", todo.completion_time()?)
+    } else if todo.updated_at.is_some() {
+        ("updated", todo.last_modified()?)
+    } else {
+        ("added", todo.last_modified()?)
+    };
+    Some(format!("{what} {}", humanize::ago(now - time)))
+}
+
+/// When a todo was created, for `--dates`: "3h ago", or the stored
+/// timestamp with `DateFormat::Full`; "unknown" if it cannot be read.
+pub fn created_date(todo: &Todo, format: DateFormat, now: DateTime<Utc>) -> String {
+    match format {
+        DateFormat::Relative => humanize::since(&todo.created_at, now),
+        DateFormat::Full if DateTime::parse_from_rfc3339(&todo.created_at).is_ok() => {
+            todo.created_at.clone()
+        }
+        DateFormat::Full => humanize::UNKNOWN.to_string(),
+    }
+}
+
+/// An RFC 3339 timestamp in local time and how long before `now` it was,
+/// e.g. "2025-04-01 09:00 (2 weeks ago)", or "unknown" if it cannot be
+/// read.
+pub fn local_time_ago(timestamp: &str, now: DateTime<Utc>) -> String {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(time) => format!(
+            "{} ({})",
+            time.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            humanize::ago(now - time.with_timezone(&Utc))
+        ),
+        Err(_) => humanize::UNKNOWN.to_string(),
+    }
+}
+
+/// An RFC 3339 timestamp in local time, or as stored if it cannot be read.
+pub fn local_time(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|time| {
+            time.with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+    use anyhow::Context;
+
+    #[test]
+    fn test_todo_json_field_names() {
+        let mut todo = Todo::new(3, "Buy milk".to_string(), Priority::High);
+        todo.created_at = "2025-01-01T09:00:00+00:00".to_string();
+        todo.add_tag("home");
+        todo.updated_at = Some("2025-01-02T09:00:00+00:00".to_string());
+        let json = serde_json::to_value(TodoJson::new(&todo, IdStyle::Sequential)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "id": 3,
+                "display_id": "3",
+                "title": "Buy milk",
+                "priority": 2,
+                "completed": false,
+                "created_at": "2025-01-01T09:00:00+00:00",
+                "due_date": null,
+                "tags": ["home"],
+                "notes": null,
+                "checklist": [],
+                "updated_at": "2025-01-02T09:00:00+00:00",
+                "completed_at": null,
+                "snoozed_until": null,
+                "pinned": false,
+                "project": null,
+            })
+        );
+
+        let hashed = TodoJson::new(&todo, IdStyle::Hash);
+        assert_eq!(hashed.id, 3);
+        assert_eq!(hashed.display_id.len(), 6);
+    }
+
+    #[test]
+    fn test_pinned_first_keeps_order_within_groups() {
+        let todos: Vec<Todo> = [
+            (1, false, false),
+            (2, true, false),
+            (3, false, false),
+            (4, true, true),
+            (5, true, false),
+        ]
+        .into_iter()
+        .map(|(id, pinned, completed)| {
+            let mut todo = Todo::new(id, format!("Todo {id}"), Priority::Low);
+            todo.pinned = pinned;
+            todo.completed = completed;
+            todo
+        })
+        .collect();
+        let ids: Vec<usize> = pinned_first(&todos).iter().map(|todo| todo.id).collect();
+        // A completed todo stays where it was, pinned or not
+        assert_eq!(ids, [2, 5, 1, 3, 4]);
+    }
+
+    #[test]
+    fn test_color_choice() {
+        let set = Some(OsStr::new("1"));
+        let empty = Some(OsStr::new(""));
+        assert!(ColorChoice::Auto.enabled(None, true));
+        assert!(ColorChoice::Auto.enabled(empty, true));
+        assert!(!ColorChoice::Auto.enabled(set, true));
+        assert!(!ColorChoice::Auto.enabled(None, false));
+        assert!(ColorChoice::Always.enabled(set, false));
+        assert!(!ColorChoice::Never.enabled(None, true));
+    }
+
+    #[test]
+    fn test_todo_line_without_color_has_no_escape_codes() {
+        colored::control::set_override(false);
+        let today = NaiveDate::from_ymd_opt(2025, 4, 15).unwrap();
+        let mut todo = Todo::new(7, "Pay rent".to_string(), Priority::Critical);
+        todo.due_date = NaiveDate::from_ymd_opt(2025, 4, 10);
+        todo.add_tag("home");
+
+        let line = format_todo_line(&todo, IdStyle::Sequential, today);
+        assert_eq!(line, "  7 [⏳] Pay rent #home (overdue by 5 days)");
+        assert!(!line.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_todo_line_summarises_checklist() {
+        colored::control::set_override(false);
+        let today = NaiveDate::from_ymd_opt(2025, 4, 15).unwrap();
+        let mut todo = Todo::new(4, "Post parcel".to_string(), Priority::Low);
+        for item in ["buy stamps", "find tape", "write label"] {
+            todo.add_checklist_item(item).unwrap();
+        }
+        todo.toggle_checklist_item(2).unwrap();
+        todo.add_tag("errands");
+
+        let line = format_todo_line(&todo, IdStyle::Sequential, today);
+        assert_eq!(line, "  4 [⏳] Post parcel [1/3] #errands");
+    }
+
+    #[test]
+    fn test_checklist_warning_on_completion() {
+        let mut todo = Todo::new(4, "Post parcel".to_string(), Priority::Low);
+        todo.set_completed(true);
+        assert_eq!(checklist_warning(&todo), None);
+
+        todo.add_checklist_item("buy stamps").unwrap();
+        todo.add_checklist_item("find tape").unwrap();
+        todo.toggle_checklist_item(1).unwrap();
+        assert_eq!(
+            checklist_warning(&todo).as_deref(),
+            Some("⚠️  Post parcel still has 1 of 2 checklist items open")
+        );
+
+        todo.set_completed(false);
+        assert_eq!(checklist_warning(&todo), None);
+        todo.set_completed(true);
+        todo.toggle_checklist_item(2).unwrap();
+        assert_eq!(checklist_warning(&todo), None);
+    }
+
+    #[test]
+    fn test_right_to_left_titles_keep_id_and_status_in_place() {
+        colored::control::set_override(false);
+        let today = NaiveDate::from_ymd_opt(2025, 4, 15).unwrap();
+        let titles = [
+            "לקנות חלב",
+            "شراء الحليب 2",
+            "👨\u{200D}👩\u{200D}👧 dinner",
+            "Cafe\u{0301} Z\u{0351}\u{0359}",
+        ];
+        for (index, title) in titles.iter().enumerate() {
+            let mut todo = Todo::new(index + 1, title.to_string(), Priority::Low);
+            todo.add_tag("home");
+            let line = format_todo_line(&todo, IdStyle::Sequential, today);
+            let prefix = format!("  {} [⏳] ", index + 1);
+            assert!(line.starts_with(&prefix), "{line}");
+            let rest = &line[prefix.len()..];
+            let title_part = rest.strip_suffix(" #home").unwrap();
+            assert_eq!(title_part, text::isolate(title));
+            assert_eq!(
+                text::display_width(&line),
+                text::display_width(&prefix) + text::display_width(title) + " #home".len()
+            );
+        }
+        let line = format_todo_line(
+            &Todo::new(1, "לקנות חלב".to_string(), Priority::Low),
+            IdStyle::Sequential,
+            today,
+        );
+        assert!(line.contains("\u{2068}לקנות חלב\u{2069}"));
+    }
+
+    #[test]
+    fn test_date_summary() {
+        let now = DateTime::parse_from_rfc3339("2025-04-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut todo = Todo::new(1, "Report".to_string(), Priority::Low);
+        todo.created_at = "2025-04-10T12:00:00+00:00".to_string();
+        assert_eq!(
+            date_summary(&todo, now).as_deref(),
+            Some("added 5 days ago")
+        );
+
+        todo.updated_at = Some("2025-04-15T09:30:00+00:00".to_string());
+        assert_eq!(date_summary(&todo, now).as_deref(), Some("updated 2h ago"));
+
+        todo.completed = true;
+        todo.completed_at = Some("2025-04-15T11:59:30+00:00".to_string());
+        assert_eq!(date_summary(&todo, now).as_deref(), Some("done just now"));
+
+        todo.completed_at = Some("whenever".to_string());
+        assert_eq!(date_summary(&todo, now), None);
+    }
+
+    #[test]
+    fn test_created_date() {
+        let now = DateTime::parse_from_rfc3339("2025-04-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut todo = Todo::new(1, "Report".to_string(), Priority::Low);
+        todo.created_at = "2025-03-01T12:00:00+00:00".to_string();
+        assert_eq!(
+            created_date(&todo, DateFormat::Relative, now),
+            "6 weeks ago"
+        );
+        assert_eq!(
+            created_date(&todo, DateFormat::Full, now),
+            "2025-03-01T12:00:00+00:00"
+        );
+        assert!(local_time_ago(&todo.created_at, now).ends_with(" (6 weeks ago)"));
+
+        // Hand-edited files can hold anything
+        todo.created_at = "last spring".to_string();
+        for format in [DateFormat::Relative, DateFormat::Full] {
+            assert_eq!(created_date(&todo, format, now), "unknown");
+        }
+        assert_eq!(local_time_ago(&todo.created_at, now), "unknown");
+    }
+
+    #[test]
+    fn test_error_json_includes_context() {
+        let error = Err::<(), _>(anyhow::anyhow!("Todo with id 9 not found"))
+            .context("Failed to complete")
+            .unwrap_err();
+        assert_eq!(
+            error_json(&error),
+            r#"{"error":"Failed to complete: Todo with id 9 not found"}"#
+        );
+    }
+}
@@ -0,0 +1,681 @@
+use crate::age;
+use crate::ids;
+use crate::models::todo::{ChecklistItem, Priority, Todo};
+use crate::output::{self, Output};
+use crate::text;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike, Utc};
+use clap::ValueEnum;
+use colored::*;
+use serde::Deserialize;
+
+/// Everything a renderer shows about one todo, worked out once from the
+/// todo, the display settings and the clock. Colors are left to the
+/// renderers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TodoView {
+    /// The ID as shown and typed
+    pub id: String,
+    /// The title, isolated if it contains right-to-left script
+    pub title: String,
+    pub priority: Priority,
+    pub completed: bool,
+    /// Pinned and still open, so listed first
+    pub pinned: bool,
+    pub overdue: bool,
+    pub due: Option<NaiveDate>,
+    /// e.g. "due in 3 days" or "overdue by 1 day"
+    pub due_description: Option<String>,
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+    pub checklist: Vec<ChecklistItem>,
+    /// Age dots, empty when turned off or not yet due any
+    pub age_marker: String,
+    /// e.g. "done 2 days ago", with `--show-dates`
+    pub dates: Option<String>,
+    /// When the todo was created, e.g. "3 weeks ago", with `--dates`
+    pub created_column: Option<String>,
+    /// When a snooze that hasn't ended yet ends, e.g. "Jun 1"
+    pub snoozed_until: Option<String>,
+    /// Creation, last change and completion times in local time, with
+    /// how long ago each was
+    pub created: String,
+    pub updated: Option<String>,
+    pub done: Option<String>,
+}
+
+impl TodoView {
+    pub fn new(todo: &Todo, output: &Output, today: NaiveDate, now: DateTime<Utc>) -> Self {
+        Self {
+            id: ids::display_id(todo, output.id_style),
+            title: text::isolate(&todo.title),
+            priority: todo.priority,
+            completed: todo.completed,
+            pinned: todo.is_pinned(),
+            overdue: todo.is_overdue(today),
+            due: todo.due_date,
+            due_description: todo.due_description(today),
+            project: todo.project.clone(),
+            tags: todo.tags.clone(),
+            notes: todo.notes.clone(),
+            checklist: todo.checklist.clone(),
+            age_marker: output
+                .age_dots
+                .as_ref()
+                .map(|thresholds| age::marker(todo, thresholds, now))
+                .unwrap_or_default(),
+            dates: output
+                .show_dates
+                .then(|| output::date_summary(todo, now))
+                .flatten(),
+            created_column: output
+                .dates
+                .map(|format| output::created_date(todo, format, now)),
+            snoozed_until: todo
+                .snooze_end()
+                .filter(|_| todo.is_snoozed(now))
+                .map(snooze_label),
+            created: output::local_time_ago(&todo.created_at, now),
+            updated: todo
+                .updated_at
+                .as_deref()
+                .map(|time| output::local_time_ago(time, now)),
+            done: todo
+                .completed_at
+                .as_deref()
+                .map(|time| output::local_time_ago(time, now)),
+        }
+    }
+
+    /// Done and total checklist items, or `None` without a checklist.
+    pub fn checklist_progress(&self) -> Option<(usize, usize)> {
+        if self.checklist.is_empty() {
+            return None;
+        }
+        let done = self.checklist.iter().filter(|item| item.done).count();
+        Some((done, self.checklist.len()))
+    }
+
+    fn hashtags(&self) -> Vec<String> {
+        self.tags.iter().map(|tag| format!("#{tag}")).collect()
+    }
+}
+
+/// A snooze end in local time: "Jun 1", with the year unless it is this
+/// year's and the time unless it is midnight.
+pub fn snooze_label(end: DateTime<Utc>) -> String {
+    let end = end.with_timezone(&Local);
+    let mut format = String::from("%b %-d");
+    if end.year() != Local::now().year() {
+        format.push_str(" %Y");
+    }
+    if end.num_seconds_from_midnight() != 0 {
+        format.push_str(" %H:%M");
+    }
+    end.format(&format).to_string()
+}
+
+/// Turns todo views into text. Every style shows every field of a view,
+/// in its list or its detail view, unless `tests::EXEMPT` says otherwise.
+pub trait Renderer {
+    /// One or more lines for a list of todos, without a trailing newline.
+    fn render_list(&self, todos: &[TodoView]) -> String;
+    /// Every detail of one todo, without a trailing newline.
+    fn render_detail(&self, todo: &TodoView) -> String;
+}
+
+/// How todos are laid out, chosen with `--style` or `style` in the config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    /// Colored lines with status emoji
+    #[default]
+    Pretty,
+    /// Short plain lines, easy to grep
+    Compact,
+    /// Aligned columns under a header
+    Table,
+}
+
+impl Style {
+    pub fn renderer(self) -> &'static dyn Renderer {
+        match self {
+            Style::Pretty => &Pretty,
+            Style::Compact => &Compact,
+            Style::Table => &Table,
+        }
+    }
+}
+
+pub struct Pretty;
+
+impl Pretty {
+    /// One line of a listing.
+    pub fn line(todo: &TodoView) -> String {
+        let status = match (todo.completed, output::emoji()) {
+            (true, true) => "✅",
+            (false, true) => "⏳",
+            (true, false) => "x",
+            (false, false) => " ",
+        };
+        let title = todo.title.as_str();
+        let colored_title = match todo.priority {
+            _ if todo.snoozed_until.is_some() => title.dimmed(),
+            _ if todo.overdue => title.red().bold(),
+            Priority::Critical => title.red().bold(),
+            Priority::High => title.yellow().bold(),
+            Priority::Medium => title.blue().bold(),
+            Priority::Low => title.normal(),
+        };
+        let due = match &todo.due_description {
+            Some(description) if todo.overdue => format!(" ({description})").red().to_string(),
+            Some(description) if !todo.completed => {
+                format!(" ({description})").dimmed().to_string()
+            }
+            _ => String::new(),
+        };
+        let project = todo
+            .project
+            .as_ref()
+            .map(|project| format!(" +{project}"))
+            .unwrap_or_default();
+        let tags: String = todo.tags.iter().map(|tag| format!(" #{tag}")).collect();
+        let notes = match (todo.notes.is_some(), output::emoji()) {
+            (false, _) => "",
+            (true, true) => " 📎",
+            (true, false) => " +notes",
+        };
+        let pin = match (todo.pinned, output::emoji()) {
+            (false, _) => "",
+            (true, true) => "📌 ",
+            (true, false) => "[pinned] ",
+        };
+        let checklist = todo
+            .checklist_progress()
+            .map(|(done, total)| format!(" [{done}/{total}]"))
+            .unwrap_or_default();
+        let mut line = format!(
+            "  {} [{status}] {pin}{colored_title}{}{notes}{}{}{due}",
+            todo.id,
+            checklist.dimmed(),
+            project.dimmed(),
+            tags.dimmed()
+        );
+        if let Some(until) = &todo.snoozed_until {
+            line = format!("{line} {}", format!("(snoozed until {until})").dimmed());
+        }
+        if !todo.age_marker.is_empty() {
+            line = format!("{line} {}", todo.age_marker.dimmed());
+        }
+        if let Some(created) = &todo.created_column {
+            line = format!("{line} {}", format!("(created {created})").dimmed());
+        }
+        if let Some(dates) = &todo.dates {
+            line = format!("{line} {}", format!("({dates})").dimmed());
+        }
+        line
+    }
+}
+
+impl Renderer for Pretty {
+    fn render_list(&self, todos: &[TodoView]) -> String {
+        todos
+            .iter()
+            .map(Pretty::line)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_detail(&self, todo: &TodoView) -> String {
+        let pin = if todo.pinned && output::emoji() {
+            "📌 "
+        } else {
+            ""
+        };
+        let mut lines = vec![
+            format!("{pin}{} {}", todo.id, todo.title.bold()),
+            format!("  Priority: {}", todo.priority),
+        ];
+        let status = if todo.completed {
+            "✅ completed"
+        } else {
+            "⏳ pending"
+        };
+        lines.push(format!("  Status:   {}", output::plain(status)));
+        if todo.pinned {
+            lines.push("  Pinned:   yes".to_string());
+        }
+        lines.push(format!("  Created:  {}", todo.created));
+        if let Some(updated) = &todo.updated {
+            lines.push(format!("  Updated:  {updated}"));
+        }
+        if let Some(done) = &todo.done {
+            lines.push(format!("  Done:     {done}"));
+        }
+        if let Some(until) = &todo.snoozed_until {
+            lines.push(format!("  Snoozed:  until {until}"));
+        }
+        if let Some(due) = todo.due {
+            match &todo.due_description {
+                Some(description) if !todo.completed => {
+                    lines.push(format!("  Due:      {due} ({description})"))
+                }
+                _ => lines.push(format!("  Due:      {due}")),
+            }
+        }
+        if let Some(project) = &todo.project {
+            lines.push(format!("  Project:  {project}"));
+        }
+        if !todo.tags.is_empty() {
+            lines.push(format!("  Tags:     {}", todo.hashtags().join(" ")));
+        }
+        if let Some((done, total)) = todo.checklist_progress() {
+            lines.push(format!("  Checklist: {done}/{total} done"));
+            for (number, item) in todo.checklist.iter().enumerate() {
+                let mark = if item.done { "x" } else { " " };
+                lines.push(format!(
+                    "    {}. [{mark}] {}",
+                    number + 1,
+                    text::isolate(&item.text)
+                ));
+            }
+        }
+        if let Some(notes) = &todo.notes {
+            lines.push("  Notes:".to_string());
+            lines.extend(notes.lines().map(|line| format!("    {line}")));
+        }
+        lines.join("\n")
+    }
+}
+
+pub struct Compact;
+
+impl Renderer for Compact {
+    fn render_list(&self, todos: &[TodoView]) -> String {
+        todos
+            .iter()
+            .map(|todo| {
+                let mut line = format!(
+                    "{} {} {}",
+                    todo.id,
+                    if todo.completed { "x" } else { "-" },
+                    todo.title
+                );
+                if todo.pinned {
+                    line.push_str(" (pinned)");
+                }
+                if let Some((done, total)) = todo.checklist_progress() {
+                    line.push_str(&format!(" [{done}/{total}]"));
+                }
+                if let Some(project) = &todo.project {
+                    line.push_str(&format!(" +{project}"));
+                }
+                for tag in todo.hashtags() {
+                    line.push_str(&format!(" {tag}"));
+                }
+                if let Some(due) = todo.due.filter(|_| !todo.completed) {
+                    line.push_str(&format!(" due:{due}"));
+                }
+                if let Some(until) = &todo.snoozed_until {
+                    line.push_str(&format!(" (snoozed until {until})"));
+                }
+                if let Some(created) = &todo.created_column {
+                    line.push_str(&format!(" (created {created})"));
+                }
+                if let Some(dates) = &todo.dates {
+                    line.push_str(&format!(" ({dates})"));
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_detail(&self, todo: &TodoView) -> String {
+        let mut fields = vec![
+            ("id", todo.id.clone()),
+            ("title", todo.title.clone()),
+            ("priority", todo.priority.to_string()),
+            ("completed", todo.completed.to_string()),
+            ("created", todo.created.clone()),
+        ];
+        if todo.pinned {
+            fields.push(("pinned", "yes".to_string()));
+        }
+        if let Some(updated) = &todo.updated {
+            fields.push(("updated", updated.clone()));
+        }
+        if let Some(done) = &todo.done {
+            fields.push(("done", done.clone()));
+        }
+        if let Some(due) = todo.due {
+            fields.push(("due", due.to_string()));
+        }
+        if let Some(until) = &todo.snoozed_until {
+            fields.push(("snoozed", format!("until {until}")));
+        }
+        if let Some(project) = &todo.project {
+            fields.push(("project", project.clone()));
+        }
+        if !todo.tags.is_empty() {
+            fields.push(("tags", todo.hashtags().join(" ")));
+        }
+        for item in &todo.checklist {
+            let mark = if item.done { "x" } else { "-" };
+            fields.push(("check", format!("{mark} {}", text::isolate(&item.text))));
+        }
+        if let Some(notes) = &todo.notes {
+            fields.push(("notes", notes.replace('\n', "\\n")));
+        }
+        fields
+            .into_iter()
+            .map(|(name, value)| format!("{name}: {value}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub struct Table;
+
+impl Table {
+    /// Rows padded into columns, the first row being the header.
+    fn columns(rows: &[Vec<String>]) -> String {
+        let count = rows.first().map_or(0, Vec::len);
+        let widths: Vec<usize> = (0..count)
+            .map(|column| {
+                rows.iter()
+                    .map(|row| text::display_width(&row[column]))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        rows.iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let cells: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| text::pad(cell, *width))
+                    .collect();
+                let line = cells.join("  ").trim_end().to_string();
+                if index == 0 {
+                    line.bold().to_string()
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Renderer for Table {
+    fn render_list(&self, todos: &[TodoView]) -> String {
+        let show_pinned = todos.iter().any(|todo| todo.pinned);
+        let show_project = todos.iter().any(|todo| todo.project.is_some());
+        let show_snoozed = todos.iter().any(|todo| todo.snoozed_until.is_some());
+        let show_dates = todos.iter().any(|todo| todo.dates.is_some());
+        let show_created = todos.iter().any(|todo| todo.created_column.is_some());
+        let mut header = ["ID", "DONE", "PRI", "TITLE", "DUE", "TAGS", "CHECK"].to_vec();
+        if show_pinned {
+            header.push("PINNED");
+        }
+        if show_project {
+            header.push("PROJECT");
+        }
+        if show_created {
+            header.push("CREATED");
+        }
+        if show_snoozed {
+            header.push("SNOOZED");
+        }
+        if show_dates {
+            header.push("DATES");
+        }
+        let mut rows = vec![header.into_iter().map(str::to_string).collect()];
+        for todo in todos {
+            let mut row = vec![
+                todo.id.clone(),
+                if todo.completed { "x" } else { "" }.to_string(),
+                todo.priority.to_string(),
+                todo.title.clone(),
+                todo.due.map(|due| due.to_string()).unwrap_or_default(),
+                todo.hashtags().join(" "),
+                todo.checklist_progress()
+                    .map(|(done, total)| format!("{done}/{total}"))
+                    .unwrap_or_default(),
+            ];
+            if show_pinned {
+                row.push(if todo.pinned { "yes" } else { "" }.to_string());
+            }
+            if show_project {
+                row.push(todo.project.clone().unwrap_or_default());
+            }
+            if show_created {
+                row.push(todo.created_column.clone().unwrap_or_default());
+            }
+            if show_snoozed {
+                row.push(todo.snoozed_until.clone().unwrap_or_default());
+            }
+            if show_dates {
+                row.push(todo.dates.clone().unwrap_or_default());
+            }
+            rows.push(row);
+        }
+        Table::columns(&rows)
+    }
+
+    fn render_detail(&self, todo: &TodoView) -> String {
+        let row = |name: &str, value: String| vec![name.to_string(), value];
+        let mut rows = vec![
+            row("FIELD", "VALUE".to_string()),
+            row("ID", todo.id.clone()),
+            row("Title", todo.title.clone()),
+            row("Priority", todo.priority.to_string()),
+            row(
+                "Status",
+                if todo.completed {
+                    "completed"
+                } else {
+                    "pending"
+                }
+                .to_string(),
+            ),
+            row("Created", todo.created.clone()),
+        ];
+        if todo.pinned {
+            rows.push(row("Pinned", "yes".to_string()));
+        }
+        if let Some(updated) = &todo.updated {
+            rows.push(row("Updated", updated.clone()));
+        }
+        if let Some(done) = &todo.done {
+            rows.push(row("Done", done.clone()));
+        }
+        if let Some(due) = todo.due {
+            rows.push(row("Due", due.to_string()));
+        }
+        if let Some(until) = &todo.snoozed_until {
+            rows.push(row("Snoozed", format!("until {until}")));
+        }
+        if let Some(project) = &todo.project {
+            rows.push(row("Project", project.clone()));
+        }
+        if !todo.tags.is_empty() {
+            rows.push(row("Tags", todo.hashtags().join(" ")));
+        }
+        for (number, item) in todo.checklist.iter().enumerate() {
+            let mark = if item.done { "x" } else { " " };
+            let value = format!("[{mark}] {}", text::isolate(&item.text));
+            rows.push(row(&format!("Check {}", number + 1), value));
+        }
+        if let Some(notes) = &todo.notes {
+            for (index, line) in notes.lines().enumerate() {
+                rows.push(row(if index == 0 { "Notes" } else { "" }, line.to_string()));
+            }
+        }
+        Table::columns(&rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A view with every field filled in.
+    fn maximal() -> TodoView {
+        TodoView {
+            id: "7".to_string(),
+            title: "Ship release".to_string(),
+            priority: Priority::High,
+            completed: false,
+            pinned: true,
+            overdue: false,
+            due: NaiveDate::from_ymd_opt(2025, 4, 17),
+            due_description: Some("due in 2 days".to_string()),
+            project: Some("launch".to_string()),
+            tags: vec!["work/release".to_string()],
+            notes: Some("Tag the build\nPost notes".to_string()),
+            checklist: vec![
+                ChecklistItem {
+                    text: "Changelog".to_string(),
+                    done: true,
+                },
+                ChecklistItem {
+                    text: "Announce".to_string(),
+                    done: false,
+                },
+            ],
+            age_marker: "··".to_string(),
+            dates: Some("updated 2h ago".to_string()),
+            created_column: Some("2 weeks ago".to_string()),
+            snoozed_until: Some("Jun 1".to_string()),
+            created: "2025-04-01 09:00".to_string(),
+            updated: Some("2025-04-15 10:00".to_string()),
+            done: Some("2025-04-15 11:00".to_string()),
+        }
+    }
+
+    fn minimal() -> TodoView {
+        TodoView {
+            id: "2".to_string(),
+            title: "Buy milk".to_string(),
+            priority: Priority::Low,
+            completed: true,
+            pinned: false,
+            overdue: false,
+            due: None,
+            due_description: None,
+            project: None,
+            tags: Vec::new(),
+            notes: None,
+            checklist: Vec::new(),
+            age_marker: String::new(),
+            dates: None,
+            created_column: None,
+            snoozed_until: None,
+            created: "2025-04-14 08:00".to_string(),
+            updated: None,
+            done: None,
+        }
+    }
+
+    /// Each field of `maximal()` and text that shows it.
+    fn field_markers() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("id", "7"),
+            ("title", "Ship release"),
+            ("priority", "high"),
+            ("pinned", "yes"),
+            ("due", "2025-04-17"),
+            ("project", "launch"),
+            ("tags", "#work/release"),
+            ("notes", "Post notes"),
+            ("checklist", "Announce"),
+            ("age_marker", "··"),
+            ("dates", "updated 2h ago"),
+            ("created_column", "2 weeks ago"),
+            ("snoozed_until", "Jun 1"),
+            ("created", "2025-04-01 09:00"),
+            ("updated", "2025-04-15 10:00"),
+            ("done", "2025-04-15 11:00"),
+        ]
+    }
+
+    /// Fields a style deliberately leaves out, and why.
+    const EXEMPT: &[(Style, &str)] = &[
+        // Age dots are a glance aid for the colored list only
+        (Style::Compact, "age_marker"),
+        (Style::Table, "age_marker"),
+    ];
+
+    #[test]
+    fn test_every_style_shows_every_field() {
+        colored::control::set_override(false);
+        let view = maximal();
+        for &style in Style::value_variants() {
+            let renderer = style.renderer();
+            let shown = format!(
+                "{}\n{}",
+                renderer.render_list(std::slice::from_ref(&view)),
+                renderer.render_detail(&view)
+            );
+            for (field, marker) in field_markers() {
+                if EXEMPT.contains(&(style, field)) {
+                    continue;
+                }
+                assert!(shown.contains(marker), "{style:?} leaves out {field}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_pretty_snapshots() {
+        colored::control::set_override(false);
+        assert_eq!(
+            Pretty.render_list(&[maximal(), minimal()]),
+            "  7 [⏳] 📌 Ship release [1/2] 📎 +launch #work/release (due in 2 days) (snoozed until Jun 1) ·· (created 2 weeks ago) (updated 2h ago)\n  \
+             2 [✅] Buy milk"
+        );
+        assert_eq!(
+            Pretty.render_detail(&minimal()),
+            "2 Buy milk\n  Priority: low\n  Status:   ✅ completed\n  Created:  2025-04-14 08:00"
+        );
+        assert!(
+            Pretty
+                .render_detail(&maximal())
+                .starts_with("📌 7 Ship release\n")
+        );
+    }
+
+    #[test]
+    fn test_compact_snapshots() {
+        assert_eq!(
+            Compact.render_list(&[maximal(), minimal()]),
+            "7 - Ship release (pinned) [1/2] +launch #work/release due:2025-04-17 (snoozed until Jun 1) (created 2 weeks ago) (updated 2h ago)\n2 x Buy milk"
+        );
+        assert_eq!(
+            Compact.render_detail(&maximal()),
+            "id: 7\ntitle: Ship release\npriority: high\ncompleted: false\n\
+             created: 2025-04-01 09:00\npinned: yes\nupdated: 2025-04-15 10:00\ndone: 2025-04-15 11:00\n\
+             due: 2025-04-17\nsnoozed: until Jun 1\nproject: launch\ntags: #work/release\ncheck: x Changelog\ncheck: - Announce\n\
+             notes: Tag the build\\nPost notes"
+        );
+    }
+
+    #[test]
+    fn test_table_snapshots() {
+        colored::control::set_override(false);
+        assert_eq!(
+            Table.render_list(&[maximal(), minimal()]),
+            "ID  DONE  PRI   TITLE         DUE         TAGS           CHECK  PINNED  PROJECT  CREATED      SNOOZED  DATES\n\
+             7         high  Ship release  2025-04-17  #work/release  1/2    yes     launch   2 weeks ago  Jun 1    updated 2h ago\n\
+             2   x     low   Buy milk"
+        );
+        assert_eq!(
+            Table.render_detail(&minimal()),
+            "FIELD     VALUE\nID        2\nTitle     Buy milk\nPriority  low\n\
+             Status    completed\nCreated   2025-04-14 08:00"
+        );
+    }
+}
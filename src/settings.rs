@@ -0,0 +1,417 @@
+use crate::config::Config;
+use crate::error::TtError;
+use crate::lists;
+use crate::models::todo::Priority;
+use crate::todo_manager::{FILE_ENV, SortKey};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+pub const DEFAULT_PRIORITY_ENV: &str = "TT_DEFAULT_PRIORITY";
+pub const HIDE_COMPLETED_ENV: &str = "TT_HIDE_COMPLETED";
+pub const SORT_ENV: &str = "TT_SORT";
+pub const NO_EMOJI_ENV: &str = "TT_NO_EMOJI";
+
+/// Where a setting's value came from, from lowest to highest precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Default,
+    Config,
+    Env,
+    Flag,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Default => "default",
+            Source::Config => "config file",
+            Source::Env => "environment",
+            Source::Flag => "command line",
+        })
+    }
+}
+
+/// An effective value and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    fn new(value: T, source: Source) -> Self {
+        Self { value, source }
+    }
+
+    /// Replace the value if `value` is given.
+    fn layer(&mut self, value: Option<T>, source: Source) {
+        if let Some(value) = value {
+            *self = Self::new(value, source);
+        }
+    }
+}
+
+/// The settings given on the command line for this run, if any.
+#[derive(Debug, Default)]
+pub struct Flags {
+    pub default_priority: Option<Priority>,
+    pub hide_completed: Option<bool>,
+    pub sort: Option<SortKey>,
+    pub no_emoji: Option<bool>,
+    pub file: Option<PathBuf>,
+    pub list: Option<String>,
+}
+
+/// Settings that can be given in the config file, the environment or on
+/// the command line, with flags beating variables beating the config file.
+#[derive(Debug)]
+pub struct Settings {
+    pub default_priority: Setting<Priority>,
+    pub hide_completed: Setting<bool>,
+    pub sort: Setting<Option<SortKey>>,
+    pub no_emoji: Setting<bool>,
+    /// Todo file named by `--file` or `TT_FILE`
+    pub file: Setting<Option<PathBuf>>,
+    /// List named by `--list` or `TT_LIST`
+    pub list: Setting<Option<String>>,
+    /// Environment variables that were ignored, and why
+    pub warnings: Vec<String>,
+}
+
+/// One line of `tt config env`.
+#[derive(Debug, Serialize)]
+pub struct EnvRow {
+    pub variable: &'static str,
+    pub value: String,
+    pub source: Source,
+}
+
+impl Settings {
+    /// Resolve every setting from `config`, the environment (read through
+    /// `env`) and `flags`. An environment variable with an invalid value is
+    /// ignored with a warning, so a bad machine-wide default never stops tt
+    /// from running.
+    pub fn resolve(config: &Config, flags: Flags, env: impl Fn(&str) -> Option<String>) -> Self {
+        let mut warnings = Vec::new();
+        let env_priority = read_env(&env, DEFAULT_PRIORITY_ENV, parse_priority, &mut warnings);
+        let env_hide_completed = read_env(&env, HIDE_COMPLETED_ENV, parse_bool, &mut warnings);
+        let env_sort = read_env(&env, SORT_ENV, parse_sort, &mut warnings);
+        let env_no_emoji = read_env(&env, NO_EMOJI_ENV, parse_bool, &mut warnings);
+        let env_file = read_env(
+            &env,
+            FILE_ENV,
+            |value| Ok(PathBuf::from(value)),
+            &mut warnings,
+        );
+        let env_list = read_env(&env, lists::ENV, parse_list, &mut warnings);
+
+        let mut default_priority = Setting::new(Priority::default(), Source::Default);
+        match config.default_priority.map(Priority::from_number) {
+            Some(Ok(priority)) => default_priority.layer(Some(priority), Source::Config),
+            Some(Err(error)) => {
+                warnings.push(format!("Ignoring default_priority in config: {error}"))
+            }
+            None => {}
+        }
+        default_priority.layer(env_priority, Source::Env);
+        default_priority.layer(flags.default_priority, Source::Flag);
+
+        let mut hide_completed = Setting::new(false, Source::Default);
+        hide_completed.layer(config.hide_completed.then_some(true), Source::Config);
+        hide_completed.layer(env_hide_completed, Source::Env);
+        hide_completed.layer(flags.hide_completed, Source::Flag);
+
+        let mut sort = Setting::new(None, Source::Default);
+        sort.layer(config.sort.map(Some), Source::Config);
+        sort.layer(env_sort.map(Some), Source::Env);
+        sort.layer(flags.sort.map(Some), Source::Flag);
+
+        let mut no_emoji = Setting::new(false, Source::Default);
+        no_emoji.layer(config.no_emoji.then_some(true), Source::Config);
+        no_emoji.layer(env_no_emoji, Source::Env);
+        no_emoji.layer(flags.no_emoji, Source::Flag);
+
+        let mut file = Setting::new(None, Source::Default);
+        file.layer(env_file.map(Some), Source::Env);
+        file.layer(flags.file.map(Some), Source::Flag);
+
+        let mut list = Setting::new(None, Source::Default);
+        list.layer(env_list.map(Some), Source::Env);
+        list.layer(flags.list.map(Some), Source::Flag);
+
+        Self {
+            default_priority,
+            hide_completed,
+            sort,
+            no_emoji,
+            file,
+            list,
+            warnings,
+        }
+    }
+
+    /// Every supported environment variable with its effective value, for
+    /// `tt config env`.
+    pub fn env_rows(&self) -> Vec<EnvRow> {
+        let row = |variable, value: String, source| EnvRow {
+            variable,
+            value,
+            source,
+        };
+        vec![
+            row(
+                DEFAULT_PRIORITY_ENV,
+                self.default_priority.value.to_string(),
+                self.default_priority.source,
+            ),
+            row(
+                HIDE_COMPLETED_ENV,
+                self.hide_completed.value.to_string(),
+                self.hide_completed.source,
+            ),
+            row(
+                SORT_ENV,
+                self.sort
+                    .value
+                    .and_then(|key| key.to_possible_value())
+                    .map_or("none".to_string(), |key| key.get_name().to_string()),
+                self.sort.source,
+            ),
+            row(
+                NO_EMOJI_ENV,
+                self.no_emoji.value.to_string(),
+                self.no_emoji.source,
+            ),
+            row(
+                FILE_ENV,
+                self.file
+                    .value
+                    .as_ref()
+                    .map_or("none".to_string(), |path| path.display().to_string()),
+                self.file.source,
+            ),
+            row(
+                lists::ENV,
+                self.list
+                    .value
+                    .clone()
+                    .unwrap_or_else(|| lists::DEFAULT.to_string()),
+                self.list.source,
+            ),
+        ]
+    }
+}
+
+/// The value of the variable `name` parsed with `parse`, or `None` if it is
+/// unset, empty or invalid. Invalid values are added to `warnings`.
+fn read_env<T>(
+    env: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    parse: fn(&str) -> Result<T, String>,
+    warnings: &mut Vec<String>,
+) -> Option<T> {
+    let value = env(name).filter(|value| !value.is_empty())?;
+    parse(&value)
+        .map_err(|error| warnings.push(format!("Ignoring {name}={value}: {error}")))
+        .ok()
+}
+
+fn parse_priority(value: &str) -> Result<Priority, String> {
+    value.trim().parse().map_err(|e: TtError| e.to_string())
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err("expected 1, true, yes, on, 0, false, no or off".to_string()),
+    }
+}
+
+fn parse_list(value: &str) -> Result<String, String> {
+    lists::validate_name(value).map_err(|error| error.to_string())?;
+    Ok(value.to_string())
+}
+
+fn parse_sort(value: &str) -> Result<SortKey, String> {
+    SortKey::from_str(value.trim(), true)
+        .map_err(|_| "expected priority, created, title or due".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn resolve(config: &Config, flags: Flags, vars: &[(&str, &str)]) -> Settings {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Settings::resolve(config, flags, |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_defaults() {
+        let settings = resolve(&Config::default(), Flags::default(), &[]);
+        assert_eq!(
+            settings.default_priority,
+            Setting::new(Priority::Low, Source::Default)
+        );
+        assert_eq!(
+            settings.hide_completed,
+            Setting::new(false, Source::Default)
+        );
+        assert_eq!(settings.sort, Setting::new(None, Source::Default));
+        assert_eq!(settings.no_emoji, Setting::new(false, Source::Default));
+        assert_eq!(settings.list, Setting::new(None, Source::Default));
+        assert!(settings.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_default_priority() {
+        let config = Config {
+            default_priority: Some(3),
+            ..Config::default()
+        };
+        let settings = resolve(&config, Flags::default(), &[]);
+        assert_eq!(
+            settings.default_priority,
+            Setting::new(Priority::Medium, Source::Config)
+        );
+
+        let settings = resolve(&config, Flags::default(), &[(DEFAULT_PRIORITY_ENV, "2")]);
+        assert_eq!(
+            settings.default_priority,
+            Setting::new(Priority::High, Source::Env)
+        );
+        let settings = resolve(&config, Flags::default(), &[(DEFAULT_PRIORITY_ENV, "High")]);
+        assert_eq!(
+            settings.default_priority,
+            Setting::new(Priority::High, Source::Env)
+        );
+
+        // An invalid variable warns and falls back to the config file
+        let settings = resolve(&config, Flags::default(), &[(DEFAULT_PRIORITY_ENV, "9")]);
+        assert_eq!(
+            settings.default_priority,
+            Setting::new(Priority::Medium, Source::Config)
+        );
+        assert_eq!(settings.warnings.len(), 1);
+        assert!(settings.warnings[0].contains("TT_DEFAULT_PRIORITY=9"));
+    }
+
+    #[test]
+    fn test_hide_completed() {
+        for (value, hide) in [("1", true), ("yes", true), ("off", false)] {
+            let settings = resolve(
+                &Config::default(),
+                Flags::default(),
+                &[(HIDE_COMPLETED_ENV, value)],
+            );
+            assert_eq!(settings.hide_completed, Setting::new(hide, Source::Env));
+        }
+        let settings = resolve(
+            &Config::default(),
+            Flags::default(),
+            &[(HIDE_COMPLETED_ENV, "sometimes")],
+        );
+        assert_eq!(
+            settings.hide_completed,
+            Setting::new(false, Source::Default)
+        );
+        assert_eq!(settings.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_sort() {
+        let settings = resolve(&Config::default(), Flags::default(), &[(SORT_ENV, "Due")]);
+        assert_eq!(settings.sort, Setting::new(Some(SortKey::Due), Source::Env));
+
+        let settings = resolve(&Config::default(), Flags::default(), &[(SORT_ENV, "size")]);
+        assert_eq!(settings.sort, Setting::new(None, Source::Default));
+        assert_eq!(settings.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_no_emoji() {
+        let settings = resolve(
+            &Config::default(),
+            Flags::default(),
+            &[(NO_EMOJI_ENV, "true")],
+        );
+        assert_eq!(settings.no_emoji, Setting::new(true, Source::Env));
+
+        // Empty counts as unset
+        let settings = resolve(&Config::default(), Flags::default(), &[(NO_EMOJI_ENV, "")]);
+        assert_eq!(settings.no_emoji, Setting::new(false, Source::Default));
+        assert!(settings.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_list() {
+        let settings = resolve(
+            &Config::default(),
+            Flags::default(),
+            &[(lists::ENV, "work")],
+        );
+        assert_eq!(
+            settings.list,
+            Setting::new(Some("work".to_string()), Source::Env)
+        );
+
+        let settings = resolve(
+            &Config::default(),
+            Flags::default(),
+            &[(lists::ENV, "../x")],
+        );
+        assert_eq!(settings.list, Setting::new(None, Source::Default));
+        assert_eq!(settings.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_conflicting_sources() {
+        let config = Config {
+            hide_completed: true,
+            sort: Some(SortKey::Title),
+            no_emoji: true,
+            ..Config::default()
+        };
+        let flags = Flags {
+            hide_completed: Some(false),
+            default_priority: Some(Priority::Critical),
+            list: Some("home".to_string()),
+            ..Flags::default()
+        };
+        let settings = resolve(
+            &config,
+            flags,
+            &[
+                (HIDE_COMPLETED_ENV, "1"),
+                (DEFAULT_PRIORITY_ENV, "2"),
+                (SORT_ENV, "created"),
+                (NO_EMOJI_ENV, "0"),
+                (lists::ENV, "work"),
+            ],
+        );
+        // Flags beat variables, which beat the config file
+        assert_eq!(settings.hide_completed, Setting::new(false, Source::Flag));
+        assert_eq!(
+            settings.default_priority,
+            Setting::new(Priority::Critical, Source::Flag)
+        );
+        assert_eq!(
+            settings.sort,
+            Setting::new(Some(SortKey::Created), Source::Env)
+        );
+        assert_eq!(settings.no_emoji, Setting::new(false, Source::Env));
+        assert_eq!(
+            settings.list,
+            Setting::new(Some("home".to_string()), Source::Flag)
+        );
+        assert!(settings.warnings.is_empty());
+    }
+}
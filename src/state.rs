@@ -0,0 +1,90 @@
+use crate::focus::Focus;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Bookkeeping tt keeps about a store between invocations, saved next to
+/// the todo file. Losing it is harmless: every field has a safe default.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// Size and modification time of the todo file after tt last wrote it
+    #[serde(default)]
+    pub last_write: Option<FileStamp>,
+    /// When the user last looked at the list or digest (RFC 3339)
+    #[serde(default)]
+    pub last_viewed: Option<String>,
+    /// The focus session in progress, if any
+    #[serde(default)]
+    pub focus: Option<Focus>,
+    /// When `tt maintenance` last ran for this store (RFC 3339)
+    #[serde(default)]
+    pub last_maintenance: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub len: u64,
+    pub modified_nanos: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified_nanos: u64::try_from(modified.as_nanos()).ok()?,
+        })
+    }
+}
+
+impl State {
+    /// Load state, falling back to defaults if it is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize state")?;
+        fs::write(path, json).context("Failed to write state file")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_missing_or_corrupt_state_is_default() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("state.json");
+        assert!(State::load(&path).last_write.is_none());
+
+        fs::write(&path, "{ not json").unwrap();
+        assert!(State::load(&path).last_write.is_none());
+    }
+
+    #[test]
+    fn test_save_and_load_stamp() {
+        let temp_dir = tempdir().unwrap();
+        let data = temp_dir.path().join("todos.json");
+        let path = temp_dir.path().join("state.json");
+        fs::write(&data, "{}").unwrap();
+
+        let state = State {
+            last_write: FileStamp::of(&data),
+            ..State::default()
+        };
+        state.save(&path).unwrap();
+
+        let loaded = State::load(&path);
+        assert_eq!(loaded.last_write, FileStamp::of(&data));
+        assert_eq!(loaded.last_write.unwrap().len, 2);
+    }
+}
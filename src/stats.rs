@@ -0,0 +1,167 @@
+use crate::models::todo::{Priority, Todo};
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// How many todos there are, how many of them are completed, and how many
+/// of the rest have each priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub total: usize,
+    pub completed: usize,
+    /// Pending todos per priority, critical first
+    pub pending_by_priority: [usize; 4],
+}
+
+impl Counts {
+    pub fn from_todos<'a>(todos: impl IntoIterator<Item = &'a Todo>) -> Self {
+        let mut counts = Self::default();
+        for todo in todos {
+            counts.total += 1;
+            if todo.completed {
+                counts.completed += 1;
+            } else {
+                counts.pending_by_priority[usize::from(todo.priority.number() - 1)] += 1;
+            }
+        }
+        counts
+    }
+
+    pub fn pending(&self) -> usize {
+        self.total - self.completed
+    }
+}
+
+/// Aggregate numbers about a todo store at a point in time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    pub total: usize,
+    pub pending: usize,
+    pub completed: usize,
+    /// Pending todos whose due date has passed
+    pub overdue: usize,
+    /// Pending todos per priority, with every priority present
+    pub pending_by_priority: BTreeMap<u8, usize>,
+    /// Age in seconds of the oldest pending todo with a readable timestamp
+    pub oldest_pending_age_secs: Option<i64>,
+}
+
+impl Stats {
+    pub fn from_todos(todos: &[Todo], now: DateTime<Utc>) -> Self {
+        let counts = Counts::from_todos(todos);
+        let oldest_pending_age_secs = todos
+            .iter()
+            .filter(|t| !t.completed)
+            .filter_map(|t| DateTime::parse_from_rfc3339(&t.created_at).ok())
+            .map(|created| (now - created.with_timezone(&Utc)).num_seconds().max(0))
+            .max();
+        let today = now.with_timezone(&Local).date_naive();
+        Self {
+            total: counts.total,
+            pending: counts.pending(),
+            completed: counts.completed,
+            overdue: todos.iter().filter(|t| t.is_overdue(today)).count(),
+            pending_by_priority: Priority::ALL
+                .iter()
+                .map(|priority| priority.number())
+                .zip(counts.pending_by_priority)
+                .collect(),
+            oldest_pending_age_secs,
+        }
+    }
+
+    pub fn oldest_pending_age(&self) -> Option<Duration> {
+        self.oldest_pending_age_secs.map(Duration::seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn todo_created(days_ago: i64, completed: bool, now: DateTime<Utc>) -> Todo {
+        Todo {
+            completed,
+            created_at: (now - Duration::days(days_ago)).to_rfc3339(),
+            priority: Priority::Low,
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn test_stats_counts_and_oldest_pending() {
+        let now = Utc::now();
+        let todos = vec![
+            todo_created(3, false, now),
+            todo_created(40, true, now),
+            todo_created(10, false, now),
+        ];
+        let stats = Stats::from_todos(&todos, now);
+
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.pending, 2);
+        assert_eq!(stats.completed, 1);
+        assert_eq!(stats.oldest_pending_age(), Some(Duration::days(10)));
+        assert_eq!(stats.overdue, 0);
+    }
+
+    #[test]
+    fn test_stats_overdue_and_priorities() {
+        let now = Utc::now();
+        let mut overdue = todo_created(3, false, now);
+        overdue.priority = Priority::Critical;
+        overdue.due_date = Some((now - Duration::days(2)).date_naive());
+        let mut done_late = todo_created(3, true, now);
+        done_late.due_date = overdue.due_date;
+        let stats = Stats::from_todos(&[overdue, done_late, todo_created(1, false, now)], now);
+
+        assert_eq!(stats.overdue, 1);
+        assert_eq!(
+            stats.pending_by_priority,
+            BTreeMap::from([(1, 1), (2, 0), (3, 0), (4, 1)])
+        );
+    }
+
+    #[test]
+    fn test_counts() {
+        let now = Utc::now();
+        let mut critical = todo_created(1, false, now);
+        critical.priority = Priority::Critical;
+        let mut done = todo_created(1, true, now);
+        done.priority = Priority::Critical;
+        let todos = [critical, done, todo_created(1, false, now)];
+        let counts = Counts::from_todos(&todos);
+
+        assert_eq!(
+            counts,
+            Counts {
+                total: 3,
+                completed: 1,
+                pending_by_priority: [1, 0, 0, 1],
+            }
+        );
+        assert_eq!(counts.pending(), 2);
+        assert_eq!(Counts::from_todos(&[]).pending(), 0);
+    }
+
+    #[test]
+    fn test_stats_ignores_unparseable_timestamps() {
+        let now = Utc::now();
+        let todos = vec![Todo {
+            created_at: "yesterday-ish".to_string(),
+            ..Todo::default()
+        }];
+        let stats = Stats::from_todos(&todos, now);
+
+        assert_eq!(stats.pending, 1);
+        assert_eq!(stats.oldest_pending_age_secs, None);
+    }
+
+    #[test]
+    fn test_stats_empty_store() {
+        let stats = Stats::from_todos(&[], Utc::now());
+        assert_eq!(stats.total, 0);
+        assert_eq!(stats.oldest_pending_age_secs, None);
+    }
+}
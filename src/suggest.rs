@@ -0,0 +1,50 @@
+/// Levenshtein distance between two strings, counted in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// The candidate closest to `input`, if it is within `max_distance` edits.
+/// Ties go to the earliest candidate.
+pub fn closest<'a>(input: &str, candidates: &[&'a str], max_distance: usize) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(input, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("priority", "priority"), 0);
+        assert_eq!(edit_distance("priorty", "priority"), 1);
+        assert_eq!(edit_distance("comlpete", "complete"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_closest() {
+        let fields = ["title", "completed", "priority"];
+        assert_eq!(closest("priorty", &fields, 2), Some("priority"));
+        assert_eq!(closest("titel", &fields, 2), Some("title"));
+        assert_eq!(closest("colour", &fields, 2), None);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::models::todo::Todo;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Separates the segments of a nested tag, as in `work/clienta/billing`.
+pub const SEPARATOR: char = '/';
+
+/// Whether `tag` is `ancestor` itself or nested somewhere beneath it.
+/// Matching is by whole segments, so `work/cli` is not an ancestor of
+/// `work/clienta`.
+pub fn is_within(tag: &str, ancestor: &str) -> bool {
+    match tag.strip_prefix(ancestor) {
+        Some("") => true,
+        Some(rest) => rest.starts_with(SEPARATOR),
+        None => false,
+    }
+}
+
+/// The tag after moving everything under `from` to `to`, or `None` when the
+/// tag is not within `from`.
+pub fn rename(tag: &str, from: &str, to: &str) -> Option<String> {
+    is_within(tag, from).then(|| format!("{to}{}", &tag[from.len()..]))
+}
+
+/// `tag` and every ancestor of it, shortest first.
+pub fn ancestors(tag: &str) -> impl Iterator<Item = &str> {
+    tag.match_indices(SEPARATOR)
+        .map(|(index, _)| &tag[..index])
+        .chain(std::iter::once(tag))
+}
+
+/// One line of `tt tags --tree`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TreeNode {
+    /// How many levels deep the tag is nested, 0 at the top
+    pub depth: usize,
+    /// The last segment of the tag
+    pub name: String,
+    /// How many todos carry this tag or one nested beneath it
+    pub count: usize,
+}
+
+/// Count todos per tag, crediting each todo once to the tags it carries and
+/// once to each of their ancestors. Sorted by tag.
+pub fn counts(todos: &[Todo]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for todo in todos {
+        let paths: BTreeSet<&str> = todo.tags.iter().flat_map(|tag| ancestors(tag)).collect();
+        for path in paths {
+            *counts.entry(path.to_string()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// The tag hierarchy in display order: each tag directly followed by the
+/// tags nested beneath it, alphabetical among siblings.
+pub fn tree(todos: &[Todo]) -> Vec<TreeNode> {
+    // Compare segment by segment: as whole strings, `work-life` would land
+    // between `work` and `work/clienta`
+    let mut counts: Vec<(String, usize)> = counts(todos).into_iter().collect();
+    counts.sort_by(|(a, _), (b, _)| a.split(SEPARATOR).cmp(b.split(SEPARATOR)));
+    counts
+        .into_iter()
+        .map(|(path, count)| {
+            let depth = path.matches(SEPARATOR).count();
+            let name = path.rsplit(SEPARATOR).next().unwrap_or(&path).to_string();
+            TreeNode { depth, name, count }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::Priority;
+
+    fn todo(tags: &[&str]) -> Todo {
+        let mut todo = Todo::new(1, "Tagged".to_string(), Priority::Low);
+        for tag in tags {
+            todo.add_tag(tag);
+        }
+        todo
+    }
+
+    #[test]
+    fn test_is_within_matches_whole_segments() {
+        assert!(is_within("work", "work"));
+        assert!(is_within("work/clienta", "work"));
+        assert!(is_within("work/clienta/billing", "work/clienta"));
+        assert!(!is_within("work/clienta", "work/cli"));
+        assert!(!is_within("workshop", "work"));
+        assert!(!is_within("work", "work/clienta"));
+        assert!(!is_within("home/work", "work"));
+    }
+
+    #[test]
+    fn test_rename_rewrites_descendants() {
+        assert_eq!(
+            rename("work/clienta/billing", "work/clienta", "work/acme"),
+            Some("work/acme/billing".to_string())
+        );
+        assert_eq!(
+            rename("work/clienta", "work/clienta", "work/acme"),
+            Some("work/acme".to_string())
+        );
+        assert_eq!(rename("work/clientab", "work/clienta", "work/acme"), None);
+        assert_eq!(rename("work", "work/clienta", "work/acme"), None);
+        assert_eq!(rename("urgent", "urgent", "now"), Some("now".to_string()));
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let found: Vec<&str> = ancestors("work/clienta/billing").collect();
+        assert_eq!(found, vec!["work", "work/clienta", "work/clienta/billing"]);
+        assert_eq!(ancestors("home").collect::<Vec<_>>(), vec!["home"]);
+    }
+
+    #[test]
+    fn test_tree_counts_each_todo_once_per_node() {
+        let todos = vec![
+            todo(&["work/clienta/billing", "work/clienta/support"]),
+            todo(&["work/clientb"]),
+            todo(&["home", "work-life"]),
+            todo(&[]),
+        ];
+        let nodes = tree(&todos);
+        let lines: Vec<(usize, &str, usize)> = nodes
+            .iter()
+            .map(|node| (node.depth, node.name.as_str(), node.count))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (0, "home", 1),
+                (0, "work", 2),
+                (1, "clienta", 1),
+                (2, "billing", 1),
+                (2, "support", 1),
+                (1, "clientb", 1),
+                (0, "work-life", 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// FIRST STRONG ISOLATE and POP DIRECTIONAL ISOLATE: the enclosed text
+/// takes the direction of its first strong character without reordering
+/// anything around it.
+const ISOLATE_START: char = '\u{2068}';
+const ISOLATE_END: char = '\u{2069}';
+
+const ELLIPSIS: &str = "…";
+
+/// Columns `text` takes up in a terminal. Measured per grapheme cluster,
+/// so combining marks and emoji joined with zero-width joiners count once;
+/// ANSI color codes take up no room.
+pub fn display_width(text: &str) -> usize {
+    strip_ansi(text)
+        .graphemes(true)
+        .map(UnicodeWidthStr::width)
+        .sum()
+}
+
+/// `text` cut down to at most `width` columns, ending in `…` when anything
+/// was cut. Cuts fall between grapheme clusters, never inside one. `text`
+/// must not contain color codes.
+pub fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let room = width.saturating_sub(ELLIPSIS.width());
+    let mut used = 0;
+    let mut cut = String::new();
+    for grapheme in text.graphemes(true) {
+        used += grapheme.width();
+        if used > room {
+            break;
+        }
+        cut.push_str(grapheme);
+    }
+    if width > 0 {
+        cut.push_str(ELLIPSIS);
+    }
+    cut
+}
+
+/// `text` followed by spaces up to `width` columns.
+pub fn pad(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{text}{}", " ".repeat(padding))
+}
+
+/// Wrap text that contains right-to-left script in bidi isolates, so that
+/// embedding it in a left-to-right row cannot move the cells around it.
+/// Other text is returned unchanged, as some terminals draw the isolates.
+pub fn isolate(text: &str) -> String {
+    if text.chars().any(is_right_to_left) {
+        format!("{ISOLATE_START}{text}{ISOLATE_END}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// Letters of right-to-left scripts (Hebrew, Arabic, Syriac, Thaana, N'Ko
+/// and their presentation forms) and explicit bidi controls.
+fn is_right_to_left(c: char) -> bool {
+    matches!(c,
+        '\u{0590}'..='\u{08FF}'
+        | '\u{FB1D}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFC}'
+        | '\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2066}'..='\u{2069}')
+}
+
+/// `text` without ANSI escape sequences (`ESC [ ... letter`).
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip to the final byte of the sequence
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "family" as one ZWJ emoji sequence: three people joined by U+200D
+    const FAMILY: &str = "👨\u{200D}👩\u{200D}👧";
+    /// "Café" spelled with a combining acute accent, and a heavily
+    /// combined letter
+    const COMBINING: &str = "Cafe\u{0301} Z\u{0351}\u{0359}\u{0345}";
+    const HEBREW: &str = "לקנות חלב";
+    const ARABIC: &str = "شراء الحليب";
+
+    #[test]
+    fn test_display_width_counts_graphemes() {
+        assert_eq!(display_width("Buy milk"), 8);
+        assert_eq!(display_width(FAMILY), 2);
+        assert_eq!(display_width(COMBINING), 6);
+        assert_eq!(display_width(HEBREW), 9);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("\x1b[1;31mred\x1b[0m"), 3);
+        assert_eq!(display_width(&isolate(ARABIC)), display_width(ARABIC));
+    }
+
+    #[test]
+    fn test_truncate_never_splits_a_grapheme() {
+        assert_eq!(truncate("Buy milk", 8), "Buy milk");
+        assert_eq!(truncate("Buy milk", 5), "Buy …");
+        assert_eq!(
+            truncate(&format!("{FAMILY}{FAMILY}"), 3),
+            format!("{FAMILY}…")
+        );
+        assert_eq!(truncate(COMBINING, 5), "Cafe\u{0301}…");
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("abc", 0), "");
+        for width in 0..12 {
+            for text in [FAMILY, COMBINING, HEBREW, ARABIC] {
+                let cut = truncate(text, width);
+                assert!(display_width(&cut) <= width);
+                assert!(text.starts_with(cut.trim_end_matches(ELLIPSIS)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pad_aligns_by_columns() {
+        let rows: Vec<String> = ["work", COMBINING, FAMILY, HEBREW]
+            .iter()
+            .map(|name| format!("{}|", pad(name, 10)))
+            .collect();
+        for row in &rows {
+            assert_eq!(display_width(row), 11);
+        }
+    }
+
+    #[test]
+    fn test_isolate_only_right_to_left_text() {
+        assert_eq!(isolate("Buy milk"), "Buy milk");
+        assert_eq!(isolate(FAMILY), FAMILY);
+        assert_eq!(isolate(HEBREW), format!("\u{2068}{HEBREW}\u{2069}"));
+        assert_eq!(isolate("Call אבא"), "\u{2068}Call אבא\u{2069}");
+    }
+}
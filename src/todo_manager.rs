@@ -1,34 +1,482 @@
-use crate::models::todo::{Todo, TodoStore};
-use anyhow::{Context, Result};
+use crate::bundle::{self, MergeCounts};
+use crate::due::Update;
+use crate::error::TtError;
+use crate::focus::Focus;
+use crate::groom;
+use crate::lists;
+use crate::lock::{LOCK_TIMEOUT, StoreLock};
+use crate::merge::{self, MergeReport};
+use crate::migrate;
+use crate::models::todo::{
+    ChecklistItem, Deletion, Priority, StoreMeta, StoreRef, Template, TemplateTodo, Todo,
+    TodoStore, TrashedTodo,
+};
+use crate::normalize;
+use crate::settings::{Setting, Source};
+use crate::state::{FileStamp, State};
+use crate::stats::Counts;
+use crate::undo::{self, Snapshot};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use clap::ValueEnum;
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the todo file, overridden by `--file`.
+pub const FILE_ENV: &str = "TT_FILE";
+
+/// Title of drafts reserved without one; they cannot be published as is.
+pub const DRAFT_TITLE: &str = "(untitled draft)";
+
+/// Field to order query results by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Highest priority (1) first
+    Priority,
+    /// Oldest first; unreadable timestamps last
+    Created,
+    /// Alphabetical, ignoring case
+    Title,
+    /// Soonest due first; todos without a due date last
+    Due,
+}
+
+/// Filters and ordering for `TodoManager::query`. The default returns every
+/// todo in stored order.
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    /// Only todos carrying every one of these tags
+    pub tags: Vec<String>,
+    /// Only todos with this completion state
+    pub completed: Option<bool>,
+    /// Only todos with this priority
+    pub priority: Option<Priority>,
+    /// Only todos in this project
+    pub project: Option<String>,
+    pub sort: Option<SortKey>,
+    pub reverse: bool,
+    /// Leave out todos that are snoozed at this time
+    pub hide_snoozed_at: Option<DateTime<Utc>>,
+}
+
+impl QueryOptions {
+    /// Filter and order `todos` according to these options.
+    pub fn apply(&self, todos: &[Todo]) -> Vec<Todo> {
+        let mut todos: Vec<Todo> = todos
+            .iter()
+            .filter(|todo| self.tags.iter().all(|tag| todo.has_tag(tag)))
+            .filter(|todo| self.completed.is_none_or(|c| todo.completed == c))
+            .filter(|todo| self.priority.is_none_or(|p| todo.priority == p))
+            .filter(|todo| {
+                self.project
+                    .as_deref()
+                    .is_none_or(|p| todo.project.as_deref() == Some(p.trim()))
+            })
+            .filter(|todo| self.hide_snoozed_at.is_none_or(|now| !todo.is_snoozed(now)))
+            .cloned()
+            .collect();
+
+        match self.sort {
+            Some(SortKey::Priority) => todos.sort_by_key(|todo| todo.priority),
+            Some(SortKey::Created) => todos.sort_by_key(|todo| {
+                let created = DateTime::parse_from_rfc3339(&todo.created_at).ok();
+                (created.is_none(), created)
+            }),
+            Some(SortKey::Title) => todos.sort_by_key(|todo| todo.title.to_lowercase()),
+            Some(SortKey::Due) => {
+                todos.sort_by_key(|todo| (todo.due_date.is_none(), todo.due_date))
+            }
+            None => {}
+        }
+        if self.reverse {
+            todos.reverse();
+        }
+        todos
+    }
+}
+
+/// How `import_todos` treats todos that look like ones already present.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DedupeStrategy {
+    /// Skip todos whose title matches an incomplete todo, ignoring case
+    SkipIncompleteTitleMatches,
+    /// Import everything
+    AllowDuplicates,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub duplicates: usize,
+}
+
+/// A todo to add with `TodoManager::add`; only the title is required.
+#[derive(Debug, Default)]
+pub struct NewTodo {
+    pub title: String,
+    pub priority: Priority,
+    pub due_date: Option<NaiveDate>,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+    pub project: Option<String>,
+}
+
+/// Changes to make to one todo; fields left as `None` (or empty) are kept.
+#[derive(Debug, Default)]
+pub struct TodoEdit {
+    pub title: Option<String>,
+    pub priority: Option<Priority>,
+    /// `Some(None)` clears the due date
+    pub due_date: Option<Option<NaiveDate>>,
+    /// `Some(None)` clears the notes
+    pub notes: Option<Option<String>>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    /// `Some(None)` takes the todo out of its project
+    pub project: Option<Option<String>>,
+}
+
+/// A todo file and, when it is one of the named lists, the list's name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub list: Option<String>,
+}
+
+impl Location {
+    /// The store picked by the global `--file` and `--list` options and the
+    /// `TT_FILE` and `TT_LIST` environment variables, in that order, or the
+    /// default list. Relative paths are taken from the current directory.
+    pub fn resolve(
+        file: &Setting<Option<PathBuf>>,
+        list: &Setting<Option<String>>,
+    ) -> Result<Self> {
+        let (flag_file, env_file) = split_by_source(file.value.as_deref(), file.source);
+        let (flag_list, env_list) = split_by_source(list.value.as_deref(), list.source);
+        match choose_store(flag_file, flag_list, env_file, env_list) {
+            StoreChoice::File(path) if path.is_absolute() => Ok(Self::from(path.to_path_buf())),
+            StoreChoice::File(path) => {
+                let cwd =
+                    std::env::current_dir().context("Could not determine current directory")?;
+                Ok(Self::from(cwd.join(path)))
+            }
+            StoreChoice::List(name) => Self::of_list(name),
+        }
+    }
+
+    /// The named list `name`. The default list is the default todo file
+    /// (see `migrate::default_file_path`); others live in `lists::dir`.
+    pub fn of_list(name: &str) -> Result<Self> {
+        lists::validate_name(name)?;
+        let path = if name == lists::DEFAULT {
+            migrate::default_file_path()?
+        } else {
+            lists::path_in(&lists::dir()?, name)
+        };
+        Ok(Self {
+            path,
+            list: Some(name.to_string()),
+        })
+    }
+}
+
+impl From<PathBuf> for Location {
+    fn from(path: PathBuf) -> Self {
+        Self { path, list: None }
+    }
+}
+
+/// Which store the options and environment ask for.
+#[derive(Debug, PartialEq, Eq)]
+enum StoreChoice<'a> {
+    File(&'a Path),
+    List(&'a str),
+}
 
 pub struct TodoManager {
     todos: Vec<Todo>,
+    next_id: usize,
     file_path: PathBuf,
+    /// Name of the list this store is, unless it was opened by path
+    list: Option<String>,
+    /// Command about to modify the store, snapshotted on its first write
+    operation: Option<String>,
+    /// Held from load to exit by commands that modify the store
+    lock: Option<StoreLock>,
+    meta: Option<StoreMeta>,
+    deleted: Vec<Deletion>,
+    templates: Vec<Template>,
+    trash: Vec<TrashedTodo>,
+    /// Save after every change, as the CLI does
+    autosave: bool,
+    /// Changes not yet written to the todo file
+    dirty: bool,
+    /// Write the todo file on one line instead of pretty-printed
+    compact: bool,
 }
 
 impl TodoManager {
-    pub fn new() -> Result<Self> {
-        let file_path = Self::get_file_path()?;
-        let mut manager = Self {
+    /// Open the todo file at `file_path`. A missing file is an empty list,
+    /// but one that cannot be read or parsed is an error rather than being
+    /// silently replaced by an empty list on the next save.
+    ///
+    /// With `lock`, other tt processes are kept out until the manager is
+    /// dropped, so concurrent load-modify-save cycles cannot lose writes.
+    pub fn open(location: impl Into<Location>, lock: bool) -> Result<Self> {
+        let mut manager = Self::unloaded(location);
+        if lock {
+            manager.lock()?;
+        }
+        manager.load_from_file().with_context(|| {
+            format!(
+                "{} could not be loaded; fix it (see `tt validate`) or rerun with --force to start over with an empty list",
+                manager.file_path.display()
+            )
+        })?;
+        Ok(manager)
+    }
+
+    /// An empty manager for the todo file at `location`, without loading it.
+    pub fn open_empty(location: impl Into<Location>, lock: bool) -> Result<Self> {
+        let mut manager = Self::unloaded(location);
+        if lock {
+            manager.lock()?;
+        }
+        Ok(manager)
+    }
+
+    /// Open the todo file at `path`, as `open` does, without locking it.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # let path = dir.path().join("todos.json");
+    /// use tt::{Priority, TodoManager};
+    ///
+    /// let mut manager = TodoManager::with_path(&path)?;
+    /// let id = manager.add_todo("Water the plants".to_string(), Priority::High, None, &[], None)?.id;
+    /// manager.mark_completed(&[id])?;
+    ///
+    /// let reopened = TodoManager::with_path(&path)?;
+    /// assert!(reopened.list_todos()[0].completed);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn with_path(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open(path.as_ref().to_path_buf(), false)
+    }
+
+    /// Turn saving after every change on (the default) or off. With it
+    /// off, changes stay in memory until `save` is called, except those
+    /// that also touch the archive, which are saved straight away.
+    ///
+    /// ```
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # let path = dir.path().join("todos.json");
+    /// use tt::{Priority, TodoManager};
+    ///
+    /// let mut manager = TodoManager::with_path(&path)?.autosave(false);
+    /// manager.add_todo("Book flights".to_string(), Priority::Critical, None, &[], None)?;
+    /// manager.add_todo("Pack".to_string(), Priority::Medium, None, &[], None)?;
+    /// assert!(!path.exists());
+    ///
+    /// manager.save()?;
+    /// assert_eq!(TodoManager::with_path(&path)?.list_todos().len(), 2);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn autosave(mut self, enabled: bool) -> Self {
+        self.autosave = enabled;
+        self
+    }
+
+    /// Save the todo file and archive as compact JSON on a single line,
+    /// smaller and quicker to write for big stores, instead of
+    /// pretty-printed. Either form loads the same.
+    pub fn compact(mut self, enabled: bool) -> Self {
+        self.compact = enabled;
+        self
+    }
+
+    fn unloaded(location: impl Into<Location>) -> Self {
+        let location = location.into();
+        Self {
             todos: Vec::new(),
-            file_path,
-        };
+            next_id: 1,
+            file_path: location.path,
+            list: location.list,
+            operation: None,
+            lock: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        }
+    }
+
+    fn lock(&mut self) -> Result<()> {
+        self.lock = Some(StoreLock::acquire(&self.lock_path(), LOCK_TIMEOUT)?);
+        Ok(())
+    }
+
+    /// Name of the list this store is, unless it was opened by path.
+    pub fn list_name(&self) -> Option<&str> {
+        self.list.as_deref()
+    }
+
+    /// The todo file and the files kept alongside it that belong to the
+    /// store, everything but the lock file.
+    pub fn store_files(&self) -> Vec<PathBuf> {
+        vec![
+            self.file_path.clone(),
+            self.archive_path(),
+            self.undo_path(),
+            self.journal_path(),
+            self.state_path(),
+        ]
+    }
+
+    // Files kept alongside the todo file must be registered with a
+    // maintenance task or listed as kept in `maintenance::KEPT`.
+
+    /// Path of the lock file kept alongside the todo file.
+    pub fn lock_path(&self) -> PathBuf {
+        self.file_path.with_extension("lock")
+    }
+
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
 
-        // Try to load existing todos, but don't fail if file doesn't exist
-        if let Err(e) = manager.load_from_file() {
-            eprintln!("⚠️  Warning: Could not load existing todos: {e}");
-            eprintln!("   Starting with empty todo list.");
+    /// Path of the state file kept alongside the todo file.
+    pub fn state_path(&self) -> PathBuf {
+        self.file_path.with_extension("state.json")
+    }
+
+    /// Whether the todo file changed since tt last wrote it, e.g. by hand.
+    /// A store tt has never written is treated as modified externally.
+    pub fn modified_externally(&self) -> bool {
+        match FileStamp::of(&self.file_path) {
+            None => false,
+            Some(stamp) => State::load(&self.state_path()).last_write != Some(stamp),
         }
+    }
 
-        Ok(manager)
+    /// When the list or digest was last viewed for this store.
+    pub fn last_viewed(&self) -> Option<DateTime<Utc>> {
+        State::load(&self.state_path())
+            .last_viewed
+            .and_then(|viewed| DateTime::parse_from_rfc3339(&viewed).ok())
+            .map(|viewed| viewed.with_timezone(&Utc))
+    }
+
+    /// Record that the list or digest was viewed. Best effort, like all
+    /// state bookkeeping.
+    pub fn mark_viewed(&self, now: DateTime<Utc>) {
+        let mut state = State::load(&self.state_path());
+        state.last_viewed = Some(now.to_rfc3339());
+        let _ = state.save(&self.state_path());
+    }
+
+    /// When maintenance last ran for this store.
+    pub fn last_maintenance(&self) -> Option<DateTime<Utc>> {
+        State::load(&self.state_path())
+            .last_maintenance
+            .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// Record that maintenance ran. Best effort, like `mark_viewed`.
+    pub fn mark_maintained(&self, now: DateTime<Utc>) {
+        let mut state = State::load(&self.state_path());
+        state.last_maintenance = Some(now.to_rfc3339());
+        let _ = state.save(&self.state_path());
+    }
+
+    /// The focus session recorded for this store, whatever its status.
+    pub fn focus(&self) -> Option<Focus> {
+        State::load(&self.state_path()).focus
+    }
+
+    /// Start (`Some`) or end (`None`) a focus session. Unlike the rest of the
+    /// state this must not be lost, so failing to save is an error.
+    pub fn set_focus(&self, focus: Option<Focus>) -> Result<()> {
+        let mut state = State::load(&self.state_path());
+        state.focus = focus;
+        state.save(&self.state_path())
+    }
+
+    /// Path of the archive of completed todos kept alongside the todo file.
+    pub fn archive_path(&self) -> PathBuf {
+        self.file_path.with_extension("archive.json")
     }
 
-    fn get_file_path() -> Result<PathBuf> {
-        dirs::home_dir()
-            .context("Could not determine home directory")
-            .map(|home| home.join(".tt.json"))
+    /// Path of the operation journal kept alongside the todo file.
+    pub fn journal_path(&self) -> PathBuf {
+        self.file_path.with_extension("journal.jsonl")
+    }
+
+    /// Path of the undo history kept alongside the todo file.
+    pub fn undo_path(&self) -> PathBuf {
+        self.file_path.with_extension("undo.json")
+    }
+
+    /// Name the command about to modify the store. Its first write
+    /// snapshots the store and archive so `undo` can put them back.
+    pub fn begin_operation(&mut self, operation: &str) {
+        self.operation = Some(operation.to_string());
+    }
+
+    fn snapshot(&mut self) -> Result<()> {
+        if let Some(operation) = self.operation.take() {
+            let snapshot = Snapshot::take(
+                &operation,
+                &self.file_path,
+                &self.archive_path(),
+                Utc::now(),
+            );
+            undo::push(&self.undo_path(), snapshot)?;
+        }
+        Ok(())
+    }
+
+    /// Restore the store and archive to how they were before the most
+    /// recent mutating command, returning that command's name, or `None`
+    /// when there is nothing to undo.
+    pub fn undo(&mut self) -> Result<Option<String>> {
+        let undo_path = self.undo_path();
+        let Some(snapshot) = undo::load(&undo_path).pop() else {
+            return Ok(None);
+        };
+
+        match &snapshot.store {
+            Some(contents) => write_file(&self.file_path, contents)?,
+            None => write_store(&self.file_path, &TodoStore::default(), self.compact)?,
+        }
+        let archive_path = self.archive_path();
+        match &snapshot.archive {
+            Some(contents) => write_file(&archive_path, contents)?,
+            None if archive_path.exists() => {
+                fs::remove_file(&archive_path).context("Failed to remove archive file")?
+            }
+            None => {}
+        }
+        self.record_write();
+
+        // Only forget the snapshot once it has been fully restored
+        undo::pop(&undo_path)?;
+
+        // A snapshot of a file that was already broken restores fine but
+        // cannot be loaded; later commands will report it
+        self.todos = Vec::new();
+        self.next_id = 1;
+        let _ = self.load_from_file();
+        Ok(Some(snapshot.operation))
     }
 
     pub fn load_from_file(&mut self) -> Result<()> {
@@ -36,252 +484,3271 @@ impl TodoManager {
             return Ok(()); // File doesn't exist yet, that's fine
         }
 
-        let content = fs::read_to_string(&self.file_path).context("Failed to read todo file")?;
+        let content = fs::read_to_string(&self.file_path)
+            .map_err(TtError::Io)
+            .context("Failed to read todo file")?;
 
-        let todo_store: TodoStore =
-            serde_json::from_str(&content).context("Failed to parse todo file as JSON")?;
+        let todo_store: TodoStore = serde_json::from_str(&content)
+            .map_err(TtError::Parse)
+            .context("Failed to parse todo file as JSON")?;
 
         self.todos = todo_store.todos;
+        self.next_id = todo_store.next_id;
+        self.meta = todo_store.meta;
+        self.deleted = todo_store.deleted;
+        self.templates = todo_store.templates;
+        self.trash = todo_store.trash;
+        note_saved_priorities(&content, &mut self.todos);
+        self.assign_missing_ids();
+        self.keep_drafts_last();
         Ok(())
     }
 
-    pub fn save_to_file(&self) -> Result<()> {
-        let todo_store = TodoStore {
-            todos: self.todos.clone(),
-        };
+    /// Give stable IDs to todos from older files that lack them (or that
+    /// were hand-edited into duplicates), continuing after the highest ID.
+    fn assign_missing_ids(&mut self) {
+        let max_id = self.todos.iter().map(|t| t.id).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id + 1);
+
+        let mut seen = HashSet::new();
+        for todo in &mut self.todos {
+            if todo.id == 0 || !seen.insert(todo.id) {
+                todo.id = self.next_id;
+                self.next_id += 1;
+            }
+        }
+    }
 
-        let json = serde_json::to_string_pretty(&todo_store)
-            .context("Failed to serialize todos to JSON")?;
+    fn index_of(&self, id: usize) -> Result<usize> {
+        self.todos
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| TtError::not_found(id).into())
+    }
 
-        // Create parent directory if it doesn't exist
-        if let Some(parent) = self.file_path.parent() {
-            fs::create_dir_all(parent).context("Failed to create directory for todo file")?;
+    /// Write the todos to the todo file. Only needed with autosave turned
+    /// off; otherwise every change is saved as it is made. Without unsaved
+    /// changes nothing is written, so the file keeps its modification time.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
         }
+        self.keep_drafts_last();
+        self.snapshot()?;
+        if let Some(meta) = &mut self.meta {
+            meta.modified_at = Utc::now().to_rfc3339();
+        }
+        write_store(&self.file_path, &self.store_ref(), self.compact)
+            .with_context(|| format!("Failed to save {}", self.file_path.display()))?;
+        self.dirty = false;
+        self.record_write();
+        Ok(())
+    }
 
-        fs::write(&self.file_path, json).context("Failed to write todo file")?;
+    /// Call after changing the todos: saves them, unless autosave is off.
+    fn changed(&mut self) -> Result<()> {
+        self.dirty = true;
+        self.keep_drafts_last();
+        if self.autosave { self.save() } else { Ok(()) }
+    }
 
-        // Set file permissions on Unix-like systems
-        #[cfg(unix)]
+    /// Call after a change that must be saved straight away even with
+    /// autosave off, such as one that also touched the archive.
+    fn save_change(&mut self) -> Result<()> {
+        self.dirty = true;
+        self.save()
+    }
+
+    /// Move drafts after the published todos, keeping each group's order,
+    /// so `list_todos` can lend out the published ones as a slice.
+    fn keep_drafts_last(&mut self) {
+        if self
+            .todos
+            .windows(2)
+            .any(|pair| pair[0].draft && !pair[1].draft)
         {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&self.file_path)
-                .context("Failed to get file metadata")?
-                .permissions();
-            perms.set_mode(0o600);
-            fs::set_permissions(&self.file_path, perms)
-                .context("Failed to set file permissions")?;
+            let (mut todos, drafts): (Vec<Todo>, Vec<Todo>) = std::mem::take(&mut self.todos)
+                .into_iter()
+                .partition(|todo| !todo.draft);
+            todos.extend(drafts);
+            self.todos = todos;
         }
+    }
 
-        Ok(())
+    /// Remember what we wrote so external edits can be detected; losing
+    /// this bookkeeping is harmless, so failures are ignored
+    fn record_write(&self) {
+        let mut state = State::load(&self.state_path());
+        state.last_write = FileStamp::of(&self.file_path);
+        let _ = state.save(&self.state_path());
     }
 
-    pub fn add_todo(&mut self, title: String, priority: u8) -> Result<Todo> {
-        let todo = Todo::new(title, priority)
-            .map_err(|e| anyhow::anyhow!("Failed to create todo with invalid priority: {}", e))?;
-        let todo_clone = todo.clone();
-        self.todos.push(todo);
+    /// Load archived todos. A missing archive is simply empty.
+    pub fn load_archive(&self) -> Result<Vec<Todo>> {
+        let path = self.archive_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
 
-        // Auto-save after modification
-        self.save_to_file()?;
+        let content = fs::read_to_string(&path).context("Failed to read archive file")?;
+        let archive: TodoStore =
+            serde_json::from_str(&content).context("Failed to parse archive file as JSON")?;
+        Ok(archive.todos)
+    }
+
+    fn save_archive(&mut self, todos: Vec<Todo>) -> Result<()> {
+        self.snapshot()?;
+        let archive = TodoStore {
+            todos,
+            next_id: 0,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+        };
+        write_store(&self.archive_path(), &archive, self.compact)
+    }
+
+    /// Move every completed todo into the archive, returning how many moved.
+    /// Nothing is written when there is nothing to archive.
+    pub fn archive_completed(&mut self) -> Result<usize> {
+        let (completed, active): (Vec<Todo>, Vec<Todo>) =
+            self.todos.iter().cloned().partition(|todo| todo.completed);
+        if completed.is_empty() {
+            return Ok(0);
+        }
+
+        let count = completed.len();
+        let mut archive = self.load_archive()?;
+        archive.extend(completed);
 
-        Ok(todo_clone)
+        // Write the archive first: a crash in between leaves a duplicate
+        // rather than losing todos
+        self.save_archive(archive)?;
+        self.todos = active;
+        self.save_change()?;
+        Ok(count)
     }
 
-    pub fn edit_todo(
+    /// Delete every completed todo, or with `older_than` those completed at
+    /// least that long before `now`, saving once. Returns how many went.
+    pub fn remove_completed(
         &mut self,
-        id: usize,
-        title: Option<String>,
-        priority: Option<u8>,
-    ) -> Result<()> {
-        if id >= self.todos.len() {
-            return Err(anyhow::anyhow!("Todo with id {} not found", id));
+        older_than: Option<Duration>,
+        now: DateTime<Utc>,
+    ) -> Result<usize> {
+        let ids: Vec<usize> = self
+            .todos
+            .iter()
+            .filter(|todo| todo.is_clearable(older_than, now))
+            .map(|todo| todo.id)
+            .collect();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        self.delete_todos(&ids).map(|removed| removed.len())
+    }
+
+    /// Move one todo from the archive back into the active list.
+    pub fn unarchive(&mut self, id: usize) -> Result<Todo> {
+        let mut archive = self.load_archive()?;
+        let index = archive
+            .iter()
+            .position(|todo| todo.id == id)
+            .ok_or_else(|| TtError::Missing(format!("Archived todo with id {id} not found")))?;
+        let mut todo = archive.remove(index);
+
+        // IDs are never reused, but a hand-edited file could still collide
+        if self.get_todo(todo.id).is_some() {
+            todo.id = self.next_id;
+            self.next_id += 1;
         }
-        if let Some(new_title) = title {
-            self.todos[id].title = new_title;
+        self.todos.push(todo.clone());
+
+        // Save the active list first: a crash in between leaves a duplicate
+        // rather than losing the todo
+        self.save_change()?;
+        self.save_archive(archive)?;
+        Ok(todo)
+    }
+
+    /// The active list as it is saved, without copying it.
+    fn store_ref(&self) -> StoreRef<'_> {
+        StoreRef {
+            todos: &self.todos,
+            next_id: self.next_id,
+            meta: self.meta.as_ref(),
+            deleted: &self.deleted,
+            templates: &self.templates,
+            trash: &self.trash,
         }
-        if let Some(new_priority) = priority {
-            self.todos[id]
-                .set_priority(new_priority)
-                .map_err(|e| anyhow::anyhow!("Failed to set invalid priority: {}", e))?;
+    }
+
+    /// The active list as it would be saved.
+    pub fn to_store(&self) -> TodoStore {
+        TodoStore {
+            todos: self.todos.clone(),
+            next_id: self.next_id,
+            meta: self.meta.clone(),
+            deleted: self.deleted.clone(),
+            templates: self.templates.clone(),
+            trash: self.trash.clone(),
         }
-        self.save_to_file()
     }
 
-    pub fn validate_priority(priority: u8) -> Result<()> {
-        Todo::validate_priority(priority)
-            .map_err(|e| anyhow::anyhow!("Priority validation failed: {}", e))
+    /// The store's name, description and timestamps, once any are set.
+    pub fn meta(&self) -> Option<&StoreMeta> {
+        self.meta.as_ref()
+    }
+
+    /// Set or clear (with a blank name) the store's display name.
+    pub fn set_store_name(&mut self, name: &str) -> Result<()> {
+        self.meta_mut().name = non_blank(name);
+        self.changed()
+    }
+
+    /// Set or clear (with blank text) the store's description.
+    pub fn set_store_description(&mut self, description: &str) -> Result<()> {
+        self.meta_mut().description = non_blank(description);
+        self.changed()
+    }
+
+    fn meta_mut(&mut self) -> &mut StoreMeta {
+        self.meta
+            .get_or_insert_with(|| StoreMeta::new(&self.todos, Utc::now()))
+    }
+
+    /// Replace the active list wholesale, e.g. from a bundle.
+    pub fn replace_store(&mut self, store: TodoStore) -> Result<()> {
+        self.todos = store.todos;
+        self.next_id = store.next_id;
+        self.meta = store.meta;
+        self.deleted = store.deleted;
+        self.templates = store.templates;
+        self.trash = store.trash;
+        self.assign_missing_ids();
+        self.changed()
+    }
+
+    /// Merge todos into the active list by stable ID, avoiding IDs already
+    /// used in the archive.
+    pub fn merge_store(&mut self, todos: Vec<Todo>) -> Result<MergeCounts> {
+        let archive = self.load_archive()?;
+        let counts = bundle::merge_todos(&mut self.todos, todos, &archive, &mut self.next_id);
+        self.changed()?;
+        Ok(counts)
     }
 
-    pub fn list_todos(&self) -> Vec<Todo> {
-        self.todos.clone()
+    /// Merge another copy of the store, such as a sync conflict file or
+    /// the copy on another machine, into this one. `base` is the copy both
+    /// started from, if known; with it, changes made to different fields of
+    /// a todo on each side are both kept.
+    pub fn merge(&mut self, theirs: TodoStore, base: Option<&TodoStore>) -> Result<MergeReport> {
+        let archive = self.load_archive()?;
+        let mut ours = self.to_store();
+        let report = merge::merge(&mut ours, theirs, base, &archive);
+        self.todos = ours.todos;
+        self.next_id = ours.next_id;
+        self.deleted = ours.deleted;
+        self.changed()?;
+        Ok(report)
     }
 
-    pub fn mark_completed(&mut self, id: usize) -> Result<()> {
-        if id >= self.todos.len() {
-            return Err(anyhow::anyhow!("Todo with id {} not found", id));
+    /// Replace the archive wholesale, e.g. from a bundle.
+    pub fn replace_archive(&mut self, todos: Vec<Todo>) -> Result<()> {
+        self.save_archive(todos)
+    }
+
+    /// Merge todos into the archive by stable ID, avoiding IDs already used
+    /// in the active list.
+    pub fn merge_archive(&mut self, todos: Vec<Todo>) -> Result<MergeCounts> {
+        let mut archive = self.load_archive()?;
+        let counts = bundle::merge_todos(&mut archive, todos, &self.todos, &mut self.next_id);
+        self.save_archive(archive)?;
+        // Renumbered todos used up IDs from the active list's counter
+        self.save_change()?;
+        Ok(counts)
+    }
+
+    pub fn add_todo(
+        &mut self,
+        title: String,
+        priority: Priority,
+        due_date: Option<NaiveDate>,
+        tags: &[String],
+        notes: Option<String>,
+    ) -> Result<&Todo> {
+        self.add(NewTodo {
+            title,
+            priority,
+            due_date,
+            tags: tags.to_vec(),
+            notes,
+            project: None,
+        })
+    }
+
+    /// Add a todo, returning it as saved.
+    pub fn add(&mut self, new: NewTodo) -> Result<&Todo> {
+        let mut todo = Todo::new(self.next_id, new.title, new.priority);
+        todo.due_date = new.due_date;
+        todo.set_notes(new.notes);
+        todo.set_project(new.project);
+        for tag in &new.tags {
+            todo.add_tag(tag);
         }
-        self.todos[id].set_completed(true);
+        // Filling in a new todo does not count as changing it
+        todo.updated_at = None;
+        let id = todo.id;
+        self.todos.push(todo);
+        self.next_id += 1;
 
         // Auto-save after modification
-        self.save_to_file()
+        self.changed()?;
+
+        // Saving moves drafts after it, so it may no longer be last
+        let index = self.index_of(id)?;
+        Ok(&self.todos[index])
     }
 
-    pub fn mark_incomplete(&mut self, id: usize) -> Result<()> {
-        if id >= self.todos.len() {
-            return Err(anyhow::anyhow!("Todo with id {} not found", id));
-        }
-        self.todos[id].set_completed(false);
+    /// Add a todo for each title and priority, in order, saving once at
+    /// the end.
+    pub fn add_todos(&mut self, todos: Vec<(String, Priority)>) -> Result<Vec<Todo>> {
+        let added: Vec<Todo> = todos
+            .into_iter()
+            .zip(self.next_id..)
+            .map(|((title, priority), id)| Todo::new(id, title, priority))
+            .collect();
+        self.next_id += added.len();
+        self.todos.extend(added.iter().cloned());
 
         // Auto-save after modification
-        self.save_to_file()
+        self.changed()?;
+        Ok(added)
+    }
+
+    /// Templates saved with `save_template`, in the order saved.
+    pub fn templates(&self) -> &[Template] {
+        &self.templates
     }
 
-    pub fn toggle_completed(&mut self, id: usize) -> Result<()> {
-        if id >= self.todos.len() {
-            return Err(anyhow::anyhow!("Todo with id {} not found", id));
+    /// Save the todos with these IDs, in that order, as a template called
+    /// `name`. Only their titles, priorities, tags and projects are kept.
+    pub fn save_template(&mut self, name: &str, ids: &[usize]) -> Result<&Template> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(TtError::Invalid("A template needs a name".to_string()).into());
         }
-        self.todos[id].toggle_completed();
+        if self.templates.iter().any(|template| template.name == name) {
+            return Err(TtError::Invalid(format!(
+                "A template named '{name}' already exists; delete it first to replace it"
+            ))
+            .into());
+        }
+        let todos = ids
+            .iter()
+            .map(|&id| {
+                self.get_todo(id)
+                    .map(TemplateTodo::of)
+                    .ok_or_else(|| TtError::not_found(id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if todos.is_empty() {
+            return Err(TtError::Invalid("A template needs at least one todo".to_string()).into());
+        }
+        self.templates.push(Template {
+            name: name.to_string(),
+            todos,
+        });
+        self.changed()?;
+        Ok(&self.templates[self.templates.len() - 1])
+    }
+
+    /// Delete the template called `name`, returning it.
+    pub fn delete_template(&mut self, name: &str) -> Result<Template> {
+        let index = self.template_index(name)?;
+        let template = self.templates.remove(index);
+        self.changed()?;
+        Ok(template)
+    }
+
+    /// Add a fresh, incomplete todo for each todo of the template called
+    /// `name`, with `prefix` in front of every title. Saves once.
+    pub fn apply_template(&mut self, name: &str, prefix: &str) -> Result<Vec<Todo>> {
+        let index = self.template_index(name)?;
+        let added: Vec<Todo> = self.templates[index]
+            .todos
+            .iter()
+            .zip(self.next_id..)
+            .map(|(item, id)| {
+                let mut todo = Todo::new(id, format!("{prefix}{}", item.title), item.priority);
+                todo.tags = item.tags.clone();
+                todo.project = item.project.clone();
+                todo
+            })
+            .collect();
+        self.next_id += added.len();
+        self.todos.extend(added.iter().cloned());
+        self.changed()?;
+        Ok(added)
+    }
+
+    fn template_index(&self, name: &str) -> Result<usize> {
+        let name = name.trim();
+        self.templates
+            .iter()
+            .position(|template| template.name == name)
+            .ok_or_else(|| {
+                TtError::Missing(format!(
+                    "No template named '{name}'; `tt template list` shows the saved ones"
+                ))
+                .into()
+            })
+    }
+
+    /// Add a hidden draft under the next ID, so a script can use the ID
+    /// before it knows the details, dropping drafts that have expired first.
+    pub fn reserve(
+        &mut self,
+        title: Option<String>,
+        priority: Priority,
+        expires_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Result<Todo> {
+        self.drop_expired_drafts(now);
+        let title = title.unwrap_or_else(|| DRAFT_TITLE.to_string());
+        let mut todo = Todo::new(self.next_id, title, priority);
+        todo.draft = true;
+        todo.draft_expires_at = expires_at.map(|at| at.to_rfc3339());
+        self.todos.push(todo.clone());
+        self.next_id += 1;
 
         // Auto-save after modification
-        self.save_to_file()
+        self.changed()?;
+        Ok(todo)
     }
 
-    pub fn delete_todo(&mut self, id: usize) -> Result<()> {
-        if id >= self.todos.len() {
-            return Err(anyhow::anyhow!("Todo with id {} not found", id));
+    /// Turn a draft into a normal pending todo with `title` (or the title it
+    /// was reserved with) and `priority` if given. It keeps its ID.
+    pub fn publish(
+        &mut self,
+        id: usize,
+        title: Option<String>,
+        priority: Option<Priority>,
+        now: DateTime<Utc>,
+    ) -> Result<Todo> {
+        self.drop_expired_drafts(now);
+        let index = self
+            .todos
+            .iter()
+            .position(|todo| todo.id == id && todo.draft)
+            .ok_or_else(|| {
+                TtError::Missing(format!("No draft with id {id} (it may have expired)"))
+            })?;
+        let title = title.unwrap_or_else(|| self.todos[index].title.clone());
+        let title = title.trim();
+        if title.is_empty() || title == DRAFT_TITLE {
+            return Err(TtError::Invalid(format!(
+                "Draft {id} has no title yet; give one with --title"
+            ))
+            .into());
         }
-        self.todos.remove(id);
+
+        let mut todo = self.todos.remove(index);
+        todo.title = title.to_string();
+        todo.priority = priority.unwrap_or(todo.priority);
+        todo.draft = false;
+        todo.draft_expires_at = None;
+        // The creation time stays, as hash-style IDs are derived from it
+        todo.updated_at = None;
+        // Listed where it was reserved: before the todos added since
+        let position = self
+            .published()
+            .position(|other| other.id > id)
+            .unwrap_or(self.list_todos().len());
+        self.todos.insert(position, todo.clone());
 
         // Auto-save after modification
-        self.save_to_file()
+        self.changed()?;
+        Ok(todo)
     }
 
-    pub fn get_todo(&self, id: usize) -> Option<&Todo> {
-        self.todos.get(id)
+    /// Remove drafts that expired by `now`; saved with the change that
+    /// follows.
+    fn drop_expired_drafts(&mut self, now: DateTime<Utc>) {
+        self.todos.retain(|todo| !todo.is_expired_draft(now));
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
 
-    fn create_test_manager() -> TodoManager {
-        let temp_dir = tempdir().unwrap();
-        let file_path = temp_dir.path().join(".tt.json");
+    /// Add todos from elsewhere under fresh IDs. Nothing is written when
+    /// every todo turns out to be a duplicate.
+    pub fn import_todos(
+        &mut self,
+        todos: Vec<Todo>,
+        dedupe: DedupeStrategy,
+    ) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        for mut todo in todos {
+            let title = todo.title.to_lowercase();
+            let duplicate = dedupe == DedupeStrategy::SkipIncompleteTitleMatches
+                && self
+                    .published()
+                    .any(|existing| !existing.completed && existing.title.to_lowercase() == title);
+            if duplicate {
+                summary.duplicates += 1;
+                continue;
+            }
+            todo.id = self.next_id;
+            self.next_id += 1;
+            self.todos.push(todo);
+            summary.imported += 1;
+        }
 
-        TodoManager {
-            todos: Vec::new(),
-            file_path,
+        if summary.imported > 0 {
+            self.changed()?;
         }
+        Ok(summary)
     }
 
-    #[test]
-    fn test_new_todo_manager() {
-        let manager = create_test_manager();
-        assert_eq!(manager.list_todos().len(), 0);
-        assert!(manager.list_todos().is_empty());
-    }
+    /// Apply a grooming plan: replace the edited todos, add the new ones
+    /// under fresh IDs, and save once. Nothing changes if an edited todo
+    /// has gone.
+    pub fn apply_groom(&mut self, plan: groom::Plan) -> Result<()> {
+        let indices = plan
+            .edits
+            .iter()
+            .map(|edit| self.index_of(edit.todo.id))
+            .collect::<Result<Vec<_>>>()?;
+        for (index, edit) in indices.into_iter().zip(plan.edits) {
+            self.todos[index] = edit.todo;
+        }
+        for mut todo in plan.added {
+            todo.id = self.next_id;
+            self.next_id += 1;
+            self.todos.push(todo);
+        }
 
-    #[test]
-    fn test_add_todo() {
-        let mut manager = create_test_manager();
-        let todo = manager.add_todo("Test todo".to_string(), 1).unwrap();
-        assert_eq!(todo.title, "Test todo");
-        assert_eq!(todo.completed, false);
-        assert_eq!(manager.list_todos().len(), 1);
+        // Auto-save after modification
+        self.changed()
     }
 
-    #[test]
-    fn test_list_todos() {
-        let mut manager = create_test_manager();
-        manager.add_todo("Todo 1".to_string(), 1).unwrap();
-        manager.add_todo("Todo 2".to_string(), 1).unwrap();
-        let todos = manager.list_todos();
-        assert_eq!(todos.len(), 2);
-        assert_eq!(todos[0].title, "Todo 1");
-        assert_eq!(todos[1].title, "Todo 2");
+    pub fn edit_todo(&mut self, id: usize, edit: TodoEdit) -> Result<()> {
+        let index = self.index_of(id)?;
+        let todo = &mut self.todos[index];
+        if let Some(new_title) = edit.title {
+            todo.set_title(new_title);
+        }
+        if let Some(new_priority) = edit.priority {
+            todo.set_priority(new_priority);
+        }
+        if let Some(new_due_date) = edit.due_date {
+            todo.set_due_date(new_due_date);
+        }
+        if let Some(new_notes) = edit.notes {
+            todo.set_notes(new_notes);
+        }
+        for tag in &edit.add_tags {
+            todo.add_tag(tag);
+        }
+        for tag in &edit.remove_tags {
+            todo.remove_tag(tag);
+        }
+        if let Some(new_project) = edit.project {
+            todo.set_project(new_project);
+        }
+        self.changed()
     }
 
-    #[test]
-    fn test_mark_completed() {
-        let mut manager = create_test_manager();
-        manager.add_todo("Test todo".to_string(), 1).unwrap();
+    /// Every project with how many todos are in it, drafts left out,
+    /// ordered by name ignoring case.
+    pub fn projects(&self) -> Vec<(String, Counts)> {
+        let mut projects: Vec<String> = self
+            .published()
+            .filter_map(|todo| todo.project.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        projects.sort_by_key(|name| (name.to_lowercase(), name.clone()));
+        projects
+            .into_iter()
+            .map(|name| {
+                let counts = Counts::from_todos(
+                    self.published()
+                        .filter(|todo| todo.project.as_deref() == Some(&name)),
+                );
+                (name, counts)
+            })
+            .collect()
+    }
 
-        // Mark as completed
-        assert!(manager.mark_completed(0).is_ok());
-        assert!(manager.get_todo(0).unwrap().completed);
+    /// Move every todo in project `from`, in both the active list and the
+    /// archive, to project `to`. Returns how many todos changed.
+    pub fn rename_project(&mut self, from: &str, to: &str) -> Result<usize> {
+        let from = Todo::normalize_project(from)
+            .ok_or_else(|| TtError::Invalid("The project to rename cannot be empty".to_string()))?;
+        let to = Todo::normalize_project(to)
+            .ok_or_else(|| TtError::Invalid("The new project name cannot be empty".to_string()))?;
 
-        // Try to mark non-existent todo
-        assert!(manager.mark_completed(1).is_err());
+        let mut archive = self.load_archive()?;
+        let archived = rename_project_in(&mut archive, &from, &to);
+        if archived > 0 {
+            self.save_archive(archive)?;
+        }
+        let active = rename_project_in(&mut self.todos, &from, &to);
+        if active > 0 {
+            self.save_change()?;
+        }
+        Ok(archived + active)
     }
 
-    #[test]
-    fn test_mark_incomplete() {
-        let mut manager = create_test_manager();
-        manager.add_todo("Test todo".to_string(), 1).unwrap();
+    /// Rename a tag and every tag nested beneath it, in both the active list
+    /// and the archive. Returns how many todos changed.
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> Result<usize> {
+        let from = Todo::normalize_tag(from)
+            .ok_or_else(|| TtError::Invalid("The tag to rename cannot be empty".to_string()))?;
+        let to = Todo::normalize_tag(to)
+            .ok_or_else(|| TtError::Invalid("The new tag cannot be empty".to_string()))?;
 
-        // Mark as completed first
-        manager.mark_completed(0).unwrap();
-        assert!(manager.get_todo(0).unwrap().completed);
+        let mut archive = self.load_archive()?;
+        let archived = rename_tag_in(&mut archive, &from, &to);
+        if archived > 0 {
+            self.save_archive(archive)?;
+        }
+        let active = rename_tag_in(&mut self.todos, &from, &to);
+        if active > 0 {
+            self.save_change()?;
+        }
+        Ok(archived + active)
+    }
 
-        // Mark as incomplete
-        assert!(manager.mark_incomplete(0).is_ok());
-        assert!(!manager.get_todo(0).unwrap().completed);
+    /// Repair priorities and timestamps with the `normalize` rules. Unless
+    /// `dry_run`, the todo file is copied to a backup before saving; with
+    /// nothing to change, nothing is written at all.
+    pub fn normalize(
+        &mut self,
+        settings: &normalize::Settings,
+        dry_run: bool,
+    ) -> Result<normalize::Report> {
+        let mut todos = self.todos.clone();
+        let rules = normalize::normalize(&mut todos, settings, Utc::now());
+        let mut report = normalize::Report {
+            rules,
+            missing_created_at: normalize::missing_created_at(&todos),
+            backup: None,
+        };
+        if dry_run || !report.changed_anything() {
+            return Ok(report);
+        }
 
-        // Try to mark non-existent todo
-        assert!(manager.mark_incomplete(1).is_err());
+        report.backup = Some(self.backup()?);
+        self.todos = todos;
+        self.save_change()?;
+        Ok(report)
     }
 
-    #[test]
-    fn test_toggle_completed() {
-        let mut manager = create_test_manager();
-        manager.add_todo("Test todo".to_string(), 1).unwrap();
+    /// Copy the todo file to `<name>.backup-<time>.json` next to it, never
+    /// overwriting an earlier backup.
+    fn backup(&self) -> Result<PathBuf> {
+        let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let backup = (1..)
+            .map(|n| match n {
+                1 => format!("backup-{stamp}.json"),
+                n => format!("backup-{stamp}-{n}.json"),
+            })
+            .map(|extension| self.file_path.with_extension(extension))
+            .find(|path| !path.exists())
+            .unwrap_or_default();
+        fs::copy(&self.file_path, &backup).with_context(|| {
+            format!(
+                "Failed to back up {} to {}; nothing was changed",
+                self.file_path.display(),
+                backup.display()
+            )
+        })?;
+        Ok(backup)
+    }
 
-        // Initially false
-        assert!(!manager.get_todo(0).unwrap().completed);
+    /// The todos in stored order, leaving out drafts reserved with
+    /// `tt reserve`.
+    pub fn list_todos(&self) -> &[Todo] {
+        // Drafts are always kept last
+        &self.todos[..self.todos.partition_point(|todo| !todo.draft)]
+    }
 
-        // Toggle to true
-        assert!(manager.toggle_completed(0).is_ok());
-        assert!(manager.get_todo(0).unwrap().completed);
+    /// How many todos there are, leaving out drafts, and of them only
+    /// those with `priority` if given.
+    pub fn counts(&self, priority: Option<Priority>) -> Counts {
+        Counts::from_todos(
+            self.published()
+                .filter(|todo| priority.is_none_or(|p| todo.priority == p)),
+        )
+    }
 
-        // Toggle back to false
-        assert!(manager.toggle_completed(0).is_ok());
-        assert!(!manager.get_todo(0).unwrap().completed);
+    /// Counts of the todos a listing with `options` is drawn from: those
+    /// with its tags, priority and project, however they are completed or
+    /// snoozed, so the footer of `tt list --tag x` describes tag x.
+    pub fn counts_matching(&self, options: &QueryOptions) -> Counts {
+        let scope = QueryOptions {
+            tags: options.tags.clone(),
+            priority: options.priority,
+            project: options.project.clone(),
+            ..QueryOptions::default()
+        };
+        Counts::from_todos(&scope.apply(self.list_todos()))
+    }
 
-        // Try to toggle non-existent todo
-        assert!(manager.toggle_completed(1).is_err());
+    fn published(&self) -> impl Iterator<Item = &Todo> {
+        self.list_todos().iter()
     }
 
-    #[test]
-    fn test_delete_todo() {
-        let mut manager = create_test_manager();
-        manager.add_todo("Todo 1".to_string(), 1).unwrap();
-        manager.add_todo("Todo 2".to_string(), 1).unwrap();
-        assert_eq!(manager.list_todos().len(), 2);
-        // Delete first todo
-        assert!(manager.delete_todo(0).is_ok());
-        assert_eq!(manager.list_todos().len(), 1);
-        assert_eq!(manager.get_todo(0).unwrap().title, "Todo 2");
-        // Try to delete non-existent todo
-        assert!(manager.delete_todo(1).is_err());
+    /// Drafts that have not expired at `now`.
+    pub fn drafts(&self, now: DateTime<Utc>) -> Vec<Todo> {
+        self.todos
+            .iter()
+            .filter(|todo| todo.draft && !todo.is_expired_draft(now))
+            .cloned()
+            .collect()
     }
 
-    #[test]
+    /// Todos matching the filters in `options`, in the requested order.
+    pub fn query(&self, options: &QueryOptions) -> Vec<Todo> {
+        options.apply(self.list_todos())
+    }
+
+    /// Todos whose title contains `query`, ignoring case. With `use_regex`
+    /// the query is a case-insensitive regular expression instead.
+    pub fn search_todos(&self, query: &str, use_regex: bool) -> Result<Vec<Todo>> {
+        let matches: Vec<Todo> = if use_regex {
+            let regex = RegexBuilder::new(query)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| {
+                    TtError::Invalid(format!("Invalid regular expression '{query}': {e}"))
+                })?;
+            self.published()
+                .filter(|todo| regex.is_match(&todo.title))
+                .cloned()
+                .collect()
+        } else {
+            let query = query.to_lowercase();
+            self.published()
+                .filter(|todo| todo.title.to_lowercase().contains(&query))
+                .cloned()
+                .collect()
+        };
+        Ok(matches)
+    }
+
+    /// Complete every todo in `ids` and save once. Nothing changes if any
+    /// of them does not exist.
+    pub fn mark_completed(&mut self, ids: &[usize]) -> Result<()> {
+        self.update_many(ids, |todo| todo.set_completed(true))
+    }
+
+    pub fn mark_incomplete(&mut self, ids: &[usize]) -> Result<()> {
+        self.update_many(ids, |todo| todo.set_completed(false))
+    }
+
+    pub fn toggle_completed(&mut self, ids: &[usize]) -> Result<()> {
+        self.update_many(ids, Todo::toggle_completed)
+    }
+
+    /// Delete every todo in `ids` for good and save once, returning them.
+    /// Nothing is deleted if any of them does not exist.
+    pub fn delete_todos(&mut self, ids: &[usize]) -> Result<Vec<Todo>> {
+        let deleted = self.remove_todos(ids, Utc::now())?;
+
+        // Auto-save after modification
+        self.changed()?;
+        Ok(deleted)
+    }
+
+    /// Move every todo in `ids` to the trash and save once, returning them.
+    /// Nothing is moved if any of them does not exist.
+    pub fn trash_todos(&mut self, ids: &[usize]) -> Result<Vec<Todo>> {
+        let now = Utc::now();
+        let trashed = self.remove_todos(ids, now)?;
+        self.trash.extend(trashed.iter().map(|todo| TrashedTodo {
+            todo: todo.clone(),
+            deleted_at: now.to_rfc3339(),
+        }));
+        self.changed()?;
+        Ok(trashed)
+    }
+
+    /// Todos in the trash, oldest deletion first.
+    pub fn trash(&self) -> &[TrashedTodo] {
+        &self.trash
+    }
+
+    /// Put a todo from the trash back into the active list exactly as it
+    /// was deleted, under its old ID unless something else has taken it.
+    pub fn restore_from_trash(&mut self, id: usize) -> Result<Todo> {
+        let index = self
+            .trash
+            .iter()
+            .position(|trashed| trashed.todo.id == id)
+            .ok_or_else(|| TtError::Missing(format!("No todo with id {id} in the trash")))?;
+        let mut todo = self.trash.remove(index).todo;
+        // Otherwise merging another copy would delete it again
+        self.deleted.retain(|deletion| !deletion.is_of(&todo));
+        if self.get_todo(todo.id).is_some() {
+            todo.id = self.next_id;
+            self.next_id += 1;
+        }
+        self.todos.push(todo.clone());
+        self.changed()?;
+        Ok(todo)
+    }
+
+    /// Delete todos in the trash for good: all of them, or with
+    /// `older_than` those deleted at least that long before `now`. Returns
+    /// how many went; nothing is written when none did.
+    pub fn purge_trash(
+        &mut self,
+        older_than: Option<Duration>,
+        now: DateTime<Utc>,
+    ) -> Result<usize> {
+        let before = self.trash.len();
+        self.trash
+            .retain(|trashed| !trashed.is_purgeable(older_than, now));
+        let purged = before - self.trash.len();
+        if purged > 0 {
+            self.changed()?;
+        }
+        Ok(purged)
+    }
+
+    /// Take the todos in `ids` out of the active list, remembering that they
+    /// were deleted so merging an older copy of the store does not bring
+    /// them back.
+    fn remove_todos(&mut self, ids: &[usize], now: DateTime<Utc>) -> Result<Vec<Todo>> {
+        // Looked up by stable ID up front, so removing one todo cannot
+        // shift another out from under us
+        for &id in ids {
+            self.index_of(id)?;
+        }
+        let (removed, kept): (Vec<Todo>, Vec<Todo>) = std::mem::take(&mut self.todos)
+            .into_iter()
+            .partition(|todo| ids.contains(&todo.id));
+        self.todos = kept;
+        merge::prune_deletions(&mut self.deleted, now);
+        self.deleted
+            .extend(removed.iter().map(|todo| Deletion::of(todo, now)));
+        Ok(removed)
+    }
+
+    fn update_many(&mut self, ids: &[usize], change: impl Fn(&mut Todo)) -> Result<()> {
+        let indices = ids
+            .iter()
+            .map(|&id| self.index_of(id))
+            .collect::<Result<Vec<_>>>()?;
+        let mut changed_any = false;
+        for index in indices {
+            // Setters stamp the todo only when they change something
+            let stamp = self.todos[index].updated_at.clone();
+            change(&mut self.todos[index]);
+            changed_any |= self.todos[index].updated_at != stamp;
+        }
+        if !changed_any {
+            return Ok(());
+        }
+
+        // Auto-save after modification
+        self.changed()
+    }
+
+    /// Move a todo to `position` (1 is the top) in the stored order that
+    /// unsorted listings show, and save. Positions past the end move it to
+    /// the bottom. Returns the positions it moved from and to; moving a todo
+    /// where it already is saves nothing. Drafts have no position, so
+    /// they are not found.
+    pub fn move_todo(&mut self, id: usize, position: usize) -> Result<(usize, usize)> {
+        if position == 0 {
+            bail!("Positions start at 1");
+        }
+        let listed = self.list_todos();
+        let from = listed
+            .iter()
+            .position(|t| t.id == id)
+            .ok_or_else(|| TtError::not_found(id))?;
+        let to = (position - 1).min(listed.len() - 1);
+        if from != to {
+            let todo = self.todos.remove(from);
+            self.todos.insert(to, todo);
+            self.changed()?;
+        }
+        Ok((from + 1, to + 1))
+    }
+
+    /// Give each todo its new due date, and save once. Nothing changes if
+    /// any of them does not exist.
+    pub fn set_due_dates(&mut self, updates: &[Update]) -> Result<()> {
+        let indices = updates
+            .iter()
+            .map(|update| self.index_of(update.id))
+            .collect::<Result<Vec<_>>>()?;
+        for (index, update) in indices.into_iter().zip(updates) {
+            self.todos[index].set_due_date(update.to);
+        }
+
+        // Auto-save after modification
+        self.changed()
+    }
+
+    /// Hide a todo from listings until `until`.
+    pub fn snooze_todo(&mut self, id: usize, until: DateTime<Utc>) -> Result<()> {
+        self.change_todo(id, |todo| {
+            todo.snooze(until);
+            Ok(())
+        })
+    }
+
+    /// End a todo's snooze early. Returns whether it was snoozed; nothing is
+    /// saved if it wasn't.
+    pub fn unsnooze_todo(&mut self, id: usize) -> Result<bool> {
+        let index = self.index_of(id)?;
+        if !self.todos[index].unsnooze() {
+            return Ok(false);
+        }
+
+        // Auto-save after modification
+        self.changed()?;
+        Ok(true)
+    }
+
+    /// Pin or unpin a todo. Returns whether that changed anything; nothing
+    /// is saved if it didn't.
+    pub fn set_pinned(&mut self, id: usize, pinned: bool) -> Result<bool> {
+        let index = self.index_of(id)?;
+        if !self.todos[index].set_pinned(pinned) {
+            return Ok(false);
+        }
+
+        // Auto-save after modification
+        self.changed()?;
+        Ok(true)
+    }
+
+    /// Add an item to a todo's checklist, returning its number.
+    pub fn add_checklist_item(&mut self, id: usize, text: &str) -> Result<usize> {
+        self.change_todo(id, |todo| todo.add_checklist_item(text))
+    }
+
+    /// Tick or untick a checklist item, returning it as it is now.
+    pub fn toggle_checklist_item(&mut self, id: usize, number: usize) -> Result<ChecklistItem> {
+        self.change_todo(id, |todo| todo.toggle_checklist_item(number).cloned())
+    }
+
+    pub fn remove_checklist_item(&mut self, id: usize, number: usize) -> Result<ChecklistItem> {
+        self.change_todo(id, |todo| todo.remove_checklist_item(number))
+    }
+
+    /// Apply one change to a todo and save, unless the change fails.
+    fn change_todo<T>(
+        &mut self,
+        id: usize,
+        change: impl FnOnce(&mut Todo) -> Result<T, TtError>,
+    ) -> Result<T> {
+        let index = self.index_of(id)?;
+        let result = change(&mut self.todos[index])?;
+
+        // Auto-save after modification
+        self.changed()?;
+        Ok(result)
+    }
+
+    /// Todos whose title contains `query`, ignoring case, with their IDs.
+    pub fn find_by_title(&self, query: &str) -> Vec<(usize, &Todo)> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        self.published()
+            .filter(|todo| todo.title.to_lowercase().contains(&query))
+            .map(|todo| (todo.id, todo))
+            .collect()
+    }
+
+    pub fn get_todo(&self, id: usize) -> Option<&Todo> {
+        self.todos.iter().find(|t| t.id == id)
+    }
+}
+
+/// Flags win over environment variables, and a file over a list at the same
+/// level. Empty variables count as unset.
+fn choose_store<'a>(
+    file: Option<&'a Path>,
+    list: Option<&'a str>,
+    env_file: Option<&'a Path>,
+    env_list: Option<&'a str>,
+) -> StoreChoice<'a> {
+    let env_file = env_file.filter(|path| !path.as_os_str().is_empty());
+    let env_list = env_list.filter(|name| !name.is_empty());
+    match (file, list, env_file, env_list) {
+        (Some(path), _, _, _) => StoreChoice::File(path),
+        (None, Some(name), _, _) => StoreChoice::List(name),
+        (None, None, Some(path), _) => StoreChoice::File(path),
+        (None, None, None, Some(name)) => StoreChoice::List(name),
+        (None, None, None, None) => StoreChoice::List(lists::DEFAULT),
+    }
+}
+
+/// A setting's value as (given by a flag, given by a variable).
+fn split_by_source<T>(value: Option<T>, source: Source) -> (Option<T>, Option<T>) {
+    match source {
+        Source::Flag => (value, None),
+        _ => (None, value),
+    }
+}
+
+fn non_blank(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Rename a tag across `todos`, returning how many changed.
+/// Priorities saved out of range are read as low. Only then, read the file
+/// again for the numbers so `tt normalize` can map or clamp them.
+fn note_saved_priorities(content: &str, todos: &mut [Todo]) {
+    #[derive(Deserialize)]
+    struct Saved {
+        todos: Vec<SavedPriority>,
+    }
+    #[derive(Deserialize)]
+    struct SavedPriority {
+        #[serde(default)]
+        priority: Option<i64>,
+    }
+
+    if !todos.iter().any(|todo| todo.priority == Priority::Low) {
+        return;
+    }
+    let Ok(saved) = serde_json::from_str::<Saved>(content) else {
+        return;
+    };
+    for (todo, saved) in todos.iter_mut().zip(saved.todos) {
+        if let Some(number) = saved.priority.filter(|&n| !(1..=4).contains(&n)) {
+            todo.saved_priority = Some(number.clamp(0, u8::MAX.into()) as u8);
+        }
+    }
+}
+
+fn rename_tag_in(todos: &mut [Todo], from: &str, to: &str) -> usize {
+    let mut changed = 0;
+    for todo in todos {
+        if todo.rename_tag(from, to) {
+            changed += 1;
+        }
+    }
+    changed
+}
+
+fn rename_project_in(todos: &mut [Todo], from: &str, to: &str) -> usize {
+    let mut changed = 0;
+    for todo in todos {
+        if todo.project.as_deref() == Some(from) {
+            todo.set_project(Some(to.to_string()));
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Serialize a store to `path`, writing through symlinks and restricting
+/// permissions to the owner on Unix-like systems. Fields always come in
+/// the same order and the file ends in a newline, so the same todos always
+/// give the same file and a change shows in a diff as the lines of the
+/// todos it touched.
+fn write_store(path: &Path, store: &impl Serialize, compact: bool) -> Result<()> {
+    let mut json = if compact {
+        serde_json::to_string(store)
+    } else {
+        serde_json::to_string_pretty(store)
+    }
+    .context("Failed to serialize todos to JSON")?;
+    json.push('\n');
+    write_file(path, &json)
+}
+
+/// Write raw contents to `path`, as `write_store` does. The data goes to
+/// an unpredictably named temporary file in the same directory (so the
+/// rename is atomic) which is synced and then renamed over the target, so
+/// a crash mid-write never leaves a truncated file behind. On failure the
+/// temporary file is removed.
+pub fn write_file(path: &Path, contents: &str) -> Result<()> {
+    // Write through symlinks to the real file so the link itself survives
+    let target = resolve_symlinks(path)?;
+    let dir = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    // Create parent directory if it doesn't exist
+    fs::create_dir_all(dir).context("Failed to create directory for todo file")?;
+
+    let prefix = temp_prefix(&target);
+    let mut temp = tempfile::Builder::new()
+        .prefix(&prefix)
+        .suffix(TEMP_SUFFIX)
+        .tempfile_in(dir)
+        .context("Failed to create temporary todo file")?;
+
+    // Set file permissions on Unix-like systems before any data lands
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        temp.as_file()
+            .set_permissions(fs::Permissions::from_mode(0o600))
+            .context("Failed to set file permissions")?;
+    }
+
+    temp.write_all(contents.as_bytes())
+        .and_then(|_| temp.as_file().sync_all())
+        .context("Failed to write todo file")?;
+    temp.persist(&target)
+        .map_err(|e| e.error)
+        .context("Failed to replace todo file")?;
+
+    remove_stale_temp_files(dir, &prefix);
+    Ok(())
+}
+
+const TEMP_SUFFIX: &str = ".tmp";
+
+/// Temporary files older than this are left over from crashed runs.
+const STALE_TEMP_AGE: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Prefix of temporary files for `path`, e.g. `.tt.json.` for `.tt.json`.
+fn temp_prefix(path: &Path) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    format!("{name}.")
+}
+
+/// Best-effort removal of temporary files that crashed runs left behind.
+/// Recent ones are left alone in case another process is mid-write.
+fn remove_stale_temp_files(dir: &Path, prefix: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !name.starts_with(prefix) || !name.ends_with(TEMP_SUFFIX) {
+            continue;
+        }
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= STALE_TEMP_AGE);
+        if stale && fs::remove_file(entry.path()).is_ok() {
+            debug(&format!(
+                "removed stale temporary file {}",
+                entry.path().display()
+            ));
+        }
+    }
+}
+
+/// Print a diagnostic when `TT_DEBUG` is set.
+fn debug(message: &str) {
+    if std::env::var_os("TT_DEBUG").is_some() {
+        eprintln!("[tt debug] {message}");
+    }
+}
+
+/// Maximum symlink hops followed before giving up on a loop.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Follow `path` through any symlinks to the file that should actually be
+/// written. A path that does not exist yet is returned as-is, but a symlink
+/// whose target is missing is an error rather than silently creating it.
+fn resolve_symlinks(path: &Path) -> Result<PathBuf> {
+    let mut current = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_HOPS {
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&current)
+                    .with_context(|| format!("Failed to read symlink {}", current.display()))?;
+                current = match current.parent() {
+                    Some(parent) if link.is_relative() => parent.join(link),
+                    _ => link,
+                };
+            }
+            Ok(_) => return Ok(current),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && current != path => {
+                return Err(anyhow::anyhow!(
+                    "Todo file {} is a symlink to {}, which does not exist",
+                    path.display(),
+                    current.display()
+                ));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(current),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to inspect {}", current.display()));
+            }
+        }
+    }
+    Err(anyhow::anyhow!(
+        "Too many levels of symlinks resolving {}",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_test_manager() -> TodoManager {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+
+        TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path,
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        }
+    }
+
+    #[test]
+    fn test_new_todo_manager() {
+        let manager = create_test_manager();
+        assert_eq!(manager.list_todos().len(), 0);
+        assert!(manager.list_todos().is_empty());
+    }
+
+    #[test]
+    fn test_add_todo() {
+        let mut manager = create_test_manager();
+        let todo = manager
+            .add_todo("Test todo".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        assert_eq!(todo.title, "Test todo");
+        assert_eq!(todo.completed, false);
+        assert_eq!(manager.list_todos().len(), 1);
+    }
+
+    #[test]
+    fn test_add_and_edit_due_date() {
+        let mut manager = create_test_manager();
+        let due = NaiveDate::from_ymd_opt(2025, 4, 15);
+        let todo = manager
+            .add_todo("File taxes".to_string(), Priority::Critical, due, &[], None)
+            .unwrap();
+        assert_eq!(todo.due_date, due);
+        let id = todo.id;
+
+        // Leaving the due date out of an edit keeps it
+        manager
+            .edit_todo(
+                id,
+                TodoEdit {
+                    title: Some("File taxes!".to_string()),
+                    ..TodoEdit::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manager.get_todo(id).unwrap().due_date, due);
+
+        let new_due = NaiveDate::from_ymd_opt(2025, 4, 20);
+        manager
+            .edit_todo(
+                id,
+                TodoEdit {
+                    due_date: Some(new_due),
+                    ..TodoEdit::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manager.get_todo(id).unwrap().due_date, new_due);
+
+        manager
+            .edit_todo(
+                id,
+                TodoEdit {
+                    due_date: Some(None),
+                    ..TodoEdit::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manager.get_todo(id).unwrap().due_date, None);
+    }
+
+    #[test]
+    fn test_notes_on_add_and_edit() {
+        let mut manager = create_test_manager();
+        let todo = manager
+            .add_todo(
+                "Plan trip".to_string(),
+                Priority::High,
+                None,
+                &[],
+                Some("Book flights\nFind a hotel".to_string()),
+            )
+            .unwrap();
+        assert_eq!(todo.notes.as_deref(), Some("Book flights\nFind a hotel"));
+        let id = todo.id;
+
+        // Editing other fields keeps the notes
+        manager
+            .edit_todo(
+                id,
+                TodoEdit {
+                    priority: Some(Priority::Critical),
+                    ..TodoEdit::default()
+                },
+            )
+            .unwrap();
+        assert!(manager.get_todo(id).unwrap().notes.is_some());
+
+        // Blank notes clear them, just like an explicit clear
+        manager
+            .edit_todo(
+                id,
+                TodoEdit {
+                    notes: Some(Some("  ".to_string())),
+                    ..TodoEdit::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manager.get_todo(id).unwrap().notes, None);
+    }
+
+    #[test]
+    fn test_tags_on_add_and_edit() {
+        let mut manager = create_test_manager();
+        let tags = vec!["Work".to_string(), "code".to_string(), "work".to_string()];
+        let todo = manager
+            .add_todo("Review PR".to_string(), Priority::High, None, &tags, None)
+            .unwrap();
+        assert_eq!(todo.tags, vec!["work", "code"]);
+        let id = todo.id;
+
+        manager
+            .edit_todo(
+                id,
+                TodoEdit {
+                    add_tags: vec!["Urgent".to_string()],
+                    remove_tags: vec!["code".to_string()],
+                    ..TodoEdit::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(manager.get_todo(id).unwrap().tags, vec!["work", "urgent"]);
+    }
+
+    #[test]
+    fn test_query_by_tags() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo(
+                "Review PR".to_string(),
+                Priority::High,
+                None,
+                &["work".to_string(), "code".to_string()],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo(
+                "Prepare demo".to_string(),
+                Priority::High,
+                None,
+                &["work".to_string()],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo(
+                "Buy milk".to_string(),
+                Priority::Low,
+                None,
+                &["home".to_string()],
+                None,
+            )
+            .unwrap();
+
+        let by_tags = |tags: &[&str]| {
+            manager.query(&QueryOptions {
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                ..QueryOptions::default()
+            })
+        };
+        let work = by_tags(&["WORK"]);
+        assert_eq!(work.len(), 2);
+
+        let work_code = by_tags(&["work", "code"]);
+        assert_eq!(work_code.len(), 1);
+        assert_eq!(work_code[0].title, "Review PR");
+
+        assert!(by_tags(&["garden"]).is_empty());
+        assert_eq!(by_tags(&[]).len(), 3);
+    }
+
+    #[test]
+    fn test_file_flag_beats_env_beats_default() {
+        let flag = Path::new("work.json");
+        let env = Path::new("/data/personal.json");
+        let file = StoreChoice::File;
+        let default = StoreChoice::List(lists::DEFAULT);
+        assert_eq!(choose_store(Some(flag), None, Some(env), None), file(flag));
+        assert_eq!(choose_store(None, None, Some(env), None), file(env));
+        assert_eq!(choose_store(None, None, Some(Path::new("")), None), default);
+        assert_eq!(choose_store(None, None, None, None), default);
+    }
+
+    #[test]
+    fn test_list_precedence() {
+        let env = Path::new("/data/personal.json");
+        let list = StoreChoice::List;
+        assert_eq!(
+            choose_store(None, Some("work"), Some(env), Some("home")),
+            list("work")
+        );
+        assert_eq!(
+            choose_store(None, None, Some(env), Some("home")),
+            StoreChoice::File(env)
+        );
+        assert_eq!(choose_store(None, None, None, Some("home")), list("home"));
+        assert_eq!(
+            choose_store(None, None, None, Some("")),
+            list(lists::DEFAULT)
+        );
+    }
+
+    #[test]
+    fn test_save_creates_parent_and_errors_name_the_file() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("lists/work.json");
+        let mut manager = TodoManager::open_empty(path.clone(), false).unwrap();
+        manager
+            .add_todo("A".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        assert!(path.exists());
+
+        // A file where the parent directory should be
+        fs::write(temp_dir.path().join("blocker"), "").unwrap();
+        let blocked = temp_dir.path().join("blocker/todos.json");
+        let mut manager = TodoManager::open_empty(blocked.clone(), false).unwrap();
+        let error = manager
+            .add_todo("A".into(), Priority::Low, None, &[], None)
+            .unwrap_err();
+        assert!(format!("{error:#}").contains(&blocked.display().to_string()));
+    }
+
+    #[test]
+    fn test_store_meta_is_only_written_once_set() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(".tt.json");
+        let mut manager = manager_at(path.clone());
+        manager
+            .add_todo("A".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("meta"));
+
+        manager.set_store_name(" Work backlog ").unwrap();
+        manager.set_store_description("Day job").unwrap();
+        let meta = manager.meta().unwrap().clone();
+        assert_eq!(meta.name.as_deref(), Some("Work backlog"));
+        assert_eq!(meta.description.as_deref(), Some("Day job"));
+
+        manager
+            .add_todo("B".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        let mut reloaded = manager_at(path);
+        reloaded.load_from_file().unwrap();
+        let reloaded_meta = reloaded.meta().unwrap();
+        assert_eq!(reloaded_meta.name, meta.name);
+        assert_eq!(reloaded_meta.created_at, meta.created_at);
+        assert!(reloaded_meta.modified_at >= meta.modified_at);
+
+        manager.set_store_name("").unwrap();
+        assert!(manager.meta().unwrap().name.is_none());
+    }
+
+    #[test]
+    fn test_merging_an_older_copy_respects_deletes_and_edits() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        manager
+            .add_todo("Gone".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Edited".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        let older_copy = manager.to_store();
+
+        manager.delete_todos(&[1]).unwrap();
+        let edit = TodoEdit {
+            title: Some("Edited here".into()),
+            ..TodoEdit::default()
+        };
+        manager.edit_todo(2, edit).unwrap();
+        assert_eq!(manager.to_store().deleted.len(), 1);
+
+        let report = manager.merge(older_copy, None).unwrap();
+        assert_eq!(report, MergeReport::default());
+        let titles: Vec<String> = manager
+            .list_todos()
+            .iter()
+            .map(|t| t.title.clone())
+            .collect();
+        assert_eq!(titles, vec!["Edited here"]);
+    }
+
+    #[test]
+    fn test_rename_tag_in_store_and_archive() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        let tags = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        manager
+            .add_todo(
+                "Invoice".into(),
+                Priority::High,
+                None,
+                &tags(&["work/clientA/billing"]),
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo(
+                "Call".into(),
+                Priority::High,
+                None,
+                &tags(&["work/clientA"]),
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo(
+                "Lookalike".into(),
+                Priority::High,
+                None,
+                &tags(&["work/clientAB"]),
+                None,
+            )
+            .unwrap();
+        manager.mark_completed(&[2]).unwrap();
+        manager.archive_completed().unwrap();
+
+        assert_eq!(manager.rename_tag("work/clientA", "work/acme").unwrap(), 2);
+        assert_eq!(manager.get_todo(1).unwrap().tags, vec!["work/acme/billing"]);
+        assert_eq!(manager.get_todo(3).unwrap().tags, vec!["work/clientab"]);
+        assert_eq!(manager.load_archive().unwrap()[0].tags, vec!["work/acme"]);
+
+        let mut reloaded = manager_at(temp_dir.path().join(".tt.json"));
+        reloaded.load_from_file().unwrap();
+        assert_eq!(
+            reloaded.get_todo(1).unwrap().tags,
+            vec!["work/acme/billing"]
+        );
+        assert_eq!(manager.rename_tag("work/clientA", "x").unwrap(), 0);
+        assert!(manager.rename_tag("work", " / ").is_err());
+    }
+
+    #[test]
+    fn test_projects_counts_filter_and_rename() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        for (title, project) in [
+            ("Plant roses", Some("  garden ")),
+            ("Ship it", Some("Work")),
+            ("Mow", Some("garden")),
+            ("Nap", None),
+        ] {
+            manager
+                .add(NewTodo {
+                    title: title.to_string(),
+                    project: project.map(str::to_string),
+                    ..NewTodo::default()
+                })
+                .unwrap();
+        }
+        manager.mark_completed(&[3]).unwrap();
+        let summary = |manager: &TodoManager| {
+            manager
+                .projects()
+                .into_iter()
+                .map(|(name, counts)| (name, counts.pending(), counts.total))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summary(&manager),
+            [("garden".to_string(), 1, 2), ("Work".to_string(), 1, 1)]
+        );
+        let in_garden = QueryOptions {
+            project: Some("garden".to_string()),
+            ..QueryOptions::default()
+        };
+        assert_eq!(ids(&manager.query(&in_garden)), vec![1, 3]);
+
+        manager.archive_completed().unwrap();
+        assert_eq!(manager.rename_project("garden", " yard ").unwrap(), 2);
+        assert_eq!(
+            manager.get_todo(1).unwrap().project.as_deref(),
+            Some("yard")
+        );
+        assert_eq!(
+            manager.load_archive().unwrap()[0].project.as_deref(),
+            Some("yard")
+        );
+        assert_eq!(manager.rename_project("garden", "x").unwrap(), 0);
+        assert!(manager.rename_project("yard", "  ").is_err());
+
+        manager
+            .edit_todo(
+                2,
+                TodoEdit {
+                    project: Some(None),
+                    ..TodoEdit::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(summary(&manager), [("yard".to_string(), 1, 1)]);
+    }
+
+    #[test]
+    fn test_templates_outlive_their_todos() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(".tt.json");
+        let mut manager = manager_at(path.clone());
+        manager
+            .add_todo(
+                "Write release notes".into(),
+                Priority::High,
+                None,
+                &["release".to_string()],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo("Bump version".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        manager.mark_completed(&[2]).unwrap();
+        assert_eq!(
+            manager
+                .save_template(" sprint ", &[2, 1])
+                .unwrap()
+                .todos
+                .len(),
+            2
+        );
+        assert!(manager.save_template("sprint", &[1]).is_err());
+        assert!(manager.save_template("other", &[9]).is_err());
+        manager.delete_todos(&[1, 2]).unwrap();
+
+        let mut reloaded = manager_at(path);
+        reloaded.load_from_file().unwrap();
+        let added = reloaded.apply_template("sprint", "Sprint 42: ").unwrap();
+        assert_eq!(ids(&added), vec![3, 4]);
+        assert_eq!(added[0].title, "Sprint 42: Bump version");
+        assert!(!added[0].completed);
+        assert_eq!(added[1].tags, vec!["release"]);
+        assert_eq!(added[1].priority, Priority::High);
+        assert_eq!(ids(reloaded.list_todos()), vec![3, 4]);
+
+        let error = reloaded.apply_template("nope", "").unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(TtError::Missing(_))));
+        assert_eq!(reloaded.delete_template("sprint").unwrap().name, "sprint");
+        assert!(reloaded.templates().is_empty());
+    }
+
+    #[test]
+    fn test_trash_restores_todos_exactly() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(".tt.json");
+        // Stores from before the trash load with an empty one
+        fs::write(&path, r#"{"todos": [], "next_id": 1}"#).unwrap();
+        let mut manager = manager_at(path.clone());
+        manager.load_from_file().unwrap();
+        assert!(manager.trash().is_empty());
+        manager
+            .add_todo("Keep".into(), Priority::Low, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Drop".into(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager.mark_completed(&[2]).unwrap();
+        let original = manager.get_todo(2).unwrap().clone();
+
+        assert_eq!(ids(&manager.trash_todos(&[2]).unwrap()), vec![2]);
+        assert_eq!(ids(manager.list_todos()), vec![1]);
+        assert!(manager.trash_todos(&[2]).is_err());
+
+        let mut reloaded = manager_at(path);
+        reloaded.load_from_file().unwrap();
+        assert_eq!(reloaded.trash()[0].todo, original);
+        assert_eq!(reloaded.restore_from_trash(2).unwrap(), original);
+        assert_eq!(reloaded.get_todo(2), Some(&original));
+        assert!(reloaded.trash().is_empty());
+        assert!(!reloaded.deleted.iter().any(|d| d.is_of(&original)));
+        let error = reloaded.restore_from_trash(2).unwrap_err();
+        assert!(matches!(error.downcast_ref(), Some(TtError::Missing(_))));
+    }
+
+    #[test]
+    fn test_purge_trash_older_than() {
+        let mut manager = create_test_manager();
+        for title in ["Old", "New"] {
+            manager
+                .add_todo(title.into(), Priority::Low, None, &[], None)
+                .unwrap();
+        }
+        manager.trash_todos(&[1, 2]).unwrap();
+        let now = Utc::now();
+        manager.trash[0].deleted_at = (now - Duration::days(40)).to_rfc3339();
+
+        assert_eq!(
+            manager.purge_trash(Some(Duration::days(30)), now).unwrap(),
+            1
+        );
+        assert_eq!(manager.trash()[0].todo.title, "New");
+        assert_eq!(
+            manager.purge_trash(Some(Duration::days(30)), now).unwrap(),
+            0
+        );
+        assert_eq!(manager.purge_trash(None, now).unwrap(), 1);
+        assert!(manager.trash().is_empty());
+    }
+
+    fn create_query_fixture() -> TodoManager {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo(
+                "banana".to_string(),
+                Priority::Medium,
+                NaiveDate::from_ymd_opt(2025, 5, 1),
+                &[],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo("Apple".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo(
+                "cherry".to_string(),
+                Priority::High,
+                NaiveDate::from_ymd_opt(2025, 4, 1),
+                &[],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo("date".to_string(), Priority::Medium, None, &[], None)
+            .unwrap();
+        manager.mark_completed(&[2]).unwrap();
+        manager.todos[0].created_at = "2025-03-01T00:00:00Z".to_string();
+        manager.todos[1].created_at = "2025-01-01T00:00:00Z".to_string();
+        manager.todos[2].created_at = "not a timestamp".to_string();
+        manager.todos[3].created_at = "2025-02-01T00:00:00Z".to_string();
+        manager
+    }
+
+    fn ids(todos: &[Todo]) -> Vec<usize> {
+        todos.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn test_query_default_keeps_stored_order() {
+        let manager = create_query_fixture();
+        assert_eq!(
+            ids(&manager.query(&QueryOptions::default())),
+            vec![1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_query_sort_keys() {
+        let manager = create_query_fixture();
+        let sorted = |sort| {
+            ids(&manager.query(&QueryOptions {
+                sort: Some(sort),
+                ..QueryOptions::default()
+            }))
+        };
+        // Stable: equal priorities keep stored order
+        assert_eq!(sorted(SortKey::Priority), vec![2, 3, 1, 4]);
+        // Unparseable created_at sorts last instead of panicking
+        assert_eq!(sorted(SortKey::Created), vec![2, 4, 1, 3]);
+        assert_eq!(sorted(SortKey::Title), vec![2, 1, 3, 4]);
+        assert_eq!(sorted(SortKey::Due), vec![3, 1, 2, 4]);
+    }
+
+    #[test]
+    fn test_query_reverse() {
+        let manager = create_query_fixture();
+        let options = QueryOptions {
+            sort: Some(SortKey::Priority),
+            reverse: true,
+            ..QueryOptions::default()
+        };
+        assert_eq!(ids(&manager.query(&options)), vec![4, 1, 3, 2]);
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let manager = create_query_fixture();
+        let completed = QueryOptions {
+            completed: Some(true),
+            ..QueryOptions::default()
+        };
+        assert_eq!(ids(&manager.query(&completed)), vec![2]);
+
+        let incomplete_p3 = QueryOptions {
+            completed: Some(false),
+            priority: Some(Priority::Medium),
+            ..QueryOptions::default()
+        };
+        assert_eq!(ids(&manager.query(&incomplete_p3)), vec![1, 4]);
+    }
+
+    #[test]
+    fn test_add_todos_saves_once() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("First".to_string(), Priority::High, None, &[], None)
+            .unwrap();
+        manager.begin_operation("add");
+        let added = manager
+            .add_todos(vec![
+                ("Second".to_string(), Priority::Critical),
+                ("Third".to_string(), Priority::Low),
+            ])
+            .unwrap();
+        assert_eq!(ids(&added), vec![2, 3]);
+        assert_eq!(ids(manager.list_todos()), vec![1, 2, 3]);
+        assert_eq!(manager.list_todos()[1].priority, Priority::Critical);
+        // One save, so one snapshot to undo
+        assert_eq!(manager.undo().unwrap().as_deref(), Some("add"));
+        assert_eq!(ids(manager.list_todos()), vec![1]);
+    }
+
+    #[test]
+    fn test_counts_matching_follows_the_filters() {
+        let mut manager = create_test_manager();
+        for (title, tags) in [("One", &["x"][..]), ("Two", &["x"]), ("Three", &[])] {
+            let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+            manager
+                .add_todo(title.to_string(), Priority::High, None, &tags, None)
+                .unwrap();
+        }
+        manager.mark_completed(&[2]).unwrap();
+
+        let tagged = QueryOptions {
+            tags: vec!["x".to_string()],
+            // Hiding completed todos narrows the listing, not the counts
+            completed: Some(false),
+            ..QueryOptions::default()
+        };
+        let counts = manager.counts_matching(&tagged);
+        assert_eq!((counts.pending(), counts.total), (1, 2));
+        let counts = manager.counts_matching(&QueryOptions::default());
+        assert_eq!((counts.pending(), counts.total), (2, 3));
+    }
+
+    #[test]
+    fn test_drafts_are_hidden_until_published() {
+        let mut manager = create_test_manager();
+        let now = Utc::now();
+        manager
+            .add_todo("Deploy".to_string(), Priority::High, None, &[], None)
+            .unwrap();
+        let draft = manager.reserve(None, Priority::Low, None, now).unwrap();
+        assert_eq!(draft.id, 2);
+        manager
+            .reserve(Some("Deploy".to_string()), Priority::Low, None, now)
+            .unwrap();
+
+        assert_eq!(ids(manager.list_todos()), vec![1]);
+        assert_eq!(ids(&manager.query(&QueryOptions::default())), vec![1]);
+        assert_eq!(
+            ids(&manager.search_todos("deploy", false).unwrap()),
+            vec![1]
+        );
+        assert_eq!(manager.find_by_title("deploy").len(), 1);
+        assert_eq!(manager.counts(None).total, 1);
+        assert_eq!(ids(&manager.drafts(now)), vec![2, 3]);
+
+        // Drafts are no duplicates for imports
+        let imported = Todo::new(0, "(untitled draft)".to_string(), Priority::Low);
+        let summary = manager
+            .import_todos(vec![imported], DedupeStrategy::SkipIncompleteTitleMatches)
+            .unwrap();
+        assert_eq!(summary.imported, 1);
+
+        let published = manager
+            .publish(
+                2,
+                Some("Ship it".to_string()),
+                Some(Priority::Critical),
+                now,
+            )
+            .unwrap();
+        assert_eq!((published.id, published.priority), (2, Priority::Critical));
+        assert!(!published.draft);
+        assert_eq!(ids(manager.list_todos()), vec![1, 2, 4]);
+        let reopened = TodoManager::open(manager.file_path.clone(), false).unwrap();
+        assert_eq!(ids(&reopened.drafts(now)), vec![3]);
+    }
+
+    #[test]
+    fn test_publishing_validates_the_draft() {
+        let mut manager = create_test_manager();
+        let now = Utc::now();
+        manager.reserve(None, Priority::Low, None, now).unwrap();
+        manager
+            .add_todo("Published".to_string(), Priority::High, None, &[], None)
+            .unwrap();
+
+        let error = manager.publish(1, None, None, now).unwrap_err();
+        assert!(error.to_string().contains("no title"), "{error}");
+        assert!(
+            manager
+                .publish(1, Some("  ".to_string()), None, now)
+                .is_err()
+        );
+        assert!(
+            manager
+                .publish(2, Some("Again".to_string()), None, now)
+                .is_err()
+        );
+        assert!(
+            manager
+                .publish(9, Some("Missing".to_string()), None, now)
+                .is_err()
+        );
+        assert!(manager.get_todo(1).unwrap().draft);
+
+        let todo = manager
+            .publish(1, Some(" Ok ".to_string()), None, now)
+            .unwrap();
+        assert_eq!((todo.title.as_str(), todo.priority), ("Ok", Priority::Low));
+    }
+
+    #[test]
+    fn test_expired_drafts_are_dropped() {
+        let mut manager = create_test_manager();
+        let now = Utc::now();
+        manager
+            .reserve(None, Priority::Low, Some(now + Duration::days(1)), now)
+            .unwrap();
+        manager.reserve(None, Priority::Low, None, now).unwrap();
+        assert_eq!(ids(&manager.drafts(now)), vec![1, 2]);
+
+        let later = now + Duration::days(2);
+        assert_eq!(ids(&manager.drafts(later)), vec![2]);
+        assert!(
+            manager
+                .publish(1, Some("Too late".to_string()), None, later)
+                .is_err()
+        );
+        manager.reserve(None, Priority::Low, None, later).unwrap();
+        assert!(manager.get_todo(1).is_none(), "dropped on the next reserve");
+        assert_eq!(ids(&manager.drafts(later)), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_snoozed_todos_are_hidden_until_they_wake() {
+        let mut manager = create_query_fixture();
+        let now = Utc::now();
+        manager.snooze_todo(1, now + Duration::days(3)).unwrap();
+        manager.snooze_todo(4, now + Duration::hours(1)).unwrap();
+        let at = |when| QueryOptions {
+            hide_snoozed_at: Some(when),
+            ..QueryOptions::default()
+        };
+        assert_eq!(ids(&manager.query(&at(now))), vec![2, 3]);
+        assert_eq!(
+            ids(&manager.query(&at(now + Duration::days(1)))),
+            vec![2, 3, 4]
+        );
+        assert_eq!(
+            ids(&manager.query(&QueryOptions::default())),
+            vec![1, 2, 3, 4]
+        );
+
+        // Snoozed todos can still be changed by ID
+        manager.mark_completed(&[1]).unwrap();
+        assert_eq!(ids(&manager.query(&at(now))), vec![1, 2, 3]);
+
+        assert!(manager.unsnooze_todo(4).unwrap());
+        assert!(!manager.unsnooze_todo(4).unwrap());
+        assert_eq!(manager.get_todo(4).unwrap().snooze_count, 1);
+        assert!(manager.snooze_todo(9, now).is_err());
+    }
+
+    #[test]
+    fn test_pinned_todos_list_first_and_persist() {
+        let mut manager = create_query_fixture();
+        assert!(manager.set_pinned(3, true).unwrap());
+        assert!(manager.set_pinned(1, true).unwrap());
+        assert!(!manager.set_pinned(1, true).unwrap());
+        assert!(manager.set_pinned(9, true).is_err());
+        manager.mark_completed(&[1]).unwrap();
+
+        let reopened = TodoManager::open(manager.file_path.clone(), false).unwrap();
+        let pinned: Vec<usize> = reopened
+            .list_todos()
+            .iter()
+            .filter(|todo| todo.pinned)
+            .map(|todo| todo.id)
+            .collect();
+        assert_eq!(pinned, vec![1, 3]);
+
+        // Pinned todos lead whatever the sort; the completed one doesn't
+        let by_title = QueryOptions {
+            sort: Some(SortKey::Title),
+            ..QueryOptions::default()
+        };
+        let listed = crate::output::pinned_first(&reopened.query(&by_title));
+        assert_eq!(ids(&listed), vec![3, 2, 1, 4]);
+
+        let mut manager = reopened;
+        assert!(manager.set_pinned(3, false).unwrap());
+        assert!(!manager.set_pinned(3, false).unwrap());
+    }
+
+    #[test]
+    fn test_reads_and_no_op_changes_leave_the_file_alone() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("todos.json");
+        let mut manager = TodoManager::with_path(&path).unwrap();
+        manager
+            .add_todo(
+                "Renew passport".to_string(),
+                Priority::High,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        manager.mark_completed(&[1]).unwrap();
+
+        let long_ago = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1000);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(long_ago)
+            .unwrap();
+        let modified = || fs::metadata(&path).unwrap().modified().unwrap();
+
+        let mut manager = TodoManager::with_path(&path).unwrap();
+        manager.list_todos();
+        manager.query(&QueryOptions::default());
+        manager.counts(None);
+        manager.save().unwrap();
+        manager.mark_completed(&[1]).unwrap();
+        assert_eq!(modified(), long_ago);
+
+        manager.mark_incomplete(&[1]).unwrap();
+        assert_ne!(modified(), long_ago);
+    }
+
+    #[test]
+    fn test_same_todos_save_the_same_file() {
+        let temp_dir = tempdir().unwrap();
+        let mut source = create_query_fixture();
+        source.delete_todos(&[2]).unwrap();
+        source.delete_todos(&[4]).unwrap();
+        let saved = |name: &str| {
+            let path = temp_dir.path().join(name);
+            let mut manager = TodoManager::with_path(&path).unwrap();
+            manager.replace_store(source.to_store()).unwrap();
+            fs::read_to_string(&path).unwrap()
+        };
+        let first = saved("a.json");
+        assert_eq!(first, saved("b.json"));
+        assert!(first.ends_with("}\n"));
+    }
+
+    #[test]
+    fn test_compact_files_are_one_line() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("todos.json");
+        let mut manager = TodoManager::with_path(&path).unwrap().compact(true);
+        manager
+            .add_todo(
+                "Renew passport".to_string(),
+                Priority::High,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        // Pretty files pick the compact ones up, and the other way round
+        let mut manager = TodoManager::with_path(&path).unwrap();
+        assert_eq!(manager.list_todos()[0].title, "Renew passport");
+        manager.mark_completed(&[1]).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().lines().count() > 1);
+    }
+
+    #[test]
+    fn test_list_todos() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Todo 1".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Todo 2".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        let todos = manager.list_todos();
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[0].title, "Todo 1");
+        assert_eq!(todos[1].title, "Todo 2");
+    }
+
+    #[test]
+    fn test_search_substring_is_case_insensitive() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo(
+                "File TAXES".to_string(),
+                Priority::Critical,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo("Buy milk".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo(
+                "Taxi to airport".to_string(),
+                Priority::Critical,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+
+        let matches = manager.search_todos("taxes", false).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].id, 1);
+
+        assert_eq!(manager.search_todos("TAX", false).unwrap().len(), 2);
+        assert!(manager.search_todos("bread", false).unwrap().is_empty());
+        // Regex metacharacters are literal without --regex
+        assert!(manager.search_todos("tax.*", false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_regex() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo(
+                "File taxes".to_string(),
+                Priority::Critical,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo("Buy milk".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Buy BREAD".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+
+        let matches = manager.search_todos("^buy (milk|bread)$", true).unwrap();
+        let ids: Vec<usize> = matches.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_search_invalid_regex_is_error() {
+        let manager = create_test_manager();
+        let err = manager.search_todos("(unclosed", true).unwrap_err();
+        assert!(err.to_string().contains("Invalid regular expression"));
+    }
+
+    #[test]
+    fn test_mark_completed() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Test todo".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+
+        // Mark as completed
+        assert!(manager.mark_completed(&[1]).is_ok());
+        assert!(manager.get_todo(1).unwrap().completed);
+
+        // Try to mark non-existent todo
+        assert!(manager.mark_completed(&[2]).is_err());
+    }
+
+    #[test]
+    fn test_mark_incomplete() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Test todo".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+
+        // Mark as completed first
+        manager.mark_completed(&[1]).unwrap();
+        assert!(manager.get_todo(1).unwrap().completed);
+
+        // Mark as incomplete
+        assert!(manager.mark_incomplete(&[1]).is_ok());
+        assert!(!manager.get_todo(1).unwrap().completed);
+
+        // Try to mark non-existent todo
+        assert!(manager.mark_incomplete(&[2]).is_err());
+    }
+
+    #[test]
+    fn test_toggle_completed() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Test todo".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+
+        // Initially false
+        assert!(!manager.get_todo(1).unwrap().completed);
+
+        // Toggle to true
+        assert!(manager.toggle_completed(&[1]).is_ok());
+        assert!(manager.get_todo(1).unwrap().completed);
+
+        // Toggle back to false
+        assert!(manager.toggle_completed(&[1]).is_ok());
+        assert!(!manager.get_todo(1).unwrap().completed);
+
+        // Try to toggle non-existent todo
+        assert!(manager.toggle_completed(&[2]).is_err());
+    }
+
+    #[test]
+    fn test_delete_todo() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Todo 1".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Todo 2".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        assert_eq!(manager.list_todos().len(), 2);
+        // Delete first todo
+        assert!(manager.delete_todos(&[1]).is_ok());
+        assert_eq!(manager.list_todos().len(), 1);
+        // Remaining todo keeps its ID
+        assert_eq!(manager.get_todo(2).unwrap().title, "Todo 2");
+        assert!(manager.get_todo(1).is_none());
+        // Try to delete non-existent todo
+        assert!(manager.delete_todos(&[1]).is_err());
+    }
+
+    #[test]
+    fn test_batch_changes_are_all_or_nothing() {
+        let mut manager = create_test_manager();
+        for title in ["One", "Two", "Three"] {
+            manager
+                .add_todo(title.to_string(), Priority::Critical, None, &[], None)
+                .unwrap();
+        }
+
+        manager.mark_completed(&[1, 3]).unwrap();
+        let completed: Vec<bool> = manager.list_todos().iter().map(|t| t.completed).collect();
+        assert_eq!(completed, vec![true, false, true]);
+
+        manager.toggle_completed(&[1, 2]).unwrap();
+        let completed: Vec<bool> = manager.list_todos().iter().map(|t| t.completed).collect();
+        assert_eq!(completed, vec![false, true, true]);
+
+        assert!(manager.mark_incomplete(&[2, 9]).is_err());
+        assert!(manager.get_todo(2).unwrap().completed);
+        assert!(manager.delete_todos(&[1, 9]).is_err());
+        assert_eq!(manager.list_todos().len(), 3);
+    }
+
+    #[test]
+    fn test_delete_todos_resolves_ids_before_removing() {
+        let mut manager = create_test_manager();
+        for title in ["One", "Two", "Three", "Four"] {
+            manager
+                .add_todo(title.to_string(), Priority::Critical, None, &[], None)
+                .unwrap();
+        }
+
+        let deleted = manager.delete_todos(&[1, 2]).unwrap();
+        let titles: Vec<&str> = deleted.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["One", "Two"]);
+        let left: Vec<usize> = manager.list_todos().iter().map(|t| t.id).collect();
+        assert_eq!(left, vec![3, 4]);
+
+        let reopened = TodoManager::open(manager.file_path.clone(), false).unwrap();
+        assert_eq!(reopened.list_todos().len(), 2);
+    }
+
+    #[test]
+    fn test_move_todo() {
+        let mut manager = create_test_manager();
+        for title in ["One", "Two", "Three", "Four"] {
+            manager
+                .add_todo(title.to_string(), Priority::High, None, &[], None)
+                .unwrap();
+        }
+        let order = |manager: &TodoManager| -> Vec<usize> {
+            manager.list_todos().iter().map(|t| t.id).collect()
+        };
+
+        assert_eq!(manager.move_todo(3, 1).unwrap(), (3, 1));
+        assert_eq!(order(&manager), vec![3, 1, 2, 4]);
+        assert_eq!(manager.move_todo(3, 4).unwrap(), (1, 4));
+        assert_eq!(order(&manager), vec![1, 2, 4, 3]);
+        assert_eq!(manager.move_todo(1, 3).unwrap(), (1, 3));
+        assert_eq!(order(&manager), vec![2, 4, 1, 3]);
+        // Past the end is the bottom
+        assert_eq!(manager.move_todo(2, 99).unwrap(), (1, 4));
+        assert_eq!(order(&manager), vec![4, 1, 3, 2]);
+        // Onto itself changes nothing
+        assert_eq!(manager.move_todo(1, 2).unwrap(), (2, 2));
+        assert_eq!(order(&manager), vec![4, 1, 3, 2]);
+
+        assert!(manager.move_todo(1, 0).is_err());
+        assert!(manager.move_todo(9, 1).is_err());
+        let reopened = TodoManager::open(manager.file_path.clone(), false).unwrap();
+        assert_eq!(order(&reopened), vec![4, 1, 3, 2]);
+    }
+
+    #[test]
+    fn test_move_todo_skips_drafts() {
+        let mut manager = create_test_manager();
+        let draft = manager
+            .reserve(None, Priority::Low, None, Utc::now())
+            .unwrap()
+            .id;
+        // Only drafts stored: nothing to move among
+        let error = manager.move_todo(draft, 1).unwrap_err();
+        assert_eq!(TtError::exit_code_of(&error), 2);
+
+        manager
+            .add_todo("One".to_string(), Priority::High, None, &[], None)
+            .unwrap();
+        assert!(manager.move_todo(draft, 1).is_err());
+        assert!(manager.get_todo(draft).unwrap().draft);
+        assert_eq!(manager.list_todos().len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_rewrites_priorities_saved_out_of_range() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("todos.json");
+        fs::write(
+            &file_path,
+            r#"{"todos":[
+                {"id":1,"title":"Zero","completed":false,"created_at":"2025-01-01T09:00:00Z","priority":0},
+                {"id":2,"title":"Seven","completed":false,"created_at":"2025-01-01T09:00:00Z","priority":7},
+                {"id":3,"title":"Mapped","completed":false,"created_at":"2025-01-01T09:00:00Z","priority":9},
+                {"id":4,"title":"Fine","completed":false,"created_at":"2025-01-01T09:00:00Z","priority":4}
+            ],"next_id":5}"#,
+        )
+        .unwrap();
+        assert!(
+            !crate::validate::validate_file(&file_path)
+                .unwrap()
+                .is_empty()
+        );
+
+        let settings = normalize::Settings::new(&["9=2".to_string()], None, &[]).unwrap();
+        let mut manager = TodoManager::open(file_path.clone(), false).unwrap();
+        let report = manager.normalize(&settings, false).unwrap();
+        assert_eq!(report.rules[0].changed, 3);
+
+        assert_eq!(crate::validate::validate_file(&file_path).unwrap(), vec![]);
+        let reopened = TodoManager::open(file_path.clone(), false).unwrap();
+        let priorities: Vec<Priority> = reopened.list_todos().iter().map(|t| t.priority).collect();
+        assert_eq!(
+            priorities,
+            vec![
+                Priority::Critical,
+                Priority::Low,
+                Priority::High,
+                Priority::Low
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_backs_up_before_saving() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("todos.json");
+        let before =
+            r#"{"todos":[{"id":1,"title":"Imported","completed":false,"priority":0}],"next_id":2}"#;
+        fs::write(&file_path, before).unwrap();
+        let settings = normalize::Settings::new(&["low=critical".to_string()], None, &[]).unwrap();
+
+        let mut manager = TodoManager::open(file_path.clone(), false).unwrap();
+        let report = manager.normalize(&settings, true).unwrap();
+        assert!(report.changed_anything());
+        assert_eq!(report.missing_created_at, 1);
+        assert_eq!(report.backup, None);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), before);
+
+        let report = manager.normalize(&settings, false).unwrap();
+        let backup = report.backup.unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), before);
+        let reopened = TodoManager::open(file_path.clone(), false).unwrap();
+        assert_eq!(reopened.list_todos()[0].priority, Priority::Critical);
+
+        // Nothing left to do: no second backup
+        let report = manager.normalize(&settings, false).unwrap();
+        assert_eq!(report.backup, None);
+
+        // Backups taken within the same second don't overwrite each other
+        let first = manager.backup().unwrap();
+        let second = manager.backup().unwrap();
+        assert_ne!(first, second);
+        assert!(first.exists() && second.exists());
+    }
+
+    #[test]
+    fn test_checklist_changes_are_saved() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo(
+                "Post parcel".to_string(),
+                Priority::Critical,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(manager.add_checklist_item(1, "buy stamps").unwrap(), 1);
+        assert_eq!(manager.add_checklist_item(1, "find tape").unwrap(), 2);
+        assert!(manager.toggle_checklist_item(1, 1).unwrap().done);
+        assert_eq!(
+            manager.remove_checklist_item(1, 2).unwrap().text,
+            "find tape"
+        );
+        assert!(manager.toggle_checklist_item(1, 2).is_err());
+        assert!(manager.add_checklist_item(2, "nope").is_err());
+
+        let reopened = TodoManager::open(manager.file_path.clone(), false).unwrap();
+        assert_eq!(
+            reopened.get_todo(1).unwrap().checklist_progress(),
+            Some((1, 1))
+        );
+    }
+
+    #[test]
+    fn test_remove_completed_filters_by_age() {
+        let mut manager = create_test_manager();
+        for title in ["Old", "Recent", "Open", "Legacy"] {
+            manager
+                .add_todo(title.to_string(), Priority::Critical, None, &[], None)
+                .unwrap();
+        }
+        let now = Utc::now();
+        manager.todos[0].completed = true;
+        manager.todos[0].completed_at = Some((now - Duration::days(10)).to_rfc3339());
+        manager.todos[1].completed = true;
+        manager.todos[1].completed_at = Some((now - Duration::days(2)).to_rfc3339());
+        manager.todos[3].completed = true;
+        manager.todos[3].created_at = (now - Duration::days(40)).to_rfc3339();
+
+        assert_eq!(
+            manager
+                .remove_completed(Some(Duration::days(7)), now)
+                .unwrap(),
+            2
+        );
+        let left: Vec<&str> = manager.todos.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(left, vec!["Recent", "Open"]);
+        assert_eq!(manager.deleted.len(), 2);
+
+        assert_eq!(manager.remove_completed(None, now).unwrap(), 1);
+        assert_eq!(manager.remove_completed(None, now).unwrap(), 0);
+        let reopened = TodoManager::open(manager.file_path.clone(), false).unwrap();
+        assert_eq!(reopened.list_todos().len(), 1);
+    }
+
+    #[test]
+    fn test_find_by_title() {
+        let mut manager = create_test_manager();
+        for title in ["File TAXES", "Pay taxes late fee", "Buy milk"] {
+            manager
+                .add_todo(title.to_string(), Priority::Critical, None, &[], None)
+                .unwrap();
+        }
+
+        let ids = |query| -> Vec<usize> {
+            manager
+                .find_by_title(query)
+                .iter()
+                .map(|(id, _)| *id)
+                .collect()
+        };
+        assert_eq!(ids("taxes"), vec![1, 2]);
+        assert_eq!(ids(" MILK "), vec![3]);
+        assert!(ids("bread").is_empty());
+        assert!(ids("").is_empty());
+    }
+
+    #[test]
+    fn test_ids_are_not_reused() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Todo 1".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Todo 2".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Todo 3".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager.delete_todos(&[3]).unwrap();
+        manager.delete_todos(&[1]).unwrap();
+
+        let todo = manager
+            .add_todo("Todo 4".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        assert_eq!(todo.id, 4);
+
+        // Operations by ID still hit the right item after deletions
+        manager.mark_completed(&[2]).unwrap();
+        assert!(manager.get_todo(2).unwrap().completed);
+        assert!(!manager.get_todo(4).unwrap().completed);
+    }
+
+    #[test]
+    fn test_load_assigns_ids_to_legacy_todos() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+        fs::write(
+            &file_path,
+            r#"{"todos": [
+                {"title": "Old 1", "completed": false, "created_at": "2025-01-01T00:00:00Z"},
+                {"title": "Old 2", "completed": true, "created_at": "2025-01-02T00:00:00Z"}
+            ]}"#,
+        )
+        .unwrap();
+
+        let mut manager = TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path: file_path.clone(),
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        };
+        manager.load_from_file().unwrap();
+        assert_eq!(manager.get_todo(1).unwrap().title, "Old 1");
+        assert_eq!(manager.get_todo(2).unwrap().title, "Old 2");
+
+        // New todos continue after the assigned IDs, and IDs persist
+        manager
+            .add_todo("New".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        let mut reloaded = TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path,
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        };
+        reloaded.load_from_file().unwrap();
+        assert_eq!(reloaded.get_todo(2).unwrap().title, "Old 2");
+        assert_eq!(reloaded.get_todo(3).unwrap().title, "New");
+    }
+
+    #[test]
+    fn test_load_reassigns_duplicate_ids() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+        fs::write(
+            &file_path,
+            r#"{"todos": [
+                {"id": 5, "title": "A", "completed": false, "created_at": "2025-01-01T00:00:00Z"},
+                {"id": 5, "title": "B", "completed": false, "created_at": "2025-01-01T00:00:00Z"}
+            ], "next_id": 6}"#,
+        )
+        .unwrap();
+
+        let mut manager = TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path,
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        };
+        manager.load_from_file().unwrap();
+        assert_eq!(manager.get_todo(5).unwrap().title, "A");
+        assert_eq!(manager.get_todo(6).unwrap().title, "B");
+    }
+
+    #[test]
+    fn test_last_viewed_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let manager = TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path: temp_dir.path().join(".tt.json"),
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        };
+        assert_eq!(manager.last_viewed(), None);
+
+        let now = DateTime::parse_from_rfc3339("2025-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        manager.mark_viewed(now);
+        assert_eq!(manager.last_viewed(), Some(now));
+    }
+
+    #[test]
+    fn test_archive_completed() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Done 1".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Open".to_string(), Priority::High, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Done 2".to_string(), Priority::Medium, None, &[], None)
+            .unwrap();
+        manager.mark_completed(&[1]).unwrap();
+        manager.mark_completed(&[3]).unwrap();
+        let original = manager.get_todo(3).unwrap().clone();
+
+        assert_eq!(manager.archive_completed().unwrap(), 2);
+        assert_eq!(ids(manager.list_todos()), vec![2]);
+
+        let archived = manager.load_archive().unwrap();
+        assert_eq!(ids(&archived), vec![1, 3]);
+        // Archived todos keep their full data
+        assert_eq!(archived[1].title, original.title);
+        assert_eq!(archived[1].priority, original.priority);
+        assert_eq!(archived[1].created_at, original.created_at);
+        assert!(archived[1].completed);
+
+        // Archiving again appends to the existing archive
+        manager.mark_completed(&[2]).unwrap();
+        assert_eq!(manager.archive_completed().unwrap(), 1);
+        assert_eq!(ids(&manager.load_archive().unwrap()), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_archive_with_nothing_completed_writes_nothing() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Open".to_string(), Priority::High, None, &[], None)
+            .unwrap();
+
+        assert_eq!(manager.archive_completed().unwrap(), 0);
+        assert!(!manager.archive_path().exists());
+    }
+
+    #[test]
+    fn test_unarchive() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Done".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Open".to_string(), Priority::High, None, &[], None)
+            .unwrap();
+        manager.mark_completed(&[1]).unwrap();
+        manager.archive_completed().unwrap();
+
+        let restored = manager.unarchive(1).unwrap();
+        assert_eq!(restored.id, 1);
+        assert!(restored.completed);
+        assert_eq!(manager.get_todo(1).unwrap().title, "Done");
+        assert!(manager.load_archive().unwrap().is_empty());
+
+        assert!(manager.unarchive(1).is_err());
+    }
+
+    fn manager_at(file_path: PathBuf) -> TodoManager {
+        TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path,
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        }
+    }
+
+    #[test]
+    fn test_undo_delete() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        manager.begin_operation("add");
+        manager
+            .add_todo(
+                "A long, carefully worded title".to_string(),
+                Priority::High,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+
+        manager.begin_operation("delete");
+        manager.delete_todos(&[1]).unwrap();
+        assert!(manager.get_todo(1).is_none());
+
+        assert_eq!(manager.undo().unwrap().as_deref(), Some("delete"));
+        let todo = manager.get_todo(1).unwrap();
+        assert_eq!(todo.title, "A long, carefully worded title");
+        assert_eq!(todo.priority, Priority::High);
+
+        // The restored file is what later commands see
+        let mut reloaded = manager_at(temp_dir.path().join(".tt.json"));
+        reloaded.load_from_file().unwrap();
+        assert!(reloaded.get_todo(1).is_some());
+        assert!(!reloaded.modified_externally());
+    }
+
+    #[test]
+    fn test_undo_edit() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        manager
+            .add_todo("Original".to_string(), Priority::Low, None, &[], None)
+            .unwrap();
+
+        manager.begin_operation("edit");
+        manager
+            .edit_todo(
+                1,
+                TodoEdit {
+                    title: Some("Changed".to_string()),
+                    priority: Some(Priority::Critical),
+                    ..TodoEdit::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(manager.undo().unwrap().as_deref(), Some("edit"));
+        let todo = manager.get_todo(1).unwrap();
+        assert_eq!(todo.title, "Original");
+        assert_eq!(todo.priority, Priority::Low);
+    }
+
+    #[test]
+    fn test_double_undo_walks_back_then_stops() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        manager.begin_operation("add");
+        manager
+            .add_todo("First".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager.begin_operation("complete");
+        manager.mark_completed(&[1]).unwrap();
+
+        assert_eq!(manager.undo().unwrap().as_deref(), Some("complete"));
+        assert!(!manager.get_todo(1).unwrap().completed);
+
+        // Undoing the very first add leaves an empty store
+        assert_eq!(manager.undo().unwrap().as_deref(), Some("add"));
+        assert!(manager.list_todos().is_empty());
+
+        assert_eq!(manager.undo().unwrap(), None);
+    }
+
+    #[test]
+    fn test_undo_archive_restores_both_files() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        manager
+            .add_todo("Done".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager.mark_completed(&[1]).unwrap();
+
+        manager.begin_operation("archive");
+        assert_eq!(manager.archive_completed().unwrap(), 1);
+
+        assert_eq!(manager.undo().unwrap().as_deref(), Some("archive"));
+        assert!(manager.get_todo(1).is_some());
+        assert!(manager.load_archive().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_failed_operation_takes_no_snapshot() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        manager.begin_operation("delete");
+        assert!(manager.delete_todos(&[42]).is_err());
+        assert_eq!(manager.undo().unwrap(), None);
+    }
+
+    #[test]
+    fn test_corrupt_file_is_refused_and_left_untouched() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+        let corrupt = r#"{"todos": [{"id": 1, "title": "Half writ"#;
+        fs::write(&file_path, corrupt).unwrap();
+
+        let error = TodoManager::open(file_path.clone(), false).err().unwrap();
+        assert!(format!("{error:#}").contains("--force"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), corrupt);
+    }
+
+    #[test]
+    fn test_forced_overwrite_of_corrupt_file_can_be_undone() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+        let corrupt = "{\"todos\": [";
+        fs::write(&file_path, corrupt).unwrap();
+
+        // What --force does: start empty, but the original bytes go into
+        // the undo history before anything is written
+        let mut manager = TodoManager::unloaded(file_path.clone());
+        manager.begin_operation("add");
+        manager
+            .add_todo("Fresh".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        assert_ne!(fs::read_to_string(&file_path).unwrap(), corrupt);
+
+        assert_eq!(manager.undo().unwrap().as_deref(), Some("add"));
+        assert!(manager.list_todos().is_empty());
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), corrupt);
+    }
+
+    #[test]
+    fn test_save_leaves_no_temp_file() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+        let mut manager = TodoManager::unloaded(file_path.clone());
+        manager
+            .add_todo("Todo".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+
+        let names: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(TEMP_SUFFIX))
+            .collect();
+        assert!(file_path.exists());
+        assert!(names.is_empty(), "left behind: {names:?}");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&file_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_concurrent_writes_never_mix() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+        let contents: Vec<String> = ["a", "b"]
+            .iter()
+            .map(|fill| format!("{{\"fill\": \"{}\"}}", fill.repeat(64 * 1024)))
+            .collect();
+
+        let handles: Vec<_> = contents
+            .iter()
+            .cloned()
+            .map(|content| {
+                let file_path = file_path.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        write_file(&file_path, &content).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let written = fs::read_to_string(&file_path).unwrap();
+        assert!(contents.contains(&written));
+    }
+
+    #[test]
+    fn test_stale_temp_files_are_cleaned_up() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+        let stale = temp_dir.path().join(".tt.json.crashed.tmp");
+        let fresh = temp_dir.path().join(".tt.json.inflight.tmp");
+        let unrelated = temp_dir.path().join("other.tmp");
+        for path in [&stale, &fresh, &unrelated] {
+            fs::write(path, "partial").unwrap();
+        }
+        let two_hours_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(7200);
+        for path in [&stale, &unrelated] {
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(two_hours_ago)
+                .unwrap();
+        }
+
+        write_file(&file_path, "{}").unwrap();
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+        assert!(unrelated.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_concurrent_adds_are_both_kept() {
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join(".tt.json");
+
+        let handles: Vec<_> = ["From terminal", "From cron"]
+            .into_iter()
+            .map(|title| {
+                let file_path = file_path.clone();
+                std::thread::spawn(move || {
+                    let mut manager = TodoManager::open(file_path, true).unwrap();
+                    // Widen the window between load and save
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    manager
+                        .add_todo(title.to_string(), Priority::Critical, None, &[], None)
+                        .unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let manager = TodoManager::open(file_path, false).unwrap();
+        let mut titles: Vec<String> = manager
+            .list_todos()
+            .iter()
+            .map(|t| t.title.clone())
+            .collect();
+        titles.sort();
+        assert_eq!(titles, vec!["From cron", "From terminal"]);
+    }
+
+    #[test]
+    fn test_merge_from_bundle_keeps_ids_unique_across_archive() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = manager_at(temp_dir.path().join(".tt.json"));
+        manager
+            .add_todo("Active".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Done".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager.mark_completed(&[2]).unwrap();
+        manager.archive_completed().unwrap();
+
+        let mut theirs = Todo::new(2, "Theirs".to_string(), Priority::High);
+        theirs.created_at = "2020-01-01T00:00:00Z".to_string();
+        let counts = manager.merge_store(vec![theirs]).unwrap();
+        assert_eq!(counts.renumbered, 1);
+        assert_eq!(manager.get_todo(3).unwrap().title, "Theirs");
+
+        // A later merge into the archive continues after the renumbered ID
+        let mut old = Todo::new(1, "Old".to_string(), Priority::High);
+        old.created_at = "2019-01-01T00:00:00Z".to_string();
+        manager.merge_archive(vec![old]).unwrap();
+        let archived: Vec<usize> = manager
+            .load_archive()
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(archived, vec![2, 4]);
+    }
+
+    #[test]
+    fn test_import_skips_incomplete_title_matches() {
+        let mut manager = create_test_manager();
+        manager
+            .add_todo("Buy milk".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager
+            .add_todo("Pay rent".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        manager.mark_completed(&[2]).unwrap();
+
+        let incoming: Vec<Todo> = ["BUY MILK", "Pay rent", "Call mom", "call mom"]
+            .iter()
+            .map(|title| Todo::new(0, title.to_string(), Priority::Medium))
+            .collect();
+        let summary = manager
+            .import_todos(incoming.clone(), DedupeStrategy::SkipIncompleteTitleMatches)
+            .unwrap();
+        // Completed "Pay rent" does not count, and the batch dedupes itself
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 2,
+                duplicates: 2
+            }
+        );
+        assert_eq!(manager.get_todo(3).unwrap().title, "Pay rent");
+        assert_eq!(manager.get_todo(4).unwrap().title, "Call mom");
+
+        let summary = manager
+            .import_todos(incoming, DedupeStrategy::AllowDuplicates)
+            .unwrap();
+        assert_eq!(summary.imported, 4);
+        assert_eq!(manager.list_todos().len(), 8);
+    }
+
+    #[test]
     fn test_get_todo() {
         let mut manager = create_test_manager();
-        manager.add_todo("Test todo".to_string(), 1).unwrap();
+        manager
+            .add_todo("Test todo".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
 
         // Get existing todo
-        let todo = manager.get_todo(0);
+        let todo = manager.get_todo(1);
         assert!(todo.is_some());
         assert_eq!(todo.unwrap().title, "Test todo");
 
         // Get non-existent todo
-        let todo = manager.get_todo(1);
+        let todo = manager.get_todo(2);
         assert!(todo.is_none());
     }
 
+    #[test]
+    fn test_modified_externally() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path: temp_dir.path().join(".tt.json"),
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        };
+
+        // Nothing on disk yet
+        assert!(!manager.modified_externally());
+
+        manager
+            .add_todo("Test todo".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        assert!(!manager.modified_externally());
+
+        fs::write(manager.file_path(), r#"{"todos": []}"#).unwrap();
+        assert!(manager.modified_externally());
+
+        // A tt write makes the file trusted again
+        manager
+            .add_todo("Another".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+        assert!(!manager.modified_externally());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_preserves_symlink() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+
+        let temp_dir = tempdir().unwrap();
+        let real = temp_dir.path().join("real.json");
+        let link = temp_dir.path().join(".tt.json");
+        fs::write(&real, r#"{"todos": []}"#).unwrap();
+        symlink(&real, &link).unwrap();
+
+        let mut manager = TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path: link.clone(),
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        };
+        manager
+            .add_todo("Synced".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert!(fs::read_to_string(&real).unwrap().contains("Synced"));
+        let mode = fs::metadata(&real).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_through_relative_symlink_across_directories() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempdir().unwrap();
+        let home = temp_dir.path().join("home");
+        let sync = temp_dir.path().join("sync");
+        fs::create_dir_all(&home).unwrap();
+        fs::create_dir_all(&sync).unwrap();
+        fs::write(sync.join("todos.json"), r#"{"todos": []}"#).unwrap();
+        // A chain of links: home/.tt.json -> home/current -> ../sync/todos.json
+        symlink("../sync/todos.json", home.join("current")).unwrap();
+        symlink("current", home.join(".tt.json")).unwrap();
+
+        let mut manager = TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path: home.join(".tt.json"),
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        };
+        manager
+            .add_todo("Synced".to_string(), Priority::Critical, None, &[], None)
+            .unwrap();
+
+        assert!(
+            fs::symlink_metadata(home.join(".tt.json"))
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert!(
+            fs::symlink_metadata(home.join("current"))
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert!(
+            fs::read_to_string(sync.join("todos.json"))
+                .unwrap()
+                .contains("Synced")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_through_dangling_symlink_is_error() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempdir().unwrap();
+        let missing = temp_dir.path().join("gone").join("todos.json");
+        let link = temp_dir.path().join(".tt.json");
+        symlink(&missing, &link).unwrap();
+
+        let mut manager = TodoManager {
+            todos: Vec::new(),
+            next_id: 1,
+            file_path: link,
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
+        };
+        let err = manager
+            .add_todo("Lost".to_string(), Priority::Critical, None, &[], None)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("does not exist"));
+        assert!(!missing.exists());
+        assert!(!missing.parent().unwrap().exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loop_is_error() {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = tempdir().unwrap();
+        let a = temp_dir.path().join("a");
+        let b = temp_dir.path().join("b");
+        symlink(&b, &a).unwrap();
+        symlink(&a, &b).unwrap();
+
+        assert!(resolve_symlinks(&a).is_err());
+    }
+
+    #[test]
+    fn test_resolve_missing_plain_path() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("new.json");
+        assert_eq!(resolve_symlinks(&path).unwrap(), path);
+    }
+
     #[test]
     fn test_save_and_load() {
         let temp_dir = tempdir().unwrap();
@@ -289,24 +3756,62 @@ mod tests {
         // Create manager and add todos
         let mut manager = TodoManager {
             todos: Vec::new(),
+            next_id: 1,
             file_path: file_path.clone(),
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
         };
-        manager.add_todo("Test todo 1".to_string(), 1).unwrap();
-        manager.add_todo("Test todo 2".to_string(), 1).unwrap();
-        manager.mark_completed(0).unwrap();
+        manager
+            .add_todo(
+                "Test todo 1".to_string(),
+                Priority::Critical,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        manager
+            .add_todo(
+                "Test todo 2".to_string(),
+                Priority::Critical,
+                None,
+                &[],
+                None,
+            )
+            .unwrap();
+        manager.mark_completed(&[1]).unwrap();
         // Verify file was created
         assert!(file_path.exists());
         // Create new manager and load from file
         let mut new_manager = TodoManager {
             todos: Vec::new(),
+            next_id: 1,
             file_path,
+            operation: None,
+            lock: None,
+            list: None,
+            meta: None,
+            deleted: Vec::new(),
+            templates: Vec::new(),
+            trash: Vec::new(),
+            autosave: true,
+            dirty: false,
+            compact: false,
         };
         new_manager.load_from_file().unwrap();
         // Verify todos were loaded correctly
         assert_eq!(new_manager.list_todos().len(), 2);
-        assert_eq!(new_manager.get_todo(0).unwrap().title, "Test todo 1");
-        assert_eq!(new_manager.get_todo(0).unwrap().completed, true);
-        assert_eq!(new_manager.get_todo(1).unwrap().title, "Test todo 2");
-        assert_eq!(new_manager.get_todo(1).unwrap().completed, false);
+        assert_eq!(new_manager.get_todo(1).unwrap().title, "Test todo 1");
+        assert!(new_manager.get_todo(1).unwrap().completed);
+        assert_eq!(new_manager.get_todo(2).unwrap().title, "Test todo 2");
+        assert!(!new_manager.get_todo(2).unwrap().completed);
     }
 }
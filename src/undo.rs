@@ -0,0 +1,161 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// How many snapshots are kept; older ones fall off the end.
+const MAX_SNAPSHOTS: usize = 10;
+
+/// The on-disk contents of a store (and its archive) from just before a
+/// mutating command ran. `None` means the file did not exist yet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub operation: String,
+    pub taken_at: String, // ISO 8601 format
+    pub store: Option<String>,
+    pub archive: Option<String>,
+}
+
+impl Snapshot {
+    /// Capture the current contents of the store and archive files.
+    pub fn take(operation: &str, store: &Path, archive: &Path, now: DateTime<Utc>) -> Self {
+        Self {
+            operation: operation.to_string(),
+            taken_at: now.to_rfc3339(),
+            store: fs::read_to_string(store).ok(),
+            archive: fs::read_to_string(archive).ok(),
+        }
+    }
+}
+
+/// Load the snapshot history, oldest first. A missing or unreadable
+/// history is empty.
+pub fn load(path: &Path) -> Vec<Snapshot> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, snapshots: &[Snapshot]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create directory for undo history")?;
+    }
+    let json = serde_json::to_string(snapshots).context("Failed to serialize undo history")?;
+    fs::write(path, json).context("Failed to write undo history")
+}
+
+/// Add a snapshot, dropping the oldest once the history is full.
+pub fn push(path: &Path, snapshot: Snapshot) -> Result<()> {
+    let mut snapshots = load(path);
+    snapshots.push(snapshot);
+    if snapshots.len() > MAX_SNAPSHOTS {
+        snapshots.drain(..snapshots.len() - MAX_SNAPSHOTS);
+    }
+    save(path, &snapshots)
+}
+
+/// Remove and return the most recent snapshot, if any.
+pub fn pop(path: &Path) -> Result<Option<Snapshot>> {
+    let mut snapshots = load(path);
+    let snapshot = snapshots.pop();
+    if snapshot.is_some() {
+        save(path, &snapshots)?;
+    }
+    Ok(snapshot)
+}
+
+/// Drop snapshots taken before `cutoff` (all of them unless `dry_run`),
+/// returning how many there are.
+pub fn prune(path: &Path, cutoff: DateTime<Utc>, dry_run: bool) -> Result<usize> {
+    let mut snapshots = load(path);
+    let before = snapshots.len();
+    snapshots.retain(|snapshot| {
+        DateTime::parse_from_rfc3339(&snapshot.taken_at).map_or(true, |taken| taken > cutoff)
+    });
+    let pruned = before - snapshots.len();
+    if pruned > 0 && !dry_run {
+        save(path, &snapshots)?;
+    }
+    Ok(pruned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn snapshot(operation: &str) -> Snapshot {
+        Snapshot {
+            operation: operation.to_string(),
+            taken_at: Utc::now().to_rfc3339(),
+            store: Some(format!("{{\"op\": \"{operation}\"}}")),
+            archive: None,
+        }
+    }
+
+    #[test]
+    fn test_push_and_pop_are_last_in_first_out() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("undo.json");
+
+        push(&path, snapshot("add")).unwrap();
+        push(&path, snapshot("delete")).unwrap();
+
+        assert_eq!(pop(&path).unwrap().unwrap().operation, "delete");
+        assert_eq!(pop(&path).unwrap().unwrap().operation, "add");
+        assert!(pop(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("undo.json");
+
+        for i in 0..MAX_SNAPSHOTS + 3 {
+            push(&path, snapshot(&format!("op{i}"))).unwrap();
+        }
+
+        let snapshots = load(&path);
+        assert_eq!(snapshots.len(), MAX_SNAPSHOTS);
+        assert_eq!(snapshots[0].operation, "op3");
+    }
+
+    #[test]
+    fn test_prune_drops_old_snapshots() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("undo.json");
+        let now = Utc::now();
+        for (operation, days) in [("old", 40), ("older", 35), ("recent", 2)] {
+            let mut snapshot = snapshot(operation);
+            snapshot.taken_at = (now - chrono::Duration::days(days)).to_rfc3339();
+            push(&path, snapshot).unwrap();
+        }
+        let cutoff = now - chrono::Duration::days(30);
+
+        assert_eq!(prune(&path, cutoff, true).unwrap(), 2);
+        assert_eq!(load(&path).len(), 3);
+        assert_eq!(prune(&path, cutoff, false).unwrap(), 2);
+        let snapshots = load(&path);
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].operation, "recent");
+        assert_eq!(prune(&path, cutoff, false).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_take_records_missing_files_as_none() {
+        let temp_dir = tempdir().unwrap();
+        let store = temp_dir.path().join(".tt.json");
+        fs::write(&store, "{}").unwrap();
+
+        let snapshot = Snapshot::take(
+            "add",
+            &store,
+            &temp_dir.path().join("missing.json"),
+            Utc::now(),
+        );
+        assert_eq!(snapshot.store.as_deref(), Some("{}"));
+        assert!(snapshot.archive.is_none());
+    }
+}
@@ -0,0 +1,1006 @@
+use crate::suggest::closest;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+/// A single problem found by strict validation, located by JSON path.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Problem {
+    pub path: String,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum FieldKind {
+    Id,
+    Count,
+    Text,
+    Bool,
+    Timestamp,
+    Date,
+    Priority,
+    Tags,
+    Notes,
+    Checklist,
+    List,
+    Object,
+}
+
+struct Field {
+    name: &'static str,
+    kind: FieldKind,
+    required: bool,
+}
+
+/// Strict mirror of `TodoStore`.
+const STORE_FIELDS: &[&str] = &["todos", "next_id", "meta", "deleted", "templates", "trash"];
+
+/// Strict mirror of `StoreMeta`.
+const META_FIELDS: &[Field] = &[
+    Field {
+        name: "name",
+        kind: FieldKind::Notes,
+        required: false,
+    },
+    Field {
+        name: "description",
+        kind: FieldKind::Notes,
+        required: false,
+    },
+    Field {
+        name: "created_at",
+        kind: FieldKind::Timestamp,
+        required: true,
+    },
+    Field {
+        name: "modified_at",
+        kind: FieldKind::Timestamp,
+        required: true,
+    },
+];
+
+/// Strict mirror of `Deletion`.
+const DELETION_FIELDS: &[Field] = &[
+    Field {
+        name: "id",
+        kind: FieldKind::Id,
+        required: true,
+    },
+    Field {
+        name: "created_at",
+        kind: FieldKind::Timestamp,
+        required: true,
+    },
+    Field {
+        name: "deleted_at",
+        kind: FieldKind::Timestamp,
+        required: true,
+    },
+];
+
+/// Strict mirror of `TrashedTodo`. The todo itself is checked like any other.
+const TRASHED_FIELDS: &[Field] = &[
+    Field {
+        name: "todo",
+        kind: FieldKind::Object,
+        required: true,
+    },
+    Field {
+        name: "deleted_at",
+        kind: FieldKind::Timestamp,
+        required: true,
+    },
+];
+
+/// Strict mirror of `Template`.
+const TEMPLATE_FIELDS: &[Field] = &[
+    Field {
+        name: "name",
+        kind: FieldKind::Text,
+        required: true,
+    },
+    Field {
+        name: "todos",
+        kind: FieldKind::List,
+        required: true,
+    },
+];
+
+/// Strict mirror of `TemplateTodo`.
+const TEMPLATE_TODO_FIELDS: &[Field] = &[
+    Field {
+        name: "title",
+        kind: FieldKind::Text,
+        required: true,
+    },
+    Field {
+        name: "priority",
+        kind: FieldKind::Priority,
+        required: false,
+    },
+    Field {
+        name: "tags",
+        kind: FieldKind::Tags,
+        required: false,
+    },
+    Field {
+        name: "project",
+        kind: FieldKind::Notes,
+        required: false,
+    },
+];
+
+/// Strict mirror of `Todo`. Optional fields are those with serde defaults.
+const TODO_FIELDS: &[Field] = &[
+    Field {
+        name: "id",
+        kind: FieldKind::Id,
+        required: false,
+    },
+    Field {
+        name: "title",
+        kind: FieldKind::Text,
+        required: true,
+    },
+    Field {
+        name: "completed",
+        kind: FieldKind::Bool,
+        required: true,
+    },
+    Field {
+        name: "created_at",
+        kind: FieldKind::Timestamp,
+        required: false,
+    },
+    Field {
+        name: "priority",
+        kind: FieldKind::Priority,
+        required: false,
+    },
+    Field {
+        name: "due_date",
+        kind: FieldKind::Date,
+        required: false,
+    },
+    Field {
+        name: "tags",
+        kind: FieldKind::Tags,
+        required: false,
+    },
+    Field {
+        name: "project",
+        kind: FieldKind::Notes,
+        required: false,
+    },
+    Field {
+        name: "snooze_count",
+        kind: FieldKind::Count,
+        required: false,
+    },
+    Field {
+        name: "snoozed_until",
+        kind: FieldKind::Timestamp,
+        required: false,
+    },
+    Field {
+        name: "notes",
+        kind: FieldKind::Notes,
+        required: false,
+    },
+    Field {
+        name: "updated_at",
+        kind: FieldKind::Timestamp,
+        required: false,
+    },
+    Field {
+        name: "completed_at",
+        kind: FieldKind::Timestamp,
+        required: false,
+    },
+    Field {
+        name: "checklist",
+        kind: FieldKind::Checklist,
+        required: false,
+    },
+    Field {
+        name: "draft",
+        kind: FieldKind::Bool,
+        required: false,
+    },
+    Field {
+        name: "draft_expires_at",
+        kind: FieldKind::Timestamp,
+        required: false,
+    },
+    Field {
+        name: "pinned",
+        kind: FieldKind::Bool,
+        required: false,
+    },
+];
+
+impl Problem {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+            suggestion: None,
+        }
+    }
+
+    fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+}
+
+pub fn validate_file(path: &Path) -> Result<Vec<Problem>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(validate_str(&content))
+}
+
+/// Validate the contents of a todo file, returning every problem found.
+pub fn validate_str(content: &str) -> Vec<Problem> {
+    let root: Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => {
+            return vec![
+                Problem::new(
+                    "$",
+                    format!("invalid JSON at line {} column {}", e.line(), e.column()),
+                )
+                .suggest(e.to_string()),
+            ];
+        }
+    };
+
+    let mut problems = Vec::new();
+    let Some(store) = root.as_object() else {
+        problems.push(
+            Problem::new(
+                "$",
+                format!("expected an object, found {}", describe(&root)),
+            )
+            .suggest("wrap the file in { \"todos\": [...] }"),
+        );
+        return problems;
+    };
+
+    check_unknown_fields("$", store, STORE_FIELDS, &mut problems);
+    if let Some(next_id) = store.get("next_id") {
+        problems.extend(check_kind("$.next_id", FieldKind::Id, next_id));
+    }
+    match store.get("meta") {
+        None | Some(Value::Null) => {}
+        Some(Value::Object(meta)) => check_fields("$.meta", meta, META_FIELDS, &mut problems),
+        Some(other) => problems.push(
+            Problem::new(
+                "$.meta",
+                format!("expected an object, found {}", describe(other)),
+            )
+            .suggest("remove 'meta' or set it with `tt store set-name`"),
+        ),
+    }
+
+    check_items(
+        "$.deleted",
+        store.get("deleted"),
+        &mut problems,
+        |path, deletion, problems| check_fields(path, deletion, DELETION_FIELDS, problems),
+    );
+    check_items(
+        "$.templates",
+        store.get("templates"),
+        &mut problems,
+        validate_template,
+    );
+    check_items(
+        "$.trash",
+        store.get("trash"),
+        &mut problems,
+        |path, trashed, problems| {
+            check_fields(path, trashed, TRASHED_FIELDS, problems);
+            if let Some(todo) = trashed.get("todo").filter(|todo| todo.is_object()) {
+                validate_todo(&format!("{path}.todo"), todo, problems);
+            }
+        },
+    );
+
+    match store.get("todos") {
+        None => {
+            problems.push(Problem::new("$", "missing field 'todos'").suggest("add \"todos\": []"))
+        }
+        Some(Value::Array(todos)) => {
+            for (index, todo) in todos.iter().enumerate() {
+                validate_todo(&format!("$.todos[{index}]"), todo, &mut problems);
+            }
+        }
+        Some(other) => problems.push(
+            Problem::new(
+                "$.todos",
+                format!("expected an array, found {}", describe(other)),
+            )
+            .suggest("make 'todos' a list: [ ... ]"),
+        ),
+    }
+
+    problems
+}
+
+fn validate_todo(path: &str, todo: &Value, problems: &mut Vec<Problem>) {
+    let Some(todo) = todo.as_object() else {
+        problems.push(Problem::new(
+            path,
+            format!("expected a todo object, found {}", describe(todo)),
+        ));
+        return;
+    };
+
+    check_fields(path, todo, TODO_FIELDS, problems);
+}
+
+fn validate_template(path: &str, template: &Map<String, Value>, problems: &mut Vec<Problem>) {
+    check_fields(path, template, TEMPLATE_FIELDS, problems);
+    check_items(
+        &format!("{path}.todos"),
+        template.get("todos").filter(|todos| todos.is_array()),
+        problems,
+        |path, todo, problems| check_fields(path, todo, TEMPLATE_TODO_FIELDS, problems),
+    );
+}
+
+/// Check each object in the list at `path`, if there is one.
+fn check_items(
+    path: &str,
+    list: Option<&Value>,
+    problems: &mut Vec<Problem>,
+    check: impl Fn(&str, &Map<String, Value>, &mut Vec<Problem>),
+) {
+    match list {
+        None => {}
+        Some(Value::Array(items)) => {
+            for (index, item) in items.iter().enumerate() {
+                let path = format!("{path}[{index}]");
+                match item.as_object() {
+                    Some(item) => check(&path, item, problems),
+                    None => problems.push(Problem::new(
+                        path,
+                        format!("expected an object, found {}", describe(item)),
+                    )),
+                }
+            }
+        }
+        Some(other) => problems.push(Problem::new(
+            path,
+            format!("expected an array, found {}", describe(other)),
+        )),
+    }
+}
+
+/// Check an object against a strict list of fields.
+fn check_fields(
+    path: &str,
+    object: &Map<String, Value>,
+    fields: &[Field],
+    problems: &mut Vec<Problem>,
+) {
+    let known: Vec<&str> = fields.iter().map(|f| f.name).collect();
+    check_unknown_fields(path, object, &known, problems);
+
+    for field in fields {
+        let field_path = format!("{path}.{}", field.name);
+        match object.get(field.name) {
+            None if field.required => problems.push(
+                Problem::new(path, format!("missing field '{}'", field.name)).suggest(format!(
+                    "add \"{}\": {}",
+                    field.name,
+                    example(field.kind)
+                )),
+            ),
+            None => {}
+            Some(value) => {
+                if let Some(problem) = check_kind(&field_path, field.kind, value) {
+                    problems.push(problem);
+                }
+            }
+        }
+    }
+}
+
+fn check_unknown_fields(
+    path: &str,
+    object: &Map<String, Value>,
+    known: &[&str],
+    problems: &mut Vec<Problem>,
+) {
+    for key in object.keys().filter(|key| !known.contains(&key.as_str())) {
+        let problem = Problem::new(format!("{path}.{key}"), format!("unknown field '{key}'"));
+        problems.push(match closest(key, known, 2) {
+            Some(name) if !object.contains_key(name) => {
+                problem.suggest(format!("did you mean '{name}'?"))
+            }
+            _ => problem.suggest("remove this field"),
+        });
+    }
+}
+
+fn check_kind(path: &str, kind: FieldKind, value: &Value) -> Option<Problem> {
+    match (kind, value) {
+        (FieldKind::Id | FieldKind::Count, Value::Number(n)) if n.is_u64() => None,
+        (FieldKind::Text, Value::String(_)) => None,
+        (FieldKind::Notes, Value::Null | Value::String(_)) => None,
+        (FieldKind::Bool, Value::Bool(_)) => None,
+        (FieldKind::Bool, Value::String(s)) if s == "true" || s == "false" => Some(
+            Problem::new(path, "expected a boolean, found a string")
+                .suggest(format!("write {s} without quotes")),
+        ),
+        (FieldKind::Timestamp, Value::String(s)) => match DateTime::parse_from_rfc3339(s) {
+            Ok(_) => None,
+            Err(_) => Some(
+                Problem::new(path, format!("'{s}' is not an ISO 8601 timestamp"))
+                    .suggest(format!("use a timestamp such as {}", example(kind))),
+            ),
+        },
+        (FieldKind::Date, Value::Null) => None,
+        (FieldKind::Date, Value::String(s)) => match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            Ok(_) => None,
+            Err(_) => Some(
+                Problem::new(path, format!("'{s}' is not a YYYY-MM-DD date"))
+                    .suggest(format!("use a date such as {}", example(kind))),
+            ),
+        },
+        (FieldKind::Priority, Value::Number(n)) => match n.as_u64() {
+            Some(1..=4) => None,
+            _ => Some(
+                Problem::new(path, format!("priority {n} is out of range"))
+                    .suggest("use a whole number from 1 (highest) to 4 (lowest)"),
+            ),
+        },
+        (FieldKind::Tags, Value::Array(tags)) => {
+            tags.iter().position(|tag| !tag.is_string()).map(|index| {
+                Problem::new(
+                    format!("{path}[{index}]"),
+                    format!("expected a string tag, found {}", describe(&tags[index])),
+                )
+                .suggest("write tags as strings, e.g. \"work\"")
+            })
+        }
+        (FieldKind::Checklist, Value::Array(items)) => {
+            items.iter().enumerate().find_map(|(index, item)| {
+                let valid = item.as_object().is_some_and(|item| {
+                    item.get("text").is_some_and(Value::is_string)
+                        && item.get("done").is_none_or(Value::is_boolean)
+                        && item.keys().all(|key| key == "text" || key == "done")
+                });
+                (!valid).then(|| {
+                    Problem::new(
+                        format!("{path}[{index}]"),
+                        format!("expected a checklist item, found {}", describe(item)),
+                    )
+                    .suggest("write items as {\"text\": \"buy stamps\", \"done\": false}")
+                })
+            })
+        }
+        (FieldKind::List, Value::Array(_)) => None,
+        (FieldKind::Object, Value::Object(_)) => None,
+        (FieldKind::Priority, Value::String(s)) if s.parse::<u8>().is_ok() => Some(
+            Problem::new(path, "expected a number, found a string")
+                .suggest(format!("write {s} without quotes")),
+        ),
+        (kind, other) => Some(
+            Problem::new(
+                path,
+                format!("expected {}, found {}", expected(kind), describe(other)),
+            )
+            .suggest(format!("use a value like {}", example(kind))),
+        ),
+    }
+}
+
+fn expected(kind: FieldKind) -> &'static str {
+    match kind {
+        FieldKind::Id | FieldKind::Count => "a whole number",
+        FieldKind::Text | FieldKind::Notes => "a string",
+        FieldKind::Bool => "a boolean",
+        FieldKind::Timestamp => "a timestamp string",
+        FieldKind::Date => "a date string",
+        FieldKind::Priority => "a number",
+        FieldKind::Tags => "a list of strings",
+        FieldKind::Checklist => "a list of checklist items",
+        FieldKind::List => "a list",
+        FieldKind::Object => "an object",
+    }
+}
+
+fn example(kind: FieldKind) -> &'static str {
+    match kind {
+        FieldKind::Id | FieldKind::Count => "1",
+        FieldKind::Text => "\"Buy milk\"",
+        FieldKind::Bool => "false",
+        FieldKind::Timestamp => "\"2025-01-01T09:00:00+00:00\"",
+        FieldKind::Date => "\"2025-04-15\"",
+        FieldKind::Priority => "4",
+        FieldKind::Tags => "[\"work\"]",
+        FieldKind::Notes => "\"Ask about the invoice\"",
+        FieldKind::Checklist => "[{\"text\": \"buy stamps\"}]",
+        FieldKind::List => "[]",
+        FieldKind::Object => "{}",
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => format!("boolean {b}"),
+        Value::Number(n) => format!("number {n}"),
+        Value::String(s) => format!("string \"{s}\""),
+        Value::Array(_) => "an array".to_string(),
+        Value::Object(_) => "an object".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::todo::{
+        ChecklistItem, Deletion, Priority, StoreMeta, Template, TemplateTodo, Todo, TodoStore,
+        TrashedTodo,
+    };
+    use crate::todo_manager::TodoManager;
+    use tempfile::tempdir;
+
+    const VALID_TODO: &str = r#"{"title": "Buy milk", "completed": false, "created_at": "2025-01-01T09:00:00+00:00", "priority": 2}"#;
+
+    fn store_with(todo: &str) -> String {
+        format!(r#"{{"todos": [{VALID_TODO}, {todo}]}}"#)
+    }
+
+    fn paths(problems: &[Problem]) -> Vec<&str> {
+        problems.iter().map(|p| p.path.as_str()).collect()
+    }
+
+    #[test]
+    fn test_valid_store() {
+        assert!(validate_str(&format!(r#"{{"todos": [{VALID_TODO}]}}"#)).is_empty());
+        assert!(validate_str(r#"{"todos": []}"#).is_empty());
+    }
+
+    #[test]
+    fn test_ids() {
+        let todo =
+            r#"{"id": 3, "title": "x", "completed": true, "created_at": "2025-01-01T09:00:00Z"}"#;
+        assert!(validate_str(&format!(r#"{{"todos": [{todo}], "next_id": 4}}"#)).is_empty());
+
+        let problems = validate_str(r#"{"todos": [], "next_id": -1}"#);
+        assert_eq!(paths(&problems), vec!["$.next_id"]);
+    }
+
+    #[test]
+    fn test_priority_is_optional() {
+        let todo = r#"{"title": "x", "completed": true, "created_at": "2025-01-01T09:00:00Z"}"#;
+        assert!(validate_str(&store_with(todo)).is_empty());
+    }
+
+    #[test]
+    fn test_syntax_error_reports_position() {
+        let problems = validate_str("{\n  \"todos\": [\n}");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].path, "$");
+        assert!(problems[0].message.contains("line 3"));
+    }
+
+    #[test]
+    fn test_root_not_object() {
+        let problems = validate_str("[]");
+        assert_eq!(paths(&problems), vec!["$"]);
+    }
+
+    #[test]
+    fn test_missing_and_mistyped_todos() {
+        assert_eq!(paths(&validate_str("{}")), vec!["$"]);
+        assert_eq!(paths(&validate_str(r#"{"todos": {}}"#)), vec!["$.todos"]);
+    }
+
+    #[test]
+    fn test_unknown_field_suggests_closest() {
+        let todo = r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "priorty": 1}"#;
+        let problems = validate_str(&store_with(todo));
+        assert_eq!(paths(&problems), vec!["$.todos[1].priorty"]);
+        assert_eq!(
+            problems[0].suggestion.as_deref(),
+            Some("did you mean 'priority'?")
+        );
+    }
+
+    #[test]
+    fn test_unknown_field_far_from_known() {
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "colour": "red"}"#,
+        ));
+        assert_eq!(problems[0].suggestion.as_deref(), Some("remove this field"));
+    }
+
+    #[test]
+    fn test_unknown_store_field() {
+        let problems = validate_str(r#"{"todos": [], "todoz": []}"#);
+        assert_eq!(paths(&problems), vec!["$.todoz"]);
+    }
+
+    #[test]
+    fn test_quoted_bool() {
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": "true", "created_at": "2025-01-01T09:00:00Z"}"#,
+        ));
+        assert_eq!(paths(&problems), vec!["$.todos[1].completed"]);
+        assert_eq!(
+            problems[0].suggestion.as_deref(),
+            Some("write true without quotes")
+        );
+    }
+
+    #[test]
+    fn test_wrong_type_bool() {
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": "yes", "created_at": "2025-01-01T09:00:00Z"}"#,
+        ));
+        assert_eq!(paths(&problems), vec!["$.todos[1].completed"]);
+        assert!(problems[0].message.contains("expected a boolean"));
+    }
+
+    #[test]
+    fn test_missing_required_fields() {
+        let problems = validate_str(&store_with(r#"{"title": "x"}"#));
+        assert_eq!(paths(&problems), vec!["$.todos[1]"]);
+        assert!(problems[0].message.contains("completed"));
+    }
+
+    #[test]
+    fn test_priority_out_of_range_and_quoted() {
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "priority": 7}"#,
+        ));
+        assert_eq!(paths(&problems), vec!["$.todos[1].priority"]);
+
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "priority": "2"}"#,
+        ));
+        assert_eq!(
+            problems[0].suggestion.as_deref(),
+            Some("write 2 without quotes")
+        );
+    }
+
+    #[test]
+    fn test_bad_timestamp() {
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": false, "created_at": "last tuesday"}"#,
+        ));
+        assert_eq!(paths(&problems), vec!["$.todos[1].created_at"]);
+    }
+
+    #[test]
+    fn test_due_date() {
+        let todo = r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "due_date": "2025-04-15"}"#;
+        assert!(validate_str(&store_with(todo)).is_empty());
+        let todo = r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "due_date": null}"#;
+        assert!(validate_str(&store_with(todo)).is_empty());
+
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "due_date": "15/04/2025"}"#,
+        ));
+        assert_eq!(paths(&problems), vec!["$.todos[1].due_date"]);
+    }
+
+    #[test]
+    fn test_tags() {
+        let todo = r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "tags": ["work"]}"#;
+        assert!(validate_str(&store_with(todo)).is_empty());
+
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "tags": ["work", 3]}"#,
+        ));
+        assert_eq!(paths(&problems), vec!["$.todos[1].tags[1]"]);
+
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "tags": "work"}"#,
+        ));
+        assert_eq!(paths(&problems), vec!["$.todos[1].tags"]);
+    }
+
+    #[test]
+    fn test_notes() {
+        let with = |notes: &str| {
+            store_with(&format!(
+                r#"{{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "notes": {notes}}}"#
+            ))
+        };
+        assert!(validate_str(&with("\"line 1\\nline 2\"")).is_empty());
+        assert!(validate_str(&with("null")).is_empty());
+        assert_eq!(paths(&validate_str(&with("[]"))), vec!["$.todos[1].notes"]);
+    }
+
+    #[test]
+    fn test_checklist() {
+        let with = |checklist: &str| {
+            store_with(&format!(
+                r#"{{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "checklist": {checklist}}}"#
+            ))
+        };
+        assert!(validate_str(&with(r#"[{"text": "a"}, {"text": "b", "done": true}]"#)).is_empty());
+        assert_eq!(
+            paths(&validate_str(&with(
+                r#"[{"text": "a"}, {"text": "b", "done": "yes"}]"#
+            ))),
+            vec!["$.todos[1].checklist[1]"]
+        );
+        assert_eq!(
+            paths(&validate_str(&with(r#"["a"]"#))),
+            vec!["$.todos[1].checklist[0]"]
+        );
+        assert_eq!(
+            paths(&validate_str(&with(r#"[{"txt": "a"}]"#))),
+            vec!["$.todos[1].checklist[0]"]
+        );
+        assert_eq!(
+            paths(&validate_str(&with("{}"))),
+            vec!["$.todos[1].checklist"]
+        );
+    }
+
+    #[test]
+    fn test_project() {
+        let with = |project: &str| {
+            store_with(&format!(
+                r#"{{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "project": {project}}}"#
+            ))
+        };
+        assert!(validate_str(&with("\"garden\"")).is_empty());
+        assert!(validate_str(&with("null")).is_empty());
+        assert_eq!(
+            paths(&validate_str(&with("[\"garden\"]"))),
+            vec!["$.todos[1].project"]
+        );
+    }
+
+    #[test]
+    fn test_snoozed_until() {
+        let with = |until: &str| {
+            store_with(&format!(
+                r#"{{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "snooze_count": 1, "snoozed_until": {until}}}"#
+            ))
+        };
+        assert!(validate_str(&with(r#""2025-01-08T09:00:00+00:00""#)).is_empty());
+
+        let problems = validate_str(&with(r#""next week""#));
+        assert_eq!(paths(&problems), vec!["$.todos[1].snoozed_until"]);
+        assert!(problems[0].message.contains("not an ISO 8601 timestamp"));
+
+        let problems = validate_str(&with("7"));
+        assert_eq!(paths(&problems), vec!["$.todos[1].snoozed_until"]);
+        assert!(problems[0].message.contains("expected a timestamp string"));
+    }
+
+    #[test]
+    fn test_draft() {
+        let todo = r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "draft": true, "draft_expires_at": "2025-01-02T09:00:00Z"}"#;
+        assert!(validate_str(&store_with(todo)).is_empty());
+
+        let problems = validate_str(&store_with(
+            r#"{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "draft": "true", "draft_expires_at": "tomorrow"}"#,
+        ));
+        assert_eq!(
+            paths(&problems),
+            vec!["$.todos[1].draft", "$.todos[1].draft_expires_at"]
+        );
+    }
+
+    #[test]
+    fn test_pinned() {
+        let with = |pinned: &str| {
+            store_with(&format!(
+                r#"{{"title": "x", "completed": false, "created_at": "2025-01-01T09:00:00Z", "pinned": {pinned}}}"#
+            ))
+        };
+        assert!(validate_str(&with("true")).is_empty());
+        assert_eq!(paths(&validate_str(&with("1"))), vec!["$.todos[1].pinned"]);
+    }
+
+    #[test]
+    fn test_meta() {
+        let with = |meta: &str| format!(r#"{{"todos": [], "meta": {meta}}}"#);
+        let stamps =
+            r#""created_at": "2025-01-01T09:00:00Z", "modified_at": "2025-02-01T09:00:00Z""#;
+        assert!(validate_str(&with(&format!(r#"{{"name": "Work", {stamps}}}"#))).is_empty());
+        assert!(validate_str(&with(&format!("{{{stamps}}}"))).is_empty());
+        assert_eq!(
+            paths(&validate_str(&with(&format!(
+                r#"{{"nmae": "Work", {stamps}}}"#
+            )))),
+            vec!["$.meta.nmae"]
+        );
+        assert_eq!(
+            paths(&validate_str(&with(r#"{"name": "Work"}"#))),
+            vec!["$.meta", "$.meta"]
+        );
+        assert_eq!(paths(&validate_str(&with("\"Work\""))), vec!["$.meta"]);
+    }
+
+    #[test]
+    fn test_deleted() {
+        let with = |deleted: &str| format!(r#"{{"todos": [], "deleted": {deleted}}}"#);
+        assert!(
+            validate_str(&with(
+                r#"[{"id": 3, "created_at": "2025-01-01T09:00:00Z", "deleted_at": "2025-02-01T09:00:00Z"}]"#
+            ))
+            .is_empty()
+        );
+        assert_eq!(
+            paths(&validate_str(&with(
+                r#"[{"id": 3, "created_at": "2025-01-01T09:00:00Z", "deleted_at": "later"}]"#
+            ))),
+            vec!["$.deleted[0].deleted_at"]
+        );
+        assert_eq!(paths(&validate_str(&with("[3]"))), vec!["$.deleted[0]"]);
+        assert_eq!(paths(&validate_str(&with("{}"))), vec!["$.deleted"]);
+    }
+
+    #[test]
+    fn test_templates() {
+        let with = |templates: &str| format!(r#"{{"todos": [], "templates": {templates}}}"#);
+        assert!(
+            validate_str(&with(
+                r#"[{"name": "weekly", "todos": [{"title": "Water plants", "priority": 2, "tags": ["home"], "project": "garden"}, {"title": "Review"}]}]"#
+            ))
+            .is_empty()
+        );
+        assert_eq!(
+            paths(&validate_str(&with(
+                r#"[{"name": "weekly", "todos": [{"title": "Review", "priority": 9, "tag": ["x"]}]}]"#
+            ))),
+            vec![
+                "$.templates[0].todos[0].tag",
+                "$.templates[0].todos[0].priority"
+            ]
+        );
+        assert_eq!(
+            paths(&validate_str(&with(r#"[{"name": 3, "todos": {}}]"#))),
+            vec!["$.templates[0].name", "$.templates[0].todos"]
+        );
+        assert_eq!(
+            paths(&validate_str(&with(r#"[{"todos": []}]"#))),
+            vec!["$.templates[0]"]
+        );
+        assert_eq!(paths(&validate_str(&with("[3]"))), vec!["$.templates[0]"]);
+        assert_eq!(paths(&validate_str(&with("{}"))), vec!["$.templates"]);
+    }
+
+    #[test]
+    fn test_saved_template() {
+        let mut todo = Todo::new(1, "Water plants".to_string(), Priority::High);
+        todo.tags = vec!["home".to_string()];
+        todo.project = Some("garden".to_string());
+        let store = TodoStore {
+            templates: vec![Template {
+                name: "weekly".to_string(),
+                todos: vec![TemplateTodo::of(&todo)],
+            }],
+            todos: vec![todo],
+            next_id: 2,
+            ..TodoStore::default()
+        };
+        let json = serde_json::to_string_pretty(&store).unwrap();
+        assert_eq!(validate_str(&json), vec![]);
+    }
+
+    #[test]
+    fn test_trash() {
+        let with = |trash: &str| format!(r#"{{"todos": [], "trash": {trash}}}"#);
+        assert!(
+            validate_str(&with(&format!(
+                r#"[{{"todo": {VALID_TODO}, "deleted_at": "2025-02-01T09:00:00Z"}}]"#
+            )))
+            .is_empty()
+        );
+        assert_eq!(
+            paths(&validate_str(&with(
+                r#"[{"todo": {"title": "x", "completed": "no", "created_at": "2025-01-01T09:00:00Z"}, "deleted_at": "later"}]"#
+            ))),
+            vec!["$.trash[0].deleted_at", "$.trash[0].todo.completed"]
+        );
+        assert_eq!(
+            paths(&validate_str(&with(r#"[{"todo": "x"}]"#))),
+            vec!["$.trash[0].todo", "$.trash[0]"]
+        );
+        assert_eq!(paths(&validate_str(&with("{}"))), vec!["$.trash"]);
+    }
+
+    #[test]
+    fn test_saved_trash() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("todos.json");
+        let mut manager = TodoManager::with_path(&path).unwrap();
+        let id = manager
+            .add_todo("Water plants".to_string(), Priority::High, None, &[], None)
+            .unwrap()
+            .id;
+        manager.trash_todos(&[id]).unwrap();
+
+        assert_eq!(manager.trash().len(), 1);
+        assert_eq!(validate_file(&path).unwrap(), vec![]);
+    }
+
+    /// Every field tt can write, set at once. The struct literals are
+    /// spelled out in full so that a new field fails to compile here
+    /// until it is added, and then fails validation until it is mirrored.
+    #[test]
+    fn test_everything_tt_writes_is_valid() {
+        let todo = Todo {
+            id: 1,
+            title: "Water plants".to_string(),
+            completed: true,
+            created_at: "2025-01-01T09:00:00+00:00".to_string(),
+            priority: Priority::High,
+            due_date: NaiveDate::from_ymd_opt(2025, 4, 15),
+            tags: vec!["home/garden".to_string()],
+            project: Some("garden".to_string()),
+            snooze_count: 2,
+            snoozed_until: Some("2025-01-03T09:00:00+00:00".to_string()),
+            notes: Some("Ferns first\nthen the rest".to_string()),
+            updated_at: Some("2025-01-02T09:00:00+00:00".to_string()),
+            completed_at: Some("2025-01-04T09:00:00+00:00".to_string()),
+            checklist: vec![ChecklistItem {
+                text: "fill the can".to_string(),
+                done: true,
+            }],
+            draft: true,
+            draft_expires_at: Some("2025-01-05T09:00:00+00:00".to_string()),
+            pinned: true,
+            saved_priority: None,
+        };
+        let store = TodoStore {
+            todos: vec![todo.clone()],
+            next_id: 3,
+            meta: Some(StoreMeta {
+                name: Some("Home".to_string()),
+                description: Some("Chores".to_string()),
+                created_at: "2025-01-01T09:00:00+00:00".to_string(),
+                modified_at: "2025-01-04T09:00:00+00:00".to_string(),
+            }),
+            deleted: vec![Deletion {
+                id: 2,
+                created_at: "2025-01-01T10:00:00+00:00".to_string(),
+                deleted_at: "2025-01-02T10:00:00+00:00".to_string(),
+            }],
+            templates: vec![Template {
+                name: "weekly".to_string(),
+                todos: vec![TemplateTodo::of(&todo)],
+            }],
+            trash: vec![TrashedTodo {
+                todo,
+                deleted_at: "2025-01-06T09:00:00+00:00".to_string(),
+            }],
+        };
+
+        let json = serde_json::to_string_pretty(&store).unwrap();
+        assert_eq!(validate_str(&json), vec![]);
+    }
+
+    #[test]
+    fn test_todo_not_object() {
+        let problems = validate_str(r#"{"todos": ["buy milk"]}"#);
+        assert_eq!(paths(&problems), vec!["$.todos[0]"]);
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let problems = validate_str(
+            r#"{"todos": [{"title": 3, "completed": "no", "created_at": "x", "priorty": 1}]}"#,
+        );
+        assert_eq!(problems.len(), 4);
+    }
+}